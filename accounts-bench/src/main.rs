@@ -2,7 +2,7 @@
 #[macro_use]
 extern crate log;
 use {
-    clap::{crate_description, crate_name, value_t, App, Arg},
+    clap::{crate_description, crate_name, value_t, value_t_or_exit, App, Arg},
     rayon::prelude::*,
     solana_measure::measure::Measure,
     solana_runtime::{
@@ -10,9 +10,12 @@ use {
             test_utils::{create_test_accounts, update_accounts_bench},
             Accounts,
         },
-        accounts_db::AccountShrinkThreshold,
+        accounts_db::{
+            AccountShrinkThreshold, AccountsDbConfig, ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS,
+        },
         accounts_index::AccountSecondaryIndexes,
         ancestors::Ancestors,
+        append_vec::AppendVecBackend,
         rent_collector::RentCollector,
     },
     solana_sdk::{genesis_config::ClusterType, pubkey::Pubkey},
@@ -52,6 +55,15 @@ fn main() {
                 .takes_value(false)
                 .help("Run clean"),
         )
+        .arg(
+            Arg::with_name("append_vec_backend")
+                .long("append_vec_backend")
+                .takes_value(true)
+                .value_name("BACKEND")
+                .possible_values(AppendVecBackend::VARIANTS)
+                .default_value("mmap")
+                .help("How account storage files are accessed"),
+        )
         .get_matches();
 
     let num_slots = value_t!(matches, "num_slots", usize).unwrap_or(4);
@@ -59,6 +71,8 @@ fn main() {
     let iterations = value_t!(matches, "iterations", usize).unwrap_or(20);
     let clean = matches.is_present("clean");
     println!("clean: {:?}", clean);
+    let append_vec_backend = value_t_or_exit!(matches, "append_vec_backend", AppendVecBackend);
+    println!("append vec backend: {:?}", append_vec_backend);
 
     let path = PathBuf::from(env::var("FARF_DIR").unwrap_or_else(|_| "farf".to_owned()))
         .join("accounts-bench");
@@ -66,12 +80,17 @@ fn main() {
     if fs::remove_dir_all(path.clone()).is_err() {
         println!("Warning: Couldn't remove {:?}", path);
     }
-    let accounts = Accounts::new_with_config_for_benches(
+    let accounts = Accounts::new_with_config(
         vec![path],
         &ClusterType::Testnet,
         AccountSecondaryIndexes::default(),
         false,
         AccountShrinkThreshold::default(),
+        Some(AccountsDbConfig {
+            append_vec_backend,
+            ..ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS
+        }),
+        None,
     );
     println!("Creating {} accounts", num_accounts);
     let mut create_time = Measure::start("create accounts");
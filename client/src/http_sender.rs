@@ -4,11 +4,12 @@ use {
     crate::{
         client_error::Result,
         rpc_custom_error,
-        rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+        rpc_request::{RpcError, RpcRequest, RpcResponseErrorData, STREAM_RESPONSE_HEADER},
         rpc_response::RpcSimulateTransactionResult,
         rpc_sender::*,
     },
     async_trait::async_trait,
+    bytes::{Buf, Bytes},
     log::*,
     reqwest::{
         self,
@@ -16,6 +17,7 @@ use {
         StatusCode,
     },
     std::{
+        io::{self, Read},
         sync::{
             atomic::{AtomicU64, Ordering},
             Arc, RwLock,
@@ -23,8 +25,12 @@ use {
         thread::sleep,
         time::{Duration, Instant},
     },
+    tokio::sync::mpsc,
 };
 
+// Chunks of a streamed response received but not yet parsed
+const MAX_BUFFERED_RESPONSE_CHUNKS: usize = 16;
+
 pub struct HttpSender {
     client: Arc<reqwest::Client>,
     url: String,
@@ -109,7 +115,52 @@ impl RpcSender for HttpSender {
         params: serde_json::Value,
     ) -> Result<serde_json::Value> {
         let mut stats_updater = StatsUpdater::new(&self.stats);
+        let response = self.post(request, params, &mut stats_updater).await?;
+        let mut json = response.json::<serde_json::Value>().await?;
+        if json["error"].is_object() {
+            return Err(rpc_error(&json["error"]).into());
+        }
+        Ok(json["result"].take())
+    }
+
+    async fn send_streamed(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<Option<Box<dyn Read + Send>>> {
+        let mut stats_updater = StatsUpdater::new(&self.stats);
+        let mut response = self.post(request, params, &mut stats_updater).await?;
+        let (sender, receiver) = mpsc::channel(MAX_BUFFERED_RESPONSE_CHUNKS);
+        tokio::spawn(async move {
+            loop {
+                let chunk = match response.chunk().await {
+                    Ok(Some(chunk)) => Ok(chunk),
+                    Ok(None) => break,
+                    Err(err) => Err(io::Error::new(io::ErrorKind::Other, err)),
+                };
+                let failed = chunk.is_err();
+                // Stop once the reader is dropped, or after the body failed
+                if sender.send(chunk).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Ok(Some(Box::new(ChunkReader::new(receiver))))
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+}
 
+impl HttpSender {
+    /// Post a request, retrying while the node is rate limiting requests
+    async fn post(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+        stats_updater: &mut StatsUpdater<'_>,
+    ) -> Result<reqwest::Response> {
         let request_id = self.request_id.fetch_add(1, Ordering::Relaxed);
         let request_json = request.build_request_json(request_id, params).to_string();
 
@@ -118,12 +169,13 @@ impl RpcSender for HttpSender {
             let response = {
                 let client = self.client.clone();
                 let request_json = request_json.clone();
-                client
+                let mut request_builder = client
                     .post(&self.url)
-                    .header(CONTENT_TYPE, "application/json")
-                    .body(request_json)
-                    .send()
-                    .await
+                    .header(CONTENT_TYPE, "application/json");
+                if request.has_streamed_response() {
+                    request_builder = request_builder.header(STREAM_RESPONSE_HEADER, "true");
+                }
+                request_builder.body(request_json).send().await
             }?;
 
             if !response.status().is_success() {
@@ -153,53 +205,84 @@ impl RpcSender for HttpSender {
                 }
                 return Err(response.error_for_status().unwrap_err().into());
             }
+            return Ok(response);
+        }
+    }
+}
 
-            let mut json = response.json::<serde_json::Value>().await?;
-            if json["error"].is_object() {
-                return match serde_json::from_value::<RpcErrorObject>(json["error"].clone()) {
-                    Ok(rpc_error_object) => {
-                        let data = match rpc_error_object.code {
-                                    rpc_custom_error::JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE => {
-                                        match serde_json::from_value::<RpcSimulateTransactionResult>(json["error"]["data"].clone()) {
-                                            Ok(data) => RpcResponseErrorData::SendTransactionPreflightFailure(data),
-                                            Err(err) => {
-                                                debug!("Failed to deserialize RpcSimulateTransactionResult: {:?}", err);
-                                                RpcResponseErrorData::Empty
-                                            }
-                                        }
-                                    },
-                                    rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY => {
-                                        match serde_json::from_value::<rpc_custom_error::NodeUnhealthyErrorData>(json["error"]["data"].clone()) {
-                                            Ok(rpc_custom_error::NodeUnhealthyErrorData {num_slots_behind}) => RpcResponseErrorData::NodeUnhealthy {num_slots_behind},
-                                            Err(_err) => {
-                                                RpcResponseErrorData::Empty
-                                            }
-                                        }
-                                    },
-                                    _ => RpcResponseErrorData::Empty
-                                };
-
-                        Err(RpcError::RpcResponseError {
-                            code: rpc_error_object.code,
-                            message: rpc_error_object.message,
-                            data,
+/// The error of the `error` object of a JSON-RPC response
+pub(crate) fn rpc_error(error: &serde_json::Value) -> RpcError {
+    match serde_json::from_value::<RpcErrorObject>(error.clone()) {
+        Ok(rpc_error_object) => {
+            let data = match rpc_error_object.code {
+                rpc_custom_error::JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE => {
+                    match serde_json::from_value::<RpcSimulateTransactionResult>(
+                        error["data"].clone(),
+                    ) {
+                        Ok(data) => RpcResponseErrorData::SendTransactionPreflightFailure(data),
+                        Err(err) => {
+                            debug!(
+                                "Failed to deserialize RpcSimulateTransactionResult: {:?}",
+                                err
+                            );
+                            RpcResponseErrorData::Empty
                         }
-                        .into())
                     }
-                    Err(err) => Err(RpcError::RpcRequestError(format!(
-                        "Failed to deserialize RPC error response: {} [{}]",
-                        serde_json::to_string(&json["error"]).unwrap(),
-                        err
-                    ))
-                    .into()),
-                };
+                }
+                rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY => {
+                    match serde_json::from_value::<rpc_custom_error::NodeUnhealthyErrorData>(
+                        error["data"].clone(),
+                    ) {
+                        Ok(rpc_custom_error::NodeUnhealthyErrorData { num_slots_behind }) => {
+                            RpcResponseErrorData::NodeUnhealthy { num_slots_behind }
+                        }
+                        Err(_err) => RpcResponseErrorData::Empty,
+                    }
+                }
+                _ => RpcResponseErrorData::Empty,
+            };
+
+            RpcError::RpcResponseError {
+                code: rpc_error_object.code,
+                message: rpc_error_object.message,
+                data,
             }
-            return Ok(json["result"].take());
         }
+        Err(err) => RpcError::RpcRequestError(format!(
+            "Failed to deserialize RPC error response: {} [{}]",
+            serde_json::to_string(error).unwrap(),
+            err
+        )),
     }
+}
 
-    fn url(&self) -> String {
-        self.url.clone()
+/// Reads the chunks of a response body, blocking until the next one arrives
+struct ChunkReader {
+    receiver: mpsc::Receiver<io::Result<Bytes>>,
+    chunk: Bytes,
+}
+
+impl ChunkReader {
+    fn new(receiver: mpsc::Receiver<io::Result<Bytes>>) -> Self {
+        Self {
+            receiver,
+            chunk: Bytes::new(),
+        }
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => self.chunk = chunk?,
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk[..len]);
+        self.chunk.advance(len);
+        Ok(len)
     }
 }
 
@@ -207,6 +290,47 @@ impl RpcSender for HttpSender {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_chunk_reader() {
+        let json = serde_json::json!({
+            "jsonrpc": "2.0",
+            "result": [{"pubkey": "11111111111111111111111111111111", "lamports": 42}],
+            "id": 1,
+        });
+        let body = json.to_string().into_bytes();
+        let (sender, receiver) = mpsc::channel(body.len());
+        for chunk in body.chunks(7) {
+            sender.try_send(Ok(Bytes::copy_from_slice(chunk))).unwrap();
+        }
+        drop(sender);
+        let parsed: serde_json::Value =
+            serde_json::from_reader(ChunkReader::new(receiver)).unwrap();
+        assert_eq!(parsed, json);
+
+        // A truncated body does not parse
+        let (sender, receiver) = mpsc::channel(1);
+        sender
+            .try_send(Ok(Bytes::copy_from_slice(&body[..body.len() - 1])))
+            .unwrap();
+        drop(sender);
+        assert!(
+            serde_json::from_reader::<_, serde_json::Value>(ChunkReader::new(receiver)).is_err()
+        );
+
+        // Neither does a body that failed to arrive
+        let (sender, receiver) = mpsc::channel(2);
+        sender
+            .try_send(Ok(Bytes::copy_from_slice(&body[..7])))
+            .unwrap();
+        sender
+            .try_send(Err(io::Error::new(io::ErrorKind::Other, "reset")))
+            .unwrap();
+        drop(sender);
+        assert!(
+            serde_json::from_reader::<_, serde_json::Value>(ChunkReader::new(receiver)).is_err()
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn http_sender_on_tokio_multi_thread() {
         let http_sender = HttpSender::new("http://localhost:1234".to_string());
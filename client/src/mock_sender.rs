@@ -9,8 +9,8 @@ use {
             Response, RpcAccountBalance, RpcBlockProduction, RpcBlockProductionRange, RpcBlockhash,
             RpcConfirmedTransactionStatusWithSignature, RpcContactInfo, RpcFees, RpcIdentity,
             RpcInflationGovernor, RpcInflationRate, RpcInflationReward, RpcKeyedAccount,
-            RpcPerfSample, RpcResponseContext, RpcSimulateTransactionResult, RpcSnapshotSlotInfo,
-            RpcStakeActivation, RpcSupply, RpcVersionInfo, RpcVoteAccountInfo,
+            RpcKeyedAccountsPage, RpcPerfSample, RpcResponseContext, RpcSimulateTransactionResult,
+            RpcSnapshotSlotInfo, RpcStakeActivation, RpcSupply, RpcVersionInfo, RpcVoteAccountInfo,
             RpcVoteAccountStatus, StakeActivationState,
         },
         rpc_sender::*,
@@ -453,7 +453,7 @@ impl RpcSender for MockSender {
                     executable: false,
                     rent_epoch: 0,
                 };
-                serde_json::to_value(vec![
+                let accounts = vec![
                     RpcKeyedAccount {
                         pubkey: PUBKEY.to_string(),
                         account: UiAccount::encode(
@@ -464,7 +464,15 @@ impl RpcSender for MockSender {
                             None,
                         )
                     }
-                ])?
+                ];
+                if params[1]["pageSize"].is_null() {
+                    serde_json::to_value(accounts)?
+                } else {
+                    serde_json::to_value(RpcKeyedAccountsPage {
+                        accounts,
+                        cursor: None,
+                    })?
+                }
             },
             _ => Value::Null,
         };
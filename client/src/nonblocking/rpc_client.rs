@@ -15,9 +15,11 @@ use crate::rpc_deprecated_config::{
 use {
     crate::{
         client_error::{ClientError, ClientErrorKind, Result as ClientResult},
-        http_sender::HttpSender,
+        http_sender::{rpc_error, HttpSender},
         mock_sender::MockSender,
-        rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClientConfig},
+        rpc_client::{
+            GetConfirmedSignaturesForAddress2Config, ProgramAccountsPage, RpcClientConfig,
+        },
         rpc_config::{RpcAccountInfoConfig, *},
         rpc_request::{RpcError, RpcRequest, RpcResponseErrorData, TokenAccountsFilter},
         rpc_response::*,
@@ -2444,7 +2446,7 @@ impl RpcClient {
         slot: Slot,
         encoding: UiTransactionEncoding,
     ) -> ClientResult<EncodedConfirmedBlock> {
-        self.send(
+        self.send_streamed(
             self.maybe_map_request(RpcRequest::GetBlock).await?,
             json!([slot, encoding]),
         )
@@ -2491,7 +2493,7 @@ impl RpcClient {
         slot: Slot,
         config: RpcBlockConfig,
     ) -> ClientResult<UiConfirmedBlock> {
-        self.send(
+        self.send_streamed(
             self.maybe_map_request(RpcRequest::GetBlock).await?,
             json!([slot, config]),
         )
@@ -2966,7 +2968,7 @@ impl RpcClient {
         };
 
         let result: Vec<RpcConfirmedTransactionStatusWithSignature> = self
-            .send(
+            .send_streamed(
                 self.maybe_map_request(RpcRequest::GetSignaturesForAddress)
                     .await?,
                 json!([address.to_string(), config]),
@@ -4272,6 +4274,7 @@ impl RpcClient {
     ///         commitment: Some(CommitmentConfig::processed()),
     ///     },
     ///     with_context: Some(false),
+    ///     ..RpcProgramAccountsConfig::default()
     /// };
     /// let accounts = rpc_client.get_program_accounts_with_config(
     ///     &alice.pubkey(),
@@ -4298,7 +4301,7 @@ impl RpcClient {
             ..config
         };
         let accounts: Vec<RpcKeyedAccount> = self
-            .send(
+            .send_streamed(
                 RpcRequest::GetProgramAccounts,
                 json!([pubkey.to_string(), config]),
             )
@@ -4306,6 +4309,48 @@ impl RpcClient {
         parse_keyed_accounts(accounts, RpcRequest::GetProgramAccounts)
     }
 
+    /// Returns a page of the accounts owned by the provided program pubkey, in pubkey order,
+    /// and the cursor to the next page.
+    ///
+    /// `config.page_size` must be set. Pass the returned cursor as `config.cursor` to get the
+    /// next page; it is `None` after the last page. The pages after the first are read at the
+    /// slot of the first while the node has the bank for that slot, then at the node's root if
+    /// that slot was rooted. They fail with a cursor expired error if the slot was not rooted.
+    ///
+    /// # RPC Reference
+    ///
+    /// This method is built on the [`getProgramAccounts`] RPC method.
+    ///
+    /// [`getProgramAccounts`]: https://docs.solana.com/developing/clients/jsonrpc-api#getprogramaccounts
+    pub async fn get_program_accounts_page(
+        &self,
+        pubkey: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> ClientResult<ProgramAccountsPage> {
+        let commitment = config
+            .account_config
+            .commitment
+            .unwrap_or_else(|| self.commitment());
+        let commitment = self.maybe_map_commitment(commitment).await?;
+        let account_config = RpcAccountInfoConfig {
+            commitment: Some(commitment),
+            ..config.account_config
+        };
+        let config = RpcProgramAccountsConfig {
+            account_config,
+            with_context: None,
+            ..config
+        };
+        let page: RpcKeyedAccountsPage = self
+            .send_streamed(
+                RpcRequest::GetProgramAccounts,
+                json!([pubkey.to_string(), config]),
+            )
+            .await?;
+        let accounts = parse_keyed_accounts(page.accounts, RpcRequest::GetProgramAccounts)?;
+        Ok((accounts, page.cursor))
+    }
+
     /// Request the transaction count.
     pub async fn get_transaction_count(&self) -> ClientResult<u64> {
         self.get_transaction_count_with_commitment(self.commitment())
@@ -5129,11 +5174,59 @@ impl RpcClient {
             .map_err(|err| ClientError::new_with_request(err.into(), request))
     }
 
+    /// Like `send`, for the requests whose response the node may stream: the response is
+    /// deserialized as it arrives, instead of after all of it is read
+    async fn send_streamed<T>(&self, request: RpcRequest, params: Value) -> ClientResult<T>
+    where
+        T: serde::de::DeserializeOwned + Send + 'static,
+    {
+        assert!(params.is_array() || params.is_null());
+
+        if !request.has_streamed_response() {
+            return self.send(request, params).await;
+        }
+        let reader = match self
+            .sender
+            .send_streamed(request, params.clone())
+            .await
+            .map_err(|err| err.into_with_request(request))?
+        {
+            Some(reader) => reader,
+            None => return self.send(request, params).await,
+        };
+        tokio::task::spawn_blocking(move || -> ClientResult<T> {
+            let response: RpcResponseObject<T> = serde_json::from_reader(reader)?;
+            if let Some(error) = response.error {
+                return Err(rpc_error(&error).into());
+            }
+            match response.result {
+                Some(result) => Ok(result),
+                // A null result
+                None => Ok(serde_json::from_value(Value::Null)?),
+            }
+        })
+        .await
+        .map_err(|err| {
+            ClientError::from(RpcError::RpcRequestError(format!(
+                "Failed to parse RPC response: {}",
+                err
+            )))
+        })?
+        .map_err(|err| err.into_with_request(request))
+    }
+
     pub fn get_transport_stats(&self) -> RpcTransportStats {
         self.sender.get_transport_stats()
     }
 }
 
+/// A JSON-RPC response, deserialized with the type of its result
+#[derive(Deserialize)]
+struct RpcResponseObject<T> {
+    result: Option<T>,
+    error: Option<Value>,
+}
+
 fn serialize_and_encode<T>(input: &T, encoding: UiTransactionEncoding) -> ClientResult<String>
 where
     T: serde::ser::Serialize,
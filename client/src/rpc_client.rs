@@ -3693,6 +3693,7 @@ impl RpcClient {
     ///         commitment: Some(CommitmentConfig::processed()),
     ///     },
     ///     with_context: Some(false),
+    ///     ..RpcProgramAccountsConfig::default()
     /// };
     /// let accounts = rpc_client.get_program_accounts_with_config(
     ///     &alice.pubkey(),
@@ -3711,6 +3712,81 @@ impl RpcClient {
         )
     }
 
+    /// Returns a page of the accounts owned by the provided program pubkey, in pubkey order,
+    /// and the cursor to the next page.
+    ///
+    /// `config.page_size` must be set. Pass the returned cursor as `config.cursor` to get the
+    /// next page; it is `None` after the last page. The pages after the first are read at the
+    /// slot of the first while the node has the bank for that slot, then at the node's root if
+    /// that slot was rooted. They fail with a cursor expired error if the slot was not rooted.
+    ///
+    /// # RPC Reference
+    ///
+    /// This method is built on the [`getProgramAccounts`] RPC method.
+    ///
+    /// [`getProgramAccounts`]: https://docs.solana.com/developing/clients/jsonrpc-api#getprogramaccounts
+    pub fn get_program_accounts_page(
+        &self,
+        pubkey: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> ClientResult<ProgramAccountsPage> {
+        self.invoke(self.rpc_client.get_program_accounts_page(pubkey, config))
+    }
+
+    /// Returns an iterator over the pages of the accounts owned by the provided program
+    /// pubkey, each with at most `page_size` accounts.
+    ///
+    /// The iterator stops after the last page, or after the first error.
+    ///
+    /// # RPC Reference
+    ///
+    /// This method is built on the [`getProgramAccounts`] RPC method.
+    ///
+    /// [`getProgramAccounts`]: https://docs.solana.com/developing/clients/jsonrpc-api#getprogramaccounts
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     rpc_client::RpcClient,
+    /// #     client_error::ClientError,
+    /// #     rpc_config::RpcProgramAccountsConfig,
+    /// # };
+    /// # use solana_sdk::{
+    /// #     signature::Signer,
+    /// #     signer::keypair::Keypair,
+    /// # };
+    /// # let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// # let alice = Keypair::new();
+    /// for page in rpc_client.get_program_accounts_pages(
+    ///     &alice.pubkey(),
+    ///     RpcProgramAccountsConfig::default(),
+    ///     1_000,
+    /// ) {
+    ///     for (pubkey, account) in page? {
+    ///         println!("{}: {} lamports", pubkey, account.lamports);
+    ///     }
+    /// }
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub fn get_program_accounts_pages(
+        &self,
+        pubkey: &Pubkey,
+        config: RpcProgramAccountsConfig,
+        page_size: usize,
+    ) -> ProgramAccountsPages<'_> {
+        ProgramAccountsPages {
+            rpc_client: self,
+            pubkey: *pubkey,
+            config: RpcProgramAccountsConfig {
+                page_size: Some(page_size),
+                cursor: None,
+                ..config
+            },
+            done: false,
+        }
+    }
+
     /// Request the transaction count.
     pub fn get_transaction_count(&self) -> ClientResult<u64> {
         self.invoke(self.rpc_client.get_transaction_count())
@@ -4054,6 +4130,43 @@ impl RpcClient {
     }
 }
 
+/// The accounts of a page of the accounts owned by a program, and the cursor to the next page,
+/// see [`RpcClient::get_program_accounts_page`]
+pub type ProgramAccountsPage = (Vec<(Pubkey, Account)>, Option<String>);
+
+/// Iterator over the pages of the accounts owned by a program, see
+/// [`RpcClient::get_program_accounts_pages`]
+pub struct ProgramAccountsPages<'a> {
+    rpc_client: &'a RpcClient,
+    pubkey: Pubkey,
+    config: RpcProgramAccountsConfig,
+    done: bool,
+}
+
+impl<'a> Iterator for ProgramAccountsPages<'a> {
+    type Item = ClientResult<Vec<(Pubkey, Account)>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self
+            .rpc_client
+            .get_program_accounts_page(&self.pubkey, self.config.clone());
+        match result {
+            Ok((accounts, cursor)) => {
+                self.done = cursor.is_none();
+                self.config.cursor = cursor;
+                Some(Ok(accounts))
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
 /// Mocks for documentation examples
 #[doc(hidden)]
 pub fn create_rpc_client_mocks() -> crate::mock_sender::Mocks {
@@ -4313,4 +4426,29 @@ mod tests {
         let is_err = rpc_client.get_latest_blockhash().is_err();
         assert!(is_err);
     }
+
+    #[test]
+    fn test_get_program_accounts_pages() {
+        let rpc_client = RpcClient::new_mock("succeeds".to_string());
+        let pages = rpc_client
+            .get_program_accounts_pages(
+                &Pubkey::new_unique(),
+                RpcProgramAccountsConfig::default(),
+                10,
+            )
+            .collect::<ClientResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0][0].0.to_string(), PUBKEY);
+
+        // the iterator ends after an error
+        let rpc_client = RpcClient::new_mock("fails".to_string());
+        let mut pages = rpc_client.get_program_accounts_pages(
+            &Pubkey::new_unique(),
+            RpcProgramAccountsConfig::default(),
+            10,
+        );
+        assert!(pages.next().unwrap().is_err());
+        assert!(pages.next().is_none());
+    }
 }
@@ -150,6 +150,11 @@ pub struct RpcProgramAccountsConfig {
     #[serde(flatten)]
     pub account_config: RpcAccountInfoConfig,
     pub with_context: Option<bool>,
+    /// Return at most this many accounts per response, along with a cursor to the next page
+    pub page_size: Option<usize>,
+    /// The cursor returned with the previous page. Every page after the first is read at
+    /// the slot of the first, so the pages are a consistent snapshot.
+    pub cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
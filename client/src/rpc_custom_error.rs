@@ -22,6 +22,9 @@ pub const JSON_RPC_SCAN_ERROR: i64 = -32012;
 pub const JSON_RPC_SERVER_ERROR_TRANSACTION_SIGNATURE_LEN_MISMATCH: i64 = -32013;
 pub const JSON_RPC_SERVER_ERROR_BLOCK_STATUS_NOT_AVAILABLE_YET: i64 = -32014;
 pub const JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION: i64 = -32015;
+pub const JSON_RPC_SERVER_ERROR_PROGRAM_ACCOUNTS_CURSOR_EXPIRED: i64 = -32016;
+pub const JSON_RPC_SERVER_ERROR_RATE_LIMITED: i64 = -32017;
+pub const JSON_RPC_SERVER_ERROR_METHOD_DISABLED: i64 = -32018;
 
 #[derive(Error, Debug)]
 pub enum RpcCustomError {
@@ -61,6 +64,12 @@ pub enum RpcCustomError {
     BlockStatusNotAvailableYet { slot: Slot },
     #[error("UnsupportedTransactionVersion")]
     UnsupportedTransactionVersion(u8),
+    #[error("ProgramAccountsCursorExpired")]
+    ProgramAccountsCursorExpired { slot: Slot },
+    #[error("RateLimited")]
+    RateLimited { method: String, retry_after_ms: u64 },
+    #[error("MethodDisabled")]
+    MethodDisabled { method: String },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -69,6 +78,12 @@ pub struct NodeUnhealthyErrorData {
     pub num_slots_behind: Option<Slot>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitedErrorData {
+    pub retry_after_ms: u64,
+}
+
 impl From<EncodeError> for RpcCustomError {
     fn from(err: EncodeError) -> Self {
         match err {
@@ -188,6 +203,31 @@ impl From<RpcCustomError> for Error {
                 message: format!("Transaction version ({}) is not supported", version),
                 data: None,
             },
+            RpcCustomError::ProgramAccountsCursorExpired { slot } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_PROGRAM_ACCOUNTS_CURSOR_EXPIRED),
+                message: format!(
+                    "Cursor expired, the bank for slot {} is no longer available; \
+                    request the first page again",
+                    slot
+                ),
+                data: None,
+            },
+            RpcCustomError::RateLimited {
+                method,
+                retry_after_ms,
+            } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_RATE_LIMITED),
+                message: format!(
+                    "Rate limit exceeded for {}; retry after {} ms",
+                    method, retry_after_ms
+                ),
+                data: Some(serde_json::json!(RateLimitedErrorData { retry_after_ms })),
+            },
+            RpcCustomError::MethodDisabled { method } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_METHOD_DISABLED),
+                message: format!("Method {} is disabled on this node", method),
+                data: None,
+            },
         }
     }
 }
@@ -9,6 +9,7 @@ use {
 const MAX_DATA_SIZE: usize = 128;
 const MAX_DATA_BASE58_SIZE: usize = 175;
 const MAX_DATA_BASE64_SIZE: usize = 172;
+pub const MAX_MEMCMP_GROUP_SIZE: usize = 8;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,65 +17,33 @@ pub enum RpcFilterType {
     DataSize(u64),
     Memcmp(Memcmp),
     TokenAccountState,
+    /// Compare the little-endian u64 at an offset of the account data
+    U64Compare(U64Compare),
+    DataSizeRange(RpcFilterRange),
+    LamportsRange(RpcFilterRange),
+    /// Match if any of the memcmps match
+    AnyMemcmp(Vec<Memcmp>),
 }
 
 impl RpcFilterType {
     pub fn verify(&self) -> Result<(), RpcFilterError> {
         match self {
             RpcFilterType::DataSize(_) => Ok(()),
-            RpcFilterType::Memcmp(compare) => {
-                let encoding = compare.encoding.as_ref().unwrap_or(&MemcmpEncoding::Binary);
-                match encoding {
-                    MemcmpEncoding::Binary => {
-                        use MemcmpEncodedBytes::*;
-                        match &compare.bytes {
-                            // DEPRECATED
-                            Binary(bytes) => {
-                                if bytes.len() > MAX_DATA_BASE58_SIZE {
-                                    return Err(RpcFilterError::Base58DataTooLarge);
-                                }
-                                let bytes = bs58::decode(&bytes)
-                                    .into_vec()
-                                    .map_err(RpcFilterError::DecodeError)?;
-                                if bytes.len() > MAX_DATA_SIZE {
-                                    Err(RpcFilterError::Base58DataTooLarge)
-                                } else {
-                                    Ok(())
-                                }
-                            }
-                            Base58(bytes) => {
-                                if bytes.len() > MAX_DATA_BASE58_SIZE {
-                                    return Err(RpcFilterError::DataTooLarge);
-                                }
-                                let bytes = bs58::decode(&bytes).into_vec()?;
-                                if bytes.len() > MAX_DATA_SIZE {
-                                    Err(RpcFilterError::DataTooLarge)
-                                } else {
-                                    Ok(())
-                                }
-                            }
-                            Base64(bytes) => {
-                                if bytes.len() > MAX_DATA_BASE64_SIZE {
-                                    return Err(RpcFilterError::DataTooLarge);
-                                }
-                                let bytes = base64::decode(&bytes)?;
-                                if bytes.len() > MAX_DATA_SIZE {
-                                    Err(RpcFilterError::DataTooLarge)
-                                } else {
-                                    Ok(())
-                                }
-                            }
-                            Bytes(bytes) => {
-                                if bytes.len() > MAX_DATA_SIZE {
-                                    return Err(RpcFilterError::DataTooLarge);
-                                }
-                                Ok(())
-                            }
-                        }
-                    }
+            RpcFilterType::Memcmp(compare) => compare.verify(),
+            RpcFilterType::TokenAccountState => Ok(()),
+            RpcFilterType::U64Compare(_) => Ok(()),
+            RpcFilterType::DataSizeRange(range) | RpcFilterType::LamportsRange(range) => {
+                range.verify()
+            }
+            RpcFilterType::AnyMemcmp(compares) => {
+                if compares.is_empty() {
+                    return Err(RpcFilterError::EmptyMemcmpGroup);
+                }
+                if compares.len() > MAX_MEMCMP_GROUP_SIZE {
+                    return Err(RpcFilterError::TooManyMemcmps);
                 }
+                compares.iter().try_for_each(Memcmp::verify)
             }
-            RpcFilterType::TokenAccountState => Ok(()),
         }
     }
 
@@ -83,6 +52,12 @@ impl RpcFilterType {
             RpcFilterType::DataSize(size) => account.data().len() as u64 == *size,
             RpcFilterType::Memcmp(compare) => compare.bytes_match(account.data()),
             RpcFilterType::TokenAccountState => Account::valid_account_data(account.data()),
+            RpcFilterType::U64Compare(compare) => compare.value_matches(account.data()),
+            RpcFilterType::DataSizeRange(range) => range.contains(account.data().len() as u64),
+            RpcFilterType::LamportsRange(range) => range.contains(account.lamports()),
+            RpcFilterType::AnyMemcmp(compares) => compares
+                .iter()
+                .any(|compare| compare.bytes_match(account.data())),
         }
     }
 }
@@ -107,6 +82,12 @@ pub enum RpcFilterError {
     Base58DecodeError(#[from] bs58::decode::Error),
     #[error("base64 decode error")]
     Base64DecodeError(#[from] base64::DecodeError),
+    #[error("range should have a bound, and min should not exceed max")]
+    InvalidRange,
+    #[error("memcmp group should not be empty")]
+    EmptyMemcmpGroup,
+    #[error("memcmp group should have at most 8 memcmps")]
+    TooManyMemcmps,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -139,6 +120,59 @@ pub struct Memcmp {
 }
 
 impl Memcmp {
+    pub fn verify(&self) -> Result<(), RpcFilterError> {
+        let encoding = self.encoding.as_ref().unwrap_or(&MemcmpEncoding::Binary);
+        match encoding {
+            MemcmpEncoding::Binary => {
+                use MemcmpEncodedBytes::*;
+                match &self.bytes {
+                    // DEPRECATED
+                    Binary(bytes) => {
+                        if bytes.len() > MAX_DATA_BASE58_SIZE {
+                            return Err(RpcFilterError::Base58DataTooLarge);
+                        }
+                        let bytes = bs58::decode(&bytes)
+                            .into_vec()
+                            .map_err(RpcFilterError::DecodeError)?;
+                        if bytes.len() > MAX_DATA_SIZE {
+                            Err(RpcFilterError::Base58DataTooLarge)
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    Base58(bytes) => {
+                        if bytes.len() > MAX_DATA_BASE58_SIZE {
+                            return Err(RpcFilterError::DataTooLarge);
+                        }
+                        let bytes = bs58::decode(&bytes).into_vec()?;
+                        if bytes.len() > MAX_DATA_SIZE {
+                            Err(RpcFilterError::DataTooLarge)
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    Base64(bytes) => {
+                        if bytes.len() > MAX_DATA_BASE64_SIZE {
+                            return Err(RpcFilterError::DataTooLarge);
+                        }
+                        let bytes = base64::decode(&bytes)?;
+                        if bytes.len() > MAX_DATA_SIZE {
+                            Err(RpcFilterError::DataTooLarge)
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    Bytes(bytes) => {
+                        if bytes.len() > MAX_DATA_SIZE {
+                            return Err(RpcFilterError::DataTooLarge);
+                        }
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+
     pub fn bytes(&self) -> Option<Cow<Vec<u8>>> {
         use MemcmpEncodedBytes::*;
         match &self.bytes {
@@ -164,6 +198,60 @@ impl Memcmp {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum U64Comparison {
+    GreaterThan,
+    LessThan,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct U64Compare {
+    /// Data offset of the little-endian u64
+    pub offset: usize,
+    pub comparison: U64Comparison,
+    pub value: u64,
+}
+
+impl U64Compare {
+    pub fn value_matches(&self, data: &[u8]) -> bool {
+        let field = match self
+            .offset
+            .checked_add(8)
+            .and_then(|end| data.get(self.offset..end))
+        {
+            Some(field) => u64::from_le_bytes(field.try_into().unwrap()),
+            None => return false,
+        };
+        match self.comparison {
+            U64Comparison::GreaterThan => field > self.value,
+            U64Comparison::LessThan => field < self.value,
+        }
+    }
+}
+
+/// An inclusive range; a missing bound is unbounded
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RpcFilterRange {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl RpcFilterRange {
+    pub fn verify(&self) -> Result<(), RpcFilterError> {
+        match (self.min, self.max) {
+            (None, None) => Err(RpcFilterError::InvalidRange),
+            (Some(min), Some(max)) if min > max => Err(RpcFilterError::InvalidRange),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        self.min.map(|min| value >= min).unwrap_or(true)
+            && self.max.map(|max| value <= max).unwrap_or(true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -269,4 +357,72 @@ mod tests {
             Err(RpcFilterError::DataTooLarge)
         );
     }
+
+    #[test]
+    fn test_u64_compare() {
+        let mut data = vec![0; 4];
+        data.extend_from_slice(&100u64.to_le_bytes());
+        let compare = |comparison, value| U64Compare {
+            offset: 4,
+            comparison,
+            value,
+        };
+        assert!(compare(U64Comparison::GreaterThan, 99).value_matches(&data));
+        assert!(!compare(U64Comparison::GreaterThan, 100).value_matches(&data));
+        assert!(compare(U64Comparison::LessThan, 101).value_matches(&data));
+        assert!(!compare(U64Comparison::LessThan, 100).value_matches(&data));
+
+        // Field overrunning data fails
+        assert!(!compare(U64Comparison::LessThan, 101).value_matches(&data[..11]));
+        assert!(!U64Compare {
+            offset: usize::MAX,
+            comparison: U64Comparison::GreaterThan,
+            value: 0,
+        }
+        .value_matches(&data));
+    }
+
+    #[test]
+    fn test_verify_filter_range_and_memcmp_group() {
+        let range = |min, max| RpcFilterRange { min, max };
+        assert_eq!(range(Some(1), Some(1)).verify(), Ok(()));
+        assert_eq!(range(None, Some(1)).verify(), Ok(()));
+        assert_eq!(
+            range(None, None).verify(),
+            Err(RpcFilterError::InvalidRange)
+        );
+        assert_eq!(
+            RpcFilterType::LamportsRange(range(Some(2), Some(1))).verify(),
+            Err(RpcFilterError::InvalidRange)
+        );
+        assert!(range(Some(1), Some(3)).contains(3));
+        assert!(!range(Some(1), Some(3)).contains(4));
+        assert!(range(None, Some(3)).contains(0));
+
+        let memcmp = Memcmp {
+            offset: 0,
+            bytes: MemcmpEncodedBytes::Bytes(vec![1]),
+            encoding: None,
+        };
+        assert_eq!(
+            RpcFilterType::AnyMemcmp(vec![]).verify(),
+            Err(RpcFilterError::EmptyMemcmpGroup)
+        );
+        assert_eq!(
+            RpcFilterType::AnyMemcmp(vec![memcmp.clone(); MAX_MEMCMP_GROUP_SIZE]).verify(),
+            Ok(())
+        );
+        assert_eq!(
+            RpcFilterType::AnyMemcmp(vec![memcmp.clone(); MAX_MEMCMP_GROUP_SIZE + 1]).verify(),
+            Err(RpcFilterError::TooManyMemcmps)
+        );
+        let too_large = Memcmp {
+            bytes: MemcmpEncodedBytes::Bytes(vec![1; MAX_DATA_SIZE + 1]),
+            ..memcmp.clone()
+        };
+        assert_eq!(
+            RpcFilterType::AnyMemcmp(vec![memcmp, too_large]).verify(),
+            Err(RpcFilterError::DataTooLarge)
+        );
+    }
 }
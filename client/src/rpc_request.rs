@@ -198,12 +198,26 @@ pub const MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT: usize = 1_000;
 pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
 pub const NUM_LARGEST_ACCOUNTS: usize = 20;
 pub const MAX_GET_PROGRAM_ACCOUNT_FILTERS: usize = 4;
+pub const MAX_GET_PROGRAM_ACCOUNTS_PAGE_SIZE: usize = 10_000;
 pub const MAX_GET_SLOT_LEADERS: usize = 5000;
 
 // Validators that are this number of slots behind are considered delinquent
 pub const DELINQUENT_VALIDATOR_SLOT_DISTANCE: u64 = 128;
 
+// Request header asking the server to stream the response body as it is serialized
+pub const STREAM_RESPONSE_HEADER: &str = "solana-stream-response";
+
 impl RpcRequest {
+    /// Whether the server may stream the response, which can be too large to buffer comfortably
+    pub fn has_streamed_response(self) -> bool {
+        matches!(
+            self,
+            RpcRequest::GetBlock
+                | RpcRequest::GetProgramAccounts
+                | RpcRequest::GetSignaturesForAddress
+        )
+    }
+
     pub(crate) fn build_request_json(self, id: u64, params: Value) -> Value {
         let jsonrpc = "2.0";
         json!({
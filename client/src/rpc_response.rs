@@ -125,6 +125,15 @@ pub struct RpcKeyedAccount {
     pub account: UiAccount,
 }
 
+/// A page of the accounts returned by `getProgramAccounts`, in pubkey order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcKeyedAccountsPage {
+    pub accounts: Vec<RpcKeyedAccount>,
+    /// Cursor to the next page, None on the last page
+    pub cursor: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub struct SlotInfo {
     pub slot: Slot,
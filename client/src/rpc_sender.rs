@@ -2,7 +2,7 @@
 use {
     crate::{client_error::Result, rpc_request::RpcRequest},
     async_trait::async_trait,
-    std::time::Duration,
+    std::{io::Read, time::Duration},
 };
 
 #[derive(Default, Clone)]
@@ -31,6 +31,17 @@ pub trait RpcSender {
         request: RpcRequest,
         params: serde_json::Value,
     ) -> Result<serde_json::Value>;
+    /// Send a request whose response the node may stream, see
+    /// [`RpcRequest::has_streamed_response`], and return a reader of the response body as it
+    /// arrives, so that the caller deserializes it without buffering all of it. Senders that
+    /// don't stream return `None`, and the request is sent with `send` instead.
+    async fn send_streamed(
+        &self,
+        _request: RpcRequest,
+        _params: serde_json::Value,
+    ) -> Result<Option<Box<dyn Read + Send>>> {
+        Ok(None)
+    }
     fn get_transport_stats(&self) -> RpcTransportStats;
     fn url(&self) -> String;
 }
@@ -0,0 +1,519 @@
+//! The `bank_diff_service` writes the accounts modified in each rooted slot to disk, so
+//! downstream systems can reconcile account state without running a Geyser plugin.
+//!
+//! There is one file per epoch, named `epoch-<epoch>.bankdiff`. It starts with a header and
+//! then has a block for each rooted slot, in slot order. A block stores its records by column:
+//!
+//! * slot: u64
+//! * number of records: u32
+//! * number of distinct owners: u32
+//! * the distinct owners: 32 bytes each
+//! * pubkeys: 32 bytes each
+//! * owners, as indexes into the distinct owners: u32 each
+//! * lamports: u64 each
+//! * hashes of the account data: 32 bytes each
+//! * write versions: u64 each
+//!
+//! Integers are little endian. Slots rooted while the local ledger is replayed at startup
+//! are not written.
+//!
+//! Replay captures the diff of a slot as it roots the slot, while its accounts are still in the
+//! accounts cache: flush, clean and shrink cannot have touched them yet, and the write versions
+//! are the ones assigned when the accounts were stored. The diffs are then written on the
+//! service's thread. If it falls `MAX_QUEUED_SLOT_DIFFS` slots behind, replay waits for it.
+use {
+    crossbeam_channel::{Receiver, RecvTimeoutError, Sender},
+    solana_measure::measure::Measure,
+    solana_runtime::{accounts_db::LoadedAccount, bank::Bank},
+    solana_sdk::{
+        account::ReadableAccount,
+        clock::{Epoch, Slot},
+        hash::{hash, Hash},
+        pubkey::Pubkey,
+    },
+    std::{
+        collections::HashMap,
+        convert::TryInto,
+        fs::{self, File, OpenOptions},
+        io::{self, Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread::{self, Builder, JoinHandle},
+        time::Duration,
+    },
+};
+
+pub type BankDiffReceiver = Receiver<(Epoch, SlotDiff)>;
+pub type BankDiffSender = Sender<(Epoch, SlotDiff)>;
+
+/// The capacity of the channel to the service
+pub const MAX_QUEUED_SLOT_DIFFS: usize = 1024;
+
+const BANK_DIFF_MAGIC: &[u8; 4] = b"BDIF";
+const BANK_DIFF_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 8;
+const WRITE_BANK_DIFF_WARNING_MS: u64 = 500;
+// A failed write is retried this many times before the service stops
+const MAX_WRITE_RETRIES: usize = 10;
+const WRITE_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// An account modified in a rooted slot
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BankDiffRecord {
+    pub pubkey: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data_hash: Hash,
+    pub write_version: u64,
+}
+
+/// The accounts modified in a rooted slot, sorted by pubkey
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SlotDiff {
+    pub slot: Slot,
+    pub records: Vec<BankDiffRecord>,
+}
+
+impl SlotDiff {
+    /// The accounts modified in the slot of `bank`. Call this before the slot is flushed from
+    /// the accounts cache, flushing assigns new write versions.
+    pub fn new_from_bank(bank: &Bank) -> Self {
+        let mut records = bank.accounts().scan_slot(bank.slot(), |loaded_account| {
+            let write_version = match &loaded_account {
+                LoadedAccount::Stored(stored_account) => stored_account.meta.write_version,
+                LoadedAccount::Cached(cached_account) => cached_account.write_version(),
+            };
+            Some(BankDiffRecord {
+                pubkey: *loaded_account.pubkey(),
+                owner: *loaded_account.owner(),
+                lamports: loaded_account.lamports(),
+                data_hash: hash(loaded_account.data()),
+                write_version,
+            })
+        });
+        records.sort_unstable_by(|a, b| a.pubkey.cmp(&b.pubkey));
+        Self {
+            slot: bank.slot(),
+            records,
+        }
+    }
+
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        let mut owners = Vec::new();
+        let mut owner_indexes = HashMap::new();
+        let record_owner_indexes: Vec<u32> = self
+            .records
+            .iter()
+            .map(|record| {
+                *owner_indexes.entry(record.owner).or_insert_with(|| {
+                    owners.push(record.owner);
+                    (owners.len() - 1) as u32
+                })
+            })
+            .collect();
+
+        buffer.extend_from_slice(&self.slot.to_le_bytes());
+        buffer.extend_from_slice(&(self.records.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(&(owners.len() as u32).to_le_bytes());
+        owners
+            .iter()
+            .for_each(|owner| buffer.extend_from_slice(owner.as_ref()));
+        self.records
+            .iter()
+            .for_each(|record| buffer.extend_from_slice(record.pubkey.as_ref()));
+        record_owner_indexes
+            .iter()
+            .for_each(|index| buffer.extend_from_slice(&index.to_le_bytes()));
+        self.records
+            .iter()
+            .for_each(|record| buffer.extend_from_slice(&record.lamports.to_le_bytes()));
+        self.records
+            .iter()
+            .for_each(|record| buffer.extend_from_slice(record.data_hash.as_ref()));
+        self.records
+            .iter()
+            .for_each(|record| buffer.extend_from_slice(&record.write_version.to_le_bytes()));
+    }
+
+    /// Decode the block at the start of `data`, returning it and its size.
+    /// None if `data` ends before the block does.
+    fn decode(data: &[u8]) -> Option<(Self, usize)> {
+        let mut cursor = Cursor { data, offset: 0 };
+        let slot = cursor.read_u64()?;
+        let num_records = cursor.read_u32()? as usize;
+        let num_owners = cursor.read_u32()? as usize;
+        let owners = (0..num_owners)
+            .map(|_| cursor.read_pubkey())
+            .collect::<Option<Vec<_>>>()?;
+        let pubkeys = (0..num_records)
+            .map(|_| cursor.read_pubkey())
+            .collect::<Option<Vec<_>>>()?;
+        let record_owners = (0..num_records)
+            .map(|_| owners.get(cursor.read_u32()? as usize).copied())
+            .collect::<Option<Vec<_>>>()?;
+        let lamports = (0..num_records)
+            .map(|_| cursor.read_u64())
+            .collect::<Option<Vec<_>>>()?;
+        let data_hashes = (0..num_records)
+            .map(|_| cursor.read(32).map(Hash::new))
+            .collect::<Option<Vec<_>>>()?;
+        let write_versions = (0..num_records)
+            .map(|_| cursor.read_u64())
+            .collect::<Option<Vec<_>>>()?;
+
+        let records = (0..num_records)
+            .map(|i| BankDiffRecord {
+                pubkey: pubkeys[i],
+                owner: record_owners[i],
+                lamports: lamports[i],
+                data_hash: data_hashes[i],
+                write_version: write_versions[i],
+            })
+            .collect();
+        Some((Self { slot, records }, cursor.offset))
+    }
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn read(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset.checked_add(len)?)?;
+        self.offset += len;
+        Some(bytes)
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        self.read(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Option<u64> {
+        self.read(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_pubkey(&mut self) -> Option<Pubkey> {
+        self.read(32).map(Pubkey::new)
+    }
+}
+
+/// Decode the blocks of a bank diff file. Also return the size of the complete blocks,
+/// a block cut short by a crash while it was written is left out.
+fn decode_bank_diff_file(data: &[u8]) -> io::Result<(Vec<SlotDiff>, usize)> {
+    if data.len() < HEADER_SIZE
+        || &data[..4] != BANK_DIFF_MAGIC
+        || u32::from_le_bytes(data[4..HEADER_SIZE].try_into().unwrap()) != BANK_DIFF_VERSION
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a bank diff file",
+        ));
+    }
+    let mut offset = HEADER_SIZE;
+    let mut slot_diffs = Vec::new();
+    while let Some((slot_diff, size)) = SlotDiff::decode(&data[offset..]) {
+        slot_diffs.push(slot_diff);
+        offset += size;
+    }
+    Ok((slot_diffs, offset))
+}
+
+/// Read the slot diffs of a bank diff file, in slot order
+pub fn read_bank_diff_file(path: &Path) -> io::Result<Vec<SlotDiff>> {
+    decode_bank_diff_file(&fs::read(path)?).map(|(slot_diffs, _)| slot_diffs)
+}
+
+pub fn bank_diff_file_path(bank_diff_path: &Path, epoch: Epoch) -> PathBuf {
+    bank_diff_path.join(format!("epoch-{}.bankdiff", epoch))
+}
+
+/// Appends slot diffs to the file of their epoch
+struct BankDiffWriter {
+    bank_diff_path: PathBuf,
+    /// the file being appended to and its epoch
+    file: Option<(Epoch, File)>,
+    /// the highest slot in the file
+    last_slot: Option<Slot>,
+    buffer: Vec<u8>,
+}
+
+impl BankDiffWriter {
+    fn new(bank_diff_path: PathBuf) -> Self {
+        Self {
+            bank_diff_path,
+            file: None,
+            last_slot: None,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn open(&mut self, epoch: Epoch) -> io::Result<()> {
+        fs::create_dir_all(&self.bank_diff_path)?;
+        let path = bank_diff_file_path(&self.bank_diff_path, epoch);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&path)?;
+        if file.metadata()?.len() < HEADER_SIZE as u64 {
+            file.set_len(0)?;
+            file.write_all(BANK_DIFF_MAGIC)?;
+            file.write_all(&BANK_DIFF_VERSION.to_le_bytes())?;
+            self.last_slot = None;
+        } else {
+            // Written before a restart. Drop any partial block at the end, and remember the
+            // last slot so slots rooted again after the restart are not written twice.
+            let (slot_diffs, len) = decode_bank_diff_file(&fs::read(&path)?)?;
+            file.set_len(len as u64)?;
+            file.seek(SeekFrom::End(0))?;
+            self.last_slot = slot_diffs.last().map(|slot_diff| slot_diff.slot);
+        }
+        self.file = Some((epoch, file));
+        Ok(())
+    }
+
+    fn write(&mut self, epoch: Epoch, slot_diff: &SlotDiff) -> io::Result<()> {
+        let result = self.try_write(epoch, slot_diff);
+        if result.is_err() {
+            // Reopen the file on the next write, which drops a partly written block
+            self.file = None;
+        }
+        result
+    }
+
+    /// Write `slot_diff`, retrying failed writes. Gives up early if `exit` is set.
+    fn write_with_retries(
+        &mut self,
+        epoch: Epoch,
+        slot_diff: &SlotDiff,
+        exit: &AtomicBool,
+    ) -> io::Result<()> {
+        let mut retries = 0;
+        loop {
+            match self.write(epoch, slot_diff) {
+                Ok(()) => return Ok(()),
+                Err(err) if retries < MAX_WRITE_RETRIES && !exit.load(Ordering::Relaxed) => {
+                    warn!(
+                        "write bank diff failed, retrying: slot {} {:?}",
+                        slot_diff.slot, err
+                    );
+                    retries += 1;
+                    thread::sleep(WRITE_RETRY_DELAY);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn try_write(&mut self, epoch: Epoch, slot_diff: &SlotDiff) -> io::Result<()> {
+        if self.file.as_ref().map(|(file_epoch, _)| *file_epoch) != Some(epoch) {
+            self.file = None;
+            self.open(epoch)?;
+        }
+        if self
+            .last_slot
+            .map(|last_slot| slot_diff.slot <= last_slot)
+            .unwrap_or(false)
+        {
+            return Ok(());
+        }
+        self.buffer.clear();
+        slot_diff.encode(&mut self.buffer);
+        let (_, file) = self.file.as_mut().unwrap();
+        file.write_all(&self.buffer)?;
+        self.last_slot = Some(slot_diff.slot);
+        Ok(())
+    }
+}
+
+pub struct BankDiffService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl BankDiffService {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        bank_diff_receiver: BankDiffReceiver,
+        bank_diff_path: PathBuf,
+        exit: &Arc<AtomicBool>,
+    ) -> Self {
+        let exit = exit.clone();
+        let mut writer = BankDiffWriter::new(bank_diff_path);
+        let thread_hdl = Builder::new()
+            .name("solana-bank-diff".to_string())
+            .spawn(move || loop {
+                if exit.load(Ordering::Relaxed) {
+                    break;
+                }
+                match bank_diff_receiver.recv_timeout(Duration::from_secs(1)) {
+                    Err(RecvTimeoutError::Disconnected) => {
+                        break;
+                    }
+                    Ok((epoch, slot_diff)) => {
+                        let mut write_bank_diff_timer = Measure::start("write_bank_diff_timer");
+                        if let Err(err) = writer.write_with_retries(epoch, &slot_diff, &exit) {
+                            // Skipping the slot would leave a gap in the diffs, stop instead
+                            error!(
+                                "write bank diff failed, stopping the bank diff service: slot {} {:?}",
+                                slot_diff.slot, err
+                            );
+                            break;
+                        }
+                        write_bank_diff_timer.stop();
+                        if write_bank_diff_timer.as_ms() > WRITE_BANK_DIFF_WARNING_MS {
+                            warn!(
+                                "write bank diff operation took: {}ms",
+                                write_bank_diff_timer.as_ms()
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crossbeam_channel::bounded,
+        solana_runtime::genesis_utils::create_genesis_config,
+        solana_sdk::account::{AccountSharedData, WritableAccount},
+        tempfile::TempDir,
+    };
+
+    fn new_slot_diff(slot: Slot, owners: &[Pubkey]) -> SlotDiff {
+        SlotDiff {
+            slot,
+            records: owners
+                .iter()
+                .enumerate()
+                .map(|(i, owner)| BankDiffRecord {
+                    pubkey: Pubkey::new_unique(),
+                    owner: *owner,
+                    lamports: i as u64,
+                    data_hash: hash(&[i as u8]),
+                    write_version: slot + i as u64,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_bank_diff_writer() {
+        let temp_dir = TempDir::new().unwrap();
+        let owner = Pubkey::new_unique();
+        let slot_diffs = vec![
+            new_slot_diff(1, &[owner, Pubkey::new_unique(), owner]),
+            new_slot_diff(2, &[]),
+            new_slot_diff(3, &[Pubkey::new_unique()]),
+        ];
+        let mut writer = BankDiffWriter::new(temp_dir.path().to_path_buf());
+        for slot_diff in &slot_diffs {
+            writer.write(0, slot_diff).unwrap();
+        }
+        writer.write(1, &slot_diffs[0]).unwrap();
+
+        let path = bank_diff_file_path(temp_dir.path(), 0);
+        assert_eq!(read_bank_diff_file(&path).unwrap(), slot_diffs);
+        assert_eq!(
+            read_bank_diff_file(&bank_diff_file_path(temp_dir.path(), 1)).unwrap(),
+            slot_diffs[..1].to_vec()
+        );
+
+        // a partial block is dropped on restart, and slots written before are not written again
+        let len = fs::metadata(&path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 1)
+            .unwrap();
+        let mut writer = BankDiffWriter::new(temp_dir.path().to_path_buf());
+        let slot_diff = new_slot_diff(4, &[owner]);
+        for slot_diff in slot_diffs[1..].iter().chain(Some(&slot_diff)) {
+            writer.write(0, slot_diff).unwrap();
+        }
+        assert_eq!(
+            read_bank_diff_file(&path).unwrap(),
+            vec![
+                slot_diffs[0].clone(),
+                slot_diffs[1].clone(),
+                slot_diffs[2].clone(),
+                slot_diff
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bank_diff_service_stops_on_write_error() {
+        let temp_dir = TempDir::new().unwrap();
+        // a file where the directory of the bank diff files should be
+        let bank_diff_path = temp_dir.path().join("bank_diff");
+        fs::write(&bank_diff_path, b"").unwrap();
+        let (bank_diff_sender, bank_diff_receiver) = bounded(MAX_QUEUED_SLOT_DIFFS);
+        let exit = Arc::new(AtomicBool::new(false));
+        let service = BankDiffService::new(bank_diff_receiver, bank_diff_path, &exit);
+
+        bank_diff_sender
+            .send((0, new_slot_diff(1, &[Pubkey::new_unique()])))
+            .unwrap();
+        // the service gives up after retrying, without being asked to exit
+        service.join().unwrap();
+        assert!(!exit.load(Ordering::Relaxed));
+        assert!(bank_diff_sender
+            .send((0, new_slot_diff(2, &[Pubkey::new_unique()])))
+            .is_err());
+    }
+
+    #[test]
+    fn test_slot_diff_new_from_bank() {
+        let genesis_config_info = create_genesis_config(10_000);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config_info.genesis_config));
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        let owner = Pubkey::new_unique();
+        let pubkey0 = Pubkey::new_unique();
+        let pubkey1 = Pubkey::new_unique();
+        let mut account = AccountSharedData::new(42, 3, &owner);
+        account.set_data(vec![1, 2, 3]);
+        bank1.store_account(&pubkey0, &account);
+        bank1.store_account(&pubkey1, &AccountSharedData::new(7, 0, &owner));
+
+        let slot_diff = SlotDiff::new_from_bank(&bank1);
+        assert_eq!(slot_diff.slot, 1);
+        let find_record = |pubkey| {
+            slot_diff
+                .records
+                .iter()
+                .find(|record| record.pubkey == pubkey)
+                .unwrap()
+        };
+        let record0 = find_record(pubkey0);
+        assert_eq!(record0.owner, owner);
+        assert_eq!(record0.lamports, 42);
+        assert_eq!(record0.data_hash, hash(&[1, 2, 3]));
+        let record1 = find_record(pubkey1);
+        assert_eq!(record1.lamports, 7);
+        assert_eq!(record1.data_hash, hash(&[]));
+        assert!(record1.write_version > record0.write_version);
+        // nothing from the parent slot
+        assert!(SlotDiff::new_from_bank(&bank0)
+            .records
+            .iter()
+            .all(|record| record.pubkey != pubkey0));
+    }
+}
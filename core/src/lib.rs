@@ -9,6 +9,7 @@
 
 pub mod accounts_hash_verifier;
 pub mod ancestor_hashes_service;
+pub mod bank_diff_service;
 pub mod banking_stage;
 pub mod broadcast_stage;
 pub mod cache_block_meta_service;
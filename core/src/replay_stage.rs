@@ -2,6 +2,7 @@
 use {
     crate::{
         ancestor_hashes_service::AncestorHashesReplayUpdateSender,
+        bank_diff_service::{BankDiffSender, SlotDiff},
         broadcast_stage::RetransmitSlotsSender,
         cache_block_meta_service::CacheBlockMetaSender,
         cluster_info_vote_listener::{
@@ -35,6 +36,7 @@ use {
         block_error::BlockError,
         blockstore::Blockstore,
         blockstore_processor::{self, BlockstoreProcessorError, TransactionStatusSender},
+        entry_notifier_interface::EntryNotifierLock,
         leader_schedule_cache::LeaderScheduleCache,
         leader_schedule_utils::first_of_consecutive_leader_slots,
     },
@@ -135,6 +137,7 @@ pub struct ReplayStageConfig {
     pub transaction_status_sender: Option<TransactionStatusSender>,
     pub rewards_recorder_sender: Option<RewardsRecorderSender>,
     pub cache_block_meta_sender: Option<CacheBlockMetaSender>,
+    pub bank_diff_sender: Option<BankDiffSender>,
     pub bank_notification_sender: Option<BankNotificationSender>,
     pub wait_for_vote_to_start_leader: bool,
     pub ancestor_hashes_replay_update_sender: AncestorHashesReplayUpdateSender,
@@ -367,6 +370,7 @@ impl ReplayStage {
         voting_sender: Sender<VoteOp>,
         drop_bank_sender: Sender<Vec<Arc<Bank>>>,
         block_metadata_notifier: Option<BlockMetadataNotifierLock>,
+        entry_notifier: Option<EntryNotifierLock>,
         transaction_cost_metrics_sender: Option<TransactionCostMetricsSender>,
     ) -> Self {
         let mut tower = tower.into();
@@ -384,6 +388,7 @@ impl ReplayStage {
             transaction_status_sender,
             rewards_recorder_sender,
             cache_block_meta_sender,
+            bank_diff_sender,
             bank_notification_sender,
             wait_for_vote_to_start_leader,
             ancestor_hashes_replay_update_sender,
@@ -488,6 +493,7 @@ impl ReplayStage {
                         &mut duplicate_slots_to_repair,
                         &ancestor_hashes_replay_update_sender,
                         block_metadata_notifier.clone(),
+                        entry_notifier.as_ref(),
                         transaction_cost_metrics_sender.as_ref(),
                     );
                     replay_active_banks_time.stop();
@@ -694,6 +700,7 @@ impl ReplayStage {
                             &block_commitment_cache,
                             &mut heaviest_subtree_fork_choice,
                             &bank_notification_sender,
+                            &bank_diff_sender,
                             &mut duplicate_slots_tracker,
                             &mut gossip_duplicate_confirmed_slots,
                             &mut unfrozen_gossip_verified_vote_hashes,
@@ -1626,6 +1633,7 @@ impl ReplayStage {
         transaction_status_sender: Option<&TransactionStatusSender>,
         replay_vote_sender: &ReplayVoteSender,
         transaction_cost_metrics_sender: Option<&TransactionCostMetricsSender>,
+        entry_notifier: Option<&EntryNotifierLock>,
         verify_recyclers: &VerifyRecyclers,
     ) -> result::Result<usize, BlockstoreProcessorError> {
         let tx_count_before = bank_progress.replay_progress.num_txs;
@@ -1642,6 +1650,8 @@ impl ReplayStage {
             Some(replay_vote_sender),
             transaction_cost_metrics_sender,
             None,
+            entry_notifier,
+            None,
             verify_recyclers,
             false,
         )?;
@@ -1740,6 +1750,7 @@ impl ReplayStage {
         block_commitment_cache: &Arc<RwLock<BlockCommitmentCache>>,
         heaviest_subtree_fork_choice: &mut HeaviestSubtreeForkChoice,
         bank_notification_sender: &Option<BankNotificationSender>,
+        bank_diff_sender: &Option<BankDiffSender>,
         duplicate_slots_tracker: &mut DuplicateSlotsTracker,
         gossip_duplicate_confirmed_slots: &mut GossipDuplicateConfirmedSlots,
         unfrozen_gossip_verified_vote_hashes: &mut UnfrozenGossipVerifiedVoteHashes,
@@ -1770,6 +1781,7 @@ impl ReplayStage {
                 .get(new_root)
                 .expect("Root bank doesn't exist")
                 .clone();
+            let old_root = bank_forks.read().unwrap().root();
             let mut rooted_banks = root_bank.parents();
             rooted_banks.push(root_bank.clone());
             let rooted_slots: Vec<_> = rooted_banks.iter().map(|bank| bank.slot()).collect();
@@ -1781,6 +1793,20 @@ impl ReplayStage {
             blockstore
                 .set_roots(rooted_slots.iter())
                 .expect("Ledger set roots failed");
+            if let Some(sender) = bank_diff_sender {
+                // Capture the new roots before they can be flushed from the accounts cache.
+                // `rooted_banks` also has the old root, which was already sent.
+                let mut new_rooted_banks: Vec<_> = rooted_banks
+                    .iter()
+                    .filter(|bank| bank.slot() > old_root)
+                    .collect();
+                new_rooted_banks.sort_unstable_by_key(|bank| bank.slot());
+                for bank in new_rooted_banks {
+                    sender
+                        .send((bank.epoch(), SlotDiff::new_from_bank(bank)))
+                        .unwrap_or_else(|err| warn!("bank_diff_sender failed: {:?}", err));
+                }
+            }
             let highest_confirmed_root = Some(
                 block_commitment_cache
                     .read()
@@ -2140,6 +2166,7 @@ impl ReplayStage {
         duplicate_slots_to_repair: &mut DuplicateSlotsToRepair,
         ancestor_hashes_replay_update_sender: &AncestorHashesReplayUpdateSender,
         block_metadata_notifier: Option<BlockMetadataNotifierLock>,
+        entry_notifier: Option<&EntryNotifierLock>,
         transaction_cost_metrics_sender: Option<&TransactionCostMetricsSender>,
     ) -> bool {
         let mut did_complete_bank = false;
@@ -2191,6 +2218,7 @@ impl ReplayStage {
                     transaction_status_sender,
                     replay_vote_sender,
                     transaction_cost_metrics_sender,
+                    entry_notifier,
                     verify_recyclers,
                 );
                 match replay_result {
@@ -3823,6 +3851,7 @@ pub mod tests {
                 None,
                 &replay_vote_sender,
                 None,
+                None,
                 &VerifyRecyclers::default(),
             );
             let max_complete_transaction_status_slot = Arc::new(AtomicU64::default());
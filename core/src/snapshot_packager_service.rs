@@ -82,6 +82,10 @@ impl SnapshotPackagerService {
                     // Archiving the snapshot package is not allowed to fail.
                     // AccountsBackgroundService calls `clean_accounts()` with a value for
                     // last_full_snapshot_slot that requires this archive call to succeed.
+                    //
+                    // Archiving also purges the old snapshot archives.  The incremental snapshot
+                    // archives that a retained incremental snapshot archive depends on in its
+                    // chain are retained too, even beyond the maximum to retain.
                     snapshot_utils::archive_snapshot_package(
                         &snapshot_package,
                         snapshot_config.maximum_full_snapshot_archives_to_retain,
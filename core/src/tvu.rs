@@ -3,6 +3,7 @@
 
 use {
     crate::{
+        bank_diff_service::BankDiffSender,
         broadcast_stage::RetransmitSlotsSender,
         cache_block_meta_service::CacheBlockMetaSender,
         cluster_info_vote_listener::{
@@ -30,7 +31,7 @@ use {
     solana_gossip::cluster_info::ClusterInfo,
     solana_ledger::{
         blockstore::Blockstore, blockstore_processor::TransactionStatusSender,
-        leader_schedule_cache::LeaderScheduleCache,
+        entry_notifier_interface::EntryNotifierLock, leader_schedule_cache::LeaderScheduleCache,
     },
     solana_poh::poh_recorder::PohRecorder,
     solana_rpc::{
@@ -118,6 +119,7 @@ impl Tvu {
         transaction_status_sender: Option<TransactionStatusSender>,
         rewards_recorder_sender: Option<RewardsRecorderSender>,
         cache_block_meta_sender: Option<CacheBlockMetaSender>,
+        bank_diff_sender: Option<BankDiffSender>,
         vote_tracker: Arc<VoteTracker>,
         retransmit_slots_sender: RetransmitSlotsSender,
         gossip_verified_vote_hash_receiver: GossipVerifiedVoteHashReceiver,
@@ -130,6 +132,7 @@ impl Tvu {
         max_slots: &Arc<MaxSlots>,
         cost_model: &Arc<RwLock<CostModel>>,
         block_metadata_notifier: Option<BlockMetadataNotifierLock>,
+        entry_notifier: Option<EntryNotifierLock>,
         wait_to_vote_slot: Option<Slot>,
         accounts_background_request_sender: AbsRequestSender,
     ) -> Self {
@@ -208,6 +211,7 @@ impl Tvu {
             transaction_status_sender,
             rewards_recorder_sender,
             cache_block_meta_sender,
+            bank_diff_sender,
             bank_notification_sender,
             wait_for_vote_to_start_leader: tvu_config.wait_for_vote_to_start_leader,
             ancestor_hashes_replay_update_sender,
@@ -264,6 +268,7 @@ impl Tvu {
             voting_sender,
             drop_bank_sender,
             block_metadata_notifier,
+            entry_notifier,
             transaction_cost_metrics_sender,
         );
 
@@ -424,6 +429,7 @@ pub mod tests {
             None,
             None,
             None,
+            None,
             Arc::<VoteTracker>::default(),
             retransmit_slots_sender,
             gossip_verified_vote_hash_receiver,
@@ -437,6 +443,7 @@ pub mod tests {
             &Arc::new(RwLock::new(CostModel::default())),
             None,
             None,
+            None,
             AbsRequestSender::default(),
         );
         exit.store(true, Ordering::Relaxed);
@@ -4,6 +4,7 @@ pub use solana_perf::report_target_features;
 use {
     crate::{
         accounts_hash_verifier::AccountsHashVerifier,
+        bank_diff_service::{BankDiffService, MAX_QUEUED_SLOT_DIFFS},
         broadcast_stage::BroadcastStageType,
         cache_block_meta_service::{CacheBlockMetaSender, CacheBlockMetaService},
         cluster_info_vote_listener::VoteTracker,
@@ -26,7 +27,9 @@ use {
     crossbeam_channel::{bounded, unbounded, Receiver},
     rand::{thread_rng, Rng},
     solana_entry::poh::compute_hash_time_ns,
-    solana_geyser_plugin_manager::geyser_plugin_service::GeyserPluginService,
+    solana_geyser_plugin_manager::{
+        geyser_plugin_manager::GeyserPluginManager, geyser_plugin_service::GeyserPluginService,
+    },
     solana_gossip::{
         cluster_info::{
             ClusterInfo, Node, DEFAULT_CONTACT_DEBUG_INTERVAL_MILLIS,
@@ -43,6 +46,7 @@ use {
         },
         blockstore_db::{BlockstoreOptions, BlockstoreRecoveryMode, LedgerColumnOptions},
         blockstore_processor::{self, TransactionStatusSender},
+        entry_notifier_interface::EntryNotifierLock,
         leader_schedule::FixedSchedule,
         leader_schedule_cache::LeaderScheduleCache,
     },
@@ -174,6 +178,8 @@ pub struct ValidatorConfig {
     pub wait_to_vote_slot: Option<Slot>,
     pub ledger_column_options: LedgerColumnOptions,
     pub runtime_config: RuntimeConfig,
+    /// Write the accounts modified in each rooted slot to files under this directory
+    pub bank_diff_path: Option<PathBuf>,
 }
 
 impl Default for ValidatorConfig {
@@ -234,6 +240,7 @@ impl Default for ValidatorConfig {
             wait_to_vote_slot: None,
             ledger_column_options: LedgerColumnOptions::default(),
             runtime_config: RuntimeConfig::default(),
+            bank_diff_path: None,
         }
     }
 }
@@ -328,6 +335,7 @@ pub struct Validator {
     transaction_status_service: Option<TransactionStatusService>,
     rewards_recorder_service: Option<RewardsRecorderService>,
     cache_block_meta_service: Option<CacheBlockMetaService>,
+    bank_diff_service: Option<BankDiffService>,
     system_monitor_service: Option<SystemMonitorService>,
     sample_performance_service: Option<SamplePerformanceService>,
     poh_timing_report_service: PohTimingReportService,
@@ -397,8 +405,11 @@ impl Validator {
             if let Some(geyser_plugin_config_files) = &config.geyser_plugin_config_files {
                 let (confirmed_bank_sender, confirmed_bank_receiver) = unbounded();
                 bank_notification_senders.push(confirmed_bank_sender);
-                let result =
-                    GeyserPluginService::new(confirmed_bank_receiver, geyser_plugin_config_files);
+                let result = GeyserPluginService::new(
+                    confirmed_bank_receiver,
+                    geyser_plugin_config_files,
+                    config.validator_exit.clone(),
+                );
                 match result {
                     Ok(geyser_plugin_service) => Some(geyser_plugin_service),
                     Err(err) => {
@@ -482,14 +493,19 @@ impl Validator {
             .as_ref()
             .and_then(|geyser_plugin_service| geyser_plugin_service.get_transaction_notifier());
 
+        let entry_notifier = geyser_plugin_service
+            .as_ref()
+            .and_then(|geyser_plugin_service| geyser_plugin_service.get_entry_notifier());
+
         let block_metadata_notifier = geyser_plugin_service
             .as_ref()
             .and_then(|geyser_plugin_service| geyser_plugin_service.get_block_metadata_notifier());
 
         info!(
-            "Geyser plugin: accounts_update_notifier: {} transaction_notifier: {}",
+            "Geyser plugin: accounts_update_notifier: {} transaction_notifier: {} entry_notifier: {}",
             accounts_update_notifier.is_some(),
-            transaction_notifier.is_some()
+            transaction_notifier.is_some(),
+            entry_notifier.is_some()
         );
 
         let system_monitor_service = Some(SystemMonitorService::new(
@@ -529,6 +545,7 @@ impl Validator {
             &start_progress,
             accounts_update_notifier,
             transaction_notifier,
+            entry_notifier.clone(),
             Some(poh_timing_point_sender.clone()),
         );
 
@@ -692,6 +709,21 @@ impl Validator {
                 None
             };
 
+        let (bank_diff_sender, bank_diff_service) = match &config.bank_diff_path {
+            Some(bank_diff_path) => {
+                let (bank_diff_sender, bank_diff_receiver) = bounded(MAX_QUEUED_SLOT_DIFFS);
+                (
+                    Some(bank_diff_sender),
+                    Some(BankDiffService::new(
+                        bank_diff_receiver,
+                        bank_diff_path.clone(),
+                        &exit,
+                    )),
+                )
+            }
+            None => (None, None),
+        };
+
         let mut block_commitment_cache = BlockCommitmentCache::default();
         block_commitment_cache.initialize_slots(
             bank_forks.read().unwrap().working_bank().slot(),
@@ -940,6 +972,7 @@ impl Validator {
             transaction_status_sender.clone(),
             rewards_recorder_sender,
             cache_block_meta_sender,
+            bank_diff_sender,
             vote_tracker.clone(),
             retransmit_slots_sender,
             gossip_verified_vote_hash_receiver,
@@ -959,6 +992,7 @@ impl Validator {
             &max_slots,
             &cost_model,
             block_metadata_notifier,
+            entry_notifier,
             config.wait_to_vote_slot,
             accounts_background_request_sender,
         );
@@ -1008,6 +1042,7 @@ impl Validator {
             transaction_status_service,
             rewards_recorder_service,
             cache_block_meta_service,
+            bank_diff_service,
             system_monitor_service,
             sample_performance_service,
             poh_timing_report_service,
@@ -1067,7 +1102,22 @@ impl Validator {
         );
     }
 
+    /// The manager of the Geyser plugins, if the validator was started with any
+    pub fn geyser_plugin_manager(&self) -> Option<Arc<RwLock<GeyserPluginManager>>> {
+        self.geyser_plugin_service
+            .as_ref()
+            .map(|geyser_plugin_service| geyser_plugin_service.plugin_manager())
+    }
+
     pub fn join(self) {
+        self.bank_forks
+            .read()
+            .unwrap()
+            .root_bank()
+            .rc
+            .accounts
+            .accounts_db
+            .stop_secondary_index_rebuild();
         drop(self.bank_forks);
         drop(self.cluster_info);
 
@@ -1112,6 +1162,10 @@ impl Validator {
                 .expect("cache_block_meta_service");
         }
 
+        if let Some(bank_diff_service) = self.bank_diff_service {
+            bank_diff_service.join().expect("bank_diff_service");
+        }
+
         if let Some(system_monitor_service) = self.system_monitor_service {
             system_monitor_service
                 .join()
@@ -1298,6 +1352,7 @@ fn load_blockstore(
     start_progress: &Arc<RwLock<ValidatorStartProgress>>,
     accounts_update_notifier: Option<AccountsUpdateNotifier>,
     transaction_notifier: Option<TransactionNotifierLock>,
+    entry_notifier: Option<EntryNotifierLock>,
     poh_timing_point_sender: Option<PohTimingSender>,
 ) -> (
     GenesisConfig,
@@ -1372,6 +1427,7 @@ fn load_blockstore(
         accounts_db_test_hash_calculation: config.accounts_db_test_hash_calculation,
         accounts_db_skip_shrink: config.accounts_db_skip_shrink,
         runtime_config: config.runtime_config.clone(),
+        entry_notifier,
         ..blockstore_processor::ProcessOptions::default()
     };
 
@@ -200,7 +200,7 @@ mod tests {
                 .unwrap()
                 .bank_snapshots_dir,
             &full_snapshot_archive_info,
-            None,
+            &[],
             old_genesis_config,
             None,
             None,
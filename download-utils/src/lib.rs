@@ -3,16 +3,27 @@ use {
     console::Emoji,
     indicatif::{ProgressBar, ProgressStyle},
     log::*,
+    solana_measure::measure::Measure,
     solana_runtime::{
+        snapshot_archive_info::{
+            FullSnapshotArchiveInfo, IncrementalSnapshotArchiveInfo, SnapshotArchiveInfoGetter,
+        },
         snapshot_package::SnapshotType,
         snapshot_utils::{self, ArchiveFormat},
     },
     solana_sdk::{clock::Slot, genesis_config::DEFAULT_GENESIS_ARCHIVE, hash::Hash},
     std::{
-        fs::{self, File},
-        io::{self, Read},
+        fs::{self, File, OpenOptions},
+        io::{self, BufWriter, Read, Write},
         net::SocketAddr,
+        ops::Range,
         path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            mpsc::{channel, RecvTimeoutError},
+            Arc,
+        },
+        thread::Builder,
         time::{Duration, Instant},
     },
 };
@@ -55,14 +66,46 @@ pub struct DownloadProgressRecord {
 type DownloadProgressCallback<'a> = Box<dyn FnMut(&DownloadProgressRecord) -> bool + 'a>;
 type DownloadProgressCallbackOption<'a> = Option<DownloadProgressCallback<'a>>;
 
+/// The maximum number of chunks of a snapshot archive downloaded in parallel
+pub const MAX_SNAPSHOT_DOWNLOAD_CHUNKS: usize = 8;
+
+/// Files are not split in chunks smaller than this when downloaded in parallel
+const MIN_DOWNLOAD_CHUNK_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The interval to poll the download progress of the chunks at
+const DOWNLOAD_PROGRESS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// This callback allows the caller to get notified of the download progress modelled by DownloadProgressRecord
 /// Return "true" to continue the download
 /// Return "false" to abort the download
+///
+/// The download is resumed from the partially downloaded temporary file of a previous attempt,
+/// if there is one and the server supports range requests.
 pub fn download_file<'a, 'b>(
     url: &str,
     destination_file: &Path,
     use_progress_bar: bool,
     progress_notify_callback: &'a mut DownloadProgressCallbackOption<'b>,
+) -> Result<(), String> {
+    download_file_in_chunks(
+        url,
+        destination_file,
+        1,
+        use_progress_bar,
+        progress_notify_callback,
+    )
+}
+
+/// Download a file in up to `max_chunks` chunks in parallel, using range requests.  Each chunk is
+/// downloaded to its own temporary file, so that an interrupted download resumes every chunk
+/// where it stopped, and the chunks are reassembled once they are all downloaded.  Falls back to
+/// a single chunk if the server does not support range requests.
+pub fn download_file_in_chunks<'a, 'b>(
+    url: &str,
+    destination_file: &Path,
+    max_chunks: usize,
+    use_progress_bar: bool,
+    progress_notify_callback: &'a mut DownloadProgressCallbackOption<'b>,
 ) -> Result<(), String> {
     if destination_file.is_file() {
         return Err(format!("{:?} already exists", destination_file));
@@ -72,144 +115,105 @@ pub fn download_file<'a, 'b>(
     fs::create_dir_all(destination_file.parent().expect("parent"))
         .map_err(|err| err.to_string())?;
 
-    let mut temp_destination_file = destination_file.to_path_buf();
-    temp_destination_file.set_file_name(format!(
-        "tmp-{}",
-        destination_file
-            .file_name()
-            .expect("file_name")
-            .to_str()
-            .expect("to_str")
-    ));
+    let temp_destination_file = build_temp_download_path(destination_file);
 
     let progress_bar = new_spinner_progress_bar();
     if use_progress_bar {
         progress_bar.set_message(format!("{}Downloading {}...", TRUCK, url));
     }
 
-    let response = reqwest::blocking::Client::new()
-        .get(url)
-        .send()
-        .and_then(|response| response.error_for_status())
-        .map_err(|err| {
+    let client = reqwest::blocking::Client::new();
+    let chunks = if max_chunks > 1 {
+        get_download_chunks(&client, url, &temp_destination_file, max_chunks).map_err(|err| {
             progress_bar.finish_and_clear();
-            err.to_string()
-        })?;
-
-    let download_size = {
-        response
-            .headers()
-            .get(reqwest::header::CONTENT_LENGTH)
-            .and_then(|content_length| content_length.to_str().ok())
-            .and_then(|content_length| content_length.parse().ok())
-            .unwrap_or(0)
-    };
-
-    if use_progress_bar {
-        progress_bar.set_length(download_size);
-        progress_bar.set_style(
-            ProgressStyle::default_bar()
-                .template(
-                    "{spinner:.green}{msg_wide}[{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})",
-                )
-                .progress_chars("=> "),
-        );
-        progress_bar.set_message(format!("{}Downloading~ {}", TRUCK, url));
+            err
+        })?
     } else {
-        info!("Downloading {} bytes from {}", download_size, url);
-    }
-
-    struct DownloadProgress<'e, 'f, R> {
-        progress_bar: ProgressBar,
-        response: R,
-        last_print: Instant,
-        current_bytes: usize,
-        last_print_bytes: usize,
-        download_size: f32,
-        use_progress_bar: bool,
-        start_time: Instant,
-        callback: &'f mut DownloadProgressCallbackOption<'e>,
-        notification_count: u64,
+        None
     }
+    .unwrap_or_else(|| {
+        vec![DownloadChunk {
+            path: temp_destination_file.clone(),
+            range: None,
+        }]
+    });
 
-    impl<'e, 'f, R: Read> Read for DownloadProgress<'e, 'f, R> {
-        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-            let n = self.response.read(buf)?;
-
-            self.current_bytes += n;
-            let total_bytes_f32 = self.current_bytes as f32;
-            let diff_bytes_f32 = (self.current_bytes - self.last_print_bytes) as f32;
-            let last_throughput = diff_bytes_f32 / self.last_print.elapsed().as_secs_f32();
-            let estimated_remaining_time = if last_throughput > 0_f32 {
-                (self.download_size - self.current_bytes as f32) / last_throughput
-            } else {
-                f32::MAX
-            };
-
-            let mut progress_record = DownloadProgressRecord {
-                elapsed_time: self.start_time.elapsed(),
-                last_elapsed_time: self.last_print.elapsed(),
-                last_throughput,
-                total_throughput: self.current_bytes as f32
-                    / self.start_time.elapsed().as_secs_f32(),
-                total_bytes: self.download_size as usize,
-                current_bytes: self.current_bytes,
-                percentage_done: 100f32 * (total_bytes_f32 / self.download_size),
-                estimated_remaining_time,
-                notification_count: self.notification_count,
-            };
-            let mut to_update_progress = false;
-            if progress_record.last_elapsed_time.as_secs() > 5 {
-                self.last_print = Instant::now();
-                self.last_print_bytes = self.current_bytes;
-                to_update_progress = true;
-                self.notification_count += 1;
-                progress_record.notification_count = self.notification_count
-            }
-
-            if self.use_progress_bar {
-                self.progress_bar.inc(n as u64);
-            } else if to_update_progress {
-                info!(
-                    "downloaded {} bytes {:.1}% {:.1} bytes/s",
-                    self.current_bytes,
-                    progress_record.percentage_done,
-                    progress_record.last_throughput,
-                );
-            }
-
-            if let Some(callback) = self.callback {
-                if to_update_progress && !callback(&progress_record) {
-                    info!("Download is aborted by the caller");
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        "Download is aborted by the caller",
-                    ));
-                }
-            }
-
-            Ok(n)
-        }
+    // The size of the download is only known up front when downloading ranges, otherwise the
+    // chunk fills it in from the response
+    let download_size = Arc::new(AtomicU64::new(
+        chunks
+            .iter()
+            .filter_map(|chunk| chunk.range.as_ref())
+            .map(|range| range.end - range.start)
+            .sum(),
+    ));
+    let current_bytes = Arc::new(AtomicU64::new(0));
+    let abort = Arc::new(AtomicBool::new(false));
+    let (result_sender, result_receiver) = channel();
+    for chunk in chunks.iter().cloned() {
+        let client = client.clone();
+        let url = url.to_string();
+        let download_size = download_size.clone();
+        let current_bytes = current_bytes.clone();
+        let abort = abort.clone();
+        let result_sender = result_sender.clone();
+        Builder::new()
+            .name("solDownloadChunk".to_string())
+            .spawn(move || {
+                let result = chunk.download(&client, &url, &download_size, &current_bytes, &abort);
+                // The receiver only goes away once every chunk is done or the download failed
+                let _ = result_sender.send(result);
+            })
+            .map_err(|err| err.to_string())?;
     }
+    drop(result_sender);
 
-    let mut source = DownloadProgress::<'b, 'a> {
+    let mut progress = DownloadProgress {
         progress_bar,
-        response,
+        url: url.to_string(),
         last_print: Instant::now(),
-        current_bytes: 0,
         last_print_bytes: 0,
-        download_size: (download_size as f32).max(1f32),
+        resumed_bytes: None,
         use_progress_bar,
         start_time: Instant::now(),
         callback: progress_notify_callback,
         notification_count: 0,
     };
+    let mut result = Ok(());
+    let mut num_chunks_done = 0;
+    while num_chunks_done < chunks.len() {
+        match result_receiver.recv_timeout(DOWNLOAD_PROGRESS_POLL_INTERVAL) {
+            Ok(chunk_result) => {
+                num_chunks_done += 1;
+                if chunk_result.is_err() && result.is_ok() {
+                    // No need to download the other chunks
+                    abort.store(true, Ordering::Relaxed);
+                    result = chunk_result;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => {
+                abort.store(true, Ordering::Relaxed);
+                result = Err("download thread panicked".to_string());
+                break;
+            }
+        }
+        if !progress.update(
+            download_size.load(Ordering::Relaxed),
+            current_bytes.load(Ordering::Relaxed),
+        ) && result.is_ok()
+        {
+            abort.store(true, Ordering::Relaxed);
+            result = Err("Download is aborted by the caller".to_string());
+        }
+    }
+    progress.progress_bar.finish_and_clear();
+    result.map_err(|err| format!("Unable to write {:?}: {}", temp_destination_file, err))?;
 
-    File::create(&temp_destination_file)
-        .and_then(|mut file| std::io::copy(&mut source, &mut file))
-        .map_err(|err| format!("Unable to write {:?}: {:?}", temp_destination_file, err))?;
+    reassemble_download_chunks(&chunks)
+        .map_err(|err| format!("Unable to reassemble {:?}: {}", temp_destination_file, err))?;
 
-    source.progress_bar.finish_and_clear();
+    let download_size = download_size.load(Ordering::Relaxed);
     info!(
         "  {}{}",
         SPARKLE,
@@ -227,6 +231,345 @@ pub fn download_file<'a, 'b>(
     Ok(())
 }
 
+/// The temporary file a download is written to, before being renamed to its destination
+fn build_temp_download_path(destination_file: &Path) -> PathBuf {
+    let mut temp_destination_file = destination_file.to_path_buf();
+    temp_destination_file.set_file_name(format!(
+        "tmp-{}",
+        destination_file
+            .file_name()
+            .expect("file_name")
+            .to_str()
+            .expect("to_str")
+    ));
+    temp_destination_file
+}
+
+/// A chunk of a download, written to its own temporary file
+#[derive(Debug, Clone, PartialEq)]
+struct DownloadChunk {
+    path: PathBuf,
+    /// The byte range of the chunk in the downloaded file, or None to download the whole file
+    /// when its size is unknown
+    range: Option<Range<u64>>,
+}
+
+impl DownloadChunk {
+    /// Download the chunk, resuming from the bytes already in its temporary file
+    fn download(
+        &self,
+        client: &reqwest::blocking::Client,
+        url: &str,
+        download_size: &AtomicU64,
+        current_bytes: &AtomicU64,
+        abort: &AtomicBool,
+    ) -> Result<(), String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| err.to_string())?;
+        let mut existing_bytes = file.metadata().map_err(|err| err.to_string())?.len();
+        if let Some(range) = &self.range {
+            if existing_bytes > range.end - range.start {
+                // The first chunk shares its temporary file with a download of the whole file,
+                // whose prefix is still valid.  Any other chunk is not a chunk of this download,
+                // start over.
+                existing_bytes = if range.start == 0 { range.end } else { 0 };
+                file.set_len(existing_bytes)
+                    .map_err(|err| err.to_string())?;
+            }
+        }
+        current_bytes.fetch_add(existing_bytes, Ordering::Relaxed);
+        let restart = |existing_bytes: &mut u64| -> Result<(), String> {
+            file.set_len(0).map_err(|err| err.to_string())?;
+            current_bytes.fetch_sub(*existing_bytes, Ordering::Relaxed);
+            *existing_bytes = 0;
+            Ok(())
+        };
+
+        let mut response = match &self.range {
+            Some(range) => {
+                if existing_bytes == range.end - range.start {
+                    return Ok(());
+                }
+                client
+                    .get(url)
+                    .header(
+                        reqwest::header::RANGE,
+                        format!("bytes={}-{}", range.start + existing_bytes, range.end - 1),
+                    )
+                    .send()
+            }
+            None if existing_bytes > 0 => {
+                let response = client
+                    .get(url)
+                    .header(reqwest::header::RANGE, format!("bytes={}-", existing_bytes))
+                    .send();
+                match response {
+                    Ok(response)
+                        if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE =>
+                    {
+                        // The partial download is not a prefix of the file, start over
+                        restart(&mut existing_bytes)?;
+                        client.get(url).send()
+                    }
+                    response => response,
+                }
+            }
+            None => client.get(url).send(),
+        }
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?;
+
+        if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+            if existing_bytes > 0 {
+                info!(
+                    "Resuming download of {} at byte {}",
+                    url,
+                    self.range.as_ref().map_or(0, |range| range.start) + existing_bytes
+                );
+            }
+        } else if self.range.is_some() {
+            return Err(format!("{} does not support range requests", url));
+        } else if existing_bytes > 0 {
+            // The server sends the whole file, start over
+            restart(&mut existing_bytes)?;
+        }
+        if self.range.is_none() {
+            let content_length = response
+                .headers()
+                .get(reqwest::header::CONTENT_LENGTH)
+                .and_then(|content_length| content_length.to_str().ok())
+                .and_then(|content_length| content_length.parse::<u64>().ok())
+                .unwrap_or(0);
+            download_size.store(existing_bytes + content_length, Ordering::Relaxed);
+        }
+
+        let mut file = BufWriter::new(file);
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            if abort.load(Ordering::Relaxed) {
+                return Err("Download is aborted".to_string());
+            }
+            let n = match response.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.to_string()),
+            };
+            file.write_all(&buf[..n]).map_err(|err| err.to_string())?;
+            current_bytes.fetch_add(n as u64, Ordering::Relaxed);
+        }
+        file.flush().map_err(|err| err.to_string())?;
+
+        if let Some(range) = &self.range {
+            let downloaded_bytes = file
+                .get_ref()
+                .metadata()
+                .map_err(|err| err.to_string())?
+                .len();
+            if downloaded_bytes != range.end - range.start {
+                return Err(format!(
+                    "Downloaded {} bytes of a chunk of {} bytes",
+                    downloaded_bytes,
+                    range.end - range.start
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split a download in chunks, if the server supports range requests.  The chunks only depend on
+/// the size of the file and `max_chunks`, so that the chunks of an interrupted download match the
+/// chunks of the download resuming it.  The first chunk is written to the temporary file that the
+/// others are appended to once downloaded.
+fn get_download_chunks(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    temp_destination_file: &Path,
+    max_chunks: usize,
+) -> Result<Option<Vec<DownloadChunk>>, String> {
+    let response = client
+        .get(url)
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?;
+    if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        return Ok(None);
+    }
+    // The size of the file is what follows the '/' of "Content-Range: bytes 0-0/<size>"
+    let download_size = match response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|content_range| content_range.to_str().ok())
+        .and_then(|content_range| content_range.rsplit_once('/'))
+        .and_then(|(_, size)| size.parse::<u64>().ok())
+    {
+        Some(download_size) => download_size,
+        None => return Ok(None),
+    };
+    Ok(Some(split_download_chunks(
+        temp_destination_file,
+        download_size,
+        max_chunks,
+    )))
+}
+
+fn split_download_chunks(
+    temp_destination_file: &Path,
+    download_size: u64,
+    max_chunks: usize,
+) -> Vec<DownloadChunk> {
+    let num_chunks = (download_size / MIN_DOWNLOAD_CHUNK_SIZE).clamp(1, max_chunks.max(1) as u64);
+    (0..num_chunks)
+        .map(|i| {
+            let path = if i == 0 {
+                temp_destination_file.to_path_buf()
+            } else {
+                let mut path = temp_destination_file.as_os_str().to_os_string();
+                path.push(format!(".part-{}", i));
+                PathBuf::from(path)
+            };
+            DownloadChunk {
+                path,
+                range: Some(download_size * i / num_chunks..download_size * (i + 1) / num_chunks),
+            }
+        })
+        .collect()
+}
+
+/// Append the downloaded chunks to the first one, removing each once appended
+fn reassemble_download_chunks(chunks: &[DownloadChunk]) -> io::Result<()> {
+    if let Some((first_chunk, other_chunks)) = chunks.split_first() {
+        let mut file = BufWriter::new(OpenOptions::new().append(true).open(&first_chunk.path)?);
+        for chunk in other_chunks {
+            io::copy(&mut File::open(&chunk.path)?, &mut file)?;
+            file.flush()?;
+            fs::remove_file(&chunk.path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove the partially downloaded files in `dir` of the downloads other than `destination_files`,
+/// which cannot be resumed anymore
+fn purge_stale_partial_downloads(dir: &Path, destination_files: &[PathBuf]) {
+    let kept_prefixes = destination_files
+        .iter()
+        .filter_map(|destination_file| {
+            build_temp_download_path(destination_file)
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .map(str::to_string)
+        })
+        .collect::<Vec<_>>();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let file_name = entry.file_name().into_string().unwrap_or_default();
+            if file_name.starts_with("tmp-")
+                && !file_name.starts_with(snapshot_utils::TMP_SNAPSHOT_ARCHIVE_PREFIX)
+                && entry.path().is_file()
+                && !kept_prefixes
+                    .iter()
+                    .any(|kept_prefix| file_name.starts_with(kept_prefix))
+            {
+                info!("Removing stale partial download {}", entry.path().display());
+                fs::remove_file(entry.path()).unwrap_or_else(|err| {
+                    warn!("Failed to remove {}: {}", entry.path().display(), err)
+                });
+            }
+        }
+    }
+}
+
+/// Reports the progress of a download, on the progress bar or in the log, and to the callback
+struct DownloadProgress<'e, 'f> {
+    progress_bar: ProgressBar,
+    url: String,
+    last_print: Instant,
+    last_print_bytes: u64,
+    /// The bytes downloaded by a previous attempt, known once the download starts
+    resumed_bytes: Option<u64>,
+    use_progress_bar: bool,
+    start_time: Instant,
+    callback: &'f mut DownloadProgressCallbackOption<'e>,
+    notification_count: u64,
+}
+
+impl<'e, 'f> DownloadProgress<'e, 'f> {
+    /// Returns false if the callback aborts the download
+    fn update(&mut self, download_size: u64, current_bytes: u64) -> bool {
+        if self.resumed_bytes.is_none() {
+            self.last_print_bytes = current_bytes;
+            if self.use_progress_bar {
+                self.progress_bar.set_length(download_size);
+                self.progress_bar.set_style(
+                    ProgressStyle::default_bar()
+                        .template(
+                            "{spinner:.green}{msg_wide}[{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})",
+                        )
+                        .progress_chars("=> "),
+                );
+                self.progress_bar
+                    .set_message(format!("{}Downloading~ {}", TRUCK, self.url));
+            } else {
+                info!("Downloading {} bytes from {}", download_size, self.url);
+            }
+        }
+        let resumed_bytes = *self.resumed_bytes.get_or_insert(current_bytes);
+        let download_size_f32 = (download_size as f32).max(1f32);
+        let diff_bytes_f32 = current_bytes.saturating_sub(self.last_print_bytes) as f32;
+        let last_throughput = diff_bytes_f32 / self.last_print.elapsed().as_secs_f32();
+        let estimated_remaining_time = if last_throughput > 0_f32 {
+            (download_size_f32 - current_bytes as f32) / last_throughput
+        } else {
+            f32::MAX
+        };
+
+        let mut progress_record = DownloadProgressRecord {
+            elapsed_time: self.start_time.elapsed(),
+            last_elapsed_time: self.last_print.elapsed(),
+            last_throughput,
+            total_throughput: current_bytes.saturating_sub(resumed_bytes) as f32
+                / self.start_time.elapsed().as_secs_f32(),
+            total_bytes: download_size as usize,
+            current_bytes: current_bytes as usize,
+            percentage_done: 100f32 * (current_bytes as f32 / download_size_f32),
+            estimated_remaining_time,
+            notification_count: self.notification_count,
+        };
+        let mut to_update_progress = false;
+        if progress_record.last_elapsed_time.as_secs() > 5 {
+            self.last_print = Instant::now();
+            self.last_print_bytes = current_bytes;
+            to_update_progress = true;
+            self.notification_count += 1;
+            progress_record.notification_count = self.notification_count
+        }
+
+        if self.use_progress_bar {
+            self.progress_bar.set_length(download_size);
+            self.progress_bar.set_position(current_bytes);
+        } else if to_update_progress {
+            info!(
+                "downloaded {} bytes {:.1}% {:.1} bytes/s",
+                current_bytes, progress_record.percentage_done, progress_record.last_throughput,
+            );
+        }
+
+        if let Some(callback) = self.callback {
+            if to_update_progress && !callback(&progress_record) {
+                info!("Download is aborted by the caller");
+                return false;
+            }
+        }
+        true
+    }
+}
+
 pub fn download_genesis_if_missing(
     rpc_addr: &SocketAddr,
     genesis_package: &Path,
@@ -252,6 +595,15 @@ pub fn download_genesis_if_missing(
 
 /// Download a snapshot archive from `rpc_addr`.  Use `snapshot_type` to specify downloading either
 /// a full snapshot or an incremental snapshot.
+///
+/// The archive is downloaded in chunks in parallel, and a download interrupted earlier resumes
+/// where it stopped, even from another node.
+///
+/// If `verify_dir` is set, the reassembled archive is also verified against the slot and hash in
+/// its name, by unpacking it into a temporary directory in `verify_dir` and hashing its accounts.
+/// This takes about as long as loading the snapshot.  An archive that does not match is renamed
+/// with an `.invalid` extension, so that it is kept for inspection but not loaded.
+#[allow(clippy::too_many_arguments)]
 pub fn download_snapshot_archive<'a, 'b>(
     rpc_addr: &SocketAddr,
     snapshot_archives_dir: &Path,
@@ -260,6 +612,7 @@ pub fn download_snapshot_archive<'a, 'b>(
     maximum_full_snapshot_archives_to_retain: usize,
     maximum_incremental_snapshot_archives_to_retain: usize,
     use_progress_bar: bool,
+    verify_dir: Option<&Path>,
     progress_notify_callback: &'a mut DownloadProgressCallbackOption<'b>,
 ) -> Result<(), String> {
     snapshot_utils::purge_old_snapshot_archives(
@@ -272,45 +625,80 @@ pub fn download_snapshot_archive<'a, 'b>(
         snapshot_utils::build_snapshot_archives_remote_dir(snapshot_archives_dir);
     fs::create_dir_all(&snapshot_archives_remote_dir).unwrap();
 
-    for archive_format in [
+    let destination_paths = [
         ArchiveFormat::TarZstd,
         ArchiveFormat::TarGzip,
         ArchiveFormat::TarBzip2,
         ArchiveFormat::Tar, // `solana-test-validator` creates uncompressed snapshots
-    ] {
-        let destination_path = match snapshot_type {
-            SnapshotType::FullSnapshot => snapshot_utils::build_full_snapshot_archive_path(
+        ArchiveFormat::IndexedZstd,
+    ]
+    .into_iter()
+    .map(|archive_format| match snapshot_type {
+        SnapshotType::FullSnapshot => snapshot_utils::build_full_snapshot_archive_path(
+            &snapshot_archives_remote_dir,
+            desired_snapshot_hash.0,
+            &desired_snapshot_hash.1,
+            archive_format,
+        ),
+        SnapshotType::IncrementalSnapshot(base_slot) => {
+            snapshot_utils::build_incremental_snapshot_archive_path(
                 &snapshot_archives_remote_dir,
+                base_slot,
                 desired_snapshot_hash.0,
                 &desired_snapshot_hash.1,
                 archive_format,
-            ),
-            SnapshotType::IncrementalSnapshot(base_slot) => {
-                snapshot_utils::build_incremental_snapshot_archive_path(
-                    &snapshot_archives_remote_dir,
-                    base_slot,
-                    desired_snapshot_hash.0,
-                    &desired_snapshot_hash.1,
-                    archive_format,
-                )
-            }
-        };
+            )
+        }
+    })
+    .collect::<Vec<_>>();
+    purge_stale_partial_downloads(&snapshot_archives_remote_dir, &destination_paths);
 
+    for destination_path in destination_paths {
         if destination_path.is_file() {
             return Ok(());
         }
 
-        match download_file(
+        match download_file_in_chunks(
             &format!(
                 "http://{}/{}",
                 rpc_addr,
                 destination_path.file_name().unwrap().to_str().unwrap()
             ),
             &destination_path,
+            MAX_SNAPSHOT_DOWNLOAD_CHUNKS,
             use_progress_bar,
             progress_notify_callback,
         ) {
-            Ok(()) => return Ok(()),
+            Ok(()) => {
+                let verify_dir = match verify_dir {
+                    Some(verify_dir) => verify_dir,
+                    None => return Ok(()),
+                };
+                return verify_downloaded_snapshot_archive(
+                    snapshot_archives_dir,
+                    verify_dir,
+                    &destination_path,
+                    snapshot_type,
+                )
+                .map_err(|err| {
+                    let invalid_path = build_invalid_snapshot_archive_path(&destination_path);
+                    match fs::rename(&destination_path, &invalid_path) {
+                        Ok(()) => format!(
+                            "Downloaded snapshot archive is invalid, kept it as {}: {}",
+                            invalid_path.display(),
+                            err
+                        ),
+                        Err(rename_err) => format!(
+                            "Downloaded snapshot archive {} is invalid: {}, and could not be \
+                             renamed to {}: {}",
+                            destination_path.display(),
+                            err,
+                            invalid_path.display(),
+                            rename_err
+                        ),
+                    }
+                });
+            }
             Err(err) => info!("{}", err),
         }
     }
@@ -319,3 +707,343 @@ pub fn download_snapshot_archive<'a, 'b>(
         desired_snapshot_hash.0, rpc_addr
     ))
 }
+
+/// Where a downloaded snapshot archive that failed verification is kept.  The extension keeps it
+/// from being mistaken for a snapshot archive, and it is replaced by the next invalid download of
+/// the same archive.
+fn build_invalid_snapshot_archive_path(snapshot_archive_path: &Path) -> PathBuf {
+    let mut invalid_path = snapshot_archive_path.as_os_str().to_os_string();
+    invalid_path.push(".invalid");
+    PathBuf::from(invalid_path)
+}
+
+/// Verify a downloaded snapshot archive against the slot and hash in its name, unpacking it in a
+/// temporary directory in `verify_dir`.  An incremental snapshot archive is verified on top of the
+/// full snapshot archive it is based on, found in `snapshot_archives_dir`.
+fn verify_downloaded_snapshot_archive(
+    snapshot_archives_dir: &Path,
+    verify_dir: &Path,
+    snapshot_archive_path: &Path,
+    snapshot_type: SnapshotType,
+) -> Result<(), String> {
+    let mut measure = Measure::start("verify downloaded snapshot archive");
+    let verified_snapshot = match snapshot_type {
+        SnapshotType::FullSnapshot => {
+            let full_snapshot_archive_info =
+                FullSnapshotArchiveInfo::new_from_path(snapshot_archive_path.to_path_buf())
+                    .map_err(|err| err.to_string())?;
+            snapshot_utils::verify_snapshot_archives(verify_dir, &full_snapshot_archive_info, &[])
+        }
+        SnapshotType::IncrementalSnapshot(base_slot) => {
+            let incremental_snapshot_archive_info =
+                IncrementalSnapshotArchiveInfo::new_from_path(snapshot_archive_path.to_path_buf())
+                    .map_err(|err| err.to_string())?;
+            let full_snapshot_archive_info =
+                snapshot_utils::get_full_snapshot_archives(snapshot_archives_dir)
+                    .into_iter()
+                    .find(|full_snapshot_archive_info| {
+                        full_snapshot_archive_info.slot() == base_slot
+                    })
+                    .ok_or_else(|| format!("no full snapshot archive for slot {}", base_slot))?;
+            snapshot_utils::verify_snapshot_archives(
+                verify_dir,
+                &full_snapshot_archive_info,
+                &[incremental_snapshot_archive_info],
+            )
+        }
+    }
+    .map_err(|err| err.to_string())?;
+    measure.stop();
+    info!(
+        "Verified downloaded snapshot archive {} for slot {}, accounts hash {}. {}",
+        snapshot_archive_path.display(),
+        verified_snapshot.slot,
+        verified_snapshot.accounts_hash,
+        measure,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{
+            io::{BufRead, BufReader},
+            net::TcpListener,
+            sync::Mutex,
+        },
+    };
+
+    /// Serve `body` over HTTP, whatever the path requested, and record the Range header of each
+    /// request.  Range requests are answered with 206, or 416 if they start past the end of
+    /// `body`, unless `support_ranges` is false.
+    fn serve(body: Vec<u8>, support_ranges: bool) -> (SocketAddr, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(vec![]));
+        let requests_ = requests.clone();
+        Builder::new()
+            .name("solTestHttp".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut range = None;
+                    for line in BufReader::new(&mut stream).lines() {
+                        let line = line.unwrap();
+                        if line.is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("range") {
+                                range = Some(value.trim().to_string());
+                            }
+                        }
+                    }
+                    let bounds = range.as_ref().filter(|_| support_ranges).map(|range| {
+                        let (start, end) = range
+                            .strip_prefix("bytes=")
+                            .unwrap()
+                            .split_once('-')
+                            .unwrap();
+                        (
+                            start.parse::<usize>().unwrap(),
+                            end.parse::<usize>()
+                                .map_or(body.len(), |end| end + 1)
+                                .min(body.len()),
+                        )
+                    });
+                    let response = match bounds {
+                        Some((start, _)) if start >= body.len() => format!(
+                            "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\n\
+                             Content-Length: 0\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        )
+                        .into_bytes(),
+                        Some((start, end)) => [
+                            format!(
+                                "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n\
+                                 Content-Length: {}\r\nConnection: close\r\n\r\n",
+                                start,
+                                end - 1,
+                                body.len(),
+                                end - start
+                            )
+                            .as_bytes(),
+                            &body[start..end],
+                        ]
+                        .concat(),
+                        None => [
+                            format!(
+                                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                                body.len()
+                            )
+                            .as_bytes(),
+                            &body[..],
+                        ]
+                        .concat(),
+                    };
+                    requests_.lock().unwrap().push(range);
+                    stream.write_all(&response).unwrap();
+                }
+            })
+            .unwrap();
+        (addr, requests)
+    }
+
+    fn test_body() -> Vec<u8> {
+        (0..1000).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_download_file_resume() {
+        let body = test_body();
+        let dir = tempfile::TempDir::new().unwrap();
+        let destination_file = dir.path().join("file");
+        let temp_destination_file = build_temp_download_path(&destination_file);
+
+        // The server sends the rest of the file
+        let (addr, requests) = serve(body.clone(), true);
+        fs::write(&temp_destination_file, &body[..400]).unwrap();
+        download_file(
+            &format!("http://{}/file", addr),
+            &destination_file,
+            false,
+            &mut None,
+        )
+        .unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), body);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![Some("bytes=400-".to_string())]
+        );
+        assert!(!temp_destination_file.exists());
+        fs::remove_file(&destination_file).unwrap();
+
+        // The server sends the whole file, the partial download is replaced
+        let (addr, requests) = serve(body.clone(), false);
+        fs::write(&temp_destination_file, vec![0xff; 400]).unwrap();
+        download_file(
+            &format!("http://{}/file", addr),
+            &destination_file,
+            false,
+            &mut None,
+        )
+        .unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), body);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![Some("bytes=400-".to_string())]
+        );
+        fs::remove_file(&destination_file).unwrap();
+
+        // The partial download is longer than the file, the download starts over
+        let (addr, requests) = serve(body.clone(), true);
+        fs::write(&temp_destination_file, vec![0xff; 1500]).unwrap();
+        download_file(
+            &format!("http://{}/file", addr),
+            &destination_file,
+            false,
+            &mut None,
+        )
+        .unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), body);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![Some("bytes=1500-".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn test_download_file_in_chunks_resume() {
+        let body = test_body();
+        let dir = tempfile::TempDir::new().unwrap();
+        let destination_file = dir.path().join("file");
+        let temp_destination_file = build_temp_download_path(&destination_file);
+
+        // The first chunk resumes where it stopped
+        let (addr, requests) = serve(body.clone(), true);
+        fs::write(&temp_destination_file, &body[..400]).unwrap();
+        download_file_in_chunks(
+            &format!("http://{}/file", addr),
+            &destination_file,
+            MAX_SNAPSHOT_DOWNLOAD_CHUNKS,
+            false,
+            &mut None,
+        )
+        .unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), body);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![
+                Some("bytes=0-0".to_string()),
+                Some("bytes=400-999".to_string())
+            ]
+        );
+        fs::remove_file(&destination_file).unwrap();
+
+        // A download of the whole file went past the end of the first chunk, which keeps the
+        // bytes of its range
+        let (addr, requests) = serve(body.clone(), true);
+        fs::write(&temp_destination_file, [&body[..], &[0xff; 500]].concat()).unwrap();
+        download_file_in_chunks(
+            &format!("http://{}/file", addr),
+            &destination_file,
+            MAX_SNAPSHOT_DOWNLOAD_CHUNKS,
+            false,
+            &mut None,
+        )
+        .unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), body);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![Some("bytes=0-0".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_download_snapshot_archive_verification() {
+        let (addr, _requests) = serve(b"not a snapshot archive".to_vec(), true);
+        let snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+        let verify_dir = tempfile::TempDir::new().unwrap();
+        let snapshot_hash = (42, Hash::new_unique());
+        let snapshot_archive_path = snapshot_utils::build_full_snapshot_archive_path(
+            &snapshot_utils::build_snapshot_archives_remote_dir(snapshot_archives_dir.path()),
+            snapshot_hash.0,
+            &snapshot_hash.1,
+            ArchiveFormat::TarZstd,
+        );
+
+        // Not verified unless asked to
+        download_snapshot_archive(
+            &addr,
+            snapshot_archives_dir.path(),
+            snapshot_hash,
+            SnapshotType::FullSnapshot,
+            1,
+            1,
+            false,
+            None,
+            &mut None,
+        )
+        .unwrap();
+        assert!(snapshot_archive_path.is_file());
+        fs::remove_file(&snapshot_archive_path).unwrap();
+
+        // An invalid archive is kept aside, and nothing is left in the verify dir
+        let err = download_snapshot_archive(
+            &addr,
+            snapshot_archives_dir.path(),
+            snapshot_hash,
+            SnapshotType::FullSnapshot,
+            1,
+            1,
+            false,
+            Some(verify_dir.path()),
+            &mut None,
+        )
+        .unwrap_err();
+        let invalid_path = build_invalid_snapshot_archive_path(&snapshot_archive_path);
+        assert!(err.contains(&invalid_path.display().to_string()));
+        assert!(!snapshot_archive_path.exists());
+        assert!(invalid_path.is_file());
+        assert_eq!(fs::read_dir(verify_dir.path()).unwrap().count(), 0);
+        assert!(
+            snapshot_utils::get_full_snapshot_archives(snapshot_archives_dir.path()).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_split_download_chunks() {
+        let temp_destination_file = Path::new("/dir/tmp-snapshot.tar.zst");
+
+        // Small files are not split
+        assert_eq!(
+            split_download_chunks(temp_destination_file, 1000, MAX_SNAPSHOT_DOWNLOAD_CHUNKS),
+            vec![DownloadChunk {
+                path: temp_destination_file.to_path_buf(),
+                range: Some(0..1000),
+            }]
+        );
+
+        let download_size = 3 * MIN_DOWNLOAD_CHUNK_SIZE + 1;
+        let chunks = split_download_chunks(temp_destination_file, download_size, 2);
+        assert_eq!(chunks.len(), 2);
+        let chunks = split_download_chunks(temp_destination_file, download_size, 8);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].path, temp_destination_file);
+        assert_eq!(
+            chunks[2].path,
+            PathBuf::from("/dir/tmp-snapshot.tar.zst.part-2")
+        );
+        // The chunks cover the whole file, without overlapping
+        assert_eq!(chunks[0].range.as_ref().unwrap().start, 0);
+        for window in chunks.windows(2) {
+            assert_eq!(
+                window[0].range.as_ref().unwrap().end,
+                window[1].range.as_ref().unwrap().start
+            );
+        }
+        assert_eq!(chunks[2].range.as_ref().unwrap().end, download_size);
+    }
+}
@@ -132,6 +132,29 @@ pub struct Entry {
     pub transactions: Vec<VersionedTransaction>,
 }
 
+/// The metadata of an Entry, without its transactions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EntrySummary {
+    /// The number of hashes since the previous Entry ID.
+    pub num_hashes: u64,
+
+    /// The SHA-256 hash `num_hashes` after the previous Entry ID.
+    pub hash: Hash,
+
+    /// The number of transactions in the Entry.
+    pub num_transactions: u64,
+}
+
+impl From<&Entry> for EntrySummary {
+    fn from(entry: &Entry) -> Self {
+        Self {
+            num_hashes: entry.num_hashes,
+            hash: entry.hash,
+            num_transactions: entry.transactions.len() as u64,
+        }
+    }
+}
+
 /// Typed entry to distinguish between transaction and tick entries
 pub enum EntryType {
     Transactions(Vec<SanitizedTransaction>),
@@ -0,0 +1,246 @@
+//! Writing records into a directory of rotating files. The files are named
+//! `geyser-<sequence>.<extension>` where the sequence increases by one with
+//! each new file, so that the files sort in the order they were written.
+use {
+    crate::record::{Record, RecordError, RecordFormat},
+    log::*,
+    std::{
+        collections::VecDeque,
+        fs::{self, File, OpenOptions},
+        io::{self, BufWriter, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+const FILE_NAME_PREFIX: &str = "geyser-";
+
+fn record_file_name(sequence: u64, format: RecordFormat) -> String {
+    format!(
+        "{}{:020}.{}",
+        FILE_NAME_PREFIX,
+        sequence,
+        format.file_extension()
+    )
+}
+
+/// Returns the sequence of a record file name in the given format
+fn parse_record_file_name(file_name: &str, format: RecordFormat) -> Option<u64> {
+    file_name
+        .strip_prefix(FILE_NAME_PREFIX)?
+        .strip_suffix(format.file_extension())?
+        .strip_suffix('.')?
+        .parse()
+        .ok()
+}
+
+/// Lists the record files in the given format found in `directory`, ordered
+/// by sequence, that is the order they were written.
+pub fn record_files(directory: &Path, format: RecordFormat) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        if let Some(sequence) = entry
+            .file_name()
+            .to_str()
+            .and_then(|file_name| parse_record_file_name(file_name, format))
+        {
+            files.push((sequence, entry.path()));
+        }
+    }
+    files.sort_unstable();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+struct CurrentFile {
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+/// Writes records to files in a directory, starting a new file once the
+/// current one reaches `max_file_size` and, when `max_file_count` is set,
+/// removing the oldest files beyond that count. A record is never split
+/// across files.
+pub struct RotatingFileWriter {
+    directory: PathBuf,
+    format: RecordFormat,
+    max_file_size: u64,
+    max_file_count: Option<usize>,
+    next_sequence: u64,
+    current_file: Option<CurrentFile>,
+    files: VecDeque<PathBuf>,
+}
+
+impl std::fmt::Debug for RotatingFileWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RotatingFileWriter")
+            .field("directory", &self.directory)
+            .field("format", &self.format)
+            .field("max_file_size", &self.max_file_size)
+            .field("max_file_count", &self.max_file_count)
+            .field("next_sequence", &self.next_sequence)
+            .finish()
+    }
+}
+
+impl RotatingFileWriter {
+    /// Creates a writer appending files to `directory`, after the record
+    /// files already there.
+    pub fn new(
+        directory: &Path,
+        format: RecordFormat,
+        max_file_size: u64,
+        max_file_count: Option<usize>,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        let files: VecDeque<_> = record_files(directory, format)?.into();
+        let next_sequence = files
+            .back()
+            .and_then(|path| path.file_name()?.to_str())
+            .and_then(|file_name| parse_record_file_name(file_name, format))
+            .map(|sequence| sequence + 1)
+            .unwrap_or_default();
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            format,
+            max_file_size,
+            max_file_count: max_file_count.map(|count| count.max(1)),
+            next_sequence,
+            current_file: None,
+            files,
+        })
+    }
+
+    pub fn write(&mut self, record: &Record) -> Result<(), RecordError> {
+        let format = self.format;
+        let current_file = match self.current_file.as_mut() {
+            Some(current_file) => current_file,
+            None => self.open_next_file()?,
+        };
+        current_file.size += record.write(&mut current_file.writer, format)?;
+        if current_file.size >= self.max_file_size {
+            self.close_current_file()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.current_file.as_mut() {
+            Some(current_file) => current_file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn open_next_file(&mut self) -> io::Result<&mut CurrentFile> {
+        let path = self
+            .directory
+            .join(record_file_name(self.next_sequence, self.format));
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        debug!("Writing records to {:?}", path);
+        self.next_sequence += 1;
+        self.files.push_back(path);
+        if let Some(max_file_count) = self.max_file_count {
+            while self.files.len() > max_file_count {
+                let oldest = self.files.pop_front().unwrap();
+                if let Err(err) = fs::remove_file(&oldest) {
+                    warn!("Failed to remove record file {:?}: {}", oldest, err);
+                }
+            }
+        }
+        Ok(self.current_file.insert(CurrentFile {
+            writer: BufWriter::new(file),
+            size: 0,
+        }))
+    }
+
+    fn close_current_file(&mut self) -> io::Result<()> {
+        match self.current_file.take() {
+            Some(mut current_file) => current_file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for RotatingFileWriter {
+    fn drop(&mut self) {
+        if let Err(err) = self.close_current_file() {
+            error!("Failed to flush record file: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            reader::RecordReader,
+            record::{SlotStatusKind, SlotStatusRecord},
+        },
+        tempfile::TempDir,
+    };
+
+    fn slot_records(count: u64) -> Vec<Record> {
+        (0..count)
+            .map(|slot| {
+                Record::SlotStatus(SlotStatusRecord {
+                    slot,
+                    parent: slot.checked_sub(1),
+                    status: SlotStatusKind::Rooted,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rotating_file_writer() {
+        for format in [RecordFormat::Bincode, RecordFormat::JsonLines] {
+            let directory = TempDir::new().unwrap();
+            let records = slot_records(100);
+            let mut writer =
+                RotatingFileWriter::new(directory.path(), format, 256, Some(100)).unwrap();
+            for record in &records[..50] {
+                writer.write(record).unwrap();
+            }
+            drop(writer);
+
+            // A new writer continues after the existing files
+            let mut writer =
+                RotatingFileWriter::new(directory.path(), format, 256, Some(100)).unwrap();
+            for record in &records[50..] {
+                writer.write(record).unwrap();
+            }
+            drop(writer);
+
+            let files = record_files(directory.path(), format).unwrap();
+            assert!(files.len() > 2);
+            let read_records: Vec<_> = RecordReader::from_directory(directory.path(), format)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(read_records, records);
+        }
+    }
+
+    #[test]
+    fn test_rotating_file_writer_max_file_count() {
+        let directory = TempDir::new().unwrap();
+        let format = RecordFormat::Bincode;
+        let mut writer = RotatingFileWriter::new(directory.path(), format, 1, Some(3)).unwrap();
+        let records = slot_records(10);
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+        drop(writer);
+
+        let files = record_files(directory.path(), format).unwrap();
+        assert_eq!(files.len(), 3);
+        let read_records: Vec<_> = RecordReader::from_directory(directory.path(), format)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read_records, records[7..]);
+    }
+}
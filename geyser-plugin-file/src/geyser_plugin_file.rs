@@ -0,0 +1,223 @@
+//! A Geyser plugin writing the account updates, slot status, transactions
+//! and block metadata it is notified of into rotating files, which can be
+//! read back with the [`RecordReader`](crate::reader::RecordReader).
+use {
+    crate::{
+        file_writer::RotatingFileWriter,
+        record::{
+            AccountRecord, BlockMetadataRecord, Record, RecordFormat, SlotStatusRecord,
+            TransactionRecord,
+        },
+    },
+    log::*,
+    serde_derive::Deserialize,
+    solana_geyser_plugin_interface::geyser_plugin_interface::{
+        GeyserPlugin, GeyserPluginError, ReplicaAccountInfoVersions, ReplicaBlockInfoVersions,
+        ReplicaTransactionInfoVersions, Result, SlotStatus,
+    },
+    std::{fs, path::PathBuf},
+};
+
+const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// The configuration of the plugin, read from the plugin config file:
+///
+/// ```json
+/// {
+///     "libpath": "/solana/target/release/libsolana_geyser_plugin_file.so",
+///     "output_dir": "/solana/geyser-records",
+///     "format": "bincode",
+///     "max_file_size": 1073741824,
+///     "max_file_count": 16,
+///     "accounts": true,
+///     "startup_accounts": false,
+///     "transactions": true
+/// }
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GeyserPluginFileConfig {
+    /// The directory the record files are written to
+    pub output_dir: PathBuf,
+    /// "bincode" for length-prefixed bincode records, "json" for JSON lines
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// The size in bytes after which a new file is started
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// The number of files to keep, the oldest ones are removed. All files
+    /// are kept when not set.
+    #[serde(default)]
+    pub max_file_count: Option<usize>,
+    /// Whether to write account updates
+    #[serde(default = "default_true")]
+    pub accounts: bool,
+    /// Whether to write the accounts restored from the snapshot at startup
+    #[serde(default)]
+    pub startup_accounts: bool,
+    /// Whether to write transactions
+    #[serde(default = "default_true")]
+    pub transactions: bool,
+}
+
+fn default_format() -> String {
+    "bincode".to_string()
+}
+
+fn default_max_file_size() -> u64 {
+    DEFAULT_MAX_FILE_SIZE
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Default, Debug)]
+pub struct GeyserPluginFile {
+    config: Option<GeyserPluginFileConfig>,
+    writer: Option<RotatingFileWriter>,
+}
+
+impl GeyserPluginFile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn write(&mut self, record: Record) -> std::result::Result<(), String> {
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| "The plugin is not loaded".to_string())?;
+        writer.write(&record).map_err(|err| err.to_string())
+    }
+}
+
+impl GeyserPlugin for GeyserPluginFile {
+    fn name(&self) -> &'static str {
+        "GeyserPluginFile"
+    }
+
+    fn on_load(&mut self, config_file: &str) -> Result<()> {
+        solana_logger::setup_with_default("info");
+        info!("Loading plugin {} from {}", self.name(), config_file);
+
+        let contents = fs::read_to_string(config_file)?;
+        let config: GeyserPluginFileConfig =
+            json5::from_str(&contents).map_err(|err| GeyserPluginError::ConfigFileReadError {
+                msg: format!("The config file is not valid: {}", err),
+            })?;
+        let format: RecordFormat = config
+            .format
+            .parse()
+            .map_err(|msg| GeyserPluginError::ConfigFileReadError { msg })?;
+        let writer = RotatingFileWriter::new(
+            &config.output_dir,
+            format,
+            config.max_file_size,
+            config.max_file_count,
+        )?;
+        info!("Writing {:?} records to {:?}", format, config.output_dir);
+        self.config = Some(config);
+        self.writer = Some(writer);
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        info!("Unloading plugin {}", self.name());
+        // Dropping the writer flushes the current file
+        self.writer = None;
+    }
+
+    fn update_account(
+        &mut self,
+        account: ReplicaAccountInfoVersions,
+        slot: u64,
+        is_startup: bool,
+    ) -> Result<()> {
+        let config = match self.config.as_ref() {
+            Some(config) => config,
+            None => return Ok(()),
+        };
+        if !config.accounts || (is_startup && !config.startup_accounts) {
+            return Ok(());
+        }
+        let ReplicaAccountInfoVersions::V0_0_1(account) = account;
+        self.write(Record::Account(AccountRecord::new(
+            account, slot, is_startup,
+        )))
+        .map_err(|msg| GeyserPluginError::AccountsUpdateError { msg })
+    }
+
+    fn notify_end_of_startup(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn update_slot_status(
+        &mut self,
+        slot: u64,
+        parent: Option<u64>,
+        status: SlotStatus,
+    ) -> Result<()> {
+        self.write(Record::SlotStatus(SlotStatusRecord {
+            slot,
+            parent,
+            status: status.into(),
+        }))
+        .map_err(|msg| GeyserPluginError::SlotStatusUpdateError { msg })?;
+        // Make the records of a slot available to readers once its status
+        // changes
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn notify_transaction(
+        &mut self,
+        transaction_info: ReplicaTransactionInfoVersions,
+        slot: u64,
+    ) -> Result<()> {
+        if !self.transaction_notifications_enabled() {
+            return Ok(());
+        }
+        let ReplicaTransactionInfoVersions::V0_0_1(transaction_info) = transaction_info;
+        self.write(Record::Transaction(Box::new(TransactionRecord::new(
+            transaction_info,
+            slot,
+        ))))
+        .map_err(|msg| GeyserPluginError::TransactionUpdateError { msg })
+    }
+
+    fn notify_block_metadata(&mut self, block_info: ReplicaBlockInfoVersions) -> Result<()> {
+        let ReplicaBlockInfoVersions::V0_0_1(block_info) = block_info;
+        self.write(Record::BlockMetadata(BlockMetadataRecord::from(block_info)))
+            .map_err(|msg| GeyserPluginError::Custom(msg.into()))
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        self.config
+            .as_ref()
+            .map(|config| config.accounts)
+            .unwrap_or_default()
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        self.config
+            .as_ref()
+            .map(|config| config.transactions)
+            .unwrap_or_default()
+    }
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+/// # Safety
+///
+/// This function returns the GeyserPluginFile pointer as trait GeyserPlugin.
+pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+    let plugin = GeyserPluginFile::new();
+    let plugin: Box<dyn GeyserPlugin> = Box::new(plugin);
+    Box::into_raw(plugin)
+}
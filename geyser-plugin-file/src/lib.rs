@@ -0,0 +1,4 @@
+pub mod file_writer;
+pub mod geyser_plugin_file;
+pub mod reader;
+pub mod record;
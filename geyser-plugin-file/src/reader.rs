@@ -0,0 +1,89 @@
+//! Reading back the records written by the file plugin
+use {
+    crate::{
+        file_writer::record_files,
+        record::{Record, RecordError, RecordFormat},
+    },
+    std::{
+        collections::VecDeque,
+        fs::File,
+        io::{self, BufRead, BufReader},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Iterates over the records of a sequence of files, in order. The iteration
+/// stops after the first error.
+#[derive(Debug)]
+pub struct RecordReader {
+    format: RecordFormat,
+    files: VecDeque<PathBuf>,
+    current_file: Option<BufReader<File>>,
+    failed: bool,
+}
+
+impl RecordReader {
+    /// Reads the given files, in the given order
+    pub fn new(files: Vec<PathBuf>, format: RecordFormat) -> Self {
+        Self {
+            format,
+            files: files.into(),
+            current_file: None,
+            failed: false,
+        }
+    }
+
+    /// Reads all the record files in `directory` written by the plugin in
+    /// the given format, in the order they were written.
+    pub fn from_directory(directory: &Path, format: RecordFormat) -> io::Result<Self> {
+        Ok(Self::new(record_files(directory, format)?, format))
+    }
+
+    fn read_record(&mut self) -> Result<Option<Record>, RecordError> {
+        loop {
+            let file = match self.current_file.as_mut() {
+                Some(file) => file,
+                None => match self.files.pop_front() {
+                    Some(path) => self.current_file.insert(BufReader::new(File::open(path)?)),
+                    None => return Ok(None),
+                },
+            };
+            let record = match self.format {
+                RecordFormat::Bincode => Record::read_bincode(file)?,
+                RecordFormat::JsonLines => read_json_line(file)?,
+            };
+            match record {
+                Some(record) => return Ok(Some(record)),
+                None => self.current_file = None,
+            }
+        }
+    }
+}
+
+/// Reads the next JSON record, skipping empty lines, returning `None` at the
+/// end of the input.
+fn read_json_line<R: BufRead>(reader: &mut R) -> Result<Option<Record>, RecordError> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if !line.trim().is_empty() {
+            return Ok(Some(serde_json::from_str(&line)?));
+        }
+    }
+}
+
+impl Iterator for RecordReader {
+    type Item = Result<Record, RecordError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.read_record();
+        self.failed = result.is_err();
+        result.transpose()
+    }
+}
@@ -0,0 +1,348 @@
+//! The records written by the file plugin, and their encoding. A file is a
+//! sequence of records in one of the [`RecordFormat`]s.
+use {
+    serde_derive::{Deserialize, Serialize},
+    solana_geyser_plugin_interface::geyser_plugin_interface::{
+        ReplicaAccountInfo, ReplicaBlockInfo, ReplicaTransactionInfo, SlotStatus,
+    },
+    solana_sdk::{
+        clock::{Slot, UnixTimestamp},
+        message::v0::LoadedAddresses,
+        pubkey::Pubkey,
+        signature::Signature,
+        transaction::{Result, VersionedTransaction},
+        transaction_context::TransactionReturnData,
+    },
+    solana_transaction_status::{InnerInstructions, Rewards},
+    std::{
+        io::{self, Read, Write},
+        str::FromStr,
+    },
+    thiserror::Error,
+};
+
+/// The maximum size of a bincode encoded record, bounding the memory used
+/// when reading a corrupted or foreign file.
+pub const MAX_BINCODE_RECORD_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum RecordError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("bincode error: {0}")]
+    Bincode(#[from] bincode::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("record of {0} bytes is too large")]
+    RecordTooLarge(u64),
+}
+
+/// How records are encoded in a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Each record is bincode encoded, prefixed with its length as a
+    /// little-endian u32
+    Bincode,
+    /// Each record is a JSON object on its own line
+    JsonLines,
+}
+
+impl RecordFormat {
+    /// The extension of the files holding records in this format
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            Self::Bincode => "bin",
+            Self::JsonLines => "jsonl",
+        }
+    }
+}
+
+impl FromStr for RecordFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> std::result::Result<Self, Self::Err> {
+        match format {
+            "bincode" => Ok(Self::Bincode),
+            "json" => Ok(Self::JsonLines),
+            _ => Err(format!(
+                "Unknown record format {}, expected \"bincode\" or \"json\"",
+                format
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AccountRecord {
+    pub pubkey: Pubkey,
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data: Vec<u8>,
+    pub write_version: u64,
+    pub slot: Slot,
+    pub is_startup: bool,
+}
+
+impl AccountRecord {
+    pub fn new(account: &ReplicaAccountInfo, slot: Slot, is_startup: bool) -> Self {
+        Self {
+            pubkey: Pubkey::new(account.pubkey),
+            lamports: account.lamports,
+            owner: Pubkey::new(account.owner),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: account.data.to_vec(),
+            write_version: account.write_version,
+            slot,
+            is_startup,
+        }
+    }
+}
+
+/// A serializable copy of [`SlotStatus`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotStatusKind {
+    Processed,
+    Rooted,
+    Confirmed,
+}
+
+impl From<SlotStatus> for SlotStatusKind {
+    fn from(status: SlotStatus) -> Self {
+        match status {
+            SlotStatus::Processed => Self::Processed,
+            SlotStatus::Rooted => Self::Rooted,
+            SlotStatus::Confirmed => Self::Confirmed,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotStatusRecord {
+    pub slot: Slot,
+    pub parent: Option<Slot>,
+    pub status: SlotStatusKind,
+}
+
+/// A transaction and the parts of its status meta that can be encoded in
+/// every [`RecordFormat`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TransactionRecord {
+    pub slot: Slot,
+    pub signature: Signature,
+    pub is_vote: bool,
+    #[serde(with = "versioned_transaction_serde")]
+    pub transaction: VersionedTransaction,
+    pub status: Result<()>,
+    pub fee: u64,
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    pub inner_instructions: Option<Vec<InnerInstructions>>,
+    pub log_messages: Option<Vec<String>>,
+    pub rewards: Option<Rewards>,
+    pub loaded_addresses: LoadedAddresses,
+    pub return_data: Option<TransactionReturnData>,
+}
+
+impl TransactionRecord {
+    pub fn new(transaction_info: &ReplicaTransactionInfo, slot: Slot) -> Self {
+        let meta = transaction_info.transaction_status_meta;
+        Self {
+            slot,
+            signature: *transaction_info.signature,
+            is_vote: transaction_info.is_vote,
+            transaction: transaction_info.transaction.to_versioned_transaction(),
+            status: meta.status.clone(),
+            fee: meta.fee,
+            pre_balances: meta.pre_balances.clone(),
+            post_balances: meta.post_balances.clone(),
+            inner_instructions: meta.inner_instructions.clone(),
+            log_messages: meta.log_messages.clone(),
+            rewards: meta.rewards.clone(),
+            loaded_addresses: meta.loaded_addresses.clone(),
+            return_data: meta.return_data.clone(),
+        }
+    }
+}
+
+/// `VersionedMessage` only round trips through binary formats, so human
+/// readable formats hold the base64 encoded bincode of the transaction.
+mod versioned_transaction_serde {
+    use {
+        serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer},
+        solana_sdk::transaction::VersionedTransaction,
+    };
+
+    pub fn serialize<S: Serializer>(
+        transaction: &VersionedTransaction,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let bytes = bincode::serialize(transaction).map_err(serde::ser::Error::custom)?;
+            serializer.serialize_str(&base64::encode(bytes))
+        } else {
+            transaction.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<VersionedTransaction, D::Error> {
+        if deserializer.is_human_readable() {
+            let encoded = String::deserialize(deserializer)?;
+            let bytes = base64::decode(encoded).map_err(D::Error::custom)?;
+            bincode::deserialize(&bytes).map_err(D::Error::custom)
+        } else {
+            VersionedTransaction::deserialize(deserializer)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockMetadataRecord {
+    pub slot: Slot,
+    pub blockhash: String,
+    pub rewards: Rewards,
+    pub block_time: Option<UnixTimestamp>,
+    pub block_height: Option<u64>,
+}
+
+impl From<&ReplicaBlockInfo<'_>> for BlockMetadataRecord {
+    fn from(block_info: &ReplicaBlockInfo) -> Self {
+        Self {
+            slot: block_info.slot,
+            blockhash: block_info.blockhash.to_string(),
+            rewards: block_info.rewards.to_vec(),
+            block_time: block_info.block_time,
+            block_height: block_info.block_height,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Record {
+    Account(AccountRecord),
+    SlotStatus(SlotStatusRecord),
+    Transaction(Box<TransactionRecord>),
+    BlockMetadata(BlockMetadataRecord),
+}
+
+impl Record {
+    /// Writes the record in the given format, returning the number of bytes
+    /// written.
+    pub fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        format: RecordFormat,
+    ) -> std::result::Result<u64, RecordError> {
+        match format {
+            RecordFormat::Bincode => {
+                let size = bincode::serialized_size(self)?;
+                if size > MAX_BINCODE_RECORD_SIZE {
+                    return Err(RecordError::RecordTooLarge(size));
+                }
+                writer.write_all(&(size as u32).to_le_bytes())?;
+                bincode::serialize_into(&mut *writer, self)?;
+                Ok(std::mem::size_of::<u32>() as u64 + size)
+            }
+            RecordFormat::JsonLines => {
+                let mut line = serde_json::to_vec(self)?;
+                line.push(b'\n');
+                writer.write_all(&line)?;
+                Ok(line.len() as u64)
+            }
+        }
+    }
+
+    /// Reads the next bincode encoded record, returning `None` at the end of
+    /// the input.
+    pub fn read_bincode<R: Read>(reader: &mut R) -> std::result::Result<Option<Self>, RecordError> {
+        let mut size = [0u8; 4];
+        match reader.read_exact(&mut size) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let size = u32::from_le_bytes(size) as u64;
+        if size > MAX_BINCODE_RECORD_SIZE {
+            return Err(RecordError::RecordTooLarge(size));
+        }
+        let mut buffer = vec![0u8; size as usize];
+        reader.read_exact(&mut buffer)?;
+        Ok(Some(bincode::deserialize(&buffer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            hash::Hash, signature::Keypair, system_transaction, transaction::TransactionError,
+        },
+    };
+
+    #[test]
+    fn test_record_round_trip() {
+        let keypair = Keypair::new();
+        let transaction = VersionedTransaction::from(system_transaction::transfer(
+            &keypair,
+            &Pubkey::new_unique(),
+            42,
+            Hash::new_unique(),
+        ));
+        let records = vec![
+            Record::Account(AccountRecord {
+                pubkey: Pubkey::new_unique(),
+                lamports: 42,
+                owner: Pubkey::new_unique(),
+                executable: false,
+                rent_epoch: 3,
+                data: vec![1, 2, 3],
+                write_version: 7,
+                slot: 9,
+                is_startup: true,
+            }),
+            Record::Transaction(Box::new(TransactionRecord {
+                slot: 9,
+                signature: transaction.signatures[0],
+                is_vote: false,
+                transaction,
+                status: Err(TransactionError::AccountInUse),
+                fee: 5000,
+                pre_balances: vec![100, 0],
+                post_balances: vec![58, 42],
+                inner_instructions: None,
+                log_messages: Some(vec!["log".to_string()]),
+                rewards: None,
+                loaded_addresses: LoadedAddresses::default(),
+                return_data: None,
+            })),
+        ];
+
+        for format in [RecordFormat::Bincode, RecordFormat::JsonLines] {
+            let mut buffer = vec![];
+            for record in &records {
+                let size = record.write(&mut buffer, format).unwrap();
+                assert!(size > 0);
+            }
+            let mut reader = buffer.as_slice();
+            let read_records: Vec<Record> = match format {
+                RecordFormat::Bincode => {
+                    std::iter::from_fn(|| Record::read_bincode(&mut reader).unwrap()).collect()
+                }
+                RecordFormat::JsonLines => reader
+                    .split(|byte| *byte == b'\n')
+                    .filter(|line| !line.is_empty())
+                    .map(|line| serde_json::from_slice(line).unwrap())
+                    .collect(),
+            };
+            assert_eq!(read_records, records);
+        }
+    }
+}
@@ -3,9 +3,12 @@
 /// In addition, the dynamic library must export a "C" function _create_plugin which
 /// creates the implementation of the plugin.
 use {
-    solana_sdk::{clock::UnixTimestamp, signature::Signature, transaction::SanitizedTransaction},
+    solana_sdk::{
+        clock::UnixTimestamp, pubkey::Pubkey, signature::Signature,
+        transaction::SanitizedTransaction,
+    },
     solana_transaction_status::{Reward, TransactionStatusMeta},
-    std::{any::Any, error, io},
+    std::{any::Any, collections::HashSet, error, io},
     thiserror::Error,
 };
 
@@ -46,6 +49,35 @@ pub enum ReplicaAccountInfoVersions<'a> {
     V0_0_1(&'a ReplicaAccountInfo<'a>),
 }
 
+/// Selects the account updates a plugin is notified of. An account matches
+/// the filter when it matches every criterion set in the filter; an empty
+/// set or an unset bound matches any account.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AccountUpdateFilter {
+    /// The accounts to select by their Pubkey
+    pub pubkeys: HashSet<Pubkey>,
+
+    /// The accounts to select by the Pubkey of their owner program
+    pub owners: HashSet<Pubkey>,
+
+    /// The minimum length of the account's data, inclusive
+    pub min_data_size: Option<usize>,
+
+    /// The maximum length of the account's data, inclusive
+    pub max_data_size: Option<usize>,
+}
+
+impl AccountUpdateFilter {
+    /// Check if an account with the given Pubkey, owner and data length
+    /// is selected by this filter
+    pub fn matches(&self, pubkey: &Pubkey, owner: &Pubkey, data_size: usize) -> bool {
+        (self.pubkeys.is_empty() || self.pubkeys.contains(pubkey))
+            && (self.owners.is_empty() || self.owners.contains(owner))
+            && self.min_data_size.map_or(true, |min| data_size >= min)
+            && self.max_data_size.map_or(true, |max| data_size <= max)
+    }
+}
+
 /// Information about a transaction
 #[derive(Clone, Debug)]
 pub struct ReplicaTransactionInfo<'a> {
@@ -83,6 +115,38 @@ pub enum ReplicaBlockInfoVersions<'a> {
     V0_0_1(&'a ReplicaBlockInfo<'a>),
 }
 
+/// Information about a PoH entry, as processed by replay
+#[derive(Clone, Debug)]
+pub struct ReplicaEntryInfo<'a> {
+    /// The slot number of the block containing this Entry
+    pub slot: u64,
+
+    /// The Entry's index in the block
+    pub index: usize,
+
+    /// The number of hashes since the previous Entry
+    pub num_hashes: u64,
+
+    /// The Entry's SHA-256 hash, generated from the previous Entry's hash with
+    /// `solana_entry::entry::next_hash()`
+    pub hash: &'a [u8],
+
+    /// The number of executed transactions in the Entry
+    pub executed_transaction_count: u64,
+
+    /// The index-in-block of the first transaction in this Entry. The
+    /// transactions of the Entry occupy the indexes
+    /// `starting_transaction_index..starting_transaction_index + executed_transaction_count`.
+    pub starting_transaction_index: usize,
+}
+
+/// A wrapper to future-proof ReplicaEntryInfo handling. To make a change to the structure of
+/// ReplicaEntryInfo, add an new enum variant wrapping a newer version, which will force plugin
+/// implementations to handle the change.
+pub enum ReplicaEntryInfoVersions<'a> {
+    V0_0_1(&'a ReplicaEntryInfo<'a>),
+}
+
 /// Errors returned by plugin calls
 #[derive(Error, Debug)]
 pub enum GeyserPluginError {
@@ -111,6 +175,10 @@ pub enum GeyserPluginError {
     /// Error when updating the transaction.
     #[error("Error updating transaction. Error message: ({msg})")]
     TransactionUpdateError { msg: String },
+
+    /// Error when updating the entry.
+    #[error("Error updating entry. Error message: ({msg})")]
+    EntryUpdateError { msg: String },
 }
 
 /// The current status of a slot
@@ -206,6 +274,14 @@ pub trait GeyserPlugin: Any + Send + Sync + std::fmt::Debug {
         Ok(())
     }
 
+    /// Called when an entry is executed by replay, after its transactions are executed.
+    /// The entry's proof of history is verified first only when verification is enabled,
+    /// which it is not with `--skip-poh-verify` or in most ledger-tool commands.
+    #[allow(unused_variables)]
+    fn notify_entry(&mut self, entry: ReplicaEntryInfoVersions) -> Result<()> {
+        Ok(())
+    }
+
     /// Check if the plugin is interested in account data
     /// Default is true -- if the plugin is not interested in
     /// account data, please return false.
@@ -213,10 +289,26 @@ pub trait GeyserPlugin: Any + Send + Sync + std::fmt::Debug {
         true
     }
 
+    /// The filters selecting the account updates the plugin is interested in.
+    /// The filters are queried once, when the plugin is loaded. An account
+    /// update, at startup or during transaction processing, is only notified
+    /// to the plugin when it matches at least one of the filters.
+    /// Default is None -- the plugin is notified of all account updates.
+    fn account_update_filters(&self) -> Option<Vec<AccountUpdateFilter>> {
+        None
+    }
+
     /// Check if the plugin is interested in transaction data
     /// Default is false -- if the plugin is not interested in
     /// transaction data, please return false.
     fn transaction_notifications_enabled(&self) -> bool {
         false
     }
+
+    /// Check if the plugin is interested in entry data
+    /// Default is false -- if the plugin is interested in
+    /// entry data, return true.
+    fn entry_notifications_enabled(&self) -> bool {
+        false
+    }
 }
@@ -1,6 +1,6 @@
 /// Module responsible for notifying plugins of account updates
 use {
-    crate::geyser_plugin_manager::GeyserPluginManager,
+    crate::geyser_plugin_manager::{account_update_filters_match, GeyserPluginManager},
     log::*,
     solana_geyser_plugin_interface::geyser_plugin_interface::{
         ReplicaAccountInfo, ReplicaAccountInfoVersions,
@@ -14,6 +14,7 @@ use {
     solana_sdk::{
         account::{AccountSharedData, ReadableAccount},
         clock::Slot,
+        pubkey::Pubkey,
     },
     std::sync::{Arc, RwLock},
 };
@@ -24,12 +25,31 @@ pub(crate) struct AccountsUpdateNotifierImpl {
 
 impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
     fn notify_account_update(&self, slot: Slot, meta: &StoredMeta, account: &AccountSharedData) {
+        let mut plugin_manager = self.plugin_manager.write().unwrap();
+        if !Self::account_update_selected(
+            &plugin_manager,
+            &meta.pubkey,
+            account.owner(),
+            account.data().len(),
+        ) {
+            return;
+        }
         if let Some(account_info) = self.accountinfo_from_shared_account_data(meta, account) {
-            self.notify_plugins_of_account_update(account_info, slot, false);
+            Self::notify_plugins_of_account_update(&mut plugin_manager, account_info, slot, false);
         }
     }
 
     fn notify_account_restore_from_snapshot(&self, slot: Slot, account: &StoredAccountMeta) {
+        let mut plugin_manager = self.plugin_manager.write().unwrap();
+        if !Self::account_update_selected(
+            &plugin_manager,
+            &account.meta.pubkey,
+            &account.account_meta.owner,
+            account.data.len(),
+        ) {
+            return;
+        }
+
         let mut measure_all = Measure::start("geyser-plugin-notify-account-restore-all");
         let mut measure_copy = Measure::start("geyser-plugin-copy-stored-account-info");
 
@@ -44,7 +64,7 @@ impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
         );
 
         if let Some(account_info) = account {
-            self.notify_plugins_of_account_update(account_info, slot, true);
+            Self::notify_plugins_of_account_update(&mut plugin_manager, account_info, slot, true);
         }
         measure_all.stop();
 
@@ -93,6 +113,23 @@ impl AccountsUpdateNotifierImpl {
         AccountsUpdateNotifierImpl { plugin_manager }
     }
 
+    /// Check if any plugin is interested in the account update, before paying
+    /// for building the ReplicaAccountInfo. The caller holds the lock on the
+    /// plugin manager until the plugins are notified, so that the plugins
+    /// checked are the ones notified.
+    fn account_update_selected(
+        plugin_manager: &GeyserPluginManager,
+        pubkey: &Pubkey,
+        owner: &Pubkey,
+        data_size: usize,
+    ) -> bool {
+        let selected = plugin_manager.account_update_selected(pubkey, owner, data_size);
+        if !selected {
+            inc_new_counter_debug!("geyser-plugin-account-update-filtered", 1);
+        }
+        selected
+    }
+
     fn accountinfo_from_shared_account_data<'a>(
         &self,
         meta: &'a StoredMeta,
@@ -119,24 +156,32 @@ impl AccountsUpdateNotifierImpl {
             owner: stored_account_meta.account_meta.owner.as_ref(),
             executable: stored_account_meta.account_meta.executable,
             rent_epoch: stored_account_meta.account_meta.rent_epoch,
-            data: stored_account_meta.data,
+            data: &stored_account_meta.data,
             write_version: stored_account_meta.meta.write_version,
         })
     }
 
     fn notify_plugins_of_account_update(
-        &self,
+        plugin_manager: &mut GeyserPluginManager,
         account: ReplicaAccountInfo,
         slot: Slot,
         is_startup: bool,
     ) {
         let mut measure2 = Measure::start("geyser-plugin-notify_plugins_of_account_update");
-        let mut plugin_manager = self.plugin_manager.write().unwrap();
 
         if plugin_manager.plugins.is_empty() {
             return;
         }
-        for plugin in plugin_manager.plugins.iter_mut() {
+        let pubkey = Pubkey::new(account.pubkey);
+        let owner = Pubkey::new(account.owner);
+        for (plugin, filters) in plugin_manager
+            .plugins
+            .iter_mut()
+            .zip(plugin_manager.account_update_filters.iter())
+        {
+            if !account_update_filters_match(filters, &pubkey, &owner, account.data.len()) {
+                continue;
+            }
             let mut measure = Measure::start("geyser-plugin-update-account");
             match plugin.update_account(
                 ReplicaAccountInfoVersions::V0_0_1(&account),
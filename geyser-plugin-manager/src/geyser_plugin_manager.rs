@@ -1,22 +1,63 @@
 /// Managing the Geyser plugins
 use {
+    crate::{
+        geyser_plugin_service::GeyserPluginServiceError,
+        queued_plugin::{NotificationQueueConfig, QueuedGeyserPlugin},
+    },
     libloading::{Library, Symbol},
     log::*,
-    solana_geyser_plugin_interface::geyser_plugin_interface::GeyserPlugin,
-    std::error::Error,
+    solana_geyser_plugin_interface::geyser_plugin_interface::{AccountUpdateFilter, GeyserPlugin},
+    solana_sdk::{exit::Exit, pubkey::Pubkey},
+    std::{
+        error::Error,
+        fs::File,
+        io::Read,
+        mem,
+        path::{Path, PathBuf},
+        sync::{Arc, RwLock},
+    },
 };
 
+/// A plugin loaded with its library, not yet managed by a `GeyserPluginManager`
+#[derive(Debug)]
+struct LoadedPlugin {
+    plugin: Box<dyn GeyserPlugin>,
+    lib: Library,
+}
+
+impl LoadedPlugin {
+    fn name(&self) -> &'static str {
+        self.plugin.name()
+    }
+
+    /// Fire the plugin's `on_unload()` method, then unload its library
+    fn unload(mut self) {
+        info!("Unloading plugin for {:?}", self.plugin.name());
+        self.plugin.on_unload();
+        // The plugin must be dropped before the library holding its code
+        drop(self.plugin);
+        drop(self.lib);
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct GeyserPluginManager {
     pub plugins: Vec<Box<dyn GeyserPlugin>>,
     libs: Vec<Library>,
+    /// The account updates selected by each plugin, indexed like `plugins`,
+    /// see `selected_account_updates`
+    pub(crate) account_update_filters: Vec<Option<Vec<AccountUpdateFilter>>>,
+    /// Exited by the queued plugins whose queue full policy is to halt
+    validator_exit: Arc<RwLock<Exit>>,
 }
 
 impl GeyserPluginManager {
-    pub fn new() -> Self {
+    pub fn new(validator_exit: Arc<RwLock<Exit>>) -> Self {
         GeyserPluginManager {
             plugins: Vec::default(),
             libs: Vec::default(),
+            account_update_filters: Vec::default(),
+            validator_exit,
         }
     }
 
@@ -29,17 +70,274 @@ impl GeyserPluginManager {
         libpath: &str,
         config_file: &str,
     ) -> Result<(), Box<dyn Error>> {
+        let loaded_plugin = Self::load_plugin_library(libpath, config_file)?;
+        self.push_plugin(loaded_plugin);
+        Ok(())
+    }
+
+    /// # Safety
+    ///
+    /// See `load_plugin`.
+    unsafe fn load_plugin_library(
+        libpath: &str,
+        config_file: &str,
+    ) -> Result<LoadedPlugin, Box<dyn Error>> {
         type PluginConstructor = unsafe fn() -> *mut dyn GeyserPlugin;
         let lib = Library::new(libpath)?;
         let constructor: Symbol<PluginConstructor> = lib.get(b"_create_plugin")?;
         let plugin_raw = constructor();
         let mut plugin = Box::from_raw(plugin_raw);
         plugin.on_load(config_file)?;
-        self.plugins.push(plugin);
-        self.libs.push(lib);
+        Ok(LoadedPlugin { plugin, lib })
+    }
+
+    fn push_plugin(&mut self, loaded_plugin: LoadedPlugin) {
+        self.account_update_filters
+            .push(selected_account_updates(loaded_plugin.plugin.as_ref()));
+        self.plugins.push(loaded_plugin.plugin);
+        self.libs.push(loaded_plugin.lib);
+    }
+
+    /// Loads the plugin described by the config file. The config file is in
+    /// JSON5 format and its `libpath` field points to the shared library
+    /// implementing the plugin; a relative `libpath` is resolved against the
+    /// directory of the config file. When the config file has a
+    /// `notification_queue` object, the plugin is notified asynchronously
+    /// through a bounded queue, see [`NotificationQueueConfig`]. Returns the
+    /// name of the loaded plugin.
+    ///
+    /// The plugin manager is only locked to add the plugin: the plugin is
+    /// loaded while the other plugins are notified.
+    pub fn load_plugin_from_config(
+        plugin_manager: &RwLock<Self>,
+        geyser_plugin_config_file: &Path,
+    ) -> Result<String, GeyserPluginServiceError> {
+        let validator_exit = plugin_manager.read().unwrap().validator_exit.clone();
+        let loaded_plugin = Self::load_from_config(geyser_plugin_config_file, validator_exit)?;
+        let name = loaded_plugin.name();
+        let result = plugin_manager.write().unwrap().add_plugin(loaded_plugin);
+        if let Err((loaded_plugin, err)) = result {
+            loaded_plugin.unload();
+            return Err(err);
+        }
+        info!(
+            "Loaded plugin {} from {:?}",
+            name, geyser_plugin_config_file
+        );
+        Ok(name.to_string())
+    }
+
+    /// Add `loaded_plugin`, handing it back when a plugin with the same name
+    /// is already loaded
+    fn add_plugin(
+        &mut self,
+        loaded_plugin: LoadedPlugin,
+    ) -> Result<(), (LoadedPlugin, GeyserPluginServiceError)> {
+        // Plugins are addressed by name when unloading or reloading them
+        let name = loaded_plugin.name();
+        if self.plugin_index(name).is_ok() {
+            let err = GeyserPluginServiceError::PluginAlreadyLoaded(format!(
+                "A plugin named {} is already loaded",
+                name
+            ));
+            return Err((loaded_plugin, err));
+        }
+        self.push_plugin(loaded_plugin);
         Ok(())
     }
 
+    /// Loads the plugin described by the config file, see
+    /// `load_plugin_from_config`, without adding it to a manager
+    fn load_from_config(
+        geyser_plugin_config_file: &Path,
+        validator_exit: Arc<RwLock<Exit>>,
+    ) -> Result<LoadedPlugin, GeyserPluginServiceError> {
+        let mut file = match File::open(geyser_plugin_config_file) {
+            Ok(file) => file,
+            Err(err) => {
+                return Err(GeyserPluginServiceError::CannotOpenConfigFile(format!(
+                    "Failed to open the plugin config file {:?}, error: {:?}",
+                    geyser_plugin_config_file, err
+                )));
+            }
+        };
+
+        let mut contents = String::new();
+        if let Err(err) = file.read_to_string(&mut contents) {
+            return Err(GeyserPluginServiceError::CannotReadConfigFile(format!(
+                "Failed to read the plugin config file {:?}, error: {:?}",
+                geyser_plugin_config_file, err
+            )));
+        }
+
+        let result: serde_json::Value = match json5::from_str(&contents) {
+            Ok(value) => value,
+            Err(err) => {
+                return Err(GeyserPluginServiceError::InvalidConfigFileFormat(format!(
+                    "The config file {:?} is not in a valid Json5 format, error: {:?}",
+                    geyser_plugin_config_file, err
+                )));
+            }
+        };
+
+        let libpath = result["libpath"]
+            .as_str()
+            .ok_or(GeyserPluginServiceError::LibPathNotSet)?;
+        let mut libpath = PathBuf::from(libpath);
+        if libpath.is_relative() {
+            let config_dir = geyser_plugin_config_file.parent().ok_or_else(|| {
+                GeyserPluginServiceError::CannotOpenConfigFile(format!(
+                    "Failed to resolve parent of {:?}",
+                    geyser_plugin_config_file,
+                ))
+            })?;
+            libpath = config_dir.join(libpath);
+        }
+
+        let notification_queue_config = match result.get("notification_queue") {
+            Some(value) => Some(NotificationQueueConfig::from_json(value).map_err(|err| {
+                GeyserPluginServiceError::InvalidConfigFileFormat(format!(
+                    "The config file {:?} has an invalid notification_queue, error: {}",
+                    geyser_plugin_config_file, err
+                ))
+            })?),
+            None => None,
+        };
+
+        let config_file = geyser_plugin_config_file
+            .as_os_str()
+            .to_str()
+            .ok_or(GeyserPluginServiceError::InvalidPluginPath)?;
+
+        let mut loaded_plugin = unsafe {
+            Self::load_plugin_library(libpath.to_str().unwrap(), config_file).map_err(|err| {
+                GeyserPluginServiceError::PluginLoadError(format!(
+                    "Failed to load the plugin library: {:?}, error: {:?}",
+                    libpath, err
+                ))
+            })?
+        };
+        if let Some(notification_queue_config) = notification_queue_config {
+            loaded_plugin.plugin = Box::new(QueuedGeyserPlugin::new(
+                loaded_plugin.plugin,
+                notification_queue_config,
+                validator_exit,
+            ));
+        }
+        Ok(loaded_plugin)
+    }
+
+    /// The names of the loaded plugins, in the order they are notified
+    pub fn list_plugins(&self) -> Vec<String> {
+        self.plugins
+            .iter()
+            .map(|plugin| plugin.name().to_string())
+            .collect()
+    }
+
+    /// Unload the plugin with the given name, firing its `on_unload()` method
+    /// before its library is unloaded.
+    ///
+    /// The plugin manager is only locked to remove the plugin: the plugin is
+    /// unloaded while the other plugins are notified.
+    pub fn unload_plugin(
+        plugin_manager: &RwLock<Self>,
+        name: &str,
+    ) -> Result<(), GeyserPluginServiceError> {
+        let removed_plugin = plugin_manager.write().unwrap().remove_plugin(name)?;
+        removed_plugin.unload();
+        Ok(())
+    }
+
+    fn remove_plugin(&mut self, name: &str) -> Result<LoadedPlugin, GeyserPluginServiceError> {
+        let index = self.plugin_index(name)?;
+        self.account_update_filters.remove(index);
+        Ok(LoadedPlugin {
+            plugin: self.plugins.remove(index),
+            lib: self.libs.remove(index),
+        })
+    }
+
+    /// Load a new instance of the plugin with the given name from the config
+    /// file, which may point to a new version of the plugin library, and
+    /// replace the loaded instance with it. The loaded instance is only
+    /// unloaded once the new one is loaded, so a plugin failing to reload
+    /// keeps running.
+    ///
+    /// The plugin manager is only locked to replace the instance: the
+    /// instances are loaded and unloaded while the other plugins are notified.
+    pub fn reload_plugin(
+        plugin_manager: &RwLock<Self>,
+        name: &str,
+        geyser_plugin_config_file: &Path,
+    ) -> Result<(), GeyserPluginServiceError> {
+        let validator_exit = {
+            let plugin_manager = plugin_manager.read().unwrap();
+            plugin_manager.plugin_index(name)?;
+            plugin_manager.validator_exit.clone()
+        };
+        let loaded_plugin = Self::load_from_config(geyser_plugin_config_file, validator_exit)?;
+        let reloaded_name = loaded_plugin.name();
+        let result = plugin_manager
+            .write()
+            .unwrap()
+            .replace_plugin(name, loaded_plugin);
+        match result {
+            Ok(replaced_plugin) => replaced_plugin.unload(),
+            Err((loaded_plugin, err)) => {
+                loaded_plugin.unload();
+                return Err(err);
+            }
+        }
+        if reloaded_name != name {
+            warn!(
+                "Plugin {} was reloaded as {} from {:?}",
+                name, reloaded_name, geyser_plugin_config_file
+            );
+        }
+        Ok(())
+    }
+
+    /// Replace the plugin with the given name by `loaded_plugin`, returning
+    /// the replaced instance, or handing `loaded_plugin` back when it cannot
+    /// replace the plugin
+    fn replace_plugin(
+        &mut self,
+        name: &str,
+        loaded_plugin: LoadedPlugin,
+    ) -> Result<LoadedPlugin, (LoadedPlugin, GeyserPluginServiceError)> {
+        // The plugin may have been unloaded while the new instance was loaded
+        let index = match self.plugin_index(name) {
+            Ok(index) => index,
+            Err(err) => return Err((loaded_plugin, err)),
+        };
+        let reloaded_name = loaded_plugin.name();
+        if reloaded_name != name && self.plugin_index(reloaded_name).is_ok() {
+            let err = GeyserPluginServiceError::PluginAlreadyLoaded(format!(
+                "Plugin {} cannot be reloaded as {}, which is already loaded",
+                name, reloaded_name
+            ));
+            return Err((loaded_plugin, err));
+        }
+        self.account_update_filters[index] =
+            selected_account_updates(loaded_plugin.plugin.as_ref());
+        let plugin = mem::replace(&mut self.plugins[index], loaded_plugin.plugin);
+        let lib = mem::replace(&mut self.libs[index], loaded_plugin.lib);
+        Ok(LoadedPlugin { plugin, lib })
+    }
+
+    fn plugin_index(&self, name: &str) -> Result<usize, GeyserPluginServiceError> {
+        self.plugins
+            .iter()
+            .position(|plugin| plugin.name() == name)
+            .ok_or_else(|| {
+                GeyserPluginServiceError::PluginNotLoaded(format!(
+                    "No plugin named {} is loaded",
+                    name
+                ))
+            })
+    }
+
     /// Unload all plugins and loaded plugin libraries, making sure to fire
     /// their `on_plugin_unload()` methods so they can do any necessary cleanup.
     pub fn unload(&mut self) {
@@ -51,6 +349,19 @@ impl GeyserPluginManager {
         for lib in self.libs.drain(..) {
             drop(lib);
         }
+        self.account_update_filters.clear();
+    }
+
+    /// Check if there is any plugin interested in the account update
+    pub fn account_update_selected(
+        &self,
+        pubkey: &Pubkey,
+        owner: &Pubkey,
+        data_size: usize,
+    ) -> bool {
+        self.account_update_filters
+            .iter()
+            .any(|filters| account_update_filters_match(filters, pubkey, owner, data_size))
     }
 
     /// Check if there is any plugin interested in account data
@@ -72,4 +383,298 @@ impl GeyserPluginManager {
         }
         false
     }
+
+    /// Check if there is any plugin interested in entry data
+    pub fn entry_notifications_enabled(&self) -> bool {
+        for plugin in &self.plugins {
+            if plugin.entry_notifications_enabled() {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// The filters selecting the account updates a plugin is notified of: the
+/// filters it declared, or none when its account data notifications are
+/// disabled.
+fn selected_account_updates(plugin: &dyn GeyserPlugin) -> Option<Vec<AccountUpdateFilter>> {
+    if plugin.account_data_notifications_enabled() {
+        plugin.account_update_filters()
+    } else {
+        Some(vec![])
+    }
+}
+
+/// Check if an account update is selected by the filters declared by a plugin.
+/// A plugin which declared no filters is interested in all account updates.
+pub(crate) fn account_update_filters_match(
+    filters: &Option<Vec<AccountUpdateFilter>>,
+    pubkey: &Pubkey,
+    owner: &Pubkey,
+    data_size: usize,
+) -> bool {
+    filters.as_ref().map_or(true, |filters| {
+        filters
+            .iter()
+            .any(|filter| filter.matches(pubkey, owner, data_size))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{
+            collections::HashSet,
+            sync::atomic::{AtomicBool, Ordering},
+        },
+    };
+
+    #[derive(Debug, Default)]
+    struct TestPlugin {
+        name: &'static str,
+        account_data_notifications_enabled: bool,
+        account_update_filters: Option<Vec<AccountUpdateFilter>>,
+        unloaded: Arc<AtomicBool>,
+    }
+
+    impl GeyserPlugin for TestPlugin {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn on_unload(&mut self) {
+            self.unloaded.store(true, Ordering::Relaxed);
+        }
+
+        fn account_data_notifications_enabled(&self) -> bool {
+            self.account_data_notifications_enabled
+        }
+
+        fn account_update_filters(&self) -> Option<Vec<AccountUpdateFilter>> {
+            self.account_update_filters.clone()
+        }
+    }
+
+    /// The test plugins are linked in the test binary, which stands for their library
+    fn loaded_plugin(plugin: TestPlugin) -> LoadedPlugin {
+        LoadedPlugin {
+            plugin: Box::new(plugin),
+            lib: libloading::os::unix::Library::this().into(),
+        }
+    }
+
+    fn push_test_plugin(plugin_manager: &mut GeyserPluginManager, plugin: TestPlugin) {
+        plugin_manager.push_plugin(loaded_plugin(plugin));
+    }
+
+    #[test]
+    fn test_account_update_selected() {
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        let mut plugin_manager = GeyserPluginManager::new(Arc::default());
+        assert!(!plugin_manager.account_update_selected(&pubkey, &owner, 0));
+
+        // Without account data notifications, a plugin selects no update
+        push_test_plugin(
+            &mut plugin_manager,
+            TestPlugin {
+                name: "disabled",
+                account_data_notifications_enabled: false,
+                account_update_filters: None,
+                ..TestPlugin::default()
+            },
+        );
+        assert!(!plugin_manager.account_update_selected(&pubkey, &owner, 0));
+
+        push_test_plugin(
+            &mut plugin_manager,
+            TestPlugin {
+                name: "by-owner",
+                account_data_notifications_enabled: true,
+                account_update_filters: Some(vec![AccountUpdateFilter {
+                    owners: HashSet::from([owner]),
+                    max_data_size: Some(10),
+                    ..AccountUpdateFilter::default()
+                }]),
+                ..TestPlugin::default()
+            },
+        );
+        assert!(plugin_manager.account_update_selected(&pubkey, &owner, 10));
+        assert!(!plugin_manager.account_update_selected(&pubkey, &owner, 11));
+        assert!(!plugin_manager.account_update_selected(&pubkey, &other, 0));
+
+        // A plugin which declared no filters selects every update
+        push_test_plugin(
+            &mut plugin_manager,
+            TestPlugin {
+                name: "all",
+                account_data_notifications_enabled: true,
+                account_update_filters: None,
+                ..TestPlugin::default()
+            },
+        );
+        assert!(plugin_manager.account_update_selected(&pubkey, &other, 11));
+    }
+
+    #[test]
+    fn test_replace_plugin() {
+        let mut plugin_manager = GeyserPluginManager::new(Arc::default());
+        let unloaded = Arc::<AtomicBool>::default();
+        push_test_plugin(
+            &mut plugin_manager,
+            TestPlugin {
+                name: "a",
+                unloaded: unloaded.clone(),
+                ..TestPlugin::default()
+            },
+        );
+        push_test_plugin(
+            &mut plugin_manager,
+            TestPlugin {
+                name: "b",
+                ..TestPlugin::default()
+            },
+        );
+
+        // The new instance takes the place of the replaced one, whose filters are updated
+        let replaced = plugin_manager
+            .replace_plugin(
+                "a",
+                loaded_plugin(TestPlugin {
+                    name: "a",
+                    account_data_notifications_enabled: true,
+                    ..TestPlugin::default()
+                }),
+            )
+            .unwrap();
+        assert_eq!(plugin_manager.list_plugins(), vec!["a", "b"]);
+        assert!(plugin_manager.plugins[0].account_data_notifications_enabled());
+        assert_eq!(plugin_manager.account_update_filters[0], None);
+        assert!(!unloaded.load(Ordering::Relaxed));
+        replaced.unload();
+        assert!(unloaded.load(Ordering::Relaxed));
+
+        // A new instance which cannot replace the plugin is handed back
+        let (not_loaded, err) = plugin_manager
+            .replace_plugin(
+                "c",
+                loaded_plugin(TestPlugin {
+                    name: "c",
+                    ..TestPlugin::default()
+                }),
+            )
+            .unwrap_err();
+        assert!(matches!(err, GeyserPluginServiceError::PluginNotLoaded(_)));
+        assert_eq!(not_loaded.name(), "c");
+        let (not_loaded, err) = plugin_manager
+            .replace_plugin(
+                "a",
+                loaded_plugin(TestPlugin {
+                    name: "b",
+                    ..TestPlugin::default()
+                }),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            GeyserPluginServiceError::PluginAlreadyLoaded(_)
+        ));
+        assert_eq!(not_loaded.name(), "b");
+        assert_eq!(plugin_manager.list_plugins(), vec!["a", "b"]);
+        assert!(plugin_manager.plugins[0].account_data_notifications_enabled());
+    }
+
+    #[test]
+    fn test_add_and_unload_plugin() {
+        let plugin_manager = RwLock::new(GeyserPluginManager::new(Arc::default()));
+        let unloaded = Arc::<AtomicBool>::default();
+        plugin_manager
+            .write()
+            .unwrap()
+            .add_plugin(loaded_plugin(TestPlugin {
+                name: "a",
+                unloaded: unloaded.clone(),
+                ..TestPlugin::default()
+            }))
+            .unwrap();
+
+        // A plugin with the name of a loaded one is handed back
+        let (not_loaded, err) = plugin_manager
+            .write()
+            .unwrap()
+            .add_plugin(loaded_plugin(TestPlugin {
+                name: "a",
+                ..TestPlugin::default()
+            }))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            GeyserPluginServiceError::PluginAlreadyLoaded(_)
+        ));
+        drop(not_loaded);
+        assert_eq!(plugin_manager.read().unwrap().list_plugins(), vec!["a"]);
+
+        assert!(matches!(
+            GeyserPluginManager::unload_plugin(&plugin_manager, "b"),
+            Err(GeyserPluginServiceError::PluginNotLoaded(_))
+        ));
+        GeyserPluginManager::unload_plugin(&plugin_manager, "a").unwrap();
+        assert!(unloaded.load(Ordering::Relaxed));
+        let plugin_manager = plugin_manager.read().unwrap();
+        assert!(plugin_manager.plugins.is_empty());
+        assert!(plugin_manager.libs.is_empty());
+        assert!(plugin_manager.account_update_filters.is_empty());
+    }
+
+    #[test]
+    fn test_load_plugin_failure() {
+        let plugin_manager = RwLock::new(GeyserPluginManager::new(Arc::default()));
+        assert!(matches!(
+            GeyserPluginManager::load_plugin_from_config(
+                &plugin_manager,
+                Path::new("no-such-geyser-plugin-config.json")
+            ),
+            Err(GeyserPluginServiceError::CannotOpenConfigFile(_))
+        ));
+        assert!(plugin_manager.read().unwrap().plugins.is_empty());
+    }
+
+    #[test]
+    fn test_reload_plugin_failure() {
+        let plugin_manager = RwLock::new(GeyserPluginManager::new(Arc::default()));
+        let unloaded = Arc::<AtomicBool>::default();
+        push_test_plugin(
+            &mut plugin_manager.write().unwrap(),
+            TestPlugin {
+                name: "a",
+                unloaded: unloaded.clone(),
+                ..TestPlugin::default()
+            },
+        );
+
+        // A plugin failing to reload keeps running
+        assert!(matches!(
+            GeyserPluginManager::reload_plugin(
+                &plugin_manager,
+                "a",
+                Path::new("no-such-geyser-plugin-config.json")
+            ),
+            Err(GeyserPluginServiceError::CannotOpenConfigFile(_))
+        ));
+        assert_eq!(plugin_manager.read().unwrap().list_plugins(), vec!["a"]);
+        assert!(!unloaded.load(Ordering::Relaxed));
+
+        assert!(matches!(
+            GeyserPluginManager::reload_plugin(
+                &plugin_manager,
+                "b",
+                Path::new("no-such-geyser-plugin-config.json")
+            ),
+            Err(GeyserPluginServiceError::PluginNotLoaded(_))
+        ));
+    }
 }
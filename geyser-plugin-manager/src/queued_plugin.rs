@@ -0,0 +1,762 @@
+//! Module running a plugin's callbacks on a dedicated worker thread, fed by
+//! a bounded notification queue, so that a slow plugin does not directly slow
+//! down replay and AccountsDb.
+use {
+    crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender, TrySendError},
+    log::*,
+    solana_geyser_plugin_interface::geyser_plugin_interface::{
+        AccountUpdateFilter, GeyserPlugin, ReplicaAccountInfo, ReplicaAccountInfoVersions,
+        ReplicaBlockInfo, ReplicaBlockInfoVersions, ReplicaEntryInfo, ReplicaEntryInfoVersions,
+        ReplicaTransactionInfo, ReplicaTransactionInfoVersions, Result, SlotStatus,
+    },
+    solana_measure::measure::Measure,
+    solana_metrics::*,
+    solana_sdk::{
+        clock::{Slot, UnixTimestamp},
+        exit::Exit,
+        signature::Signature,
+        transaction::SanitizedTransaction,
+    },
+    solana_transaction_status::{Reward, TransactionStatusMeta},
+    std::{
+        str::FromStr,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, RwLock,
+        },
+        thread::{Builder, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+const DEFAULT_QUEUE_CAPACITY: usize = 100_000;
+const STATS_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What to do with a new notification when the plugin's queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueFullPolicy {
+    /// Wait for the plugin to make room in the queue
+    Block,
+    /// Drop the oldest queued notification to make room for the new one
+    DropOldest,
+    /// Exit the validator, dropping the notifications while it exits
+    Halt,
+}
+
+impl FromStr for QueueFullPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> std::result::Result<Self, Self::Err> {
+        match policy {
+            "block" => Ok(Self::Block),
+            "drop-oldest" => Ok(Self::DropOldest),
+            "halt" => Ok(Self::Halt),
+            _ => Err(format!(
+                "Unknown queue full policy {}, expected one of \"block\", \"drop-oldest\" \
+                 or \"halt\"",
+                policy
+            )),
+        }
+    }
+}
+
+/// The configuration of a plugin's notification queue. It is read from the
+/// optional `notification_queue` object of the plugin's config file:
+///
+/// ```json
+/// "notification_queue": {
+///     "capacity": 100000,
+///     "full_policy": "drop-oldest"
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotificationQueueConfig {
+    /// The maximum number of queued notifications
+    pub capacity: usize,
+    /// What to do when the queue is full
+    pub full_policy: QueueFullPolicy,
+}
+
+impl Default for NotificationQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_QUEUE_CAPACITY,
+            full_policy: QueueFullPolicy::Block,
+        }
+    }
+}
+
+impl NotificationQueueConfig {
+    /// Parse the `notification_queue` value of a plugin's config file
+    pub fn from_json(value: &serde_json::Value) -> std::result::Result<Self, String> {
+        let mut config = Self::default();
+        let object = value
+            .as_object()
+            .ok_or_else(|| "notification_queue must be an object".to_string())?;
+        if let Some(capacity) = object.get("capacity") {
+            config.capacity = capacity
+                .as_u64()
+                .filter(|capacity| *capacity > 0)
+                .ok_or_else(|| {
+                    "notification_queue.capacity must be a positive integer".to_string()
+                })? as usize;
+        }
+        if let Some(full_policy) = object.get("full_policy") {
+            config.full_policy = full_policy
+                .as_str()
+                .ok_or_else(|| "notification_queue.full_policy must be a string".to_string())?
+                .parse()?;
+        }
+        Ok(config)
+    }
+}
+
+/// A notification owning the data it carries, so it can be queued
+#[derive(Debug)]
+enum Notification {
+    Account {
+        pubkey: Vec<u8>,
+        lamports: u64,
+        owner: Vec<u8>,
+        executable: bool,
+        rent_epoch: u64,
+        data: Vec<u8>,
+        write_version: u64,
+        slot: Slot,
+        is_startup: bool,
+    },
+    EndOfStartup,
+    SlotStatus {
+        slot: Slot,
+        parent: Option<Slot>,
+        status: SlotStatus,
+    },
+    Transaction {
+        signature: Signature,
+        is_vote: bool,
+        transaction: SanitizedTransaction,
+        transaction_status_meta: TransactionStatusMeta,
+        slot: Slot,
+    },
+    BlockMetadata {
+        slot: Slot,
+        blockhash: String,
+        rewards: Vec<Reward>,
+        block_time: Option<UnixTimestamp>,
+        block_height: Option<u64>,
+    },
+    Entry {
+        slot: Slot,
+        index: usize,
+        num_hashes: u64,
+        hash: Vec<u8>,
+        executed_transaction_count: u64,
+        starting_transaction_index: usize,
+    },
+}
+
+#[derive(Debug, Default)]
+struct QueueStats {
+    dropped: AtomicU64,
+    callbacks: AtomicU64,
+    callback_errors: AtomicU64,
+    callback_us: AtomicU64,
+    max_callback_us: AtomicU64,
+}
+
+impl QueueStats {
+    fn report(&self, name: &str, queue_depth: usize) {
+        datapoint_info!(
+            "geyser-plugin-notification-queue",
+            ("plugin", name, String),
+            ("queue_depth", queue_depth, i64),
+            ("dropped", self.dropped.swap(0, Ordering::Relaxed), i64),
+            ("callbacks", self.callbacks.swap(0, Ordering::Relaxed), i64),
+            (
+                "callback_errors",
+                self.callback_errors.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "callback_us",
+                self.callback_us.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "max_callback_us",
+                self.max_callback_us.swap(0, Ordering::Relaxed),
+                i64
+            ),
+        );
+    }
+}
+
+/// Wraps a plugin so that its notifications are queued and delivered to it on
+/// a dedicated worker thread. The wrapped plugin has already been loaded.
+#[derive(Debug)]
+pub struct QueuedGeyserPlugin {
+    name: &'static str,
+    account_data_notifications_enabled: bool,
+    transaction_notifications_enabled: bool,
+    entry_notifications_enabled: bool,
+    account_update_filters: Option<Vec<AccountUpdateFilter>>,
+    full_policy: QueueFullPolicy,
+    /// Exited by the `Halt` policy
+    validator_exit: Arc<RwLock<Exit>>,
+    halted: AtomicBool,
+    sender: Option<Sender<Notification>>,
+    // Kept to drop the oldest notifications when the queue is full
+    receiver: Receiver<Notification>,
+    stats: Arc<QueueStats>,
+    worker: Option<JoinHandle<Box<dyn GeyserPlugin>>>,
+}
+
+impl QueuedGeyserPlugin {
+    pub fn new(
+        plugin: Box<dyn GeyserPlugin>,
+        config: NotificationQueueConfig,
+        validator_exit: Arc<RwLock<Exit>>,
+    ) -> Self {
+        let name = plugin.name();
+        let account_data_notifications_enabled = plugin.account_data_notifications_enabled();
+        let transaction_notifications_enabled = plugin.transaction_notifications_enabled();
+        let entry_notifications_enabled = plugin.entry_notifications_enabled();
+        let account_update_filters = plugin.account_update_filters();
+
+        let (sender, receiver) = bounded(config.capacity);
+        let stats = Arc::<QueueStats>::default();
+        let worker = {
+            let receiver = receiver.clone();
+            let stats = stats.clone();
+            Builder::new()
+                .name(format!("solana-geyser-queue-{}", name))
+                .spawn(move || Self::run_worker(plugin, receiver, stats))
+                .unwrap()
+        };
+        info!(
+            "Notifying plugin {} through a queue of {} notifications, full policy: {:?}",
+            name, config.capacity, config.full_policy
+        );
+
+        Self {
+            name,
+            account_data_notifications_enabled,
+            transaction_notifications_enabled,
+            entry_notifications_enabled,
+            account_update_filters,
+            full_policy: config.full_policy,
+            validator_exit,
+            halted: AtomicBool::new(false),
+            sender: Some(sender),
+            receiver,
+            stats,
+            worker: Some(worker),
+        }
+    }
+
+    fn run_worker(
+        mut plugin: Box<dyn GeyserPlugin>,
+        receiver: Receiver<Notification>,
+        stats: Arc<QueueStats>,
+    ) -> Box<dyn GeyserPlugin> {
+        let mut last_report = Instant::now();
+        loop {
+            match receiver.recv_timeout(STATS_REPORT_INTERVAL) {
+                Ok(notification) => {
+                    let mut measure = Measure::start("geyser-plugin-queued-callback");
+                    let result = Self::deliver(plugin.as_mut(), notification);
+                    measure.stop();
+                    if let Err(err) = result {
+                        stats.callback_errors.fetch_add(1, Ordering::Relaxed);
+                        error!(
+                            "Failed to deliver queued notification, error: {} to plugin {}",
+                            err,
+                            plugin.name()
+                        );
+                    }
+                    stats.callbacks.fetch_add(1, Ordering::Relaxed);
+                    stats
+                        .callback_us
+                        .fetch_add(measure.as_us(), Ordering::Relaxed);
+                    stats
+                        .max_callback_us
+                        .fetch_max(measure.as_us(), Ordering::Relaxed);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if last_report.elapsed() >= STATS_REPORT_INTERVAL {
+                stats.report(plugin.name(), receiver.len());
+                last_report = Instant::now();
+            }
+        }
+        stats.report(plugin.name(), receiver.len());
+        plugin
+    }
+
+    fn deliver(plugin: &mut dyn GeyserPlugin, notification: Notification) -> Result<()> {
+        match notification {
+            Notification::Account {
+                pubkey,
+                lamports,
+                owner,
+                executable,
+                rent_epoch,
+                data,
+                write_version,
+                slot,
+                is_startup,
+            } => {
+                let account = ReplicaAccountInfo {
+                    pubkey: &pubkey,
+                    lamports,
+                    owner: &owner,
+                    executable,
+                    rent_epoch,
+                    data: &data,
+                    write_version,
+                };
+                plugin.update_account(
+                    ReplicaAccountInfoVersions::V0_0_1(&account),
+                    slot,
+                    is_startup,
+                )
+            }
+            Notification::EndOfStartup => plugin.notify_end_of_startup(),
+            Notification::SlotStatus {
+                slot,
+                parent,
+                status,
+            } => plugin.update_slot_status(slot, parent, status),
+            Notification::Transaction {
+                signature,
+                is_vote,
+                transaction,
+                transaction_status_meta,
+                slot,
+            } => {
+                let transaction_info = ReplicaTransactionInfo {
+                    signature: &signature,
+                    is_vote,
+                    transaction: &transaction,
+                    transaction_status_meta: &transaction_status_meta,
+                };
+                plugin.notify_transaction(
+                    ReplicaTransactionInfoVersions::V0_0_1(&transaction_info),
+                    slot,
+                )
+            }
+            Notification::BlockMetadata {
+                slot,
+                blockhash,
+                rewards,
+                block_time,
+                block_height,
+            } => {
+                let block_info = ReplicaBlockInfo {
+                    slot,
+                    blockhash: &blockhash,
+                    rewards: &rewards,
+                    block_time,
+                    block_height,
+                };
+                plugin.notify_block_metadata(ReplicaBlockInfoVersions::V0_0_1(&block_info))
+            }
+            Notification::Entry {
+                slot,
+                index,
+                num_hashes,
+                hash,
+                executed_transaction_count,
+                starting_transaction_index,
+            } => {
+                let entry_info = ReplicaEntryInfo {
+                    slot,
+                    index,
+                    num_hashes,
+                    hash: &hash,
+                    executed_transaction_count,
+                    starting_transaction_index,
+                };
+                plugin.notify_entry(ReplicaEntryInfoVersions::V0_0_1(&entry_info))
+            }
+        }
+    }
+
+    fn enqueue(&self, notification: Notification) {
+        let sender = match self.sender.as_ref() {
+            Some(sender) => sender,
+            None => return,
+        };
+        let result = match self.full_policy {
+            QueueFullPolicy::Block => sender.send(notification).map_err(|err| err.into()),
+            QueueFullPolicy::DropOldest => {
+                let mut notification = notification;
+                loop {
+                    match sender.try_send(notification) {
+                        Err(TrySendError::Full(returned)) => {
+                            if self.receiver.try_recv().is_ok() {
+                                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                            }
+                            notification = returned;
+                        }
+                        result => break result,
+                    }
+                }
+            }
+            QueueFullPolicy::Halt => sender.try_send(notification),
+        };
+        match result {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                // The validator exits through its exit hooks, so that it shuts down as
+                // cleanly as on any other exit. Notifications are dropped meanwhile.
+                if !self.halted.swap(true, Ordering::Relaxed) {
+                    error!(
+                        "The notification queue of plugin {} is full, halting the validator",
+                        self.name
+                    );
+                    datapoint_error!(
+                        "geyser-plugin-notification-queue-full",
+                        ("plugin", self.name, String),
+                    );
+                    self.validator_exit.write().unwrap().exit();
+                }
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                error!("The notification worker of plugin {} is gone", self.name);
+            }
+        }
+    }
+
+    /// Stop accepting notifications, let the worker deliver the queued ones
+    /// and return the wrapped plugin.
+    fn shutdown(&mut self) -> Option<Box<dyn GeyserPlugin>> {
+        drop(self.sender.take());
+        self.worker.take().and_then(|worker| match worker.join() {
+            Ok(plugin) => Some(plugin),
+            Err(_) => {
+                error!("The notification worker of plugin {} panicked", self.name);
+                None
+            }
+        })
+    }
+}
+
+impl Drop for QueuedGeyserPlugin {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl GeyserPlugin for QueuedGeyserPlugin {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn on_unload(&mut self) {
+        if let Some(mut plugin) = self.shutdown() {
+            plugin.on_unload();
+        }
+    }
+
+    fn update_account(
+        &mut self,
+        account: ReplicaAccountInfoVersions,
+        slot: u64,
+        is_startup: bool,
+    ) -> Result<()> {
+        let ReplicaAccountInfoVersions::V0_0_1(account) = account;
+        self.enqueue(Notification::Account {
+            pubkey: account.pubkey.to_vec(),
+            lamports: account.lamports,
+            owner: account.owner.to_vec(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: account.data.to_vec(),
+            write_version: account.write_version,
+            slot,
+            is_startup,
+        });
+        Ok(())
+    }
+
+    fn notify_end_of_startup(&mut self) -> Result<()> {
+        self.enqueue(Notification::EndOfStartup);
+        Ok(())
+    }
+
+    fn update_slot_status(
+        &mut self,
+        slot: u64,
+        parent: Option<u64>,
+        status: SlotStatus,
+    ) -> Result<()> {
+        self.enqueue(Notification::SlotStatus {
+            slot,
+            parent,
+            status,
+        });
+        Ok(())
+    }
+
+    fn notify_transaction(
+        &mut self,
+        transaction: ReplicaTransactionInfoVersions,
+        slot: u64,
+    ) -> Result<()> {
+        let ReplicaTransactionInfoVersions::V0_0_1(transaction) = transaction;
+        self.enqueue(Notification::Transaction {
+            signature: *transaction.signature,
+            is_vote: transaction.is_vote,
+            transaction: transaction.transaction.clone(),
+            transaction_status_meta: transaction.transaction_status_meta.clone(),
+            slot,
+        });
+        Ok(())
+    }
+
+    fn notify_block_metadata(&mut self, blockinfo: ReplicaBlockInfoVersions) -> Result<()> {
+        let ReplicaBlockInfoVersions::V0_0_1(blockinfo) = blockinfo;
+        self.enqueue(Notification::BlockMetadata {
+            slot: blockinfo.slot,
+            blockhash: blockinfo.blockhash.to_string(),
+            rewards: blockinfo.rewards.to_vec(),
+            block_time: blockinfo.block_time,
+            block_height: blockinfo.block_height,
+        });
+        Ok(())
+    }
+
+    fn notify_entry(&mut self, entry: ReplicaEntryInfoVersions) -> Result<()> {
+        let ReplicaEntryInfoVersions::V0_0_1(entry) = entry;
+        self.enqueue(Notification::Entry {
+            slot: entry.slot,
+            index: entry.index,
+            num_hashes: entry.num_hashes,
+            hash: entry.hash.to_vec(),
+            executed_transaction_count: entry.executed_transaction_count,
+            starting_transaction_index: entry.starting_transaction_index,
+        });
+        Ok(())
+    }
+
+    fn account_update_filters(&self) -> Option<Vec<AccountUpdateFilter>> {
+        self.account_update_filters.clone()
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        self.account_data_notifications_enabled
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        self.transaction_notifications_enabled
+    }
+
+    fn entry_notifications_enabled(&self) -> bool {
+        self.entry_notifications_enabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crossbeam_channel::unbounded,
+        std::{sync::Mutex, thread},
+    };
+
+    /// Records the slots it is notified of, each notification waiting for the gate to
+    /// open so that the tests control when the queue drains
+    #[derive(Debug)]
+    struct TestPlugin {
+        notified: Arc<Mutex<Vec<Slot>>>,
+        started: Sender<Slot>,
+        gate: Receiver<()>,
+        unloaded: Arc<AtomicBool>,
+    }
+
+    impl GeyserPlugin for TestPlugin {
+        fn name(&self) -> &'static str {
+            "test-plugin"
+        }
+
+        fn on_unload(&mut self) {
+            self.unloaded.store(true, Ordering::Relaxed);
+        }
+
+        fn update_slot_status(
+            &mut self,
+            slot: u64,
+            _parent: Option<u64>,
+            _status: SlotStatus,
+        ) -> Result<()> {
+            let _ = self.started.send(slot);
+            // The gate opens for good once its sender is dropped
+            let _ = self.gate.recv();
+            self.notified.lock().unwrap().push(slot);
+            Ok(())
+        }
+    }
+
+    struct TestQueue {
+        plugin: QueuedGeyserPlugin,
+        notified: Arc<Mutex<Vec<Slot>>>,
+        started: Receiver<Slot>,
+        gate: Sender<()>,
+        unloaded: Arc<AtomicBool>,
+    }
+
+    fn new_test_queue(
+        capacity: usize,
+        full_policy: QueueFullPolicy,
+        validator_exit: Arc<RwLock<Exit>>,
+    ) -> TestQueue {
+        let notified = Arc::<Mutex<Vec<Slot>>>::default();
+        let (started_sender, started) = unbounded();
+        let (gate, gate_receiver) = unbounded();
+        let unloaded = Arc::<AtomicBool>::default();
+        let plugin = TestPlugin {
+            notified: notified.clone(),
+            started: started_sender,
+            gate: gate_receiver,
+            unloaded: unloaded.clone(),
+        };
+        let plugin = QueuedGeyserPlugin::new(
+            Box::new(plugin),
+            NotificationQueueConfig {
+                capacity,
+                full_policy,
+            },
+            validator_exit,
+        );
+        TestQueue {
+            plugin,
+            notified,
+            started,
+            gate,
+            unloaded,
+        }
+    }
+
+    fn notify_slot(plugin: &QueuedGeyserPlugin, slot: Slot) {
+        plugin.enqueue(Notification::SlotStatus {
+            slot,
+            parent: None,
+            status: SlotStatus::Processed,
+        });
+    }
+
+    #[test]
+    fn test_queue_full_policy_block() {
+        let TestQueue {
+            plugin,
+            notified,
+            started,
+            gate,
+            ..
+        } = new_test_queue(1, QueueFullPolicy::Block, Arc::default());
+        let plugin = Arc::new(plugin);
+
+        // The worker holds 1 and 2 fills the queue
+        notify_slot(&plugin, 1);
+        assert_eq!(started.recv().unwrap(), 1);
+        notify_slot(&plugin, 2);
+
+        let (done_sender, done) = unbounded();
+        let notifier = {
+            let plugin = plugin.clone();
+            thread::spawn(move || {
+                notify_slot(&plugin, 3);
+                done_sender.send(()).unwrap();
+            })
+        };
+        assert_eq!(
+            done.recv_timeout(Duration::from_millis(100)),
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        drop(gate);
+        notifier.join().unwrap();
+        drop(Arc::try_unwrap(plugin).unwrap());
+        assert_eq!(*notified.lock().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_queue_full_policy_drop_oldest() {
+        let TestQueue {
+            plugin,
+            notified,
+            started,
+            gate,
+            ..
+        } = new_test_queue(2, QueueFullPolicy::DropOldest, Arc::default());
+
+        notify_slot(&plugin, 1);
+        assert_eq!(started.recv().unwrap(), 1);
+        for slot in 2..=4 {
+            notify_slot(&plugin, slot);
+        }
+        assert_eq!(plugin.stats.dropped.load(Ordering::Relaxed), 1);
+
+        drop(gate);
+        drop(plugin);
+        assert_eq!(*notified.lock().unwrap(), vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_queue_full_policy_halt() {
+        let exited = Arc::<AtomicBool>::default();
+        let validator_exit = Arc::<RwLock<Exit>>::default();
+        {
+            let exited = exited.clone();
+            validator_exit
+                .write()
+                .unwrap()
+                .register_exit(Box::new(move || exited.store(true, Ordering::Relaxed)));
+        }
+        let TestQueue {
+            plugin,
+            notified,
+            started,
+            gate,
+            ..
+        } = new_test_queue(1, QueueFullPolicy::Halt, validator_exit);
+
+        notify_slot(&plugin, 1);
+        assert_eq!(started.recv().unwrap(), 1);
+        notify_slot(&plugin, 2);
+        assert!(!exited.load(Ordering::Relaxed));
+        notify_slot(&plugin, 3);
+        assert!(exited.load(Ordering::Relaxed));
+        assert_eq!(plugin.stats.dropped.load(Ordering::Relaxed), 1);
+
+        drop(gate);
+        drop(plugin);
+        assert_eq!(*notified.lock().unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_unload_delivers_queued_notifications() {
+        let TestQueue {
+            mut plugin,
+            notified,
+            gate,
+            unloaded,
+            ..
+        } = new_test_queue(10, QueueFullPolicy::Block, Arc::default());
+        drop(gate);
+
+        for slot in 1..=5 {
+            notify_slot(&plugin, slot);
+        }
+        plugin.on_unload();
+        assert_eq!(*notified.lock().unwrap(), vec![1, 2, 3, 4, 5]);
+        assert!(unloaded.load(Ordering::Relaxed));
+
+        // Notifications after unloading are ignored
+        notify_slot(&plugin, 6);
+        assert_eq!(*notified.lock().unwrap(), vec![1, 2, 3, 4, 5]);
+    }
+}
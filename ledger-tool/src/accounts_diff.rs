@@ -0,0 +1,486 @@
+//! Comparing the accounts of two banks, to find out which accounts two
+//! validators disagreeing on a bank hash differ on.
+use {
+    serde::Serialize,
+    solana_cli_output::{QuietDisplay, VerboseDisplay},
+    solana_runtime::bank::Bank,
+    solana_sdk::{
+        account::{accounts_equal, AccountSharedData, ReadableAccount},
+        clock::Slot,
+        pubkey::Pubkey,
+    },
+    std::{
+        cmp::Ordering,
+        fmt::{self, Display, Formatter},
+        ops::RangeInclusive,
+    },
+};
+
+/// The banks are compared one range of pubkeys at a time, ranges of this
+/// many values of the first two bytes, so that only the accounts of a range
+/// are loaded at once
+const SCAN_RANGE_PREFIXES: u16 = 16;
+
+/// The ranges of pubkeys the banks are compared in, covering all pubkeys in
+/// order
+fn scan_ranges() -> impl Iterator<Item = RangeInclusive<Pubkey>> {
+    (0..=u16::MAX)
+        .step_by(SCAN_RANGE_PREFIXES as usize)
+        .map(|first_prefix| {
+            let mut start = [0x00u8; 32];
+            let mut end = [0xffu8; 32];
+            start[..2].copy_from_slice(&first_prefix.to_be_bytes());
+            end[..2].copy_from_slice(&(first_prefix + (SCAN_RANGE_PREFIXES - 1)).to_be_bytes());
+            Pubkey::new_from_array(start)..=Pubkey::new_from_array(end)
+        })
+}
+
+/// Loads the accounts of a bank within a range of pubkeys in pubkey order,
+/// optionally leaving out the sysvars
+fn range_accounts(
+    bank: &Bank,
+    range: RangeInclusive<Pubkey>,
+    include_sysvars: bool,
+) -> Vec<(Pubkey, AccountSharedData)> {
+    let mut accounts = bank
+        .rc
+        .accounts
+        .load_to_collect_rent_eagerly(&bank.ancestors, range)
+        .into_iter()
+        .filter(|(pubkey, _account, _slot)| {
+            include_sysvars || !solana_sdk::sysvar::is_sysvar_id(pubkey)
+        })
+        .map(|(pubkey, account, _slot)| (pubkey, account))
+        .collect::<Vec<_>>();
+    accounts.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    accounts
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountState {
+    pub owner: String,
+    pub lamports: u64,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data_len: usize,
+}
+
+impl From<&AccountSharedData> for AccountState {
+    fn from(account: &AccountSharedData) -> Self {
+        Self {
+            owner: account.owner().to_string(),
+            lamports: account.lamports(),
+            executable: account.executable(),
+            rent_epoch: account.rent_epoch(),
+            data_len: account.data().len(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountEntry {
+    pub pubkey: String,
+    #[serde(flatten)]
+    pub state: AccountState,
+}
+
+/// A range of account data that differs between the two banks, with the
+/// bytes of each bank in hex. A side is shorter than the range, or empty,
+/// when its data ends within the range.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDiffRange {
+    pub offset: usize,
+    pub len: usize,
+    pub first: String,
+    pub second: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifiedAccount {
+    pub pubkey: String,
+    pub first: AccountState,
+    pub second: AccountState,
+    pub data_changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_diff: Option<Vec<DataDiffRange>>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountsDiff {
+    pub first_slot: Slot,
+    pub first_bank_hash: String,
+    pub second_slot: Slot,
+    pub second_bank_hash: String,
+    /// Accounts only present in the second bank
+    pub added: Vec<AccountEntry>,
+    /// Accounts only present in the first bank
+    pub removed: Vec<AccountEntry>,
+    pub modified: Vec<ModifiedAccount>,
+}
+
+impl AccountsDiff {
+    /// Compares the accounts of two banks, optionally leaving out the sysvars
+    pub fn new(
+        first_bank: &Bank,
+        second_bank: &Bank,
+        include_sysvars: bool,
+        include_data_diff: bool,
+    ) -> Self {
+        let mut diff = Self {
+            first_slot: first_bank.slot(),
+            first_bank_hash: first_bank.hash().to_string(),
+            second_slot: second_bank.slot(),
+            second_bank_hash: second_bank.hash().to_string(),
+            added: vec![],
+            removed: vec![],
+            modified: vec![],
+        };
+        for range in scan_ranges() {
+            let first_accounts = range_accounts(first_bank, range.clone(), include_sysvars);
+            let second_accounts = range_accounts(second_bank, range, include_sysvars);
+            diff.compare(first_accounts, second_accounts, include_data_diff);
+        }
+        diff
+    }
+
+    /// Merges two lists of accounts in pubkey order into the diff
+    fn compare(
+        &mut self,
+        first_accounts: impl IntoIterator<Item = (Pubkey, AccountSharedData)>,
+        second_accounts: impl IntoIterator<Item = (Pubkey, AccountSharedData)>,
+        include_data_diff: bool,
+    ) {
+        let mut first_accounts = first_accounts.into_iter().peekable();
+        let mut second_accounts = second_accounts.into_iter().peekable();
+        loop {
+            let ordering = match (first_accounts.peek(), second_accounts.peek()) {
+                (Some((first_pubkey, _)), Some((second_pubkey, _))) => {
+                    first_pubkey.cmp(second_pubkey)
+                }
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match ordering {
+                Ordering::Less => {
+                    let (pubkey, first_account) = first_accounts.next().unwrap();
+                    self.removed.push(AccountEntry {
+                        pubkey: pubkey.to_string(),
+                        state: (&first_account).into(),
+                    });
+                }
+                Ordering::Greater => {
+                    let (pubkey, second_account) = second_accounts.next().unwrap();
+                    self.added.push(AccountEntry {
+                        pubkey: pubkey.to_string(),
+                        state: (&second_account).into(),
+                    });
+                }
+                Ordering::Equal => {
+                    let (pubkey, first_account) = first_accounts.next().unwrap();
+                    let (_, second_account) = second_accounts.next().unwrap();
+                    if !accounts_equal(&first_account, &second_account) {
+                        let data_changed = first_account.data() != second_account.data();
+                        self.modified.push(ModifiedAccount {
+                            pubkey: pubkey.to_string(),
+                            first: (&first_account).into(),
+                            second: (&second_account).into(),
+                            data_changed,
+                            data_diff: (include_data_diff && data_changed)
+                                .then(|| data_diff(first_account.data(), second_account.data())),
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the ranges of bytes that differ between two account data,
+/// coalescing the differences separated by less than a few equal bytes.
+fn data_diff(first: &[u8], second: &[u8]) -> Vec<DataDiffRange> {
+    const MIN_GAP: usize = 8;
+
+    let len = first.len().max(second.len());
+    let differs = |offset: usize| first.get(offset) != second.get(offset);
+    let hex = |data: &[u8], start: usize, end: usize| {
+        let end = end.min(data.len());
+        data.get(start..end)
+            .unwrap_or_default()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()
+    };
+
+    let mut ranges = vec![];
+    let mut offset = 0;
+    while offset < len {
+        if !differs(offset) {
+            offset += 1;
+            continue;
+        }
+        let start = offset;
+        let mut end = offset + 1;
+        let mut next = end;
+        while next < len && next < end + MIN_GAP {
+            if differs(next) {
+                end = next + 1;
+            }
+            next += 1;
+        }
+        ranges.push(DataDiffRange {
+            offset: start,
+            len: end - start,
+            first: hex(first, start, end),
+            second: hex(second, start, end),
+        });
+        offset = end;
+    }
+    ranges
+}
+
+fn write_state_change<T: PartialEq + Display>(
+    f: &mut Formatter,
+    name: &str,
+    first: T,
+    second: T,
+) -> fmt::Result {
+    if first != second {
+        writeln!(f, "    {}: {} -> {}", name, first, second)?;
+    }
+    Ok(())
+}
+
+impl Display for AccountsDiff {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "First bank:  slot {}, hash {}",
+            self.first_slot, self.first_bank_hash
+        )?;
+        writeln!(
+            f,
+            "Second bank: slot {}, hash {}",
+            self.second_slot, self.second_bank_hash
+        )?;
+        writeln!(
+            f,
+            "Added accounts: {}, removed accounts: {}, modified accounts: {}",
+            self.added.len(),
+            self.removed.len(),
+            self.modified.len()
+        )?;
+        for (sign, entries) in [("+", &self.added), ("-", &self.removed)] {
+            for entry in entries {
+                writeln!(
+                    f,
+                    "{} {} owner: {}, lamports: {}, data_len: {}",
+                    sign,
+                    entry.pubkey,
+                    entry.state.owner,
+                    entry.state.lamports,
+                    entry.state.data_len
+                )?;
+            }
+        }
+        for account in &self.modified {
+            let (first, second) = (&account.first, &account.second);
+            writeln!(f, "~ {}", account.pubkey)?;
+            write_state_change(f, "owner", &first.owner, &second.owner)?;
+            if first.lamports != second.lamports {
+                writeln!(
+                    f,
+                    "    lamports: {} -> {} ({:+})",
+                    first.lamports,
+                    second.lamports,
+                    i128::from(second.lamports) - i128::from(first.lamports)
+                )?;
+            }
+            write_state_change(f, "executable", first.executable, second.executable)?;
+            write_state_change(f, "rent_epoch", first.rent_epoch, second.rent_epoch)?;
+            write_state_change(f, "data_len", first.data_len, second.data_len)?;
+            if account.data_changed {
+                writeln!(f, "    data changed")?;
+            }
+            for range in account.data_diff.iter().flatten() {
+                writeln!(
+                    f,
+                    "    data[{}..{}]: {} -> {}",
+                    range.offset,
+                    range.offset + range.len,
+                    range.first,
+                    range.second
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for AccountsDiff {}
+impl VerboseDisplay for AccountsDiff {}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_runtime::genesis_utils::create_genesis_config,
+        solana_sdk::account::WritableAccount,
+        std::{collections::BTreeMap, sync::Arc},
+    };
+
+    #[test]
+    fn test_scan_ranges() {
+        let ranges = scan_ranges().collect::<Vec<_>>();
+        assert_eq!(ranges.len(), 4096);
+        assert_eq!(*ranges[0].start(), Pubkey::new_from_array([0x00; 32]));
+        assert_eq!(*ranges[4095].end(), Pubkey::new_from_array([0xff; 32]));
+        // The ranges follow each other without a gap
+        for (range, next_range) in ranges.iter().zip(ranges.iter().skip(1)) {
+            let mut next_start = range.end().to_bytes();
+            for byte in next_start.iter_mut().rev() {
+                let (sum, carry) = byte.overflowing_add(1);
+                *byte = sum;
+                if !carry {
+                    break;
+                }
+            }
+            assert_eq!(Pubkey::new_from_array(next_start), *next_range.start());
+        }
+    }
+
+    #[test]
+    fn test_accounts_diff_banks() {
+        let genesis_config = create_genesis_config(1_000_000).genesis_config;
+        let first_bank = Arc::new(Bank::new_for_tests(&genesis_config));
+        let owner = Pubkey::new_unique();
+        let unchanged = Pubkey::new_unique();
+        let removed = Pubkey::new_unique();
+        let modified = Pubkey::new_unique();
+        // Far apart in pubkey order, so in different scan ranges
+        let added = Pubkey::new_from_array([0xfe; 32]);
+        for pubkey in [unchanged, removed, modified] {
+            first_bank.store_account(&pubkey, &AccountSharedData::new(42, 0, &owner));
+        }
+
+        let second_bank = Bank::new_from_parent(&first_bank, &Pubkey::default(), 1);
+        second_bank.store_account(&removed, &AccountSharedData::default());
+        second_bank.store_account(&modified, &AccountSharedData::new(43, 0, &owner));
+        second_bank.store_account(&added, &AccountSharedData::new(42, 0, &owner));
+
+        let diff = AccountsDiff::new(&first_bank, &second_bank, false, false);
+        assert_eq!(diff.first_slot, 0);
+        assert_eq!(diff.second_slot, 1);
+        assert_eq!(
+            diff.added,
+            vec![AccountEntry {
+                pubkey: added.to_string(),
+                state: (&AccountSharedData::new(42, 0, &owner)).into(),
+            }]
+        );
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].pubkey, removed.to_string());
+        // The sysvars updated by the new bank are left out
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].pubkey, modified.to_string());
+        assert!(!diff.modified[0].data_changed);
+
+        let diff = AccountsDiff::new(&first_bank, &second_bank, true, false);
+        assert!(diff.modified.len() > 1);
+    }
+
+    #[test]
+    fn test_data_diff() {
+        assert!(data_diff(&[1, 2, 3], &[1, 2, 3]).is_empty());
+
+        let ranges = data_diff(
+            &[0; 32],
+            &[
+                0, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7,
+            ],
+        );
+        assert_eq!(
+            ranges,
+            vec![
+                DataDiffRange {
+                    offset: 1,
+                    len: 3,
+                    first: "000000".to_string(),
+                    second: "010001".to_string(),
+                },
+                DataDiffRange {
+                    offset: 20,
+                    len: 12,
+                    first: "000000000000000000000000".to_string(),
+                    second: "07".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_accounts() {
+        let owner = Pubkey::new_unique();
+        let unchanged = Pubkey::new_unique();
+        let removed = Pubkey::new_unique();
+        let added = Pubkey::new_unique();
+        let modified = Pubkey::new_unique();
+        let account = AccountSharedData::new(42, 4, &owner);
+        let mut modified_account = account.clone();
+        modified_account.set_lamports(43);
+        modified_account.data_as_mut_slice()[2] = 1;
+
+        let first_accounts = BTreeMap::from([
+            (unchanged, account.clone()),
+            (removed, account.clone()),
+            (modified, account.clone()),
+        ]);
+        let second_accounts = BTreeMap::from([
+            (unchanged, account.clone()),
+            (added, account.clone()),
+            (modified, modified_account),
+        ]);
+
+        let mut diff = AccountsDiff {
+            first_slot: 1,
+            first_bank_hash: String::default(),
+            second_slot: 2,
+            second_bank_hash: String::default(),
+            added: vec![],
+            removed: vec![],
+            modified: vec![],
+        };
+        diff.compare(first_accounts, second_accounts, true);
+        assert_eq!(
+            diff.added,
+            vec![AccountEntry {
+                pubkey: added.to_string(),
+                state: (&account).into(),
+            }]
+        );
+        assert_eq!(
+            diff.removed,
+            vec![AccountEntry {
+                pubkey: removed.to_string(),
+                state: (&account).into(),
+            }]
+        );
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].pubkey, modified.to_string());
+        assert_eq!(diff.modified[0].second.lamports, 43);
+        assert_eq!(
+            diff.modified[0].data_diff,
+            Some(vec![DataDiffRange {
+                offset: 2,
+                len: 1,
+                first: "00".to_string(),
+                second: "01".to_string(),
+            }])
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! A self-contained archive of confirmed blocks, to move transaction history
+//! between RPC nodes without BigTable.
+//!
+//! The archive is framed as described in `solana_runtime::archive_framing`.
+//! In version 1, its body holds the blocks, each one a zstd compressed
+//! protobuf `ConfirmedBlock` as stored in BigTable, and its index is the
+//! bincode encoded list of (slot, offset, length) of each block, sorted by
+//! slot.
+use {
+    log::*,
+    prost::Message,
+    serde::{Deserialize, Serialize},
+    solana_ledger::blockstore::Blockstore,
+    solana_runtime::archive_framing::{self, ArchiveFrame, Magic, HEADER_LEN},
+    solana_sdk::{
+        clock::Slot,
+        transaction::{MessageHash, SanitizedTransaction, SimpleAddressLoader},
+    },
+    solana_storage_proto::convert::generated,
+    solana_transaction_status::{
+        ConfirmedBlock, TransactionWithStatusMeta, VersionedConfirmedBlock,
+    },
+    std::{
+        convert::TryFrom,
+        fs::File,
+        io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+        path::Path,
+    },
+};
+
+const MAGIC: &Magic = b"SOLBLKAR";
+pub const BLOCK_ARCHIVE_VERSION: u32 = 1;
+const ZSTD_COMPRESSION_LEVEL: i32 = 3;
+
+fn invalid_data<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockArchiveIndexEntry {
+    pub slot: Slot,
+    pub offset: u64,
+    pub len: u64,
+}
+
+pub struct BlockArchiveWriter {
+    writer: BufWriter<File>,
+    offset: u64,
+    index: Vec<BlockArchiveIndexEntry>,
+}
+
+impl BlockArchiveWriter {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        archive_framing::write_header(&mut writer, MAGIC, BLOCK_ARCHIVE_VERSION)?;
+        Ok(Self {
+            writer,
+            offset: HEADER_LEN,
+            index: vec![],
+        })
+    }
+
+    /// Appends a block, blocks must be appended in increasing slot order
+    pub fn append(&mut self, slot: Slot, block: VersionedConfirmedBlock) -> io::Result<()> {
+        if let Some(last) = self.index.last() {
+            if slot <= last.slot {
+                return Err(invalid_data(format!(
+                    "Block {} appended after block {}",
+                    slot, last.slot
+                )));
+            }
+        }
+        let block = generated::ConfirmedBlock::from(block);
+        let compressed =
+            zstd::encode_all(block.encode_to_vec().as_slice(), ZSTD_COMPRESSION_LEVEL)?;
+        self.writer.write_all(&compressed)?;
+        self.index.push(BlockArchiveIndexEntry {
+            slot,
+            offset: self.offset,
+            len: compressed.len() as u64,
+        });
+        self.offset += compressed.len() as u64;
+        Ok(())
+    }
+
+    /// Writes the index and footer, returning the number of blocks archived
+    pub fn finish(mut self) -> io::Result<usize> {
+        let index = bincode::serialize(&self.index).map_err(invalid_data)?;
+        self.writer.write_all(&index)?;
+        archive_framing::write_footer(&mut self.writer, MAGIC, self.offset, index.len() as u64)?;
+        self.writer.flush()?;
+        Ok(self.index.len())
+    }
+}
+
+pub struct BlockArchiveReader {
+    reader: BufReader<File>,
+    version: u32,
+    index: Vec<BlockArchiveIndexEntry>,
+}
+
+impl BlockArchiveReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let frame = ArchiveFrame::read(&mut reader, MAGIC, BLOCK_ARCHIVE_VERSION, "block archive")?;
+        reader.seek(SeekFrom::Start(frame.index_offset))?;
+        let mut index = vec![0u8; frame.index_len as usize];
+        reader.read_exact(&mut index)?;
+        let index: Vec<BlockArchiveIndexEntry> =
+            bincode::deserialize(&index).map_err(invalid_data)?;
+        if !index
+            .iter()
+            .all(|entry| frame.body_contains(entry.offset, entry.len))
+        {
+            return Err(invalid_data("Corrupted block archive index"));
+        }
+
+        Ok(Self {
+            reader,
+            version: frame.version,
+            index,
+        })
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn index(&self) -> &[BlockArchiveIndexEntry] {
+        &self.index
+    }
+
+    /// Reads the block of the given slot, `None` if it is not archived
+    pub fn get_block(&mut self, slot: Slot) -> io::Result<Option<ConfirmedBlock>> {
+        match self.index.binary_search_by_key(&slot, |entry| entry.slot) {
+            Ok(position) => {
+                let entry = self.index[position];
+                self.read_block(&entry).map(Some)
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn read_block(&mut self, entry: &BlockArchiveIndexEntry) -> io::Result<ConfirmedBlock> {
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        let mut compressed = vec![0u8; entry.len as usize];
+        self.reader.read_exact(&mut compressed)?;
+        let encoded = zstd::decode_all(compressed.as_slice())?;
+        let block = generated::ConfirmedBlock::decode(encoded.as_slice()).map_err(invalid_data)?;
+        ConfirmedBlock::try_from(block).map_err(invalid_data)
+    }
+}
+
+/// Writes the rooted blocks of the slot range to an archive, returning the
+/// number of blocks archived. Slots without a rooted block are skipped.
+pub fn export_blocks(
+    blockstore: &Blockstore,
+    starting_slot: Slot,
+    ending_slot: Slot,
+    path: &Path,
+) -> io::Result<usize> {
+    let mut writer = BlockArchiveWriter::create(path)?;
+    let slots = blockstore
+        .rooted_slot_iterator(starting_slot)
+        .map_err(invalid_data)?
+        .take_while(|slot| *slot <= ending_slot);
+    for slot in slots {
+        match blockstore.get_rooted_block(slot, false) {
+            Ok(block) => writer.append(slot, block)?,
+            Err(err) => warn!("Skipping slot {}: {:?}", slot, err),
+        }
+    }
+    writer.finish()
+}
+
+/// Writes the transaction statuses, address signatures, rewards, block time
+/// and block height of the archived blocks to the blockstore, and marks their
+/// slots as roots, returning the number of blocks imported. The archive does
+/// not hold the entries of the blocks, so RPC serves the blocks and their
+/// transactions once the shreds of the slots are in the blockstore as well.
+pub fn import_blocks(blockstore: &Blockstore, path: &Path) -> io::Result<usize> {
+    let mut reader = BlockArchiveReader::open(path)?;
+    let index = reader.index().to_vec();
+    for entry in &index {
+        let slot = entry.slot;
+        let block = reader.read_block(entry)?;
+        for transaction in block.transactions {
+            let transaction = match transaction {
+                TransactionWithStatusMeta::Complete(transaction) => transaction,
+                TransactionWithStatusMeta::MissingMetadata(transaction) => {
+                    warn!(
+                        "Skipping transaction {} of slot {} without status",
+                        transaction.signatures[0], slot
+                    );
+                    continue;
+                }
+            };
+            let meta = transaction.meta;
+            let sanitized_transaction = SanitizedTransaction::try_create(
+                transaction.transaction,
+                MessageHash::Compute,
+                None,
+                SimpleAddressLoader::Enabled(meta.loaded_addresses.clone()),
+            )
+            .map_err(|err| invalid_data(format!("Slot {}: {}", slot, err)))?;
+            let account_locks = sanitized_transaction.get_account_locks_unchecked();
+            blockstore
+                .write_transaction_status(
+                    slot,
+                    *sanitized_transaction.signature(),
+                    account_locks.writable,
+                    account_locks.readonly,
+                    meta,
+                )
+                .map_err(invalid_data)?;
+        }
+        blockstore
+            .write_rewards(slot, block.rewards)
+            .map_err(invalid_data)?;
+        if let Some(block_time) = block.block_time {
+            blockstore
+                .cache_block_time(slot, block_time)
+                .map_err(invalid_data)?;
+        }
+        if let Some(block_height) = block.block_height {
+            blockstore
+                .cache_block_height(slot, block_height)
+                .map_err(invalid_data)?;
+        }
+    }
+    // The archived blocks were rooted in the ledger they were exported from
+    blockstore
+        .set_roots(index.iter().map(|entry| &entry.slot))
+        .map_err(invalid_data)?;
+    Ok(index.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_entry::entry::Entry,
+        solana_ledger::blockstore::entries_to_test_shreds,
+        solana_sdk::{
+            hash::Hash, message::v0::LoadedAddresses, pubkey::Pubkey, signature::Keypair,
+            system_transaction, transaction::Transaction,
+        },
+        solana_transaction_status::{
+            Reward, RewardType, TransactionStatusMeta, VersionedTransactionWithStatusMeta,
+        },
+        tempfile::TempDir,
+    };
+
+    fn test_transaction(slot: Slot) -> Transaction {
+        system_transaction::transfer(
+            &Keypair::new(),
+            &Pubkey::new_unique(),
+            slot,
+            Hash::new_unique(),
+        )
+    }
+
+    fn test_meta(slot: Slot) -> TransactionStatusMeta {
+        TransactionStatusMeta {
+            status: Ok(()),
+            fee: 5000,
+            pre_balances: vec![10_000, 0, 1],
+            post_balances: vec![5000 - slot, slot, 1],
+            inner_instructions: Some(vec![]),
+            log_messages: Some(vec!["log".to_string()]),
+            pre_token_balances: Some(vec![]),
+            post_token_balances: Some(vec![]),
+            rewards: Some(vec![]),
+            loaded_addresses: LoadedAddresses::default(),
+            return_data: None,
+        }
+    }
+
+    fn test_rewards() -> Vec<Reward> {
+        vec![Reward {
+            pubkey: Pubkey::new_unique().to_string(),
+            lamports: 42,
+            post_balance: 42,
+            reward_type: Some(RewardType::Fee),
+            commission: None,
+        }]
+    }
+
+    fn test_block(slot: Slot) -> VersionedConfirmedBlock {
+        VersionedConfirmedBlock {
+            previous_blockhash: Hash::new_unique().to_string(),
+            blockhash: Hash::new_unique().to_string(),
+            parent_slot: slot.saturating_sub(1),
+            transactions: vec![VersionedTransactionWithStatusMeta {
+                transaction: test_transaction(slot).into(),
+                meta: test_meta(slot),
+            }],
+            rewards: test_rewards(),
+            block_time: Some(slot as i64),
+            block_height: Some(slot),
+        }
+    }
+
+    #[test]
+    fn test_block_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("blocks.archive");
+        let blocks: Vec<_> = [1, 2, 5]
+            .iter()
+            .map(|slot| (*slot, test_block(*slot)))
+            .collect();
+
+        let mut writer = BlockArchiveWriter::create(&path).unwrap();
+        for (slot, block) in &blocks {
+            writer.append(*slot, block.clone()).unwrap();
+        }
+        assert!(writer.append(4, test_block(4)).is_err());
+        assert_eq!(writer.finish().unwrap(), 3);
+
+        let mut reader = BlockArchiveReader::open(&path).unwrap();
+        assert_eq!(reader.version(), BLOCK_ARCHIVE_VERSION);
+        assert_eq!(
+            reader
+                .index()
+                .iter()
+                .map(|entry| entry.slot)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 5]
+        );
+        for (slot, block) in blocks {
+            assert_eq!(
+                reader.get_block(slot).unwrap(),
+                Some(ConfirmedBlock::from(block))
+            );
+        }
+        assert_eq!(reader.get_block(3).unwrap(), None);
+
+        // A truncated archive is rejected
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &contents[..contents.len() - 1]).unwrap();
+        assert!(BlockArchiveReader::open(&path).is_err());
+    }
+
+    #[test]
+    fn test_export_import_blocks() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("blocks.archive");
+        let source = Blockstore::open(&temp_dir.path().join("source")).unwrap();
+        let destination = Blockstore::open(&temp_dir.path().join("destination")).unwrap();
+
+        // The destination ledger has the shreds of the blocks, but neither
+        // their roots nor their metadata
+        let slots = [1, 2];
+        let mut signatures = vec![];
+        for slot in slots {
+            let transaction = test_transaction(slot);
+            let entries = vec![Entry::new(&Hash::default(), 1, vec![transaction.clone()])];
+            let shreds = entries_to_test_shreds(&entries, slot, slot - 1, true, 0);
+            source.insert_shreds(shreds.clone(), None, false).unwrap();
+            destination.insert_shreds(shreds, None, false).unwrap();
+
+            let message = &transaction.message;
+            source
+                .write_transaction_status(
+                    slot,
+                    transaction.signatures[0],
+                    vec![&message.account_keys[0], &message.account_keys[1]],
+                    vec![&message.account_keys[2]],
+                    test_meta(slot),
+                )
+                .unwrap();
+            source.write_rewards(slot, test_rewards()).unwrap();
+            source.cache_block_time(slot, slot as i64).unwrap();
+            source.cache_block_height(slot, slot).unwrap();
+            signatures.push(transaction.signatures[0]);
+        }
+        source.set_roots(slots.iter()).unwrap();
+
+        assert_eq!(export_blocks(&source, 1, 2, &path).unwrap(), 2);
+        assert!(destination.get_rooted_block(1, false).is_err());
+        assert_eq!(import_blocks(&destination, &path).unwrap(), 2);
+
+        for (slot, signature) in slots.into_iter().zip(signatures) {
+            assert!(destination.is_root(slot));
+            assert_eq!(
+                destination.get_rooted_block(slot, false).unwrap(),
+                source.get_rooted_block(slot, false).unwrap()
+            );
+            assert_eq!(
+                destination.get_rooted_transaction(signature).unwrap(),
+                source.get_rooted_transaction(signature).unwrap()
+            );
+            assert!(destination
+                .get_rooted_transaction(signature)
+                .unwrap()
+                .is_some());
+        }
+    }
+}
@@ -1,10 +1,13 @@
 #![allow(clippy::integer_arithmetic)]
 use {
-    crate::{bigtable::*, ledger_path::*},
+    crate::{
+        accounts_diff::*, bigtable::*, ledger_path::*, program_profile::*, transaction_trace::*,
+    },
     clap::{
         crate_description, crate_name, value_t, value_t_or_exit, values_t_or_exit, App,
         AppSettings, Arg, ArgMatches, SubCommand,
     },
+    crossbeam_channel::unbounded,
     dashmap::DashMap,
     itertools::Itertools,
     log::*,
@@ -17,6 +20,7 @@ use {
             is_parsable, is_pow2, is_pubkey, is_pubkey_or_keypair, is_slot, is_valid_percentage,
         },
     },
+    solana_cli_output::OutputFormat,
     solana_core::system_monitor_service::SystemMonitorService,
     solana_entry::entry::Entry,
     solana_ledger::{
@@ -27,7 +31,7 @@ use {
             self, AccessType, BlockstoreOptions, BlockstoreRecoveryMode, Database,
             LedgerColumnOptions,
         },
-        blockstore_processor::{BlockstoreProcessorError, ProcessOptions},
+        blockstore_processor::{BlockstoreProcessorError, ProcessOptions, TransactionTraceSender},
         shred::Shred,
     },
     solana_measure::measure::Measure,
@@ -40,7 +44,9 @@ use {
         cost_tracker::CostTracker,
         hardened_unpack::{open_genesis_config, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
         runtime_config::RuntimeConfig,
-        snapshot_archive_info::SnapshotArchiveInfoGetter,
+        snapshot_archive_info::{
+            FullSnapshotArchiveInfo, IncrementalSnapshotArchiveInfo, SnapshotArchiveInfoGetter,
+        },
         snapshot_config::SnapshotConfig,
         snapshot_hash::StartingSnapshotHashes,
         snapshot_utils::{
@@ -73,7 +79,7 @@ use {
         collections::{BTreeMap, BTreeSet, HashMap, HashSet},
         ffi::OsStr,
         fs::File,
-        io::{self, stdout, BufRead, BufReader, Write},
+        io::{self, stdout, BufRead, BufReader, BufWriter, Write},
         path::{Path, PathBuf},
         process::{exit, Command, Stdio},
         str::FromStr,
@@ -84,8 +90,12 @@ use {
     },
 };
 
+mod accounts_diff;
 mod bigtable;
+mod block_archive;
 mod ledger_path;
+mod program_profile;
+mod transaction_trace;
 
 #[derive(PartialEq)]
 enum LedgerOutputMethod {
@@ -729,13 +739,49 @@ fn load_bank_forks(
     process_options: ProcessOptions,
     snapshot_archive_path: Option<PathBuf>,
 ) -> Result<(Arc<RwLock<BankForks>>, Option<StartingSnapshotHashes>), BlockstoreProcessorError> {
-    let bank_snapshots_dir = blockstore
-        .ledger_path()
-        .join(if blockstore.is_primary_access() {
-            "snapshot"
-        } else {
-            "snapshot.ledger-tool"
-        });
+    load_bank_forks_in_dirs(
+        arg_matches,
+        genesis_config,
+        blockstore,
+        process_options,
+        snapshot_archive_path,
+        None,
+    )
+}
+
+/// Appends `.<suffix>` to the last component of a path
+fn with_dir_suffix(path: PathBuf, suffix: Option<&str>) -> PathBuf {
+    match suffix {
+        Some(suffix) => {
+            let mut path = path.into_os_string();
+            path.push(format!(".{}", suffix));
+            PathBuf::from(path)
+        }
+        None => path,
+    }
+}
+
+/// Loads bank forks like `load_bank_forks`, with `dirs_suffix` appended to the
+/// accounts and bank snapshots directories, so that the banks loaded with
+/// different suffixes can be kept around at the same time
+fn load_bank_forks_in_dirs(
+    arg_matches: &ArgMatches,
+    genesis_config: &GenesisConfig,
+    blockstore: &Blockstore,
+    process_options: ProcessOptions,
+    snapshot_archive_path: Option<PathBuf>,
+    dirs_suffix: Option<&str>,
+) -> Result<(Arc<RwLock<BankForks>>, Option<StartingSnapshotHashes>), BlockstoreProcessorError> {
+    let bank_snapshots_dir = with_dir_suffix(
+        blockstore
+            .ledger_path()
+            .join(if blockstore.is_primary_access() {
+                "snapshot"
+            } else {
+                "snapshot.ledger-tool"
+            }),
+        dirs_suffix,
+    );
     let snapshot_config = if arg_matches.is_present("no_snapshot") {
         None
     } else {
@@ -767,6 +813,10 @@ fn load_bank_forks(
         );
         vec![non_primary_accounts_path]
     };
+    let account_paths = account_paths
+        .into_iter()
+        .map(|account_path| with_dir_suffix(account_path, dirs_suffix))
+        .collect();
 
     bank_forks_utils::load(
         genesis_config,
@@ -1093,7 +1143,7 @@ fn main() {
                 .takes_value(true)
                 .possible_values(&["json", "json-compact"])
                 .help("Return information in specified output format, \
-                       currently only available for bigtable subcommands"),
+                       currently only available for bigtable and diff-accounts subcommands"),
         )
         .arg(
             Arg::with_name("verbose")
@@ -1487,6 +1537,46 @@ fn main() {
                           base for the incremental snapshot.")
                     .conflicts_with("no_snapshot")
             )
+            .arg(
+                Arg::with_name("chained")
+                    .long("chained")
+                    .takes_value(false)
+                    .requires("incremental")
+                    .help("Base the incremental snapshot on the last incremental snapshot of the \
+                          chain that the ledger is loaded from, instead of on the full snapshot.")
+            )
+        ).subcommand(
+            SubCommand::with_name("verify-snapshot")
+            .about("Verify a snapshot archive without loading its bank: check the archive's \
+                    contents, the bank fields, the status cache, the accounts hash and the \
+                    hash in the archive's name")
+            .arg(
+                Arg::with_name("full_snapshot_archive")
+                    .index(1)
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Full snapshot archive to verify"),
+            )
+            .arg(
+                Arg::with_name("incremental_snapshot_archive")
+                    .long("incremental-snapshot-archive")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Verify this incremental snapshot archive on top of the full \
+                           snapshot archive. Repeat for each incremental snapshot of a \
+                           chain, in order"),
+            )
+            .arg(
+                Arg::with_name("unpack_dir")
+                    .long("unpack-dir")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .help("Unpack the archives in a temporary directory of DIR \
+                           [default: the directory of the full snapshot archive]"),
+            )
         ).subcommand(
             SubCommand::with_name("accounts")
             .about("Print account stats and contents after processing the ledger")
@@ -1512,6 +1602,172 @@ fn main() {
                 .help("Do not print account data when printing account contents."),
             )
             .arg(&max_genesis_archive_unpacked_size_arg)
+        ).subcommand(
+            SubCommand::with_name("diff-accounts")
+            .about("Print the accounts added, removed and modified between two banks, \
+                    each loaded from a snapshot archive or by replaying the ledger up to a slot")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("first_slot")
+                    .long("first-slot")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required_unless("first_snapshot_archive_path")
+                    .help("Load the first bank by processing the ledger up to SLOT"),
+            )
+            .arg(
+                Arg::with_name("first_snapshot_archive_path")
+                    .long("first-snapshot-archive-path")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .help("Load the first bank from the latest snapshot archive in DIR, \
+                           then up to --first-slot if given"),
+            )
+            .arg(
+                Arg::with_name("second_slot")
+                    .long("second-slot")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required_unless("second_snapshot_archive_path")
+                    .help("Load the second bank by processing the ledger up to SLOT"),
+            )
+            .arg(
+                Arg::with_name("second_snapshot_archive_path")
+                    .long("second-snapshot-archive-path")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .help("Load the second bank from the latest snapshot archive in DIR, \
+                           then up to --second-slot if given"),
+            )
+            .arg(
+                Arg::with_name("include_sysvars")
+                    .long("include-sysvars")
+                    .takes_value(false)
+                    .help("Compare sysvars too"),
+            )
+            .arg(
+                Arg::with_name("data_diff")
+                    .long("data-diff")
+                    .takes_value(false)
+                    .help("Print the ranges of data that differ in modified accounts"),
+            )
+        ).subcommand(
+            SubCommand::with_name("trace-slot")
+            .about("Replay a slot and output a JSON trace of its transactions, with the \
+                    accounts they reference before and after their execution, their compute \
+                    units, logs and result")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("slot")
+                    .index(1)
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Slot to replay, which must come after the snapshot the ledger \
+                           is loaded from, if any"),
+            )
+            .arg(
+                Arg::with_name("output_file")
+                    .long("output-file")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .help("Write the trace to FILE instead of stdout"),
+            )
+        ).subcommand(
+            SubCommand::with_name("export-blocks")
+            .about("Export the rooted blocks of a slot range, with their transaction statuses, \
+                    rewards and block times, to a block archive")
+            .arg(
+                Arg::with_name("start_slot")
+                    .long("start-slot")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required(true)
+                    .help("First slot to export"),
+            )
+            .arg(
+                Arg::with_name("end_slot")
+                    .long("end-slot")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Last slot to export"),
+            )
+            .arg(
+                Arg::with_name("output_file")
+                    .long("output-file")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Block archive to create"),
+            )
+        ).subcommand(
+            SubCommand::with_name("import-blocks")
+            .about("Import the transaction statuses, address signatures, rewards and block \
+                    times of the blocks of a block archive into the ledger, and mark their \
+                    slots as roots. The ledger needs the shreds of the blocks to serve them")
+            .arg(
+                Arg::with_name("archive")
+                    .index(1)
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Block archive created by export-blocks"),
+            )
+        ).subcommand(
+            SubCommand::with_name("profile-programs")
+            .about("Replay a range of slots and output, per program, the number of \
+                    invocations, the compute units consumed, the errors and the CPI depths")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("start_slot")
+                    .long("start-slot")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required(true)
+                    .help("First slot to profile, which must come after the snapshot the \
+                           ledger is loaded from, if any"),
+            )
+            .arg(
+                Arg::with_name("end_slot")
+                    .long("end-slot")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Last slot to profile, replay stops there"),
+            )
+            .arg(
+                Arg::with_name("format")
+                    .long("format")
+                    .value_name("FORMAT")
+                    .takes_value(true)
+                    .possible_values(&["json", "csv"])
+                    .default_value("json")
+                    .help("Output format"),
+            )
+            .arg(
+                Arg::with_name("output_file")
+                    .long("output-file")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .help("Write the profile to FILE instead of stdout"),
+            )
         ).subcommand(
             SubCommand::with_name("capitalization")
             .about("Print capitalization (aka, total supply) while checksumming it")
@@ -2268,6 +2524,10 @@ fn main() {
                     wal_recovery_mode,
                 );
                 let is_incremental = arg_matches.is_present("incremental");
+                let is_chained = arg_matches.is_present("chained");
+                let snapshot_archives_dir = snapshot_archive_path
+                    .clone()
+                    .unwrap_or_else(|| ledger_path.clone());
 
                 let snapshot_slot = if Some("ROOT") == arg_matches.value_of("snapshot_slot") {
                     blockstore
@@ -2525,12 +2785,36 @@ fn main() {
                                 );
                                 exit(1);
                             }
+                            let base_slot = if is_chained {
+                                snapshot_utils::get_incremental_snapshot_archive_chain(
+                                    &snapshot_archives_dir,
+                                    full_snapshot_slot,
+                                )
+                                .last()
+                                .map_or(
+                                    full_snapshot_slot,
+                                    |incremental_snapshot_archive_info| {
+                                        incremental_snapshot_archive_info.slot()
+                                    },
+                                )
+                            } else {
+                                full_snapshot_slot
+                            };
+                            if bank.slot() <= base_slot {
+                                eprintln!(
+                                    "Unable to create incremental snapshot: Slot must be greater than the last incremental snapshot slot. slot: {}, last incremental snapshot slot: {}",
+                                    bank.slot(),
+                                    base_slot,
+                                );
+                                exit(1);
+                            }
 
                             let incremental_snapshot_archive_info =
-                                snapshot_utils::bank_to_incremental_snapshot_archive(
+                                snapshot_utils::bank_to_chained_incremental_snapshot_archive(
                                     ledger_path,
                                     &bank,
                                     full_snapshot_slot,
+                                    base_slot,
                                     Some(snapshot_version),
                                     output_directory,
                                     ArchiveFormat::TarZstd,
@@ -2546,7 +2830,7 @@ fn main() {
                                 "Successfully created incremental snapshot for slot {}, hash {}, base slot: {}: {}",
                                 bank.slot(),
                                 bank.hash(),
-                                full_snapshot_slot,
+                                base_slot,
                                 incremental_snapshot_archive_info.path().display(),
                             );
                         } else {
@@ -2587,6 +2871,70 @@ fn main() {
                     }
                 }
             }
+            ("verify-snapshot", Some(arg_matches)) => {
+                let full_snapshot_archive_path = PathBuf::from(value_t_or_exit!(
+                    arg_matches,
+                    "full_snapshot_archive",
+                    String
+                ));
+                let full_snapshot_archive_info =
+                    FullSnapshotArchiveInfo::new_from_path(full_snapshot_archive_path.clone())
+                        .unwrap_or_else(|err| {
+                            eprintln!("Invalid full snapshot archive: {}", err);
+                            exit(1);
+                        });
+                let incremental_snapshot_archive_infos = arg_matches
+                    .values_of("incremental_snapshot_archive")
+                    .map(|paths| {
+                        paths
+                            .map(|path| {
+                                IncrementalSnapshotArchiveInfo::new_from_path(PathBuf::from(path))
+                                    .unwrap_or_else(|err| {
+                                        eprintln!("Invalid incremental snapshot archive: {}", err);
+                                        exit(1);
+                                    })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                let unpack_dir = arg_matches
+                    .value_of("unpack_dir")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| {
+                        full_snapshot_archive_path
+                            .parent()
+                            .map(Path::to_path_buf)
+                            .unwrap_or_default()
+                    });
+
+                let mut measure = Measure::start("verify snapshot");
+                match snapshot_utils::verify_snapshot_archives(
+                    &unpack_dir,
+                    &full_snapshot_archive_info,
+                    &incremental_snapshot_archive_infos,
+                ) {
+                    Ok(verified_snapshot) => {
+                        measure.stop();
+                        println!(
+                            "Verified snapshot for slot {} in {}ms",
+                            verified_snapshot.slot,
+                            measure.as_ms()
+                        );
+                        println!("Bank hash: {}", verified_snapshot.bank_hash);
+                        println!("Accounts hash: {}", verified_snapshot.accounts_hash);
+                        println!("Capitalization: {}", Sol(verified_snapshot.capitalization));
+                        println!("Storages: {}", verified_snapshot.num_storages);
+                        println!(
+                            "Status cache slots: {}",
+                            verified_snapshot.num_status_cache_slots
+                        );
+                    }
+                    Err(err) => {
+                        eprintln!("Snapshot verification failed: {}", err);
+                        exit(1);
+                    }
+                }
+            }
             ("accounts", Some(arg_matches)) => {
                 let halt_at_slot = value_t!(arg_matches, "halt_at_slot", Slot).ok();
                 let process_options = ProcessOptions {
@@ -2650,6 +2998,242 @@ fn main() {
 
                 println!("{:#?}", total_accounts_stats);
             }
+            ("diff-accounts", Some(arg_matches)) => {
+                let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+                let include_sysvars = arg_matches.is_present("include_sysvars");
+                let blockstore = open_blockstore(
+                    &ledger_path,
+                    AccessType::TryPrimaryThenSecondary,
+                    wal_recovery_mode,
+                );
+                let output_format = OutputFormat::from_matches(arg_matches, "output_format", false);
+
+                // The accounts of both banks are scanned side by side, so the
+                // second bank is loaded in its own accounts and snapshot directories
+                let load_bank = |side: &str, dirs_suffix: Option<&str>| {
+                    let side_snapshot_archive_path = arg_matches
+                        .value_of(&format!("{}_snapshot_archive_path", side))
+                        .map(PathBuf::from);
+                    let halt_at_slot = value_t!(arg_matches, &format!("{}_slot", side), Slot)
+                        .ok()
+                        .or_else(|| {
+                            let snapshot_archives_dir = side_snapshot_archive_path.as_ref()?;
+                            let full_snapshot_slot =
+                                snapshot_utils::get_highest_full_snapshot_archive_slot(
+                                    snapshot_archives_dir,
+                                )
+                                .unwrap_or_else(|| {
+                                    eprintln!(
+                                        "No snapshot archive found in {:?}",
+                                        snapshot_archives_dir
+                                    );
+                                    exit(1);
+                                });
+                            Some(
+                                snapshot_utils::get_highest_incremental_snapshot_archive_slot(
+                                    snapshot_archives_dir,
+                                    full_snapshot_slot,
+                                )
+                                .unwrap_or(full_snapshot_slot),
+                            )
+                        })
+                        .unwrap();
+                    let process_options = ProcessOptions {
+                        new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                        halt_at_slot: Some(halt_at_slot),
+                        poh_verify: false,
+                        ..ProcessOptions::default()
+                    };
+                    let (bank_forks, ..) = load_bank_forks_in_dirs(
+                        arg_matches,
+                        &genesis_config,
+                        &blockstore,
+                        process_options,
+                        side_snapshot_archive_path.or_else(|| snapshot_archive_path.clone()),
+                        dirs_suffix,
+                    )
+                    .unwrap_or_else(|err| {
+                        eprintln!("Failed to load ledger: {:?}", err);
+                        exit(1);
+                    });
+                    let bank = bank_forks.read().unwrap().get(halt_at_slot);
+                    bank.unwrap_or_else(|| {
+                        eprintln!("Error: Slot {} is not available", halt_at_slot);
+                        exit(1);
+                    })
+                };
+
+                let first_bank = load_bank("first", None);
+                let second_bank = load_bank("second", Some("diff-accounts-second"));
+
+                let mut measure = Measure::start("comparing the accounts of the banks");
+                let diff = AccountsDiff::new(
+                    &first_bank,
+                    &second_bank,
+                    include_sysvars,
+                    arg_matches.is_present("data_diff"),
+                );
+                measure.stop();
+                info!("{}", measure);
+                println!("{}", output_format.formatted_string(&diff));
+            }
+            ("trace-slot", Some(arg_matches)) => {
+                let slot = value_t_or_exit!(arg_matches, "slot", Slot);
+                let (transaction_trace_sender, transaction_trace_receiver) = unbounded();
+                let process_options = ProcessOptions {
+                    new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                    halt_at_slot: Some(slot),
+                    poh_verify: false,
+                    transaction_trace_sender: Some(TransactionTraceSender {
+                        slot,
+                        sender: transaction_trace_sender,
+                    }),
+                    ..ProcessOptions::default()
+                };
+                let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+                let blockstore = open_blockstore(
+                    &ledger_path,
+                    AccessType::TryPrimaryThenSecondary,
+                    wal_recovery_mode,
+                );
+                let (bank_forks, ..) = load_bank_forks(
+                    arg_matches,
+                    &genesis_config,
+                    &blockstore,
+                    process_options,
+                    snapshot_archive_path,
+                )
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to load ledger: {:?}", err);
+                    exit(1);
+                });
+                let bank = bank_forks.read().unwrap().get(slot).unwrap_or_else(|| {
+                    eprintln!("Error: Slot {} is not available", slot);
+                    exit(1);
+                });
+                let entries = blockstore.get_slot_entries(slot, 0).unwrap_or_else(|err| {
+                    eprintln!("Failed to load entries of slot {}: {:?}", slot, err);
+                    exit(1);
+                });
+                let traces = transaction_trace_receiver.try_iter().collect();
+                let slot_trace = SlotTrace::new(&bank, &entries, traces).unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    exit(1);
+                });
+
+                let result = match arg_matches.value_of("output_file") {
+                    Some(output_file) => File::create(output_file).and_then(|file| {
+                        serde_json::to_writer_pretty(file, &slot_trace).map_err(io::Error::from)
+                    }),
+                    None => {
+                        serde_json::to_writer_pretty(stdout(), &slot_trace).map_err(io::Error::from)
+                    }
+                };
+                if let Err(err) = result {
+                    eprintln!("Failed to write the trace: {}", err);
+                    exit(1);
+                }
+            }
+            ("export-blocks", Some(arg_matches)) => {
+                let start_slot = value_t_or_exit!(arg_matches, "start_slot", Slot);
+                let end_slot = value_t_or_exit!(arg_matches, "end_slot", Slot);
+                let output_file =
+                    PathBuf::from(value_t_or_exit!(arg_matches, "output_file", String));
+                if start_slot > end_slot {
+                    eprintln!("Error: --start-slot must not be greater than --end-slot");
+                    exit(1);
+                }
+                let blockstore = open_blockstore(
+                    &ledger_path,
+                    AccessType::TryPrimaryThenSecondary,
+                    wal_recovery_mode,
+                );
+                match block_archive::export_blocks(&blockstore, start_slot, end_slot, &output_file)
+                {
+                    Ok(block_count) => println!(
+                        "Exported {} blocks to {}",
+                        block_count,
+                        output_file.display()
+                    ),
+                    Err(err) => {
+                        eprintln!("Failed to export blocks: {}", err);
+                        exit(1);
+                    }
+                }
+            }
+            ("import-blocks", Some(arg_matches)) => {
+                let archive = PathBuf::from(value_t_or_exit!(arg_matches, "archive", String));
+                let blockstore =
+                    open_blockstore(&ledger_path, AccessType::PrimaryOnly, wal_recovery_mode);
+                match block_archive::import_blocks(&blockstore, &archive) {
+                    Ok(block_count) => println!("Imported {} blocks", block_count),
+                    Err(err) => {
+                        eprintln!("Failed to import blocks: {}", err);
+                        exit(1);
+                    }
+                }
+            }
+            ("profile-programs", Some(arg_matches)) => {
+                let start_slot = value_t_or_exit!(arg_matches, "start_slot", Slot);
+                let end_slot = value_t_or_exit!(arg_matches, "end_slot", Slot);
+                if start_slot > end_slot {
+                    eprintln!("Error: --start-slot must not be greater than --end-slot");
+                    exit(1);
+                }
+                let (program_profile_sender, program_profile_receiver) = unbounded();
+                let process_options = ProcessOptions {
+                    new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                    halt_at_slot: Some(end_slot),
+                    poh_verify: false,
+                    program_profile_sender: Some(program_profile_sender),
+                    ..ProcessOptions::default()
+                };
+                let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+                let blockstore = open_blockstore(
+                    &ledger_path,
+                    AccessType::TryPrimaryThenSecondary,
+                    wal_recovery_mode,
+                );
+                let (bank_forks, ..) = load_bank_forks(
+                    arg_matches,
+                    &genesis_config,
+                    &blockstore,
+                    process_options,
+                    snapshot_archive_path,
+                )
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to load ledger: {:?}", err);
+                    exit(1);
+                });
+                let root_slot = bank_forks.read().unwrap().root();
+                let report = ProgramProfileReport::new(
+                    start_slot,
+                    end_slot,
+                    program_profile_receiver.try_iter(),
+                );
+                if report.slot_count == 0 {
+                    eprintln!(
+                        "Error: No slot between {} and {} was replayed, the ledger was loaded \
+                         up to slot {}",
+                        start_slot, end_slot, root_slot
+                    );
+                    exit(1);
+                }
+
+                let write_report = |writer: Box<dyn Write>| match arg_matches.value_of("format") {
+                    Some("csv") => report.write_csv(writer),
+                    _ => report.write_json(writer),
+                };
+                let result = match arg_matches.value_of("output_file") {
+                    Some(output_file) => File::create(output_file)
+                        .and_then(|file| write_report(Box::new(BufWriter::new(file)))),
+                    None => write_report(Box::new(stdout())),
+                };
+                if let Err(err) = result {
+                    eprintln!("Failed to write the profile: {}", err);
+                    exit(1);
+                }
+            }
             ("capitalization", Some(arg_matches)) => {
                 let halt_at_slot = value_t!(arg_matches, "halt_at_slot", Slot).ok();
                 let process_options = ProcessOptions {
@@ -0,0 +1,247 @@
+//! Aggregating the program profiles collected while replaying a range of
+//! slots into per program statistics.
+use {
+    serde::Serialize,
+    solana_program_runtime::timings::ProgramProfile,
+    solana_sdk::{clock::Slot, pubkey::Pubkey},
+    std::{
+        collections::{BTreeMap, HashMap},
+        fmt::Display,
+        io::{self, Write},
+    },
+};
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramStats {
+    pub program_id: String,
+    pub invocations: u64,
+    pub compute_units_total: u64,
+    pub compute_units_mean: f64,
+    pub compute_units_p50: u64,
+    pub compute_units_p99: u64,
+    pub compute_units_max: u64,
+    /// Number of failed invocations per `InstructionError` variant
+    pub errors: BTreeMap<String, u64>,
+    /// Number of invocations per CPI depth, 0 being top level instructions
+    pub invocations_by_cpi_depth: BTreeMap<usize, u64>,
+}
+
+/// Returns the nearest-rank percentile of sorted values
+fn percentile(sorted_values: &[u64], percentile: usize) -> u64 {
+    if sorted_values.is_empty() {
+        return 0;
+    }
+    let rank = (percentile * sorted_values.len() + 99) / 100;
+    sorted_values[rank.saturating_sub(1).min(sorted_values.len() - 1)]
+}
+
+impl ProgramStats {
+    fn new(program_id: &Pubkey, mut profile: ProgramProfile) -> Self {
+        profile.invocation_units.sort_unstable();
+        let units = &profile.invocation_units;
+        let compute_units_total = units.iter().sum::<u64>();
+        Self {
+            program_id: program_id.to_string(),
+            invocations: units.len() as u64,
+            compute_units_total,
+            compute_units_mean: if units.is_empty() {
+                0.0
+            } else {
+                compute_units_total as f64 / units.len() as f64
+            },
+            compute_units_p50: percentile(units, 50),
+            compute_units_p99: percentile(units, 99),
+            compute_units_max: units.last().copied().unwrap_or_default(),
+            errors: profile.errors.into_iter().collect(),
+            invocations_by_cpi_depth: profile.invocations_by_cpi_depth,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramProfileReport {
+    pub start_slot: Slot,
+    pub end_slot: Slot,
+    /// Number of slots of the range that were replayed
+    pub slot_count: usize,
+    /// Programs sorted by decreasing total compute units
+    pub programs: Vec<ProgramStats>,
+}
+
+impl ProgramProfileReport {
+    /// Aggregates the program profiles of the slots within the range
+    pub fn new(
+        start_slot: Slot,
+        end_slot: Slot,
+        slot_profiles: impl IntoIterator<Item = (Slot, HashMap<Pubkey, ProgramProfile>)>,
+    ) -> Self {
+        let mut slot_count = 0;
+        let mut profiles = HashMap::<Pubkey, ProgramProfile>::new();
+        for (_slot, slot_profiles) in slot_profiles
+            .into_iter()
+            .filter(|(slot, _)| (start_slot..=end_slot).contains(slot))
+        {
+            slot_count += 1;
+            for (program_id, profile) in slot_profiles {
+                profiles.entry(program_id).or_default().accumulate(&profile);
+            }
+        }
+        let mut programs: Vec<_> = profiles
+            .into_iter()
+            .map(|(program_id, profile)| ProgramStats::new(&program_id, profile))
+            .collect();
+        programs.sort_by(|a, b| {
+            b.compute_units_total
+                .cmp(&a.compute_units_total)
+                .then_with(|| a.program_id.cmp(&b.program_id))
+        });
+        Self {
+            start_slot,
+            end_slot,
+            slot_count,
+            programs,
+        }
+    }
+
+    pub fn write_json<W: Write>(&self, writer: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(writer, self).map_err(io::Error::from)
+    }
+
+    /// Writes a CSV row per program, the error and CPI depth counts being
+    /// `key:count` pairs separated by `;`
+    pub fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        #[derive(Serialize)]
+        struct ProgramRecord<'a> {
+            program_id: &'a str,
+            invocations: u64,
+            compute_units_total: u64,
+            compute_units_mean: f64,
+            compute_units_p50: u64,
+            compute_units_p99: u64,
+            compute_units_max: u64,
+            errors: String,
+            invocations_by_cpi_depth: String,
+        }
+
+        fn counts<K: Display>(counts: impl Iterator<Item = (K, u64)>) -> String {
+            counts
+                .map(|(key, count)| format!("{}:{}", key, count))
+                .collect::<Vec<_>>()
+                .join(";")
+        }
+
+        let mut csv_writer = csv::WriterBuilder::new().from_writer(writer);
+        for program in &self.programs {
+            csv_writer
+                .serialize(ProgramRecord {
+                    program_id: &program.program_id,
+                    invocations: program.invocations,
+                    compute_units_total: program.compute_units_total,
+                    compute_units_mean: program.compute_units_mean,
+                    compute_units_p50: program.compute_units_p50,
+                    compute_units_p99: program.compute_units_p99,
+                    compute_units_max: program.compute_units_max,
+                    errors: counts(program.errors.iter().map(|(k, v)| (k, *v))),
+                    invocations_by_cpi_depth: counts(
+                        program
+                            .invocations_by_cpi_depth
+                            .iter()
+                            .map(|(k, v)| (k, *v)),
+                    ),
+                })
+                .map_err(io::Error::from)?;
+        }
+        csv_writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&[], 50), 0);
+        assert_eq!(percentile(&[7], 99), 7);
+        let values: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&values, 50), 50);
+        assert_eq!(percentile(&values, 99), 99);
+        assert_eq!(percentile(&values[..10], 99), 10);
+    }
+
+    #[test]
+    fn test_program_profile_report() {
+        let program_a = Pubkey::new_unique();
+        let program_b = Pubkey::new_unique();
+        let profile =
+            |units: Vec<u64>, errors: Vec<(&str, u64)>, depths: Vec<(usize, u64)>| ProgramProfile {
+                invocation_units: units,
+                errors: errors
+                    .into_iter()
+                    .map(|(error, count)| (error.to_string(), count))
+                    .collect(),
+                invocations_by_cpi_depth: depths.into_iter().collect(),
+            };
+        let slot_profiles = vec![
+            (
+                1,
+                HashMap::from([(program_a, profile(vec![1_000_000], vec![], vec![(0, 1)]))]),
+            ),
+            (
+                2,
+                HashMap::from([
+                    (
+                        program_a,
+                        profile(vec![300, 100], vec![("Custom", 1)], vec![(0, 1), (1, 1)]),
+                    ),
+                    (program_b, profile(vec![200], vec![], vec![(0, 1)])),
+                ]),
+            ),
+            (
+                3,
+                HashMap::from([(program_b, profile(vec![400], vec![], vec![(0, 1)]))]),
+            ),
+        ];
+
+        let report = ProgramProfileReport::new(2, 3, slot_profiles);
+        assert_eq!(report.slot_count, 2);
+        assert_eq!(
+            report.programs,
+            vec![
+                ProgramStats {
+                    program_id: program_b.to_string(),
+                    invocations: 2,
+                    compute_units_total: 600,
+                    compute_units_mean: 300.0,
+                    compute_units_p50: 200,
+                    compute_units_p99: 400,
+                    compute_units_max: 400,
+                    errors: BTreeMap::new(),
+                    invocations_by_cpi_depth: BTreeMap::from([(0, 2)]),
+                },
+                ProgramStats {
+                    program_id: program_a.to_string(),
+                    invocations: 2,
+                    compute_units_total: 400,
+                    compute_units_mean: 200.0,
+                    compute_units_p50: 100,
+                    compute_units_p99: 300,
+                    compute_units_max: 300,
+                    errors: BTreeMap::from([("Custom".to_string(), 1)]),
+                    invocations_by_cpi_depth: BTreeMap::from([(0, 1), (1, 1)]),
+                },
+            ]
+        );
+
+        let mut csv = vec![];
+        report.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("program_id,invocations,compute_units_total,"));
+        assert!(csv.contains(&format!(
+            "{},2,400,200.0,100,300,300,Custom:1,0:1;1:1",
+            program_a
+        )));
+    }
+}
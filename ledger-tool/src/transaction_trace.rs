@@ -0,0 +1,273 @@
+//! The JSON trace of the transactions replayed in a slot, meant to be diffed
+//! between runs of different validator versions.
+use {
+    serde::Serialize,
+    solana_entry::entry::Entry,
+    solana_ledger::blockstore_processor::TransactionTrace,
+    solana_runtime::bank::{Bank, TransactionExecutionResult},
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount},
+        clock::Slot,
+        signature::Signature,
+        transaction::TransactionError,
+    },
+    std::collections::HashMap,
+};
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountStateTrace {
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    /// The account data, base64 encoded
+    pub data: String,
+}
+
+impl From<&AccountSharedData> for AccountStateTrace {
+    fn from(account: &AccountSharedData) -> Self {
+        Self {
+            lamports: account.lamports(),
+            owner: account.owner().to_string(),
+            executable: account.executable(),
+            rent_epoch: account.rent_epoch(),
+            data: base64::encode(account.data()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountTrace {
+    pub pubkey: String,
+    pub writable: bool,
+    /// The account state before the transaction, `None` if the account
+    /// did not exist
+    pub pre: Option<AccountStateTrace>,
+    /// The account state after the transaction, `None` if the account does
+    /// not exist
+    pub post: Option<AccountStateTrace>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionTraceOutput {
+    /// The index of the transaction in the block
+    pub index: usize,
+    pub signature: String,
+    /// Whether the transaction was executed, and so committed with its fee
+    pub executed: bool,
+    pub err: Option<TransactionError>,
+    pub compute_units_consumed: u64,
+    pub log_messages: Option<Vec<String>>,
+    pub accounts: Vec<AccountTrace>,
+}
+
+impl TransactionTraceOutput {
+    fn new(index: usize, trace: TransactionTrace) -> Self {
+        let message = trace.transaction.message();
+        let accounts = trace
+            .pre_accounts
+            .into_iter()
+            .zip(trace.post_accounts)
+            .enumerate()
+            .map(|(i, ((pubkey, pre), (_, post)))| AccountTrace {
+                pubkey: pubkey.to_string(),
+                writable: message.is_writable(i),
+                pre: pre.as_ref().map(AccountStateTrace::from),
+                post: post.as_ref().map(AccountStateTrace::from),
+            })
+            .collect();
+        let (executed, err, compute_units_consumed, log_messages) = match trace.execution_result {
+            TransactionExecutionResult::Executed(details) => (
+                true,
+                details.status.err(),
+                details.executed_units,
+                details.log_messages,
+            ),
+            TransactionExecutionResult::NotExecuted(err) => (false, Some(err), 0, None),
+        };
+        Self {
+            index,
+            signature: trace.transaction.signature().to_string(),
+            executed,
+            err,
+            compute_units_consumed,
+            log_messages,
+            accounts,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotTrace {
+    pub slot: Slot,
+    pub parent_slot: Slot,
+    pub bank_hash: String,
+    pub transactions: Vec<TransactionTraceOutput>,
+}
+
+impl SlotTrace {
+    /// Orders the traces of the transactions replayed in the bank's slot as
+    /// they appear in the block. Fails if a transaction of the block was not
+    /// traced, which happens when the slot was not replayed.
+    pub fn new(
+        bank: &Bank,
+        entries: &[Entry],
+        traces: Vec<TransactionTrace>,
+    ) -> Result<Self, String> {
+        let mut traces: HashMap<Signature, TransactionTrace> = traces
+            .into_iter()
+            .map(|trace| (*trace.transaction.signature(), trace))
+            .collect();
+        let transactions = entries
+            .iter()
+            .flat_map(|entry| entry.transactions.iter())
+            .enumerate()
+            .map(|(index, transaction)| {
+                let signature = transaction.signatures[0];
+                traces
+                    .remove(&signature)
+                    .map(|trace| TransactionTraceOutput::new(index, trace))
+                    .ok_or_else(|| {
+                        format!(
+                            "Transaction {} of slot {} was not replayed",
+                            signature,
+                            bank.slot()
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            slot: bank.slot(),
+            parent_slot: bank.parent_slot(),
+            bank_hash: bank.hash().to_string(),
+            transactions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_runtime::genesis_utils::create_genesis_config,
+        solana_sdk::{
+            hash::Hash,
+            pubkey::Pubkey,
+            signature::{Keypair, Signer},
+            system_program, system_transaction,
+            transaction::{SanitizedTransaction, Transaction},
+        },
+    };
+
+    fn transfer(lamports: u64) -> Transaction {
+        system_transaction::transfer(
+            &Keypair::new(),
+            &Pubkey::new_unique(),
+            lamports,
+            Hash::default(),
+        )
+    }
+
+    fn trace(transaction: &Transaction) -> TransactionTrace {
+        let accounts = transaction
+            .message
+            .account_keys
+            .iter()
+            .map(|pubkey| (*pubkey, None))
+            .collect::<Vec<_>>();
+        let mut post_accounts = accounts.clone();
+        post_accounts[1].1 = Some(AccountSharedData::new(42, 0, &system_program::id()));
+        TransactionTrace {
+            slot: 0,
+            transaction: SanitizedTransaction::from_transaction_for_tests(transaction.clone()),
+            pre_accounts: accounts,
+            post_accounts,
+            execution_result: TransactionExecutionResult::NotExecuted(
+                TransactionError::AccountNotFound,
+            ),
+        }
+    }
+
+    #[test]
+    fn test_slot_trace_order() {
+        let genesis_config = create_genesis_config(1_000_000).genesis_config;
+        let bank = Bank::new_for_tests(&genesis_config);
+        let transactions = (1..=3).map(transfer).collect::<Vec<_>>();
+        let entries = vec![
+            Entry::new(&Hash::default(), 1, transactions[..2].to_vec()),
+            Entry::new(&Hash::default(), 1, vec![]),
+            Entry::new(&Hash::default(), 1, transactions[2..].to_vec()),
+        ];
+
+        // The transactions are traced in the order they are replayed, not in
+        // the order of the block
+        let traces = [2, 0, 1]
+            .iter()
+            .map(|index| trace(&transactions[*index]))
+            .collect();
+        let slot_trace = SlotTrace::new(&bank, &entries, traces).unwrap();
+        assert_eq!(slot_trace.slot, 0);
+        assert_eq!(slot_trace.bank_hash, bank.hash().to_string());
+        assert_eq!(
+            slot_trace
+                .transactions
+                .iter()
+                .map(|transaction| (transaction.index, transaction.signature.clone()))
+                .collect::<Vec<_>>(),
+            transactions
+                .iter()
+                .enumerate()
+                .map(|(index, transaction)| (index, transaction.signatures[0].to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        let transaction = &slot_trace.transactions[0];
+        assert!(!transaction.executed);
+        assert_eq!(transaction.err, Some(TransactionError::AccountNotFound));
+        assert_eq!(
+            transaction
+                .accounts
+                .iter()
+                .map(|account| account.writable)
+                .collect::<Vec<_>>(),
+            vec![true, true, false]
+        );
+        assert_eq!(transaction.accounts[1].pre, None);
+        assert_eq!(transaction.accounts[1].post.as_ref().unwrap().lamports, 42);
+    }
+
+    #[test]
+    fn test_slot_trace_missing_trace() {
+        let genesis_config = create_genesis_config(1_000_000).genesis_config;
+        let bank = Bank::new_for_tests(&genesis_config);
+        let transactions = (1..=2).map(transfer).collect::<Vec<_>>();
+        let entries = vec![Entry::new(&Hash::default(), 1, transactions.clone())];
+
+        // A slot without transactions has an empty trace
+        let slot_trace =
+            SlotTrace::new(&bank, &[Entry::new(&Hash::default(), 1, vec![])], vec![]).unwrap();
+        assert!(slot_trace.transactions.is_empty());
+
+        // A slot that was not replayed has no trace
+        assert_eq!(
+            SlotTrace::new(&bank, &entries, vec![]).unwrap_err(),
+            format!(
+                "Transaction {} of slot 0 was not replayed",
+                transactions[0].signatures[0]
+            )
+        );
+
+        // Nor does a transaction without a trace
+        assert_eq!(
+            SlotTrace::new(&bank, &entries, vec![trace(&transactions[0])]).unwrap_err(),
+            format!(
+                "Transaction {} of slot 0 was not replayed",
+                transactions[1].signatures[0]
+            )
+        );
+    }
+}
@@ -185,7 +185,7 @@ fn bank_forks_from_snapshot(
         process::exit(1);
     }
 
-    let (mut deserialized_bank, full_snapshot_archive_info, incremental_snapshot_archive_info) =
+    let (mut deserialized_bank, full_snapshot_archive_info, incremental_snapshot_archive_infos) =
         snapshot_utils::bank_from_latest_snapshot_archives(
             &snapshot_config.bank_snapshots_dir,
             &snapshot_config.snapshot_archives_dir,
@@ -219,16 +219,22 @@ fn bank_forks_from_snapshot(
             *full_snapshot_archive_info.hash(),
         ),
     };
-    let starting_incremental_snapshot_hash =
-        incremental_snapshot_archive_info.map(|incremental_snapshot_archive_info| {
-            IncrementalSnapshotHash {
+    // Peers can only download the incremental snapshots based directly on the full snapshot, so
+    // the last incremental snapshot of a longer chain is not a starting snapshot hash
+    let starting_incremental_snapshot_hash = incremental_snapshot_archive_infos
+        .last()
+        .filter(|incremental_snapshot_archive_info| {
+            incremental_snapshot_archive_info.is_based_on_full_snapshot()
+        })
+        .map(
+            |incremental_snapshot_archive_info| IncrementalSnapshotHash {
                 base: full_snapshot_hash.hash,
                 hash: (
                     incremental_snapshot_archive_info.slot(),
                     *incremental_snapshot_archive_info.hash(),
                 ),
-            }
-        });
+            },
+        );
     let starting_snapshot_hashes = StartingSnapshotHashes {
         full: full_snapshot_hash,
         incremental: starting_incremental_snapshot_hash,
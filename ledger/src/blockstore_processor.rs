@@ -1,7 +1,8 @@
 use {
     crate::{
         block_error::BlockError, blockstore::Blockstore, blockstore_db::BlockstoreError,
-        blockstore_meta::SlotMeta, leader_schedule_cache::LeaderScheduleCache,
+        blockstore_meta::SlotMeta, entry_notifier_interface::EntryNotifierLock,
+        leader_schedule_cache::LeaderScheduleCache,
     },
     chrono_humanize::{Accuracy, HumanTime, Tense},
     crossbeam_channel::Sender,
@@ -10,11 +11,12 @@ use {
     rand::{seq::SliceRandom, thread_rng},
     rayon::{prelude::*, ThreadPool},
     solana_entry::entry::{
-        self, create_ticks, Entry, EntrySlice, EntryType, EntryVerificationStatus, VerifyRecyclers,
+        self, create_ticks, Entry, EntrySlice, EntrySummary, EntryType, EntryVerificationStatus,
+        VerifyRecyclers,
     },
     solana_measure::measure::Measure,
     solana_metrics::{datapoint_error, inc_new_counter_debug},
-    solana_program_runtime::timings::{ExecuteTimingType, ExecuteTimings},
+    solana_program_runtime::timings::{ExecuteTimingType, ExecuteTimings, ProgramProfile},
     solana_rayon_threadlimit::get_thread_count,
     solana_runtime::{
         accounts_background_service::AbsRequestSender,
@@ -37,6 +39,7 @@ use {
         vote_sender_types::ReplayVoteSender,
     },
     solana_sdk::{
+        account::AccountSharedData,
         clock::{Slot, MAX_PROCESSING_AGE},
         feature_set,
         genesis_config::GenesisConfig,
@@ -154,16 +157,38 @@ fn aggregate_total_execution_units(execute_timings: &ExecuteTimings) -> u64 {
     execute_cost_units
 }
 
+/// Reads the state of the accounts referenced by each transaction of the batch
+fn collect_transaction_accounts(bank: &Bank, batch: &TransactionBatch) -> Vec<TransactionAccounts> {
+    batch
+        .sanitized_transactions()
+        .iter()
+        .map(|transaction| {
+            transaction
+                .message()
+                .account_keys()
+                .iter()
+                .map(|pubkey| (*pubkey, bank.get_account(pubkey)))
+                .collect()
+        })
+        .collect()
+}
+
 fn execute_batch(
     batch: &TransactionBatch,
     bank: &Arc<Bank>,
     transaction_status_sender: Option<&TransactionStatusSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
+    transaction_trace_sender: Option<&TransactionTraceSender>,
     timings: &mut ExecuteTimings,
     cost_capacity_meter: Arc<RwLock<BlockCostCapacityMeter>>,
 ) -> Result<()> {
     let record_token_balances = transaction_status_sender.is_some();
 
+    let transaction_trace_sender =
+        transaction_trace_sender.filter(|sender| sender.slot == bank.slot());
+    let pre_transaction_accounts =
+        transaction_trace_sender.map(|_| collect_transaction_accounts(bank, batch));
+
     let mut mint_decimals: HashMap<Pubkey, u8> = HashMap::new();
 
     let pre_token_balances = if record_token_balances {
@@ -220,6 +245,17 @@ fn execute_batch(
         ..
     } = tx_results;
 
+    if let (Some(transaction_trace_sender), Some(pre_transaction_accounts)) =
+        (transaction_trace_sender, pre_transaction_accounts)
+    {
+        transaction_trace_sender.send_transaction_traces(
+            batch.sanitized_transactions(),
+            pre_transaction_accounts,
+            collect_transaction_accounts(bank, batch),
+            &execution_results,
+        );
+    }
+
     if bank
         .feature_set
         .is_active(&feature_set::cap_accounts_data_len::id())
@@ -258,10 +294,12 @@ fn execute_batches_internal(
     entry_callback: Option<&ProcessCallback>,
     transaction_status_sender: Option<&TransactionStatusSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
+    transaction_trace_sender: Option<&TransactionTraceSender>,
     timings: &mut ExecuteTimings,
     cost_capacity_meter: Arc<RwLock<BlockCostCapacityMeter>>,
 ) -> Result<()> {
     inc_new_counter_debug!("bank-par_execute_entries-count", batches.len());
+    let profile_programs = timings.details.is_program_profiling_enabled();
     let (results, new_timings): (Vec<Result<()>>, Vec<ExecuteTimings>) =
         PAR_THREAD_POOL.with(|thread_pool| {
             thread_pool.borrow().install(|| {
@@ -269,11 +307,15 @@ fn execute_batches_internal(
                     .into_par_iter()
                     .map(|batch| {
                         let mut timings = ExecuteTimings::default();
+                        if profile_programs {
+                            timings.details.enable_program_profiling();
+                        }
                         let result = execute_batch(
                             batch,
                             bank,
                             transaction_status_sender,
                             replay_vote_sender,
+                            transaction_trace_sender,
                             &mut timings,
                             cost_capacity_meter.clone(),
                         );
@@ -301,6 +343,7 @@ fn execute_batches(
     entry_callback: Option<&ProcessCallback>,
     transaction_status_sender: Option<&TransactionStatusSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
+    transaction_trace_sender: Option<&TransactionTraceSender>,
     timings: &mut ExecuteTimings,
     cost_capacity_meter: Arc<RwLock<BlockCostCapacityMeter>>,
 ) -> Result<()> {
@@ -359,6 +402,7 @@ fn execute_batches(
         entry_callback,
         transaction_status_sender,
         replay_vote_sender,
+        transaction_trace_sender,
         timings,
         cost_capacity_meter,
     )
@@ -393,6 +437,7 @@ pub fn process_entries_for_tests(
         transaction_status_sender,
         replay_vote_sender,
         None,
+        None,
         &mut timings,
         Arc::new(RwLock::new(BlockCostCapacityMeter::default())),
     );
@@ -410,6 +455,7 @@ fn process_entries_with_callback(
     transaction_status_sender: Option<&TransactionStatusSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
     transaction_cost_metrics_sender: Option<&TransactionCostMetricsSender>,
+    transaction_trace_sender: Option<&TransactionTraceSender>,
     timings: &mut ExecuteTimings,
     cost_capacity_meter: Arc<RwLock<BlockCostCapacityMeter>>,
 ) -> Result<()> {
@@ -432,6 +478,7 @@ fn process_entries_with_callback(
                         entry_callback,
                         transaction_status_sender,
                         replay_vote_sender,
+                        transaction_trace_sender,
                         timings,
                         cost_capacity_meter.clone(),
                     )?;
@@ -489,6 +536,7 @@ fn process_entries_with_callback(
                             entry_callback,
                             transaction_status_sender,
                             replay_vote_sender,
+                            transaction_trace_sender,
                             timings,
                             cost_capacity_meter.clone(),
                         )?;
@@ -504,6 +552,7 @@ fn process_entries_with_callback(
         entry_callback,
         transaction_status_sender,
         replay_vote_sender,
+        transaction_trace_sender,
         timings,
         cost_capacity_meter,
     )?;
@@ -546,6 +595,9 @@ pub struct ProcessOptions {
     pub full_leader_cache: bool,
     pub halt_at_slot: Option<Slot>,
     pub entry_callback: Option<ProcessCallback>,
+    pub entry_notifier: Option<EntryNotifierLock>,
+    pub transaction_trace_sender: Option<TransactionTraceSender>,
+    pub program_profile_sender: Option<ProgramProfileSender>,
     pub override_num_threads: Option<usize>,
     pub new_hard_forks: Option<Vec<Slot>>,
     pub debug_keys: Option<Arc<HashSet<Pubkey>>>,
@@ -807,6 +859,12 @@ fn confirm_full_slot(
     timing: &mut ExecuteTimings,
 ) -> result::Result<(), BlockstoreProcessorError> {
     let mut confirmation_timing = ConfirmationTiming::default();
+    if opts.program_profile_sender.is_some() {
+        confirmation_timing
+            .execute_timings
+            .details
+            .enable_program_profiling();
+    }
     let skip_verification = !opts.poh_verify;
     confirm_slot(
         blockstore,
@@ -818,10 +876,27 @@ fn confirm_full_slot(
         replay_vote_sender,
         None,
         opts.entry_callback.as_ref(),
+        opts.entry_notifier.as_ref(),
+        opts.transaction_trace_sender.as_ref(),
         recyclers,
         opts.allow_dead_slots,
     )?;
 
+    if let Some(program_profile_sender) = &opts.program_profile_sender {
+        let program_profiles = confirmation_timing
+            .execute_timings
+            .details
+            .per_program_profiles
+            .take()
+            .unwrap_or_default();
+        if let Err(err) = program_profile_sender.send((bank.slot(), program_profiles)) {
+            trace!(
+                "Slot {} program profiles send failed: {:?}",
+                bank.slot(),
+                err
+            );
+        }
+    }
     timing.accumulate(&confirmation_timing.execute_timings);
 
     if !bank.is_complete() {
@@ -886,6 +961,8 @@ pub fn confirm_slot(
     replay_vote_sender: Option<&ReplayVoteSender>,
     transaction_cost_metrics_sender: Option<&TransactionCostMetricsSender>,
     entry_callback: Option<&ProcessCallback>,
+    entry_notifier: Option<&EntryNotifierLock>,
+    transaction_trace_sender: Option<&TransactionTraceSender>,
     recyclers: &VerifyRecyclers,
     allow_dead_slots: bool,
 ) -> result::Result<(), BlockstoreProcessorError> {
@@ -915,6 +992,8 @@ pub fn confirm_slot(
         replay_vote_sender,
         transaction_cost_metrics_sender,
         entry_callback,
+        entry_notifier,
+        transaction_trace_sender,
         recyclers,
     )
 }
@@ -930,6 +1009,8 @@ fn confirm_slot_entries(
     replay_vote_sender: Option<&ReplayVoteSender>,
     transaction_cost_metrics_sender: Option<&TransactionCostMetricsSender>,
     entry_callback: Option<&ProcessCallback>,
+    entry_notifier: Option<&EntryNotifierLock>,
+    transaction_trace_sender: Option<&TransactionTraceSender>,
     recyclers: &VerifyRecyclers,
 ) -> result::Result<(), BlockstoreProcessorError> {
     let slot = bank.slot();
@@ -964,6 +1045,10 @@ fn confirm_slot_entries(
     }
 
     let last_entry_hash = entries.last().map(|e| e.hash);
+    // Entries are consumed by transaction verification below, so keep their
+    // summaries around for the entry notifier.
+    let entry_summaries: Option<Vec<EntrySummary>> =
+        entry_notifier.map(|_| entries.iter().map(EntrySummary::from).collect());
     let verifier = if !skip_verification {
         datapoint_debug!("verify-batch-size", ("size", num_entries as i64, i64));
         let entry_state = entries.start_verify(&progress.last_entry, recyclers.clone());
@@ -1001,6 +1086,13 @@ fn confirm_slot_entries(
 
             let mut replay_elapsed = Measure::start("replay_elapsed");
             let mut execute_timings = ExecuteTimings::default();
+            if timing
+                .execute_timings
+                .details
+                .is_program_profiling_enabled()
+            {
+                execute_timings.details.enable_program_profiling();
+            }
             let cost_capacity_meter = Arc::new(RwLock::new(BlockCostCapacityMeter::default()));
             // Note: This will shuffle entries' transactions in-place.
             let process_result = process_entries_with_callback(
@@ -1011,6 +1103,7 @@ fn confirm_slot_entries(
                 transaction_status_sender,
                 replay_vote_sender,
                 transaction_cost_metrics_sender,
+                transaction_trace_sender,
                 &mut execute_timings,
                 cost_capacity_meter,
             )
@@ -1045,6 +1138,21 @@ fn confirm_slot_entries(
 
             process_result?;
 
+            if let (Some(entry_notifier), Some(entry_summaries)) = (entry_notifier, entry_summaries)
+            {
+                let entry_notifier = entry_notifier.read().unwrap();
+                let mut starting_transaction_index = progress.num_txs;
+                for (i, entry_summary) in entry_summaries.iter().enumerate() {
+                    entry_notifier.notify_entry(
+                        slot,
+                        progress.num_entries + i,
+                        entry_summary,
+                        starting_transaction_index,
+                    );
+                    starting_transaction_index += entry_summary.num_transactions as usize;
+                }
+            }
+
             progress.num_shreds += num_shreds;
             progress.num_entries += num_entries;
             progress.num_txs += num_txs;
@@ -1463,6 +1571,64 @@ impl TransactionStatusSender {
     }
 }
 
+/// The state of the accounts referenced by a transaction
+pub type TransactionAccounts = Vec<(Pubkey, Option<AccountSharedData>)>;
+
+/// A replayed transaction, with the state of the accounts it references
+/// before and after its execution
+pub struct TransactionTrace {
+    pub slot: Slot,
+    pub transaction: SanitizedTransaction,
+    pub pre_accounts: TransactionAccounts,
+    pub post_accounts: TransactionAccounts,
+    pub execution_result: TransactionExecutionResult,
+}
+
+/// Sends a [`TransactionTrace`] for each transaction replayed in `slot`.
+/// Reading the accounts of every transaction is expensive, this is meant for
+/// debugging tools.
+#[derive(Clone)]
+pub struct TransactionTraceSender {
+    pub slot: Slot,
+    pub sender: Sender<TransactionTrace>,
+}
+
+impl TransactionTraceSender {
+    fn send_transaction_traces(
+        &self,
+        transactions: &[SanitizedTransaction],
+        pre_transaction_accounts: Vec<TransactionAccounts>,
+        post_transaction_accounts: Vec<TransactionAccounts>,
+        execution_results: &[TransactionExecutionResult],
+    ) {
+        for (((transaction, pre_accounts), post_accounts), execution_result) in transactions
+            .iter()
+            .zip(pre_transaction_accounts)
+            .zip(post_transaction_accounts)
+            .zip(execution_results)
+        {
+            if let Err(err) = self.sender.send(TransactionTrace {
+                slot: self.slot,
+                transaction: transaction.clone(),
+                pre_accounts,
+                post_accounts,
+                execution_result: execution_result.clone(),
+            }) {
+                trace!(
+                    "Slot {} transaction trace send failed: {:?}",
+                    self.slot,
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// Sends the [`ProgramProfile`]s of the programs executed in each slot
+/// replayed by `process_blockstore`. Profiling every program invocation has a
+/// cost, this is meant for debugging tools.
+pub type ProgramProfileSender = Sender<(Slot, HashMap<Pubkey, ProgramProfile>)>;
+
 pub type CacheBlockMetaSender = Sender<Arc<Bank>>;
 
 pub fn cache_block_meta(bank: &Arc<Bank>, cache_block_meta_sender: Option<&CacheBlockMetaSender>) {
@@ -1532,8 +1698,11 @@ fn check_accounts_data_size<'a>(
 pub mod tests {
     use {
         super::*,
-        crate::genesis_utils::{
-            create_genesis_config, create_genesis_config_with_leader, GenesisConfigInfo,
+        crate::{
+            entry_notifier_interface::EntryNotifier,
+            genesis_utils::{
+                create_genesis_config, create_genesis_config_with_leader, GenesisConfigInfo,
+            },
         },
         matches::assert_matches,
         rand::{thread_rng, Rng},
@@ -1545,13 +1714,13 @@ pub mod tests {
             vote_account::VoteAccount,
         },
         solana_sdk::{
-            account::{AccountSharedData, WritableAccount},
+            account::{AccountSharedData, ReadableAccount, WritableAccount},
             epoch_schedule::EpochSchedule,
             hash::Hash,
             pubkey::Pubkey,
             signature::{Keypair, Signer},
             system_instruction::SystemError,
-            system_transaction,
+            system_program, system_transaction,
             transaction::{Transaction, TransactionError},
         },
         solana_vote_program::{
@@ -1559,7 +1728,10 @@ pub mod tests {
             vote_state::{VoteState, VoteStateVersions, MAX_LOCKOUT_HISTORY},
             vote_transaction,
         },
-        std::{collections::BTreeSet, sync::RwLock},
+        std::{
+            collections::{BTreeMap, BTreeSet},
+            sync::RwLock,
+        },
         trees::tr,
     };
 
@@ -2416,6 +2588,225 @@ pub mod tests {
         assert_eq!(*callback_counter.write().unwrap(), 2);
     }
 
+    #[test]
+    fn test_process_ledger_options_entry_notifier() {
+        struct TestEntryNotifier {
+            notified: RwLock<Vec<(Slot, usize, EntrySummary, usize)>>,
+        }
+
+        impl EntryNotifier for TestEntryNotifier {
+            fn notify_entry(
+                &self,
+                slot: Slot,
+                index: usize,
+                entry: &EntrySummary,
+                starting_transaction_index: usize,
+            ) {
+                self.notified.write().unwrap().push((
+                    slot,
+                    index,
+                    *entry,
+                    starting_transaction_index,
+                ));
+            }
+        }
+
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let (ledger_path, last_entry_hash) = create_new_tmp_ledger_auto_delete!(&genesis_config);
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let blockhash = genesis_config.hash();
+        let keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+
+        let txs = vec![
+            system_transaction::transfer(&mint_keypair, &keypairs[0].pubkey(), 1, blockhash),
+            system_transaction::transfer(&mint_keypair, &keypairs[1].pubkey(), 1, blockhash),
+        ];
+        let entry_1 = next_entry(&last_entry_hash, 1, txs);
+
+        let tx = system_transaction::transfer(&mint_keypair, &keypairs[2].pubkey(), 1, blockhash);
+        let entry_2 = next_entry(&entry_1.hash, 1, vec![tx]);
+
+        let mut entries = vec![entry_1, entry_2];
+        entries.extend(create_ticks(
+            genesis_config.ticks_per_slot,
+            0,
+            last_entry_hash,
+        ));
+        let expected: Vec<_> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let starting_transaction_index = match index {
+                    0 => 0,
+                    1 => 2,
+                    _ => 3,
+                };
+                (
+                    1,
+                    index,
+                    EntrySummary::from(entry),
+                    starting_transaction_index,
+                )
+            })
+            .collect();
+        blockstore
+            .write_entries(
+                1,
+                0,
+                0,
+                genesis_config.ticks_per_slot,
+                None,
+                true,
+                &Arc::new(Keypair::new()),
+                entries,
+                0,
+            )
+            .unwrap();
+
+        let entry_notifier = Arc::new(RwLock::new(TestEntryNotifier {
+            notified: RwLock::default(),
+        }));
+        let opts = ProcessOptions {
+            override_num_threads: Some(1),
+            entry_notifier: Some(entry_notifier.clone()),
+            accounts_db_test_hash_calculation: true,
+            ..ProcessOptions::default()
+        };
+        test_process_blockstore(&genesis_config, &blockstore, opts);
+
+        let notified = entry_notifier
+            .read()
+            .unwrap()
+            .notified
+            .read()
+            .unwrap()
+            .clone();
+        // Slot 0 only holds ticks
+        let slot_1_notified: Vec<_> = notified.into_iter().filter(|n| n.0 == 1).collect();
+        assert_eq!(slot_1_notified, expected);
+    }
+
+    #[test]
+    fn test_process_ledger_options_transaction_trace_sender() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let (ledger_path, last_entry_hash) = create_new_tmp_ledger_auto_delete!(&genesis_config);
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let blockhash = genesis_config.hash();
+        let keypair = Keypair::new();
+
+        let tx = system_transaction::transfer(&mint_keypair, &keypair.pubkey(), 1, blockhash);
+        let mut entries = vec![next_entry(&last_entry_hash, 1, vec![tx.clone()])];
+        entries.extend(create_ticks(
+            genesis_config.ticks_per_slot,
+            0,
+            last_entry_hash,
+        ));
+        blockstore
+            .write_entries(
+                1,
+                0,
+                0,
+                genesis_config.ticks_per_slot,
+                None,
+                true,
+                &Arc::new(Keypair::new()),
+                entries,
+                0,
+            )
+            .unwrap();
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let opts = ProcessOptions {
+            transaction_trace_sender: Some(TransactionTraceSender { slot: 1, sender }),
+            accounts_db_test_hash_calculation: true,
+            ..ProcessOptions::default()
+        };
+        test_process_blockstore(&genesis_config, &blockstore, opts);
+
+        let traces: Vec<_> = receiver.try_iter().collect();
+        assert_eq!(traces.len(), 1);
+        let trace = &traces[0];
+        assert_eq!(trace.slot, 1);
+        assert_eq!(trace.transaction.signature(), &tx.signatures[0]);
+        assert!(trace.execution_result.was_executed_successfully());
+        assert_eq!(trace.pre_accounts[1], (keypair.pubkey(), None));
+        let (pubkey, post_account) = &trace.post_accounts[1];
+        assert_eq!(pubkey, &keypair.pubkey());
+        assert_eq!(post_account.as_ref().unwrap().lamports(), 1);
+        let (pubkey, pre_account) = &trace.pre_accounts[0];
+        assert_eq!(pubkey, &mint_keypair.pubkey());
+        assert!(
+            pre_account.as_ref().unwrap().lamports()
+                > trace.post_accounts[0].1.as_ref().unwrap().lamports()
+        );
+    }
+
+    #[test]
+    fn test_process_ledger_options_program_profile_sender() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let (ledger_path, last_entry_hash) = create_new_tmp_ledger_auto_delete!(&genesis_config);
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let blockhash = genesis_config.hash();
+
+        let success_tx =
+            system_transaction::transfer(&mint_keypair, &Pubkey::new_unique(), 1, blockhash);
+        // Fails with `InstructionError::Custom`, the mint doesn't hold enough lamports
+        let fail_tx =
+            system_transaction::transfer(&mint_keypair, &Pubkey::new_unique(), 1_000, blockhash);
+        let success_entry = next_entry(&last_entry_hash, 1, vec![success_tx]);
+        let fail_entry = next_entry(&success_entry.hash, 1, vec![fail_tx]);
+        let last_entry_hash = fail_entry.hash;
+        let mut entries = vec![success_entry, fail_entry];
+        entries.extend(create_ticks(
+            genesis_config.ticks_per_slot,
+            0,
+            last_entry_hash,
+        ));
+        blockstore
+            .write_entries(
+                1,
+                0,
+                0,
+                genesis_config.ticks_per_slot,
+                None,
+                true,
+                &Arc::new(Keypair::new()),
+                entries,
+                0,
+            )
+            .unwrap();
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let opts = ProcessOptions {
+            program_profile_sender: Some(sender),
+            accounts_db_test_hash_calculation: true,
+            ..ProcessOptions::default()
+        };
+        test_process_blockstore(&genesis_config, &blockstore, opts);
+
+        let program_profiles: HashMap<_, _> = receiver.try_iter().collect();
+        let profile = program_profiles
+            .get(&1)
+            .unwrap()
+            .get(&system_program::id())
+            .unwrap();
+        assert_eq!(profile.invocation_units.len(), 2);
+        assert_eq!(profile.errors, HashMap::from([("Custom".to_string(), 1)]));
+        assert_eq!(profile.invocations_by_cpi_depth, BTreeMap::from([(0, 2)]));
+    }
+
     #[test]
     fn test_process_entries_tick() {
         let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(1000);
@@ -3751,6 +4142,8 @@ pub mod tests {
             None,
             None,
             None,
+            None,
+            None,
             &VerifyRecyclers::default(),
         )
     }
@@ -0,0 +1,18 @@
+use {
+    solana_entry::entry::EntrySummary,
+    solana_sdk::clock::Slot,
+    std::sync::{Arc, RwLock},
+};
+
+/// Interface for notifying of entries as they are replayed
+pub trait EntryNotifier {
+    fn notify_entry(
+        &self,
+        slot: Slot,
+        index: usize,
+        entry: &EntrySummary,
+        starting_transaction_index: usize,
+    );
+}
+
+pub type EntryNotifierLock = Arc<RwLock<dyn EntryNotifier + Sync + Send>>;
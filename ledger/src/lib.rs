@@ -15,6 +15,7 @@ pub mod blockstore_db;
 pub mod blockstore_meta;
 pub mod blockstore_processor;
 pub mod builtins;
+pub mod entry_notifier_interface;
 pub mod erasure;
 pub mod genesis_utils;
 pub mod leader_schedule;
@@ -696,6 +696,7 @@ fn test_snapshot_download() {
             .unwrap()
             .maximum_incremental_snapshot_archives_to_retain,
         false,
+        None,
         &mut None,
     )
     .unwrap();
@@ -810,6 +811,7 @@ fn test_incremental_snapshot_download() {
             .unwrap()
             .maximum_incremental_snapshot_archives_to_retain,
         false,
+        None,
         &mut None,
     )
     .unwrap();
@@ -835,6 +837,7 @@ fn test_incremental_snapshot_download() {
             .unwrap()
             .maximum_incremental_snapshot_archives_to_retain,
         false,
+        None,
         &mut None,
     )
     .unwrap();
@@ -963,6 +966,7 @@ fn test_incremental_snapshot_download_with_crossing_full_snapshot_interval_at_st
             .unwrap()
             .maximum_incremental_snapshot_archives_to_retain,
         false,
+        None,
         &mut None,
     )
     .unwrap();
@@ -997,6 +1001,7 @@ fn test_incremental_snapshot_download_with_crossing_full_snapshot_interval_at_st
             .unwrap()
             .maximum_incremental_snapshot_archives_to_retain,
         false,
+        None,
         &mut None,
     )
     .unwrap();
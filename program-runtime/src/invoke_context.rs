@@ -969,6 +969,12 @@ impl<'a> InvokeContext<'a> {
 
         // Pop the invoke_stack to restore previous state
         let _ = self.pop();
+        self.timings.record_program_invocation(
+            &program_id,
+            *compute_units_consumed,
+            &result,
+            nesting_level,
+        );
         result
     }
 
@@ -1,9 +1,9 @@
 use {
     core::fmt,
     enum_iterator::IntoEnumIterator,
-    solana_sdk::{pubkey::Pubkey, saturating_add_assign},
+    solana_sdk::{instruction::InstructionError, pubkey::Pubkey, saturating_add_assign},
     std::{
-        collections::HashMap,
+        collections::{BTreeMap, HashMap},
         ops::{Index, IndexMut},
     },
 };
@@ -39,6 +39,46 @@ impl ProgramTiming {
     }
 }
 
+/// Execution statistics of a program, collected for every invocation of the
+/// program, including cross-program invocations, when program profiling is
+/// enabled.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ProgramProfile {
+    /// Compute units consumed by each invocation, including the units
+    /// consumed by the programs it invoked
+    pub invocation_units: Vec<u64>,
+    /// Number of failed invocations per `InstructionError` variant
+    pub errors: HashMap<String, u64>,
+    /// Number of invocations per CPI depth, 0 being top level instructions
+    pub invocations_by_cpi_depth: BTreeMap<usize, u64>,
+}
+
+impl ProgramProfile {
+    pub fn accumulate(&mut self, other: &ProgramProfile) {
+        self.invocation_units
+            .extend_from_slice(&other.invocation_units);
+        for (error, count) in &other.errors {
+            saturating_add_assign!(*self.errors.entry(error.clone()).or_default(), *count);
+        }
+        for (cpi_depth, count) in &other.invocations_by_cpi_depth {
+            saturating_add_assign!(
+                *self.invocations_by_cpi_depth.entry(*cpi_depth).or_default(),
+                *count
+            );
+        }
+    }
+}
+
+/// Returns the name of the variant of an `InstructionError`, without its
+/// fields
+pub fn instruction_error_variant(error: &InstructionError) -> String {
+    format!("{:?}", error)
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
 /// Used as an index for `Metrics`.
 #[derive(Debug, IntoEnumIterator)]
 pub enum ExecuteTimingType {
@@ -172,6 +212,8 @@ pub struct ExecuteDetailsTimings {
     pub create_executor_verify_code_us: u64,
     pub create_executor_jit_compile_us: u64,
     pub per_program_timings: HashMap<Pubkey, ProgramTiming>,
+    /// Per program profiles, only collected when not `None`
+    pub per_program_profiles: Option<HashMap<Pubkey, ProgramProfile>>,
 }
 
 impl ExecuteDetailsTimings {
@@ -208,6 +250,54 @@ impl ExecuteDetailsTimings {
             let program_timing = self.per_program_timings.entry(*id).or_default();
             program_timing.accumulate_program_timings(other);
         }
+        if let Some(other_profiles) = &other.per_program_profiles {
+            let profiles = self
+                .per_program_profiles
+                .get_or_insert_with(HashMap::default);
+            for (id, other) in other_profiles {
+                profiles.entry(*id).or_default().accumulate(other);
+            }
+        }
+    }
+
+    /// Starts collecting a `ProgramProfile` per program
+    pub fn enable_program_profiling(&mut self) {
+        self.per_program_profiles
+            .get_or_insert_with(HashMap::default);
+    }
+
+    pub fn is_program_profiling_enabled(&self) -> bool {
+        self.per_program_profiles.is_some()
+    }
+
+    /// Records an invocation of a program, if program profiling is enabled
+    pub fn record_program_invocation(
+        &mut self,
+        program_id: &Pubkey,
+        compute_units_consumed: u64,
+        result: &Result<(), InstructionError>,
+        cpi_depth: usize,
+    ) {
+        if let Some(profiles) = self.per_program_profiles.as_mut() {
+            let profile = profiles.entry(*program_id).or_default();
+            profile.invocation_units.push(compute_units_consumed);
+            if let Err(err) = result {
+                saturating_add_assign!(
+                    *profile
+                        .errors
+                        .entry(instruction_error_variant(err))
+                        .or_default(),
+                    1
+                );
+            }
+            saturating_add_assign!(
+                *profile
+                    .invocations_by_cpi_depth
+                    .entry(cpi_depth)
+                    .or_default(),
+                1
+            );
+        }
     }
 
     pub fn accumulate_program(
@@ -320,6 +410,42 @@ mod tests {
         assert_eq!(execute_details_timings, other_execute_details_timings);
     }
 
+    #[test]
+    fn test_execute_details_timing_program_profiles() {
+        let program_id = Pubkey::new_unique();
+        let mut execute_details_timings = ExecuteDetailsTimings::default();
+
+        // Nothing is recorded unless profiling is enabled
+        execute_details_timings.record_program_invocation(&program_id, 1, &Ok(()), 0);
+        assert!(!execute_details_timings.is_program_profiling_enabled());
+
+        execute_details_timings.enable_program_profiling();
+        execute_details_timings.record_program_invocation(&program_id, 10, &Ok(()), 0);
+        execute_details_timings.record_program_invocation(
+            &program_id,
+            20,
+            &Err(InstructionError::Custom(42)),
+            1,
+        );
+
+        let mut other_execute_details_timings = ExecuteDetailsTimings::default();
+        other_execute_details_timings.accumulate(&execute_details_timings);
+        other_execute_details_timings.accumulate(&execute_details_timings);
+
+        let profile = other_execute_details_timings
+            .per_program_profiles
+            .as_ref()
+            .unwrap()
+            .get(&program_id)
+            .unwrap();
+        assert_eq!(profile.invocation_units, vec![10, 20, 10, 20]);
+        assert_eq!(profile.errors, HashMap::from([("Custom".to_string(), 2)]));
+        assert_eq!(
+            profile.invocations_by_cpi_depth,
+            BTreeMap::from([(0, 2), (1, 2)])
+        );
+    }
+
     #[test]
     fn execute_timings_saturating_add_in_place() {
         let mut timings = ExecuteTimings::default();
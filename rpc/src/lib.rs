@@ -6,8 +6,10 @@ pub mod parsed_token_accounts;
 pub mod rpc;
 pub mod rpc_completed_slots_service;
 pub mod rpc_health;
+pub mod rpc_method_access;
 pub mod rpc_pubsub;
 pub mod rpc_pubsub_service;
+pub mod rpc_rate_limiter;
 pub mod rpc_service;
 pub mod rpc_subscription_tracker;
 pub mod rpc_subscriptions;
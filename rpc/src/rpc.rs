@@ -3,7 +3,8 @@
 use {
     crate::{
         max_slots::MaxSlots, optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
-        parsed_token_accounts::*, rpc_health::*,
+        parsed_token_accounts::*, rpc_health::*, rpc_method_access::RpcMethodAccess,
+        rpc_rate_limiter::RpcRateLimiter,
     },
     bincode::{config::Options, serialize},
     crossbeam_channel::{unbounded, Receiver, Sender},
@@ -23,7 +24,8 @@ use {
         rpc_request::{
             TokenAccountsFilter, DELINQUENT_VALIDATOR_SLOT_DISTANCE,
             MAX_GET_CONFIRMED_BLOCKS_RANGE, MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT,
-            MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE, MAX_GET_PROGRAM_ACCOUNT_FILTERS,
+            MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE,
+            MAX_GET_PROGRAM_ACCOUNTS_PAGE_SIZE, MAX_GET_PROGRAM_ACCOUNT_FILTERS,
             MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS, MAX_GET_SLOT_LEADERS, MAX_MULTIPLE_ACCOUNTS,
             NUM_LARGEST_ACCOUNTS,
         },
@@ -96,10 +98,11 @@ use {
         cmp::{max, min},
         collections::{HashMap, HashSet},
         convert::TryFrom,
-        net::SocketAddr,
+        net::{IpAddr, SocketAddr},
+        ops::Bound,
         str::FromStr,
         sync::{
-            atomic::{AtomicBool, AtomicU64, Ordering},
+            atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
             Arc, Mutex, RwLock,
         },
         time::Duration,
@@ -130,6 +133,91 @@ pub enum OptionalContext<T> {
     NoContext(T),
 }
 
+/// The accounts of a `getProgramAccounts` response: all of them, or one page if the request
+/// set a page size
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RpcProgramAccounts {
+    All(Vec<RpcKeyedAccount>),
+    Page(RpcKeyedAccountsPage),
+}
+
+/// Where a paginated `getProgramAccounts` request continues: the accounts after `pubkey`,
+/// in the bank of `slot`, or in the root bank once a rooted `slot` is pruned. Clients only see
+/// it encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramAccountsCursor {
+    pub slot: Slot,
+    pub pubkey: Pubkey,
+}
+
+impl ProgramAccountsCursor {
+    const ENCODED_LEN: usize = 8 + 32;
+
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(Self::ENCODED_LEN);
+        bytes.extend_from_slice(&self.slot.to_le_bytes());
+        bytes.extend_from_slice(self.pubkey.as_ref());
+        bs58::encode(bytes).into_string()
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self> {
+        bs58::decode(cursor)
+            .into_vec()
+            .ok()
+            .filter(|bytes| bytes.len() == Self::ENCODED_LEN)
+            .map(|bytes| Self {
+                slot: Slot::from_le_bytes(bytes[..8].try_into().unwrap()),
+                pubkey: Pubkey::new(&bytes[8..]),
+            })
+            .ok_or_else(|| Error::invalid_params("Invalid cursor".to_string()))
+    }
+}
+
+/// Requests one page of the accounts of a `getProgramAccounts` request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramAccountsPageConfig {
+    pub page_size: usize,
+    /// None for the first page
+    pub cursor: Option<ProgramAccountsCursor>,
+}
+
+/// The accounts found by `getProgramAccounts`, encoded as they are iterated
+pub type EncodedProgramAccounts = Box<dyn Iterator<Item = Result<RpcKeyedAccount>> + Send>;
+
+/// The accounts found by a `getProgramAccounts` request, before they are encoded
+pub struct ProgramAccountsScan {
+    pub bank: Arc<Bank>,
+    pub accounts: EncodedProgramAccounts,
+    /// The cursor of the next page if the request set a page size, None after the last page
+    pub next_cursor: Option<Option<String>>,
+}
+
+/// The verified params of a `getProgramAccounts` request
+pub struct ProgramAccountsParams {
+    pub program_id: Pubkey,
+    pub config: Option<RpcAccountInfoConfig>,
+    pub filters: Vec<RpcFilterType>,
+    pub with_context: bool,
+    pub page_config: Option<ProgramAccountsPageConfig>,
+}
+
+/// A block read for `getBlock`, with the encoding the request asked for
+pub struct BlockToEncode {
+    pub block: ConfirmedBlock,
+    pub encoding: UiTransactionEncoding,
+    pub options: BlockEncodingOptions,
+}
+
+impl BlockToEncode {
+    pub fn encode(self) -> Result<UiConfirmedBlock> {
+        Ok(self
+            .block
+            .encode_with_options(self.encoding, self.options)
+            .map_err(RpcCustomError::from)?)
+    }
+}
+
 fn is_finalized(
     block_commitment_cache: &BlockCommitmentCache,
     bank: &Bank,
@@ -154,6 +242,10 @@ pub struct JsonRpcConfig {
     pub full_api: bool,
     pub obsolete_v1_7_api: bool,
     pub rpc_scan_and_fix_roots: bool,
+    pub rate_limiter: Option<Arc<RpcRateLimiter>>,
+    pub method_access: RpcMethodAccess,
+    /// Listener serving trusted clients, without the method access rules and rate limits
+    pub unrestricted_rpc_addr: Option<SocketAddr>,
 }
 
 impl JsonRpcConfig {
@@ -202,6 +294,8 @@ pub struct JsonRpcRequestProcessor {
     max_slots: Arc<MaxSlots>,
     leader_schedule_cache: Arc<LeaderScheduleCache>,
     max_complete_transaction_status_slot: Arc<AtomicU64>,
+    /// The IP of the client making the request, None if unknown
+    client_ip: Option<IpAddr>,
 }
 impl Metadata for JsonRpcRequestProcessor {}
 
@@ -311,11 +405,60 @@ impl JsonRpcRequestProcessor {
                 max_slots,
                 leader_schedule_cache,
                 max_complete_transaction_status_slot,
+                client_ip: None,
             },
             receiver,
         )
     }
 
+    /// A processor for the requests of the client at `client_ip`
+    pub fn with_client_ip(&self, client_ip: Option<IpAddr>) -> Self {
+        Self {
+            client_ip,
+            ..self.clone()
+        }
+    }
+
+    /// A processor for trusted clients, ignoring the method access rules and rate limits
+    pub fn unrestricted(&self) -> Self {
+        let mut processor = self.clone();
+        processor.config.method_access = RpcMethodAccess::default();
+        processor.config.rate_limiter = None;
+        processor
+    }
+
+    /// Reject the calls to methods disabled by `JsonRpcConfig::method_access`
+    pub fn check_method_access(&self, method: &str) -> Result<()> {
+        if self.config.method_access.is_allowed(method) {
+            Ok(())
+        } else {
+            Err(RpcCustomError::MethodDisabled {
+                method: method.to_string(),
+            }
+            .into())
+        }
+    }
+
+    /// Take the rate limit tokens for a request of the client for `method`
+    pub fn check_rate_limit(&self, method: &str) -> Result<()> {
+        match &self.config.rate_limiter {
+            Some(rate_limiter) => {
+                rate_limiter
+                    .check(self.client_ip, method)
+                    .map_err(|retry_after| {
+                        RpcCustomError::RateLimited {
+                            method: method.to_string(),
+                            retry_after_ms: u64::try_from(retry_after.as_millis())
+                                .unwrap_or(u64::MAX)
+                                .max(1),
+                        }
+                        .into()
+                    })
+            }
+            None => Ok(()),
+        }
+    }
+
     // Useful for unit testing
     pub fn new_from_bank(bank: &Arc<Bank>, socket_addr_space: SocketAddrSpace) -> Self {
         let genesis_hash = bank.hash();
@@ -365,6 +508,7 @@ impl JsonRpcRequestProcessor {
             max_slots: Arc::new(MaxSlots::default()),
             leader_schedule_cache: Arc::new(LeaderScheduleCache::new_from_bank(bank)),
             max_complete_transaction_status_slot: Arc::new(AtomicU64::default()),
+            client_ip: None,
         }
     }
 
@@ -412,42 +556,129 @@ impl JsonRpcRequestProcessor {
         &self,
         program_id: &Pubkey,
         config: Option<RpcAccountInfoConfig>,
-        mut filters: Vec<RpcFilterType>,
+        filters: Vec<RpcFilterType>,
         with_context: bool,
-    ) -> Result<OptionalContext<Vec<RpcKeyedAccount>>> {
+        page_config: Option<ProgramAccountsPageConfig>,
+    ) -> Result<OptionalContext<RpcProgramAccounts>> {
+        let ProgramAccountsScan {
+            bank,
+            accounts,
+            next_cursor,
+        } = self.scan_program_accounts(program_id, config, filters, page_config)?;
+        let accounts = accounts.collect::<Result<Vec<_>>>()?;
+        let accounts = match next_cursor {
+            Some(cursor) => RpcProgramAccounts::Page(RpcKeyedAccountsPage { accounts, cursor }),
+            None => RpcProgramAccounts::All(accounts),
+        };
+        Ok(match with_context {
+            true => OptionalContext::Context(new_response(&bank, accounts)),
+            false => OptionalContext::NoContext(accounts),
+        })
+    }
+
+    /// Find the accounts of a `getProgramAccounts` request, leaving them to be encoded as
+    /// they are iterated
+    pub fn scan_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: Option<RpcAccountInfoConfig>,
+        mut filters: Vec<RpcFilterType>,
+        page_config: Option<ProgramAccountsPageConfig>,
+    ) -> Result<ProgramAccountsScan> {
         let config = config.unwrap_or_default();
-        let bank = self.bank(config.commitment);
+        let cursor = page_config.and_then(|page_config| page_config.cursor);
+        let bank = match cursor {
+            Some(cursor) => {
+                let bank_forks = self.bank_forks.read().unwrap();
+                match bank_forks.get(cursor.slot) {
+                    // Later pages are read from the same bank as the first while it is in bank
+                    // forks
+                    Some(bank) => bank.clone(),
+                    // The banks before the root are pruned as the root advances.  Later pages
+                    // can't be read from the same state as the first one, so the cursor expires.
+                    None => {
+                        return Err(RpcCustomError::ProgramAccountsCursorExpired {
+                            slot: cursor.slot,
+                        }
+                        .into())
+                    }
+                }
+            }
+            None => self.bank(config.commitment),
+        };
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         let data_slice_config = config.data_slice;
         check_slice_and_encoding(&encoding, data_slice_config.is_some())?;
         optimize_filters(&mut filters);
         let keyed_accounts = {
+            let page_config = page_config.as_ref();
             if let Some(owner) = get_spl_token_owner_filter(program_id, &filters) {
-                self.get_filtered_spl_token_accounts_by_owner(&bank, program_id, &owner, filters)?
+                self.get_filtered_spl_token_accounts_by_owner(
+                    &bank,
+                    program_id,
+                    &owner,
+                    filters,
+                    page_config,
+                )?
             } else if let Some(mint) = get_spl_token_mint_filter(program_id, &filters) {
-                self.get_filtered_spl_token_accounts_by_mint(&bank, program_id, &mint, filters)?
+                self.get_filtered_spl_token_accounts_by_mint(
+                    &bank,
+                    program_id,
+                    &mint,
+                    filters,
+                    page_config,
+                )?
+            } else if let Some(index_key) =
+                get_custom_index_filter(program_id, &filters, &self.config.account_indexes)
+            {
+                self.get_filtered_custom_indexed_accounts(
+                    &bank,
+                    program_id,
+                    index_key,
+                    filters,
+                    page_config,
+                )?
             } else {
-                self.get_filtered_program_accounts(&bank, program_id, filters)?
+                self.get_filtered_program_accounts(&bank, program_id, filters, page_config)?
+            }
+        };
+        let (keyed_accounts, next_cursor) = match page_config {
+            Some(page_config) => {
+                let (keyed_accounts, next_pubkey) =
+                    select_program_accounts_page(keyed_accounts, page_config.page_size);
+                let next_cursor = next_pubkey.map(|pubkey| {
+                    ProgramAccountsCursor {
+                        slot: bank.slot(),
+                        pubkey,
+                    }
+                    .encode()
+                });
+                (keyed_accounts, Some(next_cursor))
             }
+            None => (keyed_accounts, None),
         };
-        let accounts = if is_known_spl_token_id(program_id)
+        if encoding == UiAccountEncoding::Binary || encoding == UiAccountEncoding::Base58 {
+            // Fail the request before any account is encoded
+            for (_, account) in &keyed_accounts {
+                check_base58_data_len(account)?;
+            }
+        }
+        let accounts: EncodedProgramAccounts = if is_known_spl_token_id(program_id)
             && encoding == UiAccountEncoding::JsonParsed
         {
-            get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect()
+            Box::new(get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).map(Ok))
         } else {
-            keyed_accounts
-                .into_iter()
-                .map(|(pubkey, account)| {
-                    Ok(RpcKeyedAccount {
-                        pubkey: pubkey.to_string(),
-                        account: encode_account(&account, &pubkey, encoding, data_slice_config)?,
-                    })
+            Box::new(keyed_accounts.into_iter().map(move |(pubkey, account)| {
+                Ok(RpcKeyedAccount {
+                    pubkey: pubkey.to_string(),
+                    account: encode_account(&account, &pubkey, encoding, data_slice_config)?,
                 })
-                .collect::<Result<Vec<_>>>()?
+            }))
         };
-        Ok(match with_context {
-            true => OptionalContext::Context(new_response(&bank, accounts)),
-            false => OptionalContext::NoContext(accounts),
+        Ok(ProgramAccountsScan {
+            bank,
+            accounts,
+            next_cursor,
         })
     }
 
@@ -1004,12 +1235,24 @@ impl JsonRpcRequestProcessor {
         slot: Slot,
         config: Option<RpcEncodingConfigWrapper<RpcBlockConfig>>,
     ) -> Result<Option<UiConfirmedBlock>> {
+        self.get_block_to_encode(slot, config)
+            .await?
+            .map(BlockToEncode::encode)
+            .transpose()
+    }
+
+    /// Read the block of a `getBlock` request, leaving it to be encoded
+    pub async fn get_block_to_encode(
+        &self,
+        slot: Slot,
+        config: Option<RpcEncodingConfigWrapper<RpcBlockConfig>>,
+    ) -> Result<Option<BlockToEncode>> {
         if self.config.enable_rpc_transaction_history {
             let config = config
                 .map(|config| config.convert_to_current())
                 .unwrap_or_default();
             let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Json);
-            let encoding_options = BlockEncodingOptions {
+            let options = BlockEncodingOptions {
                 transaction_details: config.transaction_details.unwrap_or_default(),
                 show_rewards: config.rewards.unwrap_or(true),
                 max_supported_transaction_version: config.max_supported_transaction_version,
@@ -1028,61 +1271,52 @@ impl JsonRpcRequestProcessor {
                 self.check_status_is_complete(slot)?;
                 let result = self.blockstore.get_rooted_block(slot, true);
                 self.check_blockstore_root(&result, slot)?;
-                let encode_block = |confirmed_block: ConfirmedBlock| -> Result<UiConfirmedBlock> {
-                    let mut encoded_block = confirmed_block
-                        .encode_with_options(encoding, encoding_options)
-                        .map_err(RpcCustomError::from)?;
+                let to_encode = |mut block: ConfirmedBlock| {
                     if slot == 0 {
-                        encoded_block.block_time = Some(self.genesis_creation_time());
-                        encoded_block.block_height = Some(0);
+                        block.block_time = Some(self.genesis_creation_time());
+                        block.block_height = Some(0);
+                    }
+                    BlockToEncode {
+                        block,
+                        encoding,
+                        options,
                     }
-                    Ok(encoded_block)
                 };
                 if result.is_err() {
                     if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
                         let bigtable_result =
                             bigtable_ledger_storage.get_confirmed_block(slot).await;
                         self.check_bigtable_result(&bigtable_result)?;
-                        return bigtable_result.ok().map(encode_block).transpose();
+                        return Ok(bigtable_result.ok().map(to_encode));
                     }
                 }
                 self.check_slot_cleaned_up(&result, slot)?;
-                return result
-                    .ok()
-                    .map(ConfirmedBlock::from)
-                    .map(encode_block)
-                    .transpose();
+                return Ok(result.ok().map(ConfirmedBlock::from).map(to_encode));
             } else if commitment.is_confirmed() {
                 // Check if block is confirmed
                 let confirmed_bank = self.bank(Some(CommitmentConfig::confirmed()));
                 if confirmed_bank.status_cache_ancestors().contains(&slot) {
                     self.check_status_is_complete(slot)?;
                     let result = self.blockstore.get_complete_block(slot, true);
-                    return result
-                        .ok()
-                        .map(ConfirmedBlock::from)
-                        .map(|mut confirmed_block| -> Result<UiConfirmedBlock> {
-                            if confirmed_block.block_time.is_none()
-                                || confirmed_block.block_height.is_none()
-                            {
-                                let r_bank_forks = self.bank_forks.read().unwrap();
-                                let bank = r_bank_forks.get(slot).cloned();
-                                if let Some(bank) = bank {
-                                    if confirmed_block.block_time.is_none() {
-                                        confirmed_block.block_time =
-                                            Some(bank.clock().unix_timestamp);
-                                    }
-                                    if confirmed_block.block_height.is_none() {
-                                        confirmed_block.block_height = Some(bank.block_height());
-                                    }
+                    return Ok(result.ok().map(ConfirmedBlock::from).map(|mut block| {
+                        if block.block_time.is_none() || block.block_height.is_none() {
+                            let r_bank_forks = self.bank_forks.read().unwrap();
+                            let bank = r_bank_forks.get(slot).cloned();
+                            if let Some(bank) = bank {
+                                if block.block_time.is_none() {
+                                    block.block_time = Some(bank.clock().unix_timestamp);
+                                }
+                                if block.block_height.is_none() {
+                                    block.block_height = Some(bank.block_height());
                                 }
                             }
-
-                            Ok(confirmed_block
-                                .encode_with_options(encoding, encoding_options)
-                                .map_err(RpcCustomError::from)?)
-                        })
-                        .transpose();
+                        }
+                        BlockToEncode {
+                            block,
+                            encoding,
+                            options,
+                        }
+                    }));
                 }
             }
         } else {
@@ -1493,9 +1727,25 @@ impl JsonRpcRequestProcessor {
         address: Pubkey,
         before: Option<Signature>,
         until: Option<Signature>,
-        mut limit: usize,
+        limit: usize,
         commitment: Option<CommitmentConfig>,
     ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        Ok(self
+            .iter_signatures_for_address(address, before, until, limit, commitment)
+            .await?
+            .collect())
+    }
+
+    /// Find the signatures of a `getSignaturesForAddress` request, leaving the confirmation
+    /// status and block time of each to be looked up as they are iterated
+    pub async fn iter_signatures_for_address(
+        &self,
+        address: Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        mut limit: usize,
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<impl Iterator<Item = RpcConfirmedTransactionStatusWithSignature> + Send> {
         let commitment = commitment.unwrap_or_default();
         check_is_at_least_confirmed(commitment)?;
 
@@ -1520,27 +1770,23 @@ impl JsonRpcRequestProcessor {
                 .get_confirmed_signatures_for_address2(address, highest_slot, before, until, limit)
                 .map_err(|err| Error::invalid_params(format!("{}", err)))?;
 
-            let map_results = |results: Vec<ConfirmedTransactionStatusWithSignature>| {
-                results
-                    .into_iter()
-                    .map(|x| {
-                        let mut item: RpcConfirmedTransactionStatusWithSignature = x.into();
-                        if item.slot <= highest_confirmed_root {
-                            item.confirmation_status =
-                                Some(TransactionConfirmationStatus::Finalized);
-                        } else {
-                            item.confirmation_status =
-                                Some(TransactionConfirmationStatus::Confirmed);
-                            if item.block_time.is_none() {
-                                let r_bank_forks = self.bank_forks.read().unwrap();
-                                item.block_time = r_bank_forks
-                                    .get(item.slot)
-                                    .map(|bank| bank.clock().unix_timestamp);
-                            }
+            let bank_forks = self.bank_forks.clone();
+            let map_results = move |results: Vec<ConfirmedTransactionStatusWithSignature>| {
+                results.into_iter().map(move |x| {
+                    let mut item: RpcConfirmedTransactionStatusWithSignature = x.into();
+                    if item.slot <= highest_confirmed_root {
+                        item.confirmation_status = Some(TransactionConfirmationStatus::Finalized);
+                    } else {
+                        item.confirmation_status = Some(TransactionConfirmationStatus::Confirmed);
+                        if item.block_time.is_none() {
+                            let r_bank_forks = bank_forks.read().unwrap();
+                            item.block_time = r_bank_forks
+                                .get(item.slot)
+                                .map(|bank| bank.clock().unix_timestamp);
                         }
-                        item
-                    })
-                    .collect()
+                    }
+                    item
+                })
             };
 
             if results.len() < limit {
@@ -1766,7 +2012,7 @@ impl JsonRpcRequestProcessor {
             ));
         }
         let mut token_balances: Vec<RpcTokenAccountBalance> = self
-            .get_filtered_spl_token_accounts_by_mint(&bank, &mint_owner, mint, vec![])?
+            .get_filtered_spl_token_accounts_by_mint(&bank, &mint_owner, mint, vec![], None)?
             .into_iter()
             .map(|(address, account)| {
                 let amount = TokenAccount::unpack(account.data())
@@ -1819,6 +2065,7 @@ impl JsonRpcRequestProcessor {
             &token_program_id,
             owner,
             filters,
+            None,
         )?;
         let accounts = if encoding == UiAccountEncoding::JsonParsed {
             get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect()
@@ -1865,13 +2112,19 @@ impl JsonRpcRequestProcessor {
         ];
         // Optional filter on Mint address, uses mint account index for scan
         let keyed_accounts = if let Some(mint) = mint {
-            self.get_filtered_spl_token_accounts_by_mint(&bank, &token_program_id, &mint, filters)?
+            self.get_filtered_spl_token_accounts_by_mint(
+                &bank,
+                &token_program_id,
+                &mint,
+                filters,
+                None,
+            )?
         } else {
             // Filter on Token Account state
             filters.push(RpcFilterType::DataSize(
                 TokenAccount::get_packed_len() as u64
             ));
-            self.get_filtered_program_accounts(&bank, &token_program_id, filters)?
+            self.get_filtered_program_accounts(&bank, &token_program_id, filters, None)?
         };
         let accounts = if encoding == UiAccountEncoding::JsonParsed {
             get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect()
@@ -1889,14 +2142,17 @@ impl JsonRpcRequestProcessor {
         Ok(new_response(&bank, accounts))
     }
 
-    /// Use a set of filters to get an iterator of keyed program accounts from a bank
+    /// Use a set of filters to get an iterator of keyed program accounts from a bank.  With
+    /// `page_config`, only the accounts after its cursor are scanned.
     fn get_filtered_program_accounts(
         &self,
         bank: &Arc<Bank>,
         program_id: &Pubkey,
         mut filters: Vec<RpcFilterType>,
+        page_config: Option<&ProgramAccountsPageConfig>,
     ) -> RpcCustomResult<Vec<(Pubkey, AccountSharedData)>> {
         optimize_filters(&mut filters);
+        let scan_config = program_accounts_scan_config(page_config);
         let filter_closure = |account: &AccountSharedData| {
             filters
                 .iter()
@@ -1923,16 +2179,35 @@ impl JsonRpcRequestProcessor {
                         // accounts.
                         account.owner() == program_id && filter_closure(account)
                     },
-                    &ScanConfig::default(),
+                    &scan_config,
                     bank.byte_limit_for_scans(),
                 )
                 .map_err(|e| RpcCustomError::ScanError {
                     message: e.to_string(),
                 })?)
         } else {
+            // The unindexed scan is in pubkey order, so a page is complete once one more account
+            // than it holds is found, which tells that another page follows
+            let max_accounts =
+                page_config.map(|page_config| page_config.page_size.saturating_add(1));
+            let num_accounts = AtomicUsize::default();
             // this path does not need to provide a mb limit because we only want to support secondary indexes
             Ok(bank
-                .get_filtered_program_accounts(program_id, filter_closure, &ScanConfig::default())
+                .get_filtered_program_accounts(
+                    program_id,
+                    |account| {
+                        let use_account = filter_closure(account);
+                        if use_account
+                            && max_accounts.map_or(false, |max_accounts| {
+                                num_accounts.fetch_add(1, Ordering::Relaxed) + 1 >= max_accounts
+                            })
+                        {
+                            scan_config.abort();
+                        }
+                        use_account
+                    },
+                    &scan_config,
+                )
                 .map_err(|e| RpcCustomError::ScanError {
                     message: e.to_string(),
                 })?)
@@ -1946,6 +2221,7 @@ impl JsonRpcRequestProcessor {
         program_id: &Pubkey,
         owner_key: &Pubkey,
         mut filters: Vec<RpcFilterType>,
+        page_config: Option<&ProgramAccountsPageConfig>,
     ) -> RpcCustomResult<Vec<(Pubkey, AccountSharedData)>> {
         // The by-owner accounts index checks for Token Account state and Owner address on
         // inclusion. However, due to the current AccountsDb implementation, an account may remain
@@ -1980,14 +2256,14 @@ impl JsonRpcRequestProcessor {
                                 .iter()
                                 .all(|filter_type| filter_type.allows(account))
                     },
-                    &ScanConfig::default(),
+                    &program_accounts_scan_config(page_config),
                     bank.byte_limit_for_scans(),
                 )
                 .map_err(|e| RpcCustomError::ScanError {
                     message: e.to_string(),
                 })?)
         } else {
-            self.get_filtered_program_accounts(bank, program_id, filters)
+            self.get_filtered_program_accounts(bank, program_id, filters, page_config)
         }
     }
 
@@ -1998,6 +2274,7 @@ impl JsonRpcRequestProcessor {
         program_id: &Pubkey,
         mint_key: &Pubkey,
         mut filters: Vec<RpcFilterType>,
+        page_config: Option<&ProgramAccountsPageConfig>,
     ) -> RpcCustomResult<Vec<(Pubkey, AccountSharedData)>> {
         // The by-mint accounts index checks for Token Account state and Mint address on inclusion.
         // However, due to the current AccountsDb implementation, an account may remain in storage
@@ -2031,17 +2308,56 @@ impl JsonRpcRequestProcessor {
                                 .iter()
                                 .all(|filter_type| filter_type.allows(account))
                     },
-                    &ScanConfig::default(),
+                    &program_accounts_scan_config(page_config),
                     bank.byte_limit_for_scans(),
                 )
                 .map_err(|e| RpcCustomError::ScanError {
                     message: e.to_string(),
                 })?)
         } else {
-            self.get_filtered_program_accounts(bank, program_id, filters)
+            self.get_filtered_program_accounts(bank, program_id, filters, page_config)
         }
     }
 
+    /// Get an iterator of program accounts by the key of a custom account index
+    fn get_filtered_custom_indexed_accounts(
+        &self,
+        bank: &Arc<Bank>,
+        program_id: &Pubkey,
+        index_key: IndexKey,
+        filters: Vec<RpcFilterType>,
+        page_config: Option<&ProgramAccountsPageConfig>,
+    ) -> RpcCustomResult<Vec<(Pubkey, AccountSharedData)>> {
+        let key = match &index_key {
+            IndexKey::Custom(_, key) => key,
+            _ => unreachable!("not a custom index key"),
+        };
+        if !self.config.account_indexes.include_key(key) {
+            return Err(RpcCustomError::KeyExcludedFromSecondaryIndex {
+                index_key: key.to_string(),
+            });
+        }
+        // The custom indexes only check for the account owner and the indexed field on
+        // inclusion, and an account may remain in storage as a zero-lamport
+        // AccountSharedData::Default() after being wiped.  The request's filters, which include
+        // the memcmp on the indexed field, avoid returning these accounts.
+        Ok(bank
+            .get_filtered_indexed_accounts(
+                &index_key,
+                |account| {
+                    account.owner() == program_id
+                        && filters
+                            .iter()
+                            .all(|filter_type| filter_type.allows(account))
+                },
+                &program_accounts_scan_config(page_config),
+                bank.byte_limit_for_scans(),
+            )
+            .map_err(|e| RpcCustomError::ScanError {
+                message: e.to_string(),
+            })?)
+    }
+
     fn get_latest_blockhash(
         &self,
         commitment: Option<CommitmentConfig>,
@@ -2082,21 +2398,27 @@ impl JsonRpcRequestProcessor {
 }
 
 fn optimize_filters(filters: &mut [RpcFilterType]) {
-    filters.iter_mut().for_each(|filter_type| {
-        if let RpcFilterType::Memcmp(compare) = filter_type {
-            use MemcmpEncodedBytes::*;
-            match &compare.bytes {
-                #[allow(deprecated)]
-                Binary(bytes) | Base58(bytes) => {
-                    compare.bytes = Bytes(bs58::decode(bytes).into_vec().unwrap());
-                }
-                Base64(bytes) => {
-                    compare.bytes = Bytes(base64::decode(bytes).unwrap());
-                }
-                _ => {}
-            }
+    filters
+        .iter_mut()
+        .for_each(|filter_type| match filter_type {
+            RpcFilterType::Memcmp(compare) => optimize_memcmp(compare),
+            RpcFilterType::AnyMemcmp(compares) => compares.iter_mut().for_each(optimize_memcmp),
+            _ => {}
+        })
+}
+
+fn optimize_memcmp(compare: &mut Memcmp) {
+    use MemcmpEncodedBytes::*;
+    match &compare.bytes {
+        #[allow(deprecated)]
+        Binary(bytes) | Base58(bytes) => {
+            compare.bytes = Bytes(bs58::decode(bytes).into_vec().unwrap());
         }
-    })
+        Base64(bytes) => {
+            compare.bytes = Bytes(base64::decode(bytes).unwrap());
+        }
+        _ => {}
+    }
 }
 
 fn verify_transaction(
@@ -2121,6 +2443,63 @@ fn verify_filter(input: &RpcFilterType) -> Result<()> {
         .map_err(|e| Error::invalid_params(format!("Invalid param: {:?}", e)))
 }
 
+pub(crate) fn verify_program_accounts_params(
+    program_id_str: &str,
+    config: Option<RpcProgramAccountsConfig>,
+) -> Result<ProgramAccountsParams> {
+    let program_id = verify_pubkey(program_id_str)?;
+    let (config, filters, with_context, page_size, cursor) = if let Some(config) = config {
+        (
+            Some(config.account_config),
+            config.filters.unwrap_or_default(),
+            config.with_context.unwrap_or_default(),
+            config.page_size,
+            config.cursor,
+        )
+    } else {
+        (None, vec![], false, None, None)
+    };
+    let page_config = match (page_size, cursor) {
+        (Some(page_size), cursor) => {
+            if page_size == 0 || page_size > MAX_GET_PROGRAM_ACCOUNTS_PAGE_SIZE {
+                return Err(Error::invalid_params(format!(
+                    "Invalid page size; min 1, max {}",
+                    MAX_GET_PROGRAM_ACCOUNTS_PAGE_SIZE
+                )));
+            }
+            Some(ProgramAccountsPageConfig {
+                page_size,
+                cursor: cursor
+                    .as_deref()
+                    .map(ProgramAccountsCursor::decode)
+                    .transpose()?,
+            })
+        }
+        (None, Some(_)) => {
+            return Err(Error::invalid_params(
+                "A cursor requires a page size".to_string(),
+            ));
+        }
+        (None, None) => None,
+    };
+    if filters.len() > MAX_GET_PROGRAM_ACCOUNT_FILTERS {
+        return Err(Error::invalid_params(format!(
+            "Too many filters provided; max {}",
+            MAX_GET_PROGRAM_ACCOUNT_FILTERS
+        )));
+    }
+    for filter in &filters {
+        verify_filter(filter)?;
+    }
+    Ok(ProgramAccountsParams {
+        program_id,
+        config,
+        filters,
+        with_context,
+        page_config,
+    })
+}
+
 fn verify_pubkey(input: &str) -> Result<Pubkey> {
     input
         .parse()
@@ -2154,7 +2533,7 @@ fn verify_token_account_filter(
     }
 }
 
-fn verify_and_parse_signatures_for_address_params(
+pub(crate) fn verify_and_parse_signatures_for_address_params(
     address: String,
     before: Option<String>,
     until: Option<String>,
@@ -2235,9 +2614,16 @@ fn encode_account<T: ReadableAccount>(
     encoding: UiAccountEncoding,
     data_slice: Option<UiDataSliceConfig>,
 ) -> Result<UiAccount> {
-    if (encoding == UiAccountEncoding::Binary || encoding == UiAccountEncoding::Base58)
-        && account.data().len() > MAX_BASE58_BYTES
-    {
+    if encoding == UiAccountEncoding::Binary || encoding == UiAccountEncoding::Base58 {
+        check_base58_data_len(account)?;
+    }
+    Ok(UiAccount::encode(
+        pubkey, account, encoding, None, data_slice,
+    ))
+}
+
+fn check_base58_data_len<T: ReadableAccount>(account: &T) -> Result<()> {
+    if account.data().len() > MAX_BASE58_BYTES {
         let message = format!("Encoded binary (base 58) data should be less than {} bytes, please use Base64 encoding.", MAX_BASE58_BYTES);
         Err(error::Error {
             code: error::ErrorCode::InvalidRequest,
@@ -2245,10 +2631,37 @@ fn encode_account<T: ReadableAccount>(
             data: None,
         })
     } else {
-        Ok(UiAccount::encode(
-            pubkey, account, encoding, None, data_slice,
-        ))
+        Ok(())
+    }
+}
+
+/// Scan only the accounts after the cursor of a page, in pubkey order
+fn program_accounts_scan_config(page_config: Option<&ProgramAccountsPageConfig>) -> ScanConfig {
+    ScanConfig {
+        range: page_config
+            .and_then(|page_config| page_config.cursor)
+            .map(|cursor| (Bound::Excluded(cursor.pubkey), Bound::Unbounded)),
+        ..ScanConfig::default()
     }
+    .recreate_with_abort()
+}
+
+/// Select the first `page_size` accounts in pubkey order, the accounts before the cursor are
+/// not scanned. Also return the last pubkey of the page if more accounts follow it.
+fn select_program_accounts_page(
+    mut keyed_accounts: Vec<(Pubkey, AccountSharedData)>,
+    page_size: usize,
+) -> (Vec<(Pubkey, AccountSharedData)>, Option<Pubkey>) {
+    let has_more = keyed_accounts.len() > page_size;
+    if has_more {
+        keyed_accounts.select_nth_unstable_by(page_size, |(a, _), (b, _)| a.cmp(b));
+        keyed_accounts.truncate(page_size);
+    }
+    keyed_accounts.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    let next_pubkey = has_more
+        .then(|| keyed_accounts.last().map(|(pubkey, _)| *pubkey))
+        .flatten();
+    (keyed_accounts, next_pubkey)
 }
 
 /// Analyze custom filters to determine if the result will be a subset of spl-token accounts by
@@ -2335,6 +2748,32 @@ fn get_spl_token_mint_filter(program_id: &Pubkey, filters: &[RpcFilterType]) ->
     }
 }
 
+/// Analyze custom filters to determine if the result will be a subset of the accounts of a custom
+/// account index: one of the memcmp filters must compare the whole indexed field of a custom index
+/// declared for `program_id`.
+/// NOTE: `optimize_filters()` should almost always be called before using this method because of
+/// the strict match on `MemcmpEncodedBytes::Bytes`.
+fn get_custom_index_filter(
+    program_id: &Pubkey,
+    filters: &[RpcFilterType],
+    account_indexes: &AccountSecondaryIndexes,
+) -> Option<IndexKey> {
+    account_indexes
+        .custom_indexes_of(program_id)
+        .find_map(|custom_index| {
+            filters.iter().find_map(|filter| match filter {
+                RpcFilterType::Memcmp(Memcmp {
+                    offset,
+                    bytes: MemcmpEncodedBytes::Bytes(bytes),
+                    ..
+                }) if *offset == custom_index.offset => custom_index
+                    .key_from_bytes(bytes)
+                    .map(|key| IndexKey::Custom(*custom_index, key)),
+                _ => None,
+            })
+        })
+}
+
 /// Analyze a passed Pubkey that may be a Token program id or Mint address to determine the program
 /// id and optional Mint
 fn get_token_program_id_and_mint(
@@ -2877,7 +3316,7 @@ pub mod rpc_accounts {
             meta: Self::Metadata,
             program_id_str: String,
             config: Option<RpcProgramAccountsConfig>,
-        ) -> Result<OptionalContext<Vec<RpcKeyedAccount>>>;
+        ) -> Result<OptionalContext<RpcProgramAccounts>>;
 
         #[rpc(meta, name = "getBlockCommitment")]
         fn get_block_commitment(
@@ -3003,31 +3442,19 @@ pub mod rpc_accounts {
             meta: Self::Metadata,
             program_id_str: String,
             config: Option<RpcProgramAccountsConfig>,
-        ) -> Result<OptionalContext<Vec<RpcKeyedAccount>>> {
+        ) -> Result<OptionalContext<RpcProgramAccounts>> {
             debug!(
                 "get_program_accounts rpc request received: {:?}",
                 program_id_str
             );
-            let program_id = verify_pubkey(&program_id_str)?;
-            let (config, filters, with_context) = if let Some(config) = config {
-                (
-                    Some(config.account_config),
-                    config.filters.unwrap_or_default(),
-                    config.with_context.unwrap_or_default(),
-                )
-            } else {
-                (None, vec![], false)
-            };
-            if filters.len() > MAX_GET_PROGRAM_ACCOUNT_FILTERS {
-                return Err(Error::invalid_params(format!(
-                    "Too many filters provided; max {}",
-                    MAX_GET_PROGRAM_ACCOUNT_FILTERS
-                )));
-            }
-            for filter in &filters {
-                verify_filter(filter)?;
-            }
-            meta.get_program_accounts(&program_id, config, filters, with_context)
+            let ProgramAccountsParams {
+                program_id,
+                config,
+                filters,
+                with_context,
+                page_config,
+            } = verify_program_accounts_params(&program_id_str, config)?;
+            meta.get_program_accounts(&program_id, config, filters, with_context, page_config)
         }
 
         fn get_block_commitment(
@@ -4397,7 +4824,8 @@ pub mod tests {
         solana_address_lookup_table_program::state::{AddressLookupTable, LookupTableMeta},
         solana_client::{
             rpc_custom_error::{
-                JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+                JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE, JSON_RPC_SERVER_ERROR_METHOD_DISABLED,
+                JSON_RPC_SERVER_ERROR_PROGRAM_ACCOUNTS_CURSOR_EXPIRED,
                 JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE,
                 JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION,
             },
@@ -4411,8 +4839,8 @@ pub mod tests {
             genesis_utils::{create_genesis_config, GenesisConfigInfo},
         },
         solana_runtime::{
-            accounts_background_service::AbsRequestSender, commitment::BlockCommitment,
-            non_circulating_supply::non_circulating_accounts,
+            accounts_background_service::AbsRequestSender, accounts_index::CustomSecondaryIndex,
+            commitment::BlockCommitment, non_circulating_supply::non_circulating_accounts,
         },
         solana_sdk::{
             account::{Account, WritableAccount},
@@ -4781,6 +5209,32 @@ pub mod tests {
         assert_eq!(request_processor.get_transaction_count(None), 1);
     }
 
+    #[test]
+    fn test_rpc_request_processor_method_access() {
+        let genesis = create_genesis_config(100);
+        let bank = Arc::new(Bank::new_for_tests(&genesis.genesis_config));
+        let mut request_processor =
+            JsonRpcRequestProcessor::new_from_bank(&bank, SocketAddrSpace::Unspecified);
+        request_processor.config.method_access = RpcMethodAccess {
+            denied_methods: ["getProgramAccounts".to_string()].into_iter().collect(),
+            read_only: true,
+            ..RpcMethodAccess::default()
+        };
+        assert!(request_processor.check_method_access("getBalance").is_ok());
+        for method in ["getProgramAccounts", "sendTransaction", "requestAirdrop"] {
+            let err = request_processor.check_method_access(method).unwrap_err();
+            assert_eq!(
+                err.code,
+                ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_METHOD_DISABLED)
+            );
+        }
+
+        let request_processor = request_processor.unrestricted();
+        assert!(request_processor
+            .check_method_access("sendTransaction")
+            .is_ok());
+    }
+
     #[test]
     fn test_rpc_get_balance() {
         let genesis = create_genesis_config(20);
@@ -5497,6 +5951,161 @@ pub mod tests {
         assert_eq!(result.len(), 0);
     }
 
+    #[test]
+    fn test_rpc_get_program_accounts_pages() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+
+        let program_id = Pubkey::new_unique();
+        let mut pubkeys = (0..5)
+            .map(|_| {
+                let pubkey = Pubkey::new_unique();
+                bank.store_account(&pubkey, &AccountSharedData::new(42, 0, &program_id));
+                pubkey
+            })
+            .collect::<Vec<_>>();
+        pubkeys.sort();
+
+        let mut cursor = None;
+        let mut cursors = vec![];
+        let mut pages = vec![];
+        loop {
+            let request = create_test_request(
+                "getProgramAccounts",
+                Some(json!([
+                    program_id.to_string(),
+                    {"pageSize": 2, "cursor": cursor},
+                ])),
+            );
+            let page: RpcKeyedAccountsPage = parse_success_result(rpc.handle_request_sync(request));
+            pages.push(
+                page.accounts
+                    .into_iter()
+                    .map(|account| account.pubkey.parse::<Pubkey>().unwrap())
+                    .collect::<Vec<_>>(),
+            );
+            cursor = page.cursor;
+            match &cursor {
+                Some(cursor) => cursors.push(cursor.clone()),
+                None => break,
+            }
+        }
+        assert_eq!(pages, vec![&pubkeys[..2], &pubkeys[2..4], &pubkeys[4..]]);
+
+        // Once the root advances past the bank of a cursor, the cursor has expired
+        rpc.blockstore.set_roots(std::iter::once(&0)).unwrap();
+        rpc.add_roots_to_blockstore(vec![1]);
+        assert!(rpc.bank_forks.read().unwrap().get(0).is_none());
+        let request = create_test_request(
+            "getProgramAccounts",
+            Some(json!([
+                program_id.to_string(),
+                {"pageSize": 2, "cursor": cursors[0]},
+            ])),
+        );
+        let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(code, JSON_RPC_SERVER_ERROR_PROGRAM_ACCOUNTS_CURSOR_EXPIRED);
+
+        // So has a cursor at a slot that was never in bank forks
+        let cursor = ProgramAccountsCursor {
+            slot: 42,
+            pubkey: pubkeys[0],
+        };
+        let request = create_test_request(
+            "getProgramAccounts",
+            Some(json!([
+                program_id.to_string(),
+                {"pageSize": 2, "cursor": cursor.encode()},
+            ])),
+        );
+        let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(code, JSON_RPC_SERVER_ERROR_PROGRAM_ACCOUNTS_CURSOR_EXPIRED);
+
+        for config in [
+            json!({"pageSize": 2, "cursor": "invalid"}),
+            json!({"pageSize": 0}),
+            json!({"cursor": cursor.encode()}),
+        ] {
+            let request = create_test_request(
+                "getProgramAccounts",
+                Some(json!([program_id.to_string(), config])),
+            );
+            let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+            assert_eq!(code, ErrorCode::InvalidParams.code());
+        }
+    }
+
+    #[test]
+    fn test_rpc_get_program_accounts_range_filters() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+
+        // Account i holds i * 10 lamports and the u64 i at offset 0, followed by i bytes
+        let program_id = Pubkey::new_unique();
+        for i in 1..=4u64 {
+            let mut data = i.to_le_bytes().to_vec();
+            data.resize(8 + i as usize, 0);
+            let mut account = AccountSharedData::new(i * 10, data.len(), &program_id);
+            account.set_data(data);
+            bank.store_account(&Pubkey::new_unique(), &account);
+        }
+
+        let num_filtered_accounts = |filters: Value| {
+            let request = create_test_request(
+                "getProgramAccounts",
+                Some(json!([program_id.to_string(), {"filters": filters}])),
+            );
+            let result: Vec<RpcKeyedAccount> =
+                parse_success_result(rpc.handle_request_sync(request));
+            result.len()
+        };
+        assert_eq!(
+            num_filtered_accounts(json!([{
+                "u64Compare": {"offset": 0, "comparison": "greaterThan", "value": 1},
+            }])),
+            3
+        );
+        assert_eq!(
+            num_filtered_accounts(json!([{
+                "u64Compare": {"offset": 0, "comparison": "lessThan", "value": 4},
+            }, {
+                "u64Compare": {"offset": 0, "comparison": "greaterThan", "value": 1},
+            }])),
+            2
+        );
+        assert_eq!(
+            num_filtered_accounts(json!([{"dataSizeRange": {"min": 10, "max": 11}}])),
+            2
+        );
+        assert_eq!(
+            num_filtered_accounts(json!([{"lamportsRange": {"max": 20}}])),
+            2
+        );
+        assert_eq!(
+            num_filtered_accounts(json!([{"anyMemcmp": [{
+                "offset": 0,
+                "bytes": bs58::encode(1u64.to_le_bytes()).into_string(),
+            }, {
+                "offset": 0,
+                "bytes": bs58::encode(3u64.to_le_bytes()).into_string(),
+            }]}])),
+            2
+        );
+
+        for filters in [
+            json!([{"lamportsRange": {}}]),
+            json!([{"dataSizeRange": {"min": 2, "max": 1}}]),
+            json!([{"anyMemcmp": []}]),
+        ] {
+            let request = create_test_request(
+                "getProgramAccounts",
+                Some(json!([program_id.to_string(), {"filters": filters}])),
+            );
+            let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+            assert_eq!(code, ErrorCode::InvalidParams.code());
+        }
+    }
+
     #[test]
     fn test_rpc_simulate_transaction() {
         let rpc = RpcHandler::start();
@@ -7620,6 +8229,55 @@ pub mod tests {
         .is_none());
     }
 
+    #[test]
+    fn test_get_custom_index_filter() {
+        let program_id = Pubkey::new_unique();
+        let custom_index = CustomSecondaryIndex::new(program_id, 8, 32).unwrap();
+        let mut account_indexes = AccountSecondaryIndexes::default();
+        account_indexes
+            .indexes
+            .insert(AccountIndex::Custom(custom_index));
+        let key = Pubkey::new_unique();
+        let memcmp = |offset, bytes: &[u8]| {
+            RpcFilterType::Memcmp(Memcmp {
+                offset,
+                bytes: MemcmpEncodedBytes::Bytes(bytes.to_vec()),
+                encoding: None,
+            })
+        };
+
+        assert!(matches!(
+            get_custom_index_filter(
+                &program_id,
+                &[RpcFilterType::DataSize(100), memcmp(8, key.as_ref())],
+                &account_indexes,
+            ),
+            Some(IndexKey::Custom(index, index_key)) if index == custom_index && index_key == key
+        ));
+
+        // Filtering on another offset
+        assert!(
+            get_custom_index_filter(&program_id, &[memcmp(0, key.as_ref())], &account_indexes)
+                .is_none()
+        );
+
+        // Filtering on part of the indexed field
+        assert!(get_custom_index_filter(
+            &program_id,
+            &[memcmp(8, &key.as_ref()[..16])],
+            &account_indexes
+        )
+        .is_none());
+
+        // Wrong program id
+        assert!(get_custom_index_filter(
+            &Pubkey::new_unique(),
+            &[memcmp(8, key.as_ref())],
+            &account_indexes
+        )
+        .is_none());
+    }
+
     #[test]
     fn test_rpc_single_gossip() {
         let exit = Arc::new(AtomicBool::new(false));
@@ -0,0 +1,226 @@
+//! Which RPC methods a node serves, beyond the method groups enabled by `JsonRpcConfig`.
+//!
+//! The rules are read from a JSON file, flags can add to them:
+//! ```json
+//! {
+//!   "allowedMethods": ["getAccountInfo", "getBalance", "sendTransaction"],
+//!   "deniedMethods": ["getProgramAccounts"],
+//!   "readOnly": true
+//! }
+//! ```
+//! A method is served if it is allowed, or there is no allowlist, and it is not denied.
+//! In read-only mode the methods that submit transactions are denied too. The rules for a
+//! method apply to its deprecated aliases, see `DEPRECATED_METHOD_ALIASES`.
+//!
+//! The rules only apply to the JSON RPC port. The PubSub websocket on the next port serves
+//! subscriptions, which the rules do not name, so it is left as configured by `PubSubConfig`.
+use std::{collections::HashSet, fs, io, iter, path::Path};
+
+/// Methods that submit transactions to the cluster, denied in read-only mode
+pub const WRITE_METHODS: &[&str] = &["sendTransaction", "requestAirdrop"];
+
+/// Deprecated methods, and the methods replacing them that serve the same data. Denying or
+/// allowing a method denies or allows its deprecated aliases too, so that a denied method is
+/// not served under its old name.
+pub const DEPRECATED_METHOD_ALIASES: &[(&str, &str)] = &[
+    ("confirmTransaction", "getSignatureStatuses"),
+    ("getConfirmedBlock", "getBlock"),
+    ("getConfirmedBlocks", "getBlocks"),
+    ("getConfirmedBlocksWithLimit", "getBlocksWithLimit"),
+    (
+        "getConfirmedSignaturesForAddress",
+        "getSignaturesForAddress",
+    ),
+    (
+        "getConfirmedSignaturesForAddress2",
+        "getSignaturesForAddress",
+    ),
+    ("getConfirmedTransaction", "getTransaction"),
+    ("getRecentBlockhash", "getLatestBlockhash"),
+    ("getSignatureConfirmation", "getSignatureStatuses"),
+    ("getSignatureStatus", "getSignatureStatuses"),
+    ("getSnapshotSlot", "getHighestSnapshotSlot"),
+    ("getTotalSupply", "getSupply"),
+];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct RpcMethodAccess {
+    /// If set, the only methods served
+    pub allowed_methods: Option<HashSet<String>>,
+    /// Methods never served
+    pub denied_methods: HashSet<String>,
+    /// Deny the `WRITE_METHODS`
+    pub read_only: bool,
+}
+
+impl RpcMethodAccess {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = fs::File::open(path)?;
+        serde_json::from_reader(file).map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+    }
+
+    pub fn is_allowed(&self, method: &str) -> bool {
+        let replacing_method = DEPRECATED_METHOD_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == method)
+            .map(|(_, replacing_method)| *replacing_method);
+        let is_named_in = |methods: &HashSet<String>| {
+            iter::once(method)
+                .chain(replacing_method)
+                .any(|method| methods.contains(method))
+        };
+        self.allowed_methods
+            .as_ref()
+            .map(is_named_in)
+            .unwrap_or(true)
+            && !is_named_in(&self.denied_methods)
+            && !(self.read_only && WRITE_METHODS.contains(&method))
+    }
+
+    /// Check that the rules only name `served_methods`, so that a misspelled or disabled
+    /// method is not silently ignored
+    pub fn verify<'a>(
+        &self,
+        served_methods: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), String> {
+        let served_methods: HashSet<_> = served_methods.into_iter().collect();
+        let mut unknown_methods: Vec<_> = self
+            .allowed_methods
+            .iter()
+            .flatten()
+            .chain(&self.denied_methods)
+            .map(String::as_str)
+            .filter(|method| !served_methods.contains(method))
+            .collect();
+        if unknown_methods.is_empty() {
+            return Ok(());
+        }
+        unknown_methods.sort_unstable();
+        unknown_methods.dedup();
+        Err(format!(
+            "RPC methods not served by this node: {}",
+            unknown_methods.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_ledger::get_tmp_ledger_path};
+
+    #[test]
+    fn test_rpc_method_access() {
+        let access = RpcMethodAccess::default();
+        assert!(access.is_allowed("getSlot"));
+        assert!(access.is_allowed("sendTransaction"));
+
+        let access = RpcMethodAccess {
+            read_only: true,
+            denied_methods: ["getProgramAccounts".to_string()].into_iter().collect(),
+            ..RpcMethodAccess::default()
+        };
+        assert!(access.is_allowed("getSlot"));
+        assert!(!access.is_allowed("getProgramAccounts"));
+        assert!(!access.is_allowed("sendTransaction"));
+        assert!(!access.is_allowed("requestAirdrop"));
+
+        // the denylist and read-only mode win over the allowlist
+        let access = RpcMethodAccess {
+            allowed_methods: Some(
+                ["getSlot", "getBalance", "sendTransaction"]
+                    .iter()
+                    .map(|method| method.to_string())
+                    .collect(),
+            ),
+            denied_methods: ["getBalance".to_string()].into_iter().collect(),
+            read_only: true,
+        };
+        assert!(access.is_allowed("getSlot"));
+        assert!(!access.is_allowed("getBalance"));
+        assert!(!access.is_allowed("sendTransaction"));
+        assert!(!access.is_allowed("getAccountInfo"));
+
+        // the rules for a method apply to its deprecated aliases, not the other way around
+        let access = RpcMethodAccess {
+            allowed_methods: Some(
+                [
+                    "getBlock",
+                    "getSignaturesForAddress",
+                    "getConfirmedTransaction",
+                ]
+                .iter()
+                .map(|method| method.to_string())
+                .collect(),
+            ),
+            denied_methods: ["getSignaturesForAddress".to_string()]
+                .into_iter()
+                .collect(),
+            read_only: false,
+        };
+        assert!(access.is_allowed("getBlock"));
+        assert!(access.is_allowed("getConfirmedBlock"));
+        assert!(!access.is_allowed("getSignaturesForAddress"));
+        assert!(!access.is_allowed("getConfirmedSignaturesForAddress2"));
+        assert!(access.is_allowed("getConfirmedTransaction"));
+        assert!(!access.is_allowed("getTransaction"));
+    }
+
+    #[test]
+    fn test_rpc_method_access_verify() {
+        let served_methods = ["getSlot", "getBalance", "sendTransaction"];
+        assert!(RpcMethodAccess::default().verify(served_methods).is_ok());
+
+        let access = RpcMethodAccess {
+            allowed_methods: Some(["getSlot".to_string()].into_iter().collect()),
+            denied_methods: ["getBalance".to_string()].into_iter().collect(),
+            read_only: true,
+        };
+        assert!(access.verify(served_methods).is_ok());
+
+        let access = RpcMethodAccess {
+            allowed_methods: Some(
+                ["getSlot", "getSlots"]
+                    .iter()
+                    .map(|method| method.to_string())
+                    .collect(),
+            ),
+            denied_methods: ["getBalances".to_string(), "getSlots".to_string()]
+                .into_iter()
+                .collect(),
+            read_only: false,
+        };
+        assert_eq!(
+            access.verify(served_methods),
+            Err("RPC methods not served by this node: getBalances, getSlots".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rpc_method_access_load() {
+        let ledger_path = get_tmp_ledger_path!();
+        fs::create_dir(&ledger_path).unwrap();
+        let path = ledger_path.join("rpc-method-access.json");
+
+        fs::write(
+            &path,
+            r#"{"allowedMethods": ["getSlot"], "readOnly": true}"#,
+        )
+        .unwrap();
+        let access = RpcMethodAccess::load(&path).unwrap();
+        assert_eq!(
+            access,
+            RpcMethodAccess {
+                allowed_methods: Some(["getSlot".to_string()].into_iter().collect()),
+                denied_methods: HashSet::default(),
+                read_only: true,
+            }
+        );
+
+        // misspelled rules are not silently ignored
+        fs::write(&path, r#"{"allowMethods": ["getSlot"]}"#).unwrap();
+        assert!(RpcMethodAccess::load(&path).is_err());
+
+        fs::remove_dir_all(ledger_path).unwrap();
+    }
+}
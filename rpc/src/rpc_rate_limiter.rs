@@ -0,0 +1,463 @@
+//! Token bucket rate limits on RPC requests, per client IP and per method.
+//!
+//! Each request takes its method's weight in tokens, so that an expensive scan costs as much
+//! as many cheap requests. A request is rejected, with a hint of when to retry, if either the
+//! bucket of its client IP or the bucket of its method is short of tokens.
+//!
+//! The client IP is the address of the peer of the connection. Behind a reverse proxy, with
+//! `RpcRateLimitConfig::trust_forwarded_for`, it is the last address of the `X-Forwarded-For`
+//! header instead, as appended by the proxy.
+//!
+//! At most `MAX_TRACKED_CLIENTS` client IPs get a bucket of their own between two usage
+//! reports; the clients beyond it share a single bucket, so that a flood of new addresses
+//! can't grow the limiter's memory without bound.
+use {
+    solana_sdk::timing::AtomicInterval,
+    std::{
+        cmp::Reverse,
+        collections::HashMap,
+        net::IpAddr,
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+/// Weight of the methods without an entry in `RpcRateLimitConfig::method_weights`
+pub const DEFAULT_METHOD_WEIGHT: u64 = 1;
+
+// Scans, and methods returning many accounts or transactions, are the expensive ones
+const DEFAULT_METHOD_WEIGHTS: &[(&str, u64)] = &[
+    ("getProgramAccounts", 100),
+    ("getLargestAccounts", 100),
+    ("getSupply", 100),
+    ("getTokenLargestAccounts", 50),
+    ("getTokenAccountsByOwner", 20),
+    ("getTokenAccountsByDelegate", 20),
+    ("getBlock", 10),
+    ("getConfirmedBlock", 10),
+    ("getSignaturesForAddress", 10),
+    ("getConfirmedSignaturesForAddress2", 10),
+    ("getMultipleAccounts", 5),
+    ("getTransaction", 2),
+    ("getConfirmedTransaction", 2),
+];
+
+const USAGE_REPORT_INTERVAL_MS: u64 = 60_000;
+// Clients reported to metrics at the end of each interval
+const NUM_REPORTED_CONSUMERS: usize = 10;
+// Clients kept for the admin RPC at the end of each interval
+const NUM_TOP_CONSUMERS: usize = 100;
+// Client IPs with a bucket and usage of their own
+const MAX_TRACKED_CLIENTS: usize = 100_000;
+
+/// A bucket with no tokens per second or no burst denies every request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBucketConfig {
+    pub tokens_per_second: u64,
+    /// Most tokens the bucket holds, which is the largest burst of requests allowed
+    pub burst: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcRateLimitConfig {
+    /// Limit on the requests of each client IP
+    pub per_ip: Option<TokenBucketConfig>,
+    /// Limits on the requests of all the clients for a method, by method name
+    pub per_method: HashMap<String, TokenBucketConfig>,
+    /// Weight of each method, by method name
+    pub method_weights: HashMap<String, u64>,
+    /// Take the client IP from the `X-Forwarded-For` header rather than from the connection.
+    /// Only safe behind a reverse proxy that sets the header, as clients can forge it.
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RpcRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            per_ip: None,
+            per_method: HashMap::default(),
+            method_weights: DEFAULT_METHOD_WEIGHTS
+                .iter()
+                .map(|(method, weight)| (method.to_string(), *weight))
+                .collect(),
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+impl RpcRateLimitConfig {
+    pub fn method_weight(&self, method: &str) -> u64 {
+        self.method_weights
+            .get(method)
+            .copied()
+            .unwrap_or(DEFAULT_METHOD_WEIGHT)
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(config: &TokenBucketConfig, now: Instant) -> Self {
+        Self {
+            tokens: config.burst as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, config: &TokenBucketConfig, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * config.tokens_per_second as f64)
+            .min(config.burst as f64);
+        self.last_refill = now;
+    }
+
+    /// How long until the bucket holds `weight` tokens, zero if it already does
+    fn wait_time(&self, config: &TokenBucketConfig, weight: u64) -> Duration {
+        if config.tokens_per_second == 0 || config.burst == 0 {
+            return Duration::MAX;
+        }
+        // A request weighing more than the burst would never be allowed otherwise
+        let missing = weight.min(config.burst) as f64 - self.tokens;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / config.tokens_per_second as f64)
+        }
+    }
+
+    fn consume(&mut self, config: &TokenBucketConfig, weight: u64) {
+        self.tokens -= weight.min(config.burst) as f64;
+    }
+
+    fn is_full(&self, config: &TokenBucketConfig) -> bool {
+        self.tokens >= config.burst as f64
+    }
+}
+
+/// The requests of a client IP over the last usage interval
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcClientUsage {
+    /// None for the requests of unknown clients, and of the clients beyond the ones tracked
+    pub ip: Option<IpAddr>,
+    pub requests: u64,
+    /// Sum of the weights of the requests
+    pub weight: u64,
+    pub rate_limited_requests: u64,
+}
+
+#[derive(Debug)]
+pub struct RpcRateLimiter {
+    config: RpcRateLimitConfig,
+    ip_buckets: Mutex<HashMap<Option<IpAddr>, TokenBucket>>,
+    method_buckets: Mutex<HashMap<String, TokenBucket>>,
+    usage: Mutex<HashMap<Option<IpAddr>, RpcClientUsage>>,
+    top_consumers: Mutex<Vec<RpcClientUsage>>,
+    last_report: AtomicInterval,
+}
+
+impl RpcRateLimiter {
+    pub fn new(config: RpcRateLimitConfig) -> Self {
+        Self {
+            config,
+            ip_buckets: Mutex::default(),
+            method_buckets: Mutex::default(),
+            usage: Mutex::default(),
+            top_consumers: Mutex::default(),
+            last_report: AtomicInterval::default(),
+        }
+    }
+
+    pub fn config(&self) -> &RpcRateLimitConfig {
+        &self.config
+    }
+
+    /// Take the tokens for a request of `client_ip` for `method`.
+    /// Return how long to wait before retrying if the request is rate limited.
+    pub fn check(&self, client_ip: Option<IpAddr>, method: &str) -> Result<(), Duration> {
+        let result = self.check_at(client_ip, method, Instant::now());
+        if self.last_report.should_update(USAGE_REPORT_INTERVAL_MS) {
+            self.report();
+        }
+        result
+    }
+
+    fn check_at(
+        &self,
+        client_ip: Option<IpAddr>,
+        method: &str,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let weight = self.config.method_weight(method);
+        let result = {
+            let mut ip_buckets = self.ip_buckets.lock().unwrap();
+            let mut method_buckets = self.method_buckets.lock().unwrap();
+            let mut ip_bucket = match &self.config.per_ip {
+                Some(config) => {
+                    let client_ip = Self::tracked_client_ip(&ip_buckets, client_ip);
+                    let bucket = ip_buckets
+                        .entry(client_ip)
+                        .or_insert_with(|| TokenBucket::new(config, now));
+                    bucket.refill(config, now);
+                    Some((bucket, config))
+                }
+                None => None,
+            };
+            let mut method_bucket = match self.config.per_method.get(method) {
+                Some(config) => {
+                    let bucket = method_buckets
+                        .entry(method.to_string())
+                        .or_insert_with(|| TokenBucket::new(config, now));
+                    bucket.refill(config, now);
+                    Some((bucket, config))
+                }
+                None => None,
+            };
+            // Only take tokens from either bucket if both allow the request
+            let wait_time = ip_bucket
+                .iter()
+                .chain(method_bucket.iter())
+                .map(|(bucket, config)| bucket.wait_time(config, weight))
+                .max()
+                .unwrap_or_default();
+            if wait_time.is_zero() {
+                for (bucket, config) in ip_bucket.iter_mut().chain(method_bucket.iter_mut()) {
+                    bucket.consume(config, weight);
+                }
+                Ok(())
+            } else {
+                Err(wait_time)
+            }
+        };
+
+        let mut usage = self.usage.lock().unwrap();
+        let client_ip = Self::tracked_client_ip(&usage, client_ip);
+        let usage = usage.entry(client_ip).or_insert_with(|| RpcClientUsage {
+            ip: client_ip,
+            ..RpcClientUsage::default()
+        });
+        usage.requests += 1;
+        match result {
+            Ok(()) => usage.weight += weight,
+            Err(_) => usage.rate_limited_requests += 1,
+        }
+        result
+    }
+
+    /// `client_ip`, or None if `clients` has no room for another client
+    fn tracked_client_ip<T>(
+        clients: &HashMap<Option<IpAddr>, T>,
+        client_ip: Option<IpAddr>,
+    ) -> Option<IpAddr> {
+        if clients.len() < MAX_TRACKED_CLIENTS || clients.contains_key(&client_ip) {
+            client_ip
+        } else {
+            None
+        }
+    }
+
+    /// The clients with the heaviest requests over the last usage interval, heaviest first
+    pub fn top_consumers(&self) -> Vec<RpcClientUsage> {
+        self.top_consumers.lock().unwrap().clone()
+    }
+
+    /// Report the top consumers of the interval that just ended, and forget the idle clients
+    fn report(&self) {
+        let mut usage: Vec<_> = std::mem::take(&mut *self.usage.lock().unwrap())
+            .into_values()
+            .collect();
+        let rate_limited_requests: u64 =
+            usage.iter().map(|usage| usage.rate_limited_requests).sum();
+        datapoint_info!(
+            "rpc-rate-limiter",
+            ("clients", usage.len(), i64),
+            (
+                "requests",
+                usage.iter().map(|usage| usage.requests).sum::<u64>(),
+                i64
+            ),
+            ("rate_limited_requests", rate_limited_requests, i64),
+        );
+        let key = |usage: &RpcClientUsage| Reverse((usage.weight, usage.rate_limited_requests));
+        if usage.len() > NUM_TOP_CONSUMERS {
+            usage.select_nth_unstable_by_key(NUM_TOP_CONSUMERS, key);
+            usage.truncate(NUM_TOP_CONSUMERS);
+        }
+        usage.sort_unstable_by_key(key);
+        for usage in usage.iter().take(NUM_REPORTED_CONSUMERS) {
+            datapoint_info!(
+                "rpc-rate-limiter-top-consumer",
+                (
+                    "ip",
+                    usage
+                        .ip
+                        .map(|ip| ip.to_string())
+                        .unwrap_or_else(|| "unknown".to_string()),
+                    String
+                ),
+                ("requests", usage.requests, i64),
+                ("weight", usage.weight, i64),
+                ("rate_limited_requests", usage.rate_limited_requests, i64),
+            );
+        }
+        *self.top_consumers.lock().unwrap() = usage;
+
+        // A full bucket is the same as a new one
+        if let Some(config) = &self.config.per_ip {
+            let now = Instant::now();
+            self.ip_buckets.lock().unwrap().retain(|_, bucket| {
+                bucket.refill(config, now);
+                !bucket.is_full(config)
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::net::Ipv4Addr};
+
+    #[test]
+    fn test_rate_limiter_per_ip() {
+        let rate_limiter = RpcRateLimiter::new(RpcRateLimitConfig {
+            per_ip: Some(TokenBucketConfig {
+                tokens_per_second: 10,
+                burst: 100,
+            }),
+            ..RpcRateLimitConfig::default()
+        });
+        let ip = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        let other_ip = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+        let now = Instant::now();
+
+        // A scan takes the whole burst
+        assert_eq!(rate_limiter.check_at(ip, "getProgramAccounts", now), Ok(()));
+        assert_eq!(
+            rate_limiter.check_at(ip, "getSlot", now),
+            Err(Duration::from_millis(100))
+        );
+        // Other clients have their own bucket
+        assert_eq!(rate_limiter.check_at(other_ip, "getSlot", now), Ok(()));
+        assert_eq!(rate_limiter.check_at(None, "getSlot", now), Ok(()));
+
+        let later = now + Duration::from_millis(100);
+        assert_eq!(rate_limiter.check_at(ip, "getSlot", later), Ok(()));
+        assert_eq!(
+            rate_limiter.check_at(ip, "getProgramAccounts", later),
+            Err(Duration::from_secs(10))
+        );
+
+        rate_limiter.report();
+        let top_consumers = rate_limiter.top_consumers();
+        assert_eq!(
+            top_consumers[0],
+            RpcClientUsage {
+                ip,
+                requests: 4,
+                weight: 101,
+                rate_limited_requests: 2,
+            }
+        );
+        assert_eq!(top_consumers.len(), 3);
+    }
+
+    #[test]
+    fn test_rate_limiter_per_method() {
+        let mut config = RpcRateLimitConfig::default();
+        config.per_method.insert(
+            "getProgramAccounts".to_string(),
+            TokenBucketConfig {
+                tokens_per_second: 100,
+                burst: 200,
+            },
+        );
+        config.method_weights.insert("getSlot".to_string(), 0);
+        let rate_limiter = RpcRateLimiter::new(config);
+        let now = Instant::now();
+
+        // The limit applies to all the clients together
+        for i in 0..2 {
+            let ip = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, i)));
+            assert_eq!(rate_limiter.check_at(ip, "getProgramAccounts", now), Ok(()));
+        }
+        assert_eq!(
+            rate_limiter.check_at(None, "getProgramAccounts", now),
+            Err(Duration::from_secs(1))
+        );
+        // Other methods are not limited
+        assert_eq!(rate_limiter.check_at(None, "getSlot", now), Ok(()));
+    }
+
+    #[test]
+    fn test_rate_limiter_zero_limits_deny() {
+        let mut config = RpcRateLimitConfig {
+            per_ip: Some(TokenBucketConfig {
+                tokens_per_second: 10,
+                burst: 0,
+            }),
+            ..RpcRateLimitConfig::default()
+        };
+        let now = Instant::now();
+        let rate_limiter = RpcRateLimiter::new(config.clone());
+        assert_eq!(
+            rate_limiter.check_at(None, "getSlot", now),
+            Err(Duration::MAX)
+        );
+
+        config.per_ip = None;
+        config.per_method.insert(
+            "getProgramAccounts".to_string(),
+            TokenBucketConfig {
+                tokens_per_second: 0,
+                burst: 100,
+            },
+        );
+        let rate_limiter = RpcRateLimiter::new(config);
+        assert_eq!(
+            rate_limiter.check_at(None, "getProgramAccounts", now),
+            Err(Duration::MAX)
+        );
+        assert_eq!(rate_limiter.check_at(None, "getSlot", now), Ok(()));
+    }
+
+    #[test]
+    fn test_rate_limiter_max_tracked_clients() {
+        let rate_limiter = RpcRateLimiter::new(RpcRateLimitConfig {
+            per_ip: Some(TokenBucketConfig {
+                tokens_per_second: 1,
+                burst: 1,
+            }),
+            ..RpcRateLimitConfig::default()
+        });
+        let now = Instant::now();
+        let ip = |i: usize| Some(IpAddr::V4(Ipv4Addr::from(i as u32 + 1)));
+        for i in 0..MAX_TRACKED_CLIENTS {
+            assert_eq!(rate_limiter.check_at(ip(i), "getSlot", now), Ok(()));
+        }
+        // The clients beyond the tracked ones share a bucket
+        let untracked_ip = ip(MAX_TRACKED_CLIENTS);
+        assert_eq!(rate_limiter.check_at(untracked_ip, "getSlot", now), Ok(()));
+        assert!(rate_limiter
+            .check_at(ip(MAX_TRACKED_CLIENTS + 1), "getSlot", now)
+            .is_err());
+        assert_eq!(
+            rate_limiter.ip_buckets.lock().unwrap().len(),
+            MAX_TRACKED_CLIENTS + 1
+        );
+        assert_eq!(
+            rate_limiter.usage.lock().unwrap().len(),
+            MAX_TRACKED_CLIENTS + 1
+        );
+
+        // Tracked clients keep their own bucket
+        assert!(rate_limiter.check_at(ip(0), "getSlot", now).is_err());
+        assert_eq!(
+            rate_limiter.check_at(ip(0), "getSlot", now + Duration::from_secs(1)),
+            Ok(())
+        );
+    }
+}
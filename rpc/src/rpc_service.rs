@@ -12,13 +12,44 @@ use {
         rpc_health::*,
     },
     crossbeam_channel::unbounded,
-    jsonrpc_core::{futures::prelude::*, MetaIoHandler},
+    jsonrpc_core::{
+        futures::{
+            future::{self, Either},
+            prelude::*,
+            stream,
+        },
+        middleware::{Middleware, NoopCallFuture, NoopFuture},
+        Call, Error, Id, MetaIoHandler, MethodCall, Notification, Output, Params, Result, Version,
+    },
     jsonrpc_http_server::{
-        hyper, AccessControlAllowOrigin, CloseHandle, DomainsValidation, RequestMiddleware,
-        RequestMiddlewareAction, ServerBuilder,
+        cors,
+        hyper::{
+            self,
+            body::HttpBody,
+            server::conn::AddrStream,
+            service::{make_service_fn, Service},
+        },
+        AccessControlAllowOrigin, MetaExtractor, RequestMiddleware, RequestMiddlewareAction,
+        RestApi, Rpc, ServerHandler,
     },
     regex::Regex,
-    solana_client::rpc_cache::LargestAccountsCache,
+    serde::{
+        de::DeserializeOwned,
+        ser::{Error as _, SerializeSeq, Serializer},
+        Serialize,
+    },
+    solana_client::{
+        rpc_cache::LargestAccountsCache,
+        rpc_config::{
+            RpcBlockConfig, RpcEncodingConfigWrapper, RpcProgramAccountsConfig,
+            RpcSignaturesForAddressConfig,
+        },
+        rpc_custom_error::RpcCustomError,
+        rpc_request::STREAM_RESPONSE_HEADER,
+        rpc_response::{
+            Response as RpcResponse, RpcConfirmedTransactionStatusWithSignature, RpcResponseContext,
+        },
+    },
     solana_gossip::cluster_info::ClusterInfo,
     solana_ledger::{
         bigtable_upload_service::BigTableUploadService, blockstore::Blockstore,
@@ -33,21 +64,37 @@ use {
         snapshot_utils,
     },
     solana_sdk::{
-        exit::Exit, genesis_config::DEFAULT_GENESIS_DOWNLOAD_PATH, hash::Hash,
-        native_token::lamports_to_sol, pubkey::Pubkey,
+        clock::{Slot, UnixTimestamp},
+        exit::Exit,
+        genesis_config::DEFAULT_GENESIS_DOWNLOAD_PATH,
+        hash::Hash,
+        native_token::lamports_to_sol,
+        pubkey::Pubkey,
     },
     solana_send_transaction_service::send_transaction_service::{self, SendTransactionService},
     solana_storage_bigtable::CredentialType,
+    solana_transaction_status::{
+        EncodeError, EncodedTransactionWithStatusMeta, Rewards, TransactionDetails,
+    },
     std::{
+        cell::RefCell,
         collections::HashSet,
-        net::SocketAddr,
+        convert::Infallible,
+        fmt,
+        io::{self, SeekFrom, Write},
+        net::{IpAddr, SocketAddr},
         path::{Path, PathBuf},
         sync::{
             atomic::{AtomicBool, AtomicU64, Ordering},
             Arc, Mutex, RwLock,
         },
+        task,
         thread::{self, Builder, JoinHandle},
     },
+    tokio::{
+        io::{AsyncReadExt, AsyncSeekExt},
+        sync::{mpsc, watch},
+    },
     tokio_util::codec::{BytesCodec, FramedRead},
 };
 
@@ -55,6 +102,12 @@ const FULL_SNAPSHOT_REQUEST_PATH: &str = "/snapshot.tar.bz2";
 const INCREMENTAL_SNAPSHOT_REQUEST_PATH: &str = "/incremental-snapshot.tar.bz2";
 const LARGEST_ACCOUNTS_CACHE_DURATION: u64 = 60 * 60 * 2;
 
+// Methods whose responses are streamed, when the request asks for it
+const STREAMED_METHODS: [&str; 3] = ["getProgramAccounts", "getBlock", "getSignaturesForAddress"];
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+// Chunks serialized ahead of a slow client; bounds the memory held by each streamed response
+const MAX_BUFFERED_STREAM_CHUNKS: usize = 16;
+
 pub struct JsonRpcService {
     thread_hdl: JoinHandle<()>,
 
@@ -64,6 +117,56 @@ pub struct JsonRpcService {
     close_handle: Option<CloseHandle>,
 }
 
+/// The part of a file requested with a `Range` header.  Only single byte ranges are served
+/// partially, any other `Range` header is ignored and the whole file is served.
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    Full,
+    /// Inclusive bounds of the range, as in the `Content-Range` header
+    Partial {
+        start: u64,
+        end: u64,
+    },
+    Unsatisfiable,
+}
+
+impl ByteRange {
+    fn from_header(range: &str, file_length: u64) -> Self {
+        let spec = match range.trim().strip_prefix("bytes=") {
+            Some(spec) if !spec.contains(',') => spec.trim(),
+            _ => return Self::Full,
+        };
+        let (start, end) = match spec.split_once('-') {
+            Some(bounds) => bounds,
+            None => return Self::Full,
+        };
+        let (start, end) = match (start.parse::<u64>(), end) {
+            // "bytes=start-": from start to the end of the file
+            (Ok(start), "") => (start, file_length.saturating_sub(1)),
+            // "bytes=start-end": end past the end of the file is clamped
+            (Ok(start), end) => match end.parse::<u64>() {
+                Ok(end) if end >= start => (start, end.min(file_length.saturating_sub(1))),
+                _ => return Self::Full,
+            },
+            // "bytes=-suffix": the last suffix bytes of the file
+            (Err(_), suffix) if start.is_empty() => match suffix.parse::<u64>() {
+                Ok(0) => return Self::Unsatisfiable,
+                Ok(suffix) => (
+                    file_length.saturating_sub(suffix),
+                    file_length.saturating_sub(1),
+                ),
+                Err(_) => return Self::Full,
+            },
+            _ => return Self::Full,
+        };
+        if start >= file_length {
+            Self::Unsatisfiable
+        } else {
+            Self::Partial { start, end }
+        }
+    }
+}
+
 struct RpcRequestMiddleware {
     ledger_path: PathBuf,
     full_snapshot_archive_path_regex: Regex,
@@ -71,6 +174,7 @@ struct RpcRequestMiddleware {
     snapshot_config: Option<SnapshotConfig>,
     bank_forks: Arc<RwLock<BankForks>>,
     health: Arc<RpcHealth>,
+    streaming_responder: Option<StreamingResponder>,
 }
 
 impl RpcRequestMiddleware {
@@ -79,6 +183,7 @@ impl RpcRequestMiddleware {
         snapshot_config: Option<SnapshotConfig>,
         bank_forks: Arc<RwLock<BankForks>>,
         health: Arc<RpcHealth>,
+        streaming_responder: Option<StreamingResponder>,
     ) -> Self {
         Self {
             ledger_path,
@@ -93,6 +198,7 @@ impl RpcRequestMiddleware {
             snapshot_config,
             bank_forks,
             health,
+            streaming_responder,
         }
     }
 
@@ -170,7 +276,7 @@ impl RpcRequestMiddleware {
         }
     }
 
-    fn process_file_get(&self, path: &str) -> RequestMiddlewareAction {
+    fn process_file_get(&self, path: &str, range: Option<&str>) -> RequestMiddlewareAction {
         let stem = path.split_at(1).1; // Drop leading '/' from path
         let filename = {
             match path {
@@ -185,30 +291,64 @@ impl RpcRequestMiddleware {
             }
         };
 
-        let file_length = std::fs::metadata(&filename)
-            .map(|m| m.len())
-            .unwrap_or(0)
-            .to_string();
-        info!("get {} -> {:?} ({} bytes)", path, filename, file_length);
+        let file_length = std::fs::metadata(&filename).map(|m| m.len()).unwrap_or(0);
+        let byte_range = range.map_or(ByteRange::Full, |range| {
+            ByteRange::from_header(range, file_length)
+        });
+        info!(
+            "get {} -> {:?} ({} bytes, {:?})",
+            path, filename, file_length, byte_range
+        );
         RequestMiddlewareAction::Respond {
             should_validate_hosts: true,
-            response: Box::pin(async {
+            response: Box::pin(async move {
                 match Self::open_no_follow(filename).await {
                     Err(err) => Ok(if err.kind() == std::io::ErrorKind::NotFound {
                         Self::not_found()
                     } else {
                         Self::internal_server_error()
                     }),
-                    Ok(file) => {
-                        let stream =
-                            FramedRead::new(file, BytesCodec::new()).map_ok(|b| b.freeze());
-                        let body = hyper::Body::wrap_stream(stream);
-
-                        Ok(hyper::Response::builder()
-                            .header(hyper::header::CONTENT_LENGTH, file_length)
-                            .body(body)
-                            .unwrap())
-                    }
+                    Ok(mut file) => Ok(match byte_range {
+                        ByteRange::Full => {
+                            let stream =
+                                FramedRead::new(file, BytesCodec::new()).map_ok(|b| b.freeze());
+                            let body = hyper::Body::wrap_stream(stream);
+
+                            hyper::Response::builder()
+                                .header(hyper::header::CONTENT_LENGTH, file_length.to_string())
+                                .header(hyper::header::ACCEPT_RANGES, "bytes")
+                                .body(body)
+                                .unwrap()
+                        }
+                        ByteRange::Partial { start, end } => {
+                            if file.seek(SeekFrom::Start(start)).await.is_err() {
+                                return Ok(Self::internal_server_error());
+                            }
+                            let length = end - start + 1;
+                            let stream = FramedRead::new(file.take(length), BytesCodec::new())
+                                .map_ok(|b| b.freeze());
+                            let body = hyper::Body::wrap_stream(stream);
+
+                            hyper::Response::builder()
+                                .status(hyper::StatusCode::PARTIAL_CONTENT)
+                                .header(hyper::header::CONTENT_LENGTH, length.to_string())
+                                .header(
+                                    hyper::header::CONTENT_RANGE,
+                                    format!("bytes {}-{}/{}", start, end, file_length),
+                                )
+                                .header(hyper::header::ACCEPT_RANGES, "bytes")
+                                .body(body)
+                                .unwrap()
+                        }
+                        ByteRange::Unsatisfiable => hyper::Response::builder()
+                            .status(hyper::StatusCode::RANGE_NOT_SATISFIABLE)
+                            .header(
+                                hyper::header::CONTENT_RANGE,
+                                format!("bytes */{}", file_length),
+                            )
+                            .body(hyper::Body::empty())
+                            .unwrap(),
+                    }),
                 }
             }),
         }
@@ -280,13 +420,24 @@ impl RequestMiddleware for RpcRequestMiddleware {
                 .unwrap()
                 .into()
         } else if self.is_file_get_path(request.uri().path()) {
-            self.process_file_get(request.uri().path())
+            let range = request
+                .headers()
+                .get(hyper::header::RANGE)
+                .and_then(|range| range.to_str().ok());
+            self.process_file_get(request.uri().path(), range)
         } else if request.uri().path() == "/health" {
             hyper::Response::builder()
                 .status(hyper::StatusCode::OK)
                 .body(hyper::Body::from(self.health_check()))
                 .unwrap()
                 .into()
+        } else if request.method() == hyper::Method::POST
+            && request.headers().contains_key(STREAM_RESPONSE_HEADER)
+        {
+            match &self.streaming_responder {
+                Some(streaming_responder) => streaming_responder.respond(request),
+                None => request.into(),
+            }
         } else {
             request.into()
         }
@@ -318,6 +469,606 @@ fn process_rest(bank_forks: &Arc<RwLock<BankForks>>, path: &str) -> Option<Strin
     }
 }
 
+/// The result of a method whose response is streamed, encoded as it is serialized
+#[derive(Serialize)]
+#[serde(untagged)]
+enum StreamedResult {
+    ProgramAccounts(OptionalContext<StreamedProgramAccounts>),
+    Block(Option<StreamedBlock>),
+    SignaturesForAddress(StreamedSeq<SignatureStatuses>),
+}
+
+type SignatureStatuses = Box<
+    dyn Iterator<Item = std::result::Result<RpcConfirmedTransactionStatusWithSignature, Infallible>>
+        + Send,
+>;
+
+/// Serialized like `RpcProgramAccounts`
+#[derive(Serialize)]
+#[serde(untagged)]
+enum StreamedProgramAccounts {
+    All(StreamedSeq<EncodedProgramAccounts>),
+    Page {
+        accounts: StreamedSeq<EncodedProgramAccounts>,
+        cursor: Option<String>,
+    },
+}
+
+impl StreamedProgramAccounts {
+    fn new(scan: ProgramAccountsScan, with_context: bool) -> OptionalContext<Self> {
+        let ProgramAccountsScan {
+            bank,
+            accounts,
+            next_cursor,
+        } = scan;
+        let accounts = StreamedSeq::new(accounts);
+        let accounts = match next_cursor {
+            Some(cursor) => Self::Page { accounts, cursor },
+            None => Self::All(accounts),
+        };
+        match with_context {
+            true => OptionalContext::Context(RpcResponse {
+                context: RpcResponseContext { slot: bank.slot() },
+                value: accounts,
+            }),
+            false => OptionalContext::NoContext(accounts),
+        }
+    }
+}
+
+type EncodedBlockItems<T> = Box<dyn Iterator<Item = std::result::Result<T, EncodeError>> + Send>;
+
+/// Serialized like `UiConfirmedBlock`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamedBlock {
+    previous_blockhash: String,
+    blockhash: String,
+    parent_slot: Slot,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transactions: Option<StreamedSeq<EncodedBlockItems<EncodedTransactionWithStatusMeta>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signatures: Option<StreamedSeq<EncodedBlockItems<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rewards: Option<Rewards>,
+    block_time: Option<UnixTimestamp>,
+    block_height: Option<u64>,
+}
+
+impl StreamedBlock {
+    /// Fails like `BlockToEncode::encode` if a transaction can't be encoded, but before any
+    /// transaction is encoded
+    fn new(block_to_encode: BlockToEncode) -> Result<Self> {
+        let BlockToEncode {
+            block,
+            encoding,
+            options,
+        } = block_to_encode;
+        let (transactions, signatures) = match options.transaction_details {
+            TransactionDetails::Full => {
+                let max_supported_transaction_version = options.max_supported_transaction_version;
+                for transaction in &block.transactions {
+                    transaction
+                        .validate_version(max_supported_transaction_version)
+                        .map_err(RpcCustomError::from)?;
+                }
+                let transactions: EncodedBlockItems<_> =
+                    Box::new(block.transactions.into_iter().map(move |transaction| {
+                        transaction.encode(encoding, max_supported_transaction_version)
+                    }));
+                (Some(StreamedSeq::new(transactions)), None)
+            }
+            TransactionDetails::Signatures => {
+                let signatures: EncodedBlockItems<_> = Box::new(
+                    block
+                        .transactions
+                        .into_iter()
+                        .map(|transaction| Ok(transaction.transaction_signature().to_string())),
+                );
+                (None, Some(StreamedSeq::new(signatures)))
+            }
+            TransactionDetails::None => (None, None),
+        };
+        Ok(Self {
+            previous_blockhash: block.previous_blockhash,
+            blockhash: block.blockhash,
+            parent_slot: block.parent_slot,
+            transactions,
+            signatures,
+            rewards: options.show_rewards.then(|| block.rewards),
+            block_time: block.block_time,
+            block_height: block.block_height,
+        })
+    }
+}
+
+/// Serializes the items of an iterator as a JSON array, encoding each one as it is written.
+/// An item that fails to encode fails the serialization. `Serialize` only borrows, so the
+/// iterator is taken out of a cell: it serializes once.
+struct StreamedSeq<I>(RefCell<Option<I>>);
+
+impl<I> StreamedSeq<I> {
+    fn new(items: I) -> Self {
+        Self(RefCell::new(Some(items)))
+    }
+}
+
+impl<I, T, E> Serialize for StreamedSeq<I>
+where
+    I: Iterator<Item = std::result::Result<T, E>>,
+    T: Serialize,
+    E: fmt::Display,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let items = self
+            .0
+            .borrow_mut()
+            .take()
+            .ok_or_else(|| S::Error::custom("streamed sequence was already serialized"))?;
+        let mut seq = serializer.serialize_seq(None)?;
+        for item in items {
+            seq.serialize_element(&item.map_err(S::Error::custom)?)?;
+        }
+        seq.end()
+    }
+}
+
+/// Serves the requests that ask for a streamed response.
+///
+/// The response of a streamed method is serialized in a blocking thread straight into
+/// the chunks of the response body, instead of into one string holding the whole response.
+/// A client reading slowly holds up serialization, so at most a few chunks are buffered.
+/// Other requests, batches included, are handled as usual and sent in one piece.
+#[derive(Clone)]
+struct StreamingResponder {
+    io: Arc<MetaIoHandler<JsonRpcRequestProcessor, RpcCallMiddleware>>,
+    request_processor: JsonRpcRequestProcessor,
+    trust_forwarded_for: bool,
+}
+
+impl StreamingResponder {
+    fn respond(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
+        let responder = self.clone();
+        let client_ip = client_ip(&request, self.trust_forwarded_for);
+        RequestMiddlewareAction::Respond {
+            should_validate_hosts: true,
+            response: Box::pin(async move {
+                Ok(match read_request_body(request.into_body()).await? {
+                    Some(body) => responder.process(body, client_ip).await,
+                    None => hyper::Response::builder()
+                        .status(hyper::StatusCode::PAYLOAD_TOO_LARGE)
+                        .body(hyper::Body::empty())
+                        .unwrap(),
+                })
+            }),
+        }
+    }
+
+    async fn process(
+        &self,
+        body: Vec<u8>,
+        client_ip: Option<IpAddr>,
+    ) -> hyper::Response<hyper::Body> {
+        let meta = self.request_processor.with_client_ip(client_ip);
+        let call = match serde_json::from_slice(&body) {
+            Ok(jsonrpc_core::Request::Single(Call::MethodCall(call)))
+                if call.jsonrpc == Some(Version::V2)
+                    && STREAMED_METHODS.contains(&call.method.as_str()) =>
+            {
+                call
+            }
+            _ => {
+                let body = String::from_utf8_lossy(&body);
+                let response = self.io.handle_request(&body, meta).await;
+                return json_response(hyper::Body::from(
+                    response.map(|response| response + "\n").unwrap_or_default(),
+                ));
+            }
+        };
+        let MethodCall {
+            method, params, id, ..
+        } = call;
+        if let Err(err) = meta
+            .check_method_access(&method)
+            .and_then(|()| meta.check_rate_limit(&method))
+        {
+            return json_response(stream_response(Err(err), id));
+        }
+        let result = match method.as_str() {
+            "getProgramAccounts" => parse_params::<(String, Option<RpcProgramAccountsConfig>)>(
+                params, 2,
+            )
+            .and_then(|(program_id_str, config)| {
+                streamed_program_accounts(&meta, program_id_str, config)
+            }),
+            "getBlock" => {
+                match parse_params::<(Slot, Option<RpcEncodingConfigWrapper<RpcBlockConfig>>)>(
+                    params, 2,
+                ) {
+                    Ok((slot, config)) => streamed_block(&meta, slot, config).await,
+                    Err(err) => Err(err),
+                }
+            }
+            "getSignaturesForAddress" => {
+                match parse_params::<(String, Option<RpcSignaturesForAddressConfig>)>(params, 2) {
+                    Ok((address, config)) => {
+                        streamed_signatures_for_address(&meta, address, config).await
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            _ => unreachable!(),
+        };
+        inc_new_counter_info!("rpc-streamed_response", 1);
+        json_response(stream_response(result, id))
+    }
+}
+
+// The accounts, block or signatures of a streamed response are read before it is streamed, so
+// that a slow client holds no locks. They are encoded as they are serialized.
+
+fn streamed_program_accounts(
+    meta: &JsonRpcRequestProcessor,
+    program_id_str: String,
+    config: Option<RpcProgramAccountsConfig>,
+) -> Result<StreamedResult> {
+    debug!(
+        "get_program_accounts rpc request received: {:?}",
+        program_id_str
+    );
+    let ProgramAccountsParams {
+        program_id,
+        config,
+        filters,
+        with_context,
+        page_config,
+    } = verify_program_accounts_params(&program_id_str, config)?;
+    let scan = meta.scan_program_accounts(&program_id, config, filters, page_config)?;
+    Ok(StreamedResult::ProgramAccounts(
+        StreamedProgramAccounts::new(scan, with_context),
+    ))
+}
+
+async fn streamed_block(
+    meta: &JsonRpcRequestProcessor,
+    slot: Slot,
+    config: Option<RpcEncodingConfigWrapper<RpcBlockConfig>>,
+) -> Result<StreamedResult> {
+    debug!("get_block rpc request received: {:?}", slot);
+    let block = meta.get_block_to_encode(slot, config).await?;
+    Ok(StreamedResult::Block(
+        block.map(StreamedBlock::new).transpose()?,
+    ))
+}
+
+async fn streamed_signatures_for_address(
+    meta: &JsonRpcRequestProcessor,
+    address: String,
+    config: Option<RpcSignaturesForAddressConfig>,
+) -> Result<StreamedResult> {
+    let config = config.unwrap_or_default();
+    let (address, before, until, limit) = verify_and_parse_signatures_for_address_params(
+        address,
+        config.before,
+        config.until,
+        config.limit,
+    )?;
+    let statuses: SignatureStatuses = Box::new(
+        meta.iter_signatures_for_address(address, before, until, limit, config.commitment)
+            .await?
+            .map(Ok),
+    );
+    Ok(StreamedResult::SignaturesForAddress(StreamedSeq::new(
+        statuses,
+    )))
+}
+
+/// The IP of the client making `request`: the peer of the connection, or with
+/// `trust_forwarded_for`, the address appended to `X-Forwarded-For` by the proxy in
+/// front of the node
+fn client_ip(request: &hyper::Request<hyper::Body>, trust_forwarded_for: bool) -> Option<IpAddr> {
+    trust_forwarded_for
+        .then(|| forwarded_client_ip(request.headers()))
+        .flatten()
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<PeerAddr>()
+                .map(|PeerAddr(peer_addr)| peer_addr.ip())
+        })
+}
+
+/// The client IP appended to `X-Forwarded-For` by the proxy in front of the node, if any
+fn forwarded_client_ip(headers: &hyper::HeaderMap) -> Option<IpAddr> {
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .last()?
+        .to_str()
+        .ok()?
+        .rsplit(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Rejects the calls to disabled methods, and those over the limits of
+/// `JsonRpcConfig::rate_limiter`
+#[derive(Debug, Default)]
+struct RpcCallMiddleware;
+
+impl Middleware<JsonRpcRequestProcessor> for RpcCallMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = NoopCallFuture;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let (method, id, jsonrpc) = match &call {
+            Call::MethodCall(MethodCall {
+                method,
+                id,
+                jsonrpc,
+                ..
+            }) => (method, Some(id), jsonrpc),
+            Call::Notification(Notification {
+                method, jsonrpc, ..
+            }) => (method, None, jsonrpc),
+            Call::Invalid { .. } => return Either::Right(next(call, meta)),
+        };
+        match meta
+            .check_method_access(method)
+            .and_then(|()| meta.check_rate_limit(method))
+        {
+            Ok(()) => Either::Right(next(call, meta)),
+            Err(err) => {
+                let output = id.map(|id| Output::from(Err(err), id.clone(), *jsonrpc));
+                Either::Left(Box::pin(future::ready(output)))
+            }
+        }
+    }
+}
+
+/// Parse positional params like the methods of the RPC traits do, with missing trailing
+/// params taken as null
+fn parse_params<T: DeserializeOwned>(params: Params, num_params: usize) -> Result<T> {
+    let mut params = match params {
+        Params::Array(params) if params.len() <= num_params => params,
+        Params::None => vec![],
+        _ => {
+            return Err(Error::invalid_params(format!(
+                "Expected at most {} positional params",
+                num_params
+            )))
+        }
+    };
+    params.resize(num_params, serde_json::Value::Null);
+    Params::Array(params).parse()
+}
+
+async fn read_request_body(mut body: hyper::Body) -> hyper::Result<Option<Vec<u8>>> {
+    let mut bytes = vec![];
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > MAX_REQUEST_PAYLOAD_SIZE {
+            return Ok(None);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Some(bytes))
+}
+
+fn json_response(body: hyper::Body) -> hyper::Response<hyper::Body> {
+    hyper::Response::builder()
+        .header(
+            hyper::header::CONTENT_TYPE,
+            "application/json; charset=utf-8",
+        )
+        .header(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(body)
+        .unwrap()
+}
+
+/// A response body that is serialized as it is sent
+fn stream_response(result: Result<StreamedResult>, id: Id) -> hyper::Body {
+    let (sender, mut receiver) = mpsc::channel(MAX_BUFFERED_STREAM_CHUNKS);
+    tokio::task::spawn_blocking(move || {
+        let mut writer = ChunkWriter::new(sender);
+        if let Err(err) = write_response(&mut writer, result, id).and_then(|()| writer.flush()) {
+            // Fail the body rather than end it early, so that the client does not take
+            // a truncated response for a complete one
+            debug!("Failed to stream response: {}", err);
+            let _ = writer.sender.blocking_send(Err(err));
+        }
+    });
+    hyper::Body::wrap_stream(stream::poll_fn(move |cx| receiver.poll_recv(cx)))
+}
+
+fn write_response(
+    writer: &mut impl Write,
+    result: Result<StreamedResult>,
+    id: Id,
+) -> io::Result<()> {
+    match result {
+        Ok(result) => {
+            writer.write_all(br#"{"jsonrpc":"2.0","result":"#)?;
+            serde_json::to_writer(&mut *writer, &result)?;
+            writer.write_all(br#","id":"#)?;
+            serde_json::to_writer(&mut *writer, &id)?;
+            writer.write_all(b"}\n")
+        }
+        Err(err) => {
+            serde_json::to_writer(&mut *writer, &Output::from(Err(err), id, Some(Version::V2)))?;
+            writer.write_all(b"\n")
+        }
+    }
+}
+
+/// Writes into the chunks of a streamed response body
+struct ChunkWriter {
+    chunk: Vec<u8>,
+    sender: mpsc::Sender<io::Result<Vec<u8>>>,
+}
+
+impl ChunkWriter {
+    fn new(sender: mpsc::Sender<io::Result<Vec<u8>>>) -> Self {
+        Self {
+            chunk: Vec::with_capacity(STREAM_CHUNK_SIZE),
+            sender,
+        }
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.chunk.extend_from_slice(buf);
+        if self.chunk.len() >= STREAM_CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Send the chunk so far, blocking while the client is behind
+    fn flush(&mut self) -> io::Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.chunk, Vec::with_capacity(STREAM_CHUNK_SIZE));
+        self.sender
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "response body was dropped"))
+    }
+}
+
+/// The address of the peer of the connection a request came in on, in the request extensions
+#[derive(Debug, Clone, Copy)]
+struct PeerAddr(SocketAddr);
+
+type RpcServerHandler = ServerHandler<JsonRpcRequestProcessor, RpcCallMiddleware>;
+
+/// Handles the requests of a connection, after recording the connection's peer address in them
+struct PeerAddrService {
+    peer_addr: SocketAddr,
+    handler: RpcServerHandler,
+}
+
+impl Service<hyper::Request<hyper::Body>> for PeerAddrService {
+    type Response = hyper::Response<hyper::Body>;
+    type Error = hyper::Error;
+    type Future = <RpcServerHandler as Service<hyper::Request<hyper::Body>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> task::Poll<hyper::Result<()>> {
+        self.handler.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: hyper::Request<hyper::Body>) -> Self::Future {
+        request.extensions_mut().insert(PeerAddr(self.peer_addr));
+        self.handler.call(request)
+    }
+}
+
+/// Bind `rpc_addr` and return the future serving JSON RPC over HTTP on it, until `close`
+/// changes. `ServerBuilder` of jsonrpc_http_server doesn't pass the peer address of a
+/// connection on to its requests, so the server is put together from its handler instead.
+fn start_http_server<E: MetaExtractor<JsonRpcRequestProcessor>>(
+    runtime: &tokio::runtime::Runtime,
+    rpc_addr: &SocketAddr,
+    io: MetaIoHandler<JsonRpcRequestProcessor, RpcCallMiddleware>,
+    meta_extractor: E,
+    request_middleware: RpcRequestMiddleware,
+    mut close: watch::Receiver<bool>,
+) -> hyper::Result<impl Future<Output = hyper::Result<()>>> {
+    let rpc = Rpc {
+        handler: Arc::new(io),
+        extractor: Arc::new(meta_extractor),
+    };
+    let weak_rpc = rpc.downgrade();
+    let request_middleware: Arc<dyn RequestMiddleware> = Arc::new(request_middleware);
+    let make_service = make_service_fn(move |connection: &AddrStream| {
+        let service = PeerAddrService {
+            peer_addr: connection.remote_addr(),
+            handler: ServerHandler::new(
+                weak_rpc.clone(),
+                Some(vec![AccessControlAllowOrigin::Any]),
+                Some(86400),
+                cors::AccessControlAllowHeaders::Any,
+                None,
+                request_middleware.clone(),
+                RestApi::Disabled,
+                None,
+                MAX_REQUEST_PAYLOAD_SIZE,
+                true,
+            ),
+        };
+        async move { Ok::<_, Infallible>(service) }
+    });
+    let _guard = runtime.enter();
+    let server = hyper::Server::try_bind(rpc_addr)?
+        .http1_keepalive(true)
+        .tcp_nodelay(true)
+        // Recover from accept errors, such as too many open files, rather than stop serving
+        .tcp_sleep_on_accept_errors(true)
+        .serve(make_service)
+        .with_graceful_shutdown(async move {
+            while !*close.borrow() {
+                if close.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+    Ok(async move {
+        // The connections only hold on to a weak reference to the handler
+        let _rpc = rpc;
+        server.await
+    })
+}
+
+/// Stops the HTTP servers of a `JsonRpcService`
+#[derive(Clone)]
+struct CloseHandle(Arc<watch::Sender<bool>>);
+
+impl CloseHandle {
+    fn close(&self) {
+        let _ = self.0.send(true);
+    }
+}
+
+fn new_io_handler(
+    full_api: bool,
+    obsolete_v1_7_api: bool,
+) -> MetaIoHandler<JsonRpcRequestProcessor, RpcCallMiddleware> {
+    let mut io = MetaIoHandler::with_middleware(RpcCallMiddleware);
+
+    io.extend_with(rpc_minimal::MinimalImpl.to_delegate());
+    if full_api {
+        io.extend_with(rpc_bank::BankDataImpl.to_delegate());
+        io.extend_with(rpc_accounts::AccountsDataImpl.to_delegate());
+        io.extend_with(rpc_full::FullImpl.to_delegate());
+        io.extend_with(rpc_deprecated_v1_7::DeprecatedV1_7Impl.to_delegate());
+        io.extend_with(rpc_deprecated_v1_9::DeprecatedV1_9Impl.to_delegate());
+    }
+    if obsolete_v1_7_api {
+        io.extend_with(rpc_obsolete_v1_7::ObsoleteV1_7Impl.to_delegate());
+    }
+    io
+}
+
+/// The methods served on the JSON RPC port with the given API groups enabled
+pub fn rpc_method_names(full_api: bool, obsolete_v1_7_api: bool) -> Vec<String> {
+    new_io_handler(full_api, obsolete_v1_7_api)
+        .into_iter()
+        .map(|(method, _)| method)
+        .collect()
+}
+
 impl JsonRpcService {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -424,6 +1175,12 @@ impl JsonRpcService {
 
         let full_api = config.full_api;
         let obsolete_v1_7_api = config.obsolete_v1_7_api;
+        let unrestricted_rpc_addr = config.unrestricted_rpc_addr;
+        let trust_forwarded_for = config
+            .rate_limiter
+            .as_ref()
+            .map(|rate_limiter| rate_limiter.config().trust_forwarded_for)
+            .unwrap_or_default();
         let (request_processor, receiver) = JsonRpcRequestProcessor::new(
             config,
             snapshot_config.clone(),
@@ -463,53 +1220,69 @@ impl JsonRpcService {
             .spawn(move || {
                 renice_this_thread(rpc_niceness_adj).unwrap();
 
-                let mut io = MetaIoHandler::default();
+                let (close_sender, close_receiver) = watch::channel(false);
+                let start_server =
+                    |rpc_addr: &SocketAddr, request_processor: JsonRpcRequestProcessor| {
+                        let io = new_io_handler(full_api, obsolete_v1_7_api);
+
+                        // The streamed methods are all part of the full api
+                        let streaming_responder = full_api.then(|| StreamingResponder {
+                            io: Arc::new(new_io_handler(full_api, obsolete_v1_7_api)),
+                            request_processor: request_processor.clone(),
+                            trust_forwarded_for,
+                        });
+                        let request_middleware = RpcRequestMiddleware::new(
+                            ledger_path.clone(),
+                            snapshot_config.clone(),
+                            bank_forks.clone(),
+                            health.clone(),
+                            streaming_responder,
+                        );
+                        start_http_server(
+                            &runtime,
+                            rpc_addr,
+                            io,
+                            move |request: &hyper::Request<hyper::Body>| {
+                                request_processor
+                                    .with_client_ip(client_ip(request, trust_forwarded_for))
+                            },
+                            request_middleware,
+                            close_receiver.clone(),
+                        )
+                    };
 
-                io.extend_with(rpc_minimal::MinimalImpl.to_delegate());
-                if full_api {
-                    io.extend_with(rpc_bank::BankDataImpl.to_delegate());
-                    io.extend_with(rpc_accounts::AccountsDataImpl.to_delegate());
-                    io.extend_with(rpc_full::FullImpl.to_delegate());
-                    io.extend_with(rpc_deprecated_v1_7::DeprecatedV1_7Impl.to_delegate());
-                    io.extend_with(rpc_deprecated_v1_9::DeprecatedV1_9Impl.to_delegate());
-                }
-                if obsolete_v1_7_api {
-                    io.extend_with(rpc_obsolete_v1_7::ObsoleteV1_7Impl.to_delegate());
-                }
+                let server = match start_server(&rpc_addr, request_processor.clone()) {
+                    Ok(server) => server,
+                    Err(e) => {
+                        warn!(
+                            "JSON RPC service unavailable error: {:?}. \n\
+                               Also, check that port {} is not already in use by another application",
+                            e,
+                            rpc_addr.port()
+                        );
+                        return;
+                    }
+                };
 
-                let request_middleware = RpcRequestMiddleware::new(
-                    ledger_path,
-                    snapshot_config,
-                    bank_forks.clone(),
-                    health.clone(),
-                );
-                let server = ServerBuilder::with_meta_extractor(
-                    io,
-                    move |_req: &hyper::Request<hyper::Body>| request_processor.clone(),
-                )
-                .event_loop_executor(runtime.handle().clone())
-                .threads(1)
-                .cors(DomainsValidation::AllowOnly(vec![
-                    AccessControlAllowOrigin::Any,
-                ]))
-                .cors_max_age(86400)
-                .request_middleware(request_middleware)
-                .max_request_body_size(MAX_REQUEST_PAYLOAD_SIZE)
-                .start_http(&rpc_addr);
-
-                if let Err(e) = server {
-                    warn!(
-                        "JSON RPC service unavailable error: {:?}. \n\
-                           Also, check that port {} is not already in use by another application",
-                        e,
-                        rpc_addr.port()
-                    );
-                    return;
+                let unrestricted_server = unrestricted_rpc_addr.and_then(|unrestricted_rpc_addr| {
+                    info!("unrestricted rpc bound to {:?}", unrestricted_rpc_addr);
+                    start_server(&unrestricted_rpc_addr, request_processor.unrestricted())
+                        .map_err(|e| {
+                            warn!("Unrestricted JSON RPC service unavailable error: {:?}", e);
+                        })
+                        .ok()
+                });
+
+                close_handle_sender
+                    .send(CloseHandle(Arc::new(close_sender)))
+                    .unwrap();
+                let (result, unrestricted_result) = runtime.block_on(future::join(
+                    server,
+                    future::OptionFuture::from(unrestricted_server),
+                ));
+                if let Err(e) = result.and(unrestricted_result.unwrap_or(Ok(()))) {
+                    warn!("JSON RPC service error: {:?}", e);
                 }
-
-                let server = server.unwrap();
-                close_handle_sender.send(server.close_handle()).unwrap();
-                server.wait();
                 exit_bigtable_ledger_upload_service.store(true, Ordering::Relaxed);
             })
             .unwrap();
@@ -543,7 +1316,11 @@ impl JsonRpcService {
 mod tests {
     use {
         super::*,
-        crate::rpc::create_validator_exit,
+        crate::{
+            rpc::create_validator_exit,
+            rpc_method_access::{DEPRECATED_METHOD_ALIASES, WRITE_METHODS},
+        },
+        solana_client::rpc_client::RpcClient,
         solana_gossip::{
             contact_info::ContactInfo,
             crds::GossipRoute,
@@ -555,11 +1332,17 @@ mod tests {
         },
         solana_runtime::bank::Bank,
         solana_sdk::{
+            account::AccountSharedData,
             genesis_config::{ClusterType, DEFAULT_GENESIS_ARCHIVE},
             signature::Signer,
             signer::keypair::Keypair,
+            system_transaction,
         },
         solana_streamer::socket::SocketAddrSpace,
+        solana_transaction_status::{
+            BlockEncodingOptions, ConfirmedBlock, Reward, TransactionWithStatusMeta,
+            UiTransactionEncoding,
+        },
         std::{
             io::Write,
             net::{IpAddr, Ipv4Addr},
@@ -627,10 +1410,41 @@ mod tests {
                 .get_balance(&mint_keypair.pubkey(), None)
                 .value
         );
+        // served over http
+        let rpc_client = RpcClient::new_socket(SocketAddr::new(
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            rpc_addr.port(),
+        ));
+        assert_eq!(
+            rpc_client.get_balance(&mint_keypair.pubkey()).unwrap(),
+            10_000
+        );
         rpc_service.exit();
         rpc_service.join().unwrap();
     }
 
+    #[test]
+    fn test_client_ip() {
+        let peer_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let forwarded_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let mut request = hyper::Request::builder()
+            .header("x-forwarded-for", format!("192.168.0.1, {}", forwarded_ip))
+            .body(hyper::Body::empty())
+            .unwrap();
+        assert_eq!(client_ip(&request, false), None);
+        assert_eq!(client_ip(&request, true), Some(forwarded_ip));
+
+        request
+            .extensions_mut()
+            .insert(PeerAddr(SocketAddr::new(peer_ip, 8899)));
+        // the header is ignored unless trusted
+        assert_eq!(client_ip(&request, false), Some(peer_ip));
+        assert_eq!(client_ip(&request, true), Some(forwarded_ip));
+
+        request.headers_mut().remove("x-forwarded-for");
+        assert_eq!(client_ip(&request, true), Some(peer_ip));
+    }
+
     fn create_bank_forks() -> Arc<RwLock<BankForks>> {
         let GenesisConfigInfo {
             mut genesis_config, ..
@@ -659,12 +1473,14 @@ mod tests {
             None,
             bank_forks.clone(),
             RpcHealth::stub(),
+            None,
         );
         let rrm_with_snapshot_config = RpcRequestMiddleware::new(
             PathBuf::from("/"),
             Some(SnapshotConfig::default()),
             bank_forks,
             RpcHealth::stub(),
+            None,
         );
 
         assert!(rrm.is_file_get_path(DEFAULT_GENESIS_DOWNLOAD_PATH));
@@ -735,10 +1551,11 @@ mod tests {
             None,
             create_bank_forks(),
             RpcHealth::stub(),
+            None,
         );
 
         // File does not exist => request should fail.
-        let action = rrm.process_file_get(DEFAULT_GENESIS_DOWNLOAD_PATH);
+        let action = rrm.process_file_get(DEFAULT_GENESIS_DOWNLOAD_PATH, None);
         if let RequestMiddlewareAction::Respond { response, .. } = action {
             let response = runtime.block_on(response);
             let response = response.unwrap();
@@ -753,7 +1570,7 @@ mod tests {
         }
 
         // Normal file exist => request should succeed.
-        let action = rrm.process_file_get(DEFAULT_GENESIS_DOWNLOAD_PATH);
+        let action = rrm.process_file_get(DEFAULT_GENESIS_DOWNLOAD_PATH, None);
         if let RequestMiddlewareAction::Respond { response, .. } = action {
             let response = runtime.block_on(response);
             let response = response.unwrap();
@@ -772,7 +1589,7 @@ mod tests {
             symlink::symlink_file("wrong", &genesis_path).unwrap();
 
             // File is a symbolic link => request should fail.
-            let action = rrm.process_file_get(DEFAULT_GENESIS_DOWNLOAD_PATH);
+            let action = rrm.process_file_get(DEFAULT_GENESIS_DOWNLOAD_PATH, None);
             if let RequestMiddlewareAction::Respond { response, .. } = action {
                 let response = runtime.block_on(response);
                 let response = response.unwrap();
@@ -783,6 +1600,118 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_byte_range_from_header() {
+        assert_eq!(
+            ByteRange::from_header("bytes=0-99", 1000),
+            ByteRange::Partial { start: 0, end: 99 }
+        );
+        assert_eq!(
+            ByteRange::from_header("bytes=900-", 1000),
+            ByteRange::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            ByteRange::from_header("bytes=900-5000", 1000),
+            ByteRange::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            ByteRange::from_header("bytes=-100", 1000),
+            ByteRange::Partial {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            ByteRange::from_header("bytes=-5000", 1000),
+            ByteRange::Partial { start: 0, end: 999 }
+        );
+        assert_eq!(
+            ByteRange::from_header("bytes=1000-", 1000),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(
+            ByteRange::from_header("bytes=-0", 1000),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(
+            ByteRange::from_header("bytes=0-", 0),
+            ByteRange::Unsatisfiable
+        );
+
+        // Malformed and multiple ranges are ignored
+        assert_eq!(ByteRange::from_header("bytes=99-0", 1000), ByteRange::Full);
+        assert_eq!(
+            ByteRange::from_header("bytes=0-1,5-9", 1000),
+            ByteRange::Full
+        );
+        assert_eq!(ByteRange::from_header("bytes=a-b", 1000), ByteRange::Full);
+        assert_eq!(ByteRange::from_header("items=0-1", 1000), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_process_file_get_range() {
+        let runtime = Runtime::new().unwrap();
+
+        let ledger_path = get_tmp_ledger_path!();
+        std::fs::create_dir(&ledger_path).unwrap();
+        {
+            let mut file =
+                std::fs::File::create(ledger_path.join(DEFAULT_GENESIS_ARCHIVE)).unwrap();
+            file.write_all(b"0123456789").unwrap();
+        }
+        let rrm = RpcRequestMiddleware::new(
+            ledger_path.clone(),
+            None,
+            create_bank_forks(),
+            RpcHealth::stub(),
+            None,
+        );
+        let get = |range| {
+            if let RequestMiddlewareAction::Respond { response, .. } =
+                rrm.process_file_get(DEFAULT_GENESIS_DOWNLOAD_PATH, range)
+            {
+                let response = runtime.block_on(response).unwrap();
+                let status = response.status();
+                let content_range = response
+                    .headers()
+                    .get(hyper::header::CONTENT_RANGE)
+                    .map(|content_range| content_range.to_str().unwrap().to_string());
+                let body = runtime
+                    .block_on(hyper::body::to_bytes(response.into_body()))
+                    .unwrap();
+                (status, content_range, body)
+            } else {
+                panic!("Unexpected RequestMiddlewareAction variant");
+            }
+        };
+
+        let (status, content_range, body) = get(None);
+        assert_eq!(status, hyper::StatusCode::OK);
+        assert_eq!(content_range, None);
+        assert_eq!(&body[..], b"0123456789");
+
+        let (status, content_range, body) = get(Some("bytes=2-5"));
+        assert_eq!(status, hyper::StatusCode::PARTIAL_CONTENT);
+        assert_eq!(content_range.as_deref(), Some("bytes 2-5/10"));
+        assert_eq!(&body[..], b"2345");
+
+        let (status, content_range, body) = get(Some("bytes=7-"));
+        assert_eq!(status, hyper::StatusCode::PARTIAL_CONTENT);
+        assert_eq!(content_range.as_deref(), Some("bytes 7-9/10"));
+        assert_eq!(&body[..], b"789");
+
+        let (status, content_range, body) = get(Some("bytes=10-"));
+        assert_eq!(status, hyper::StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(content_range.as_deref(), Some("bytes */10"));
+        assert!(body.is_empty());
+    }
+
     #[test]
     fn test_health_check_with_no_known_validators() {
         let rm = RpcRequestMiddleware::new(
@@ -790,6 +1719,7 @@ mod tests {
             None,
             create_bank_forks(),
             RpcHealth::stub(),
+            None,
         );
         assert_eq!(rm.health_check(), "ok");
     }
@@ -886,4 +1816,176 @@ mod tests {
             .unwrap();
         assert_eq!(rm.health_check(), "behind");
     }
+
+    #[test]
+    fn test_streaming_responder() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank = Arc::new(Bank::new_for_tests(&genesis_config));
+        let program_id = Pubkey::new_unique();
+        for lamports in 1..=100 {
+            let account = AccountSharedData::new(lamports, 1000, &program_id);
+            bank.store_account(&Pubkey::new_unique(), &account);
+        }
+        let responder = StreamingResponder {
+            io: Arc::new(new_io_handler(true, false)),
+            request_processor: JsonRpcRequestProcessor::new_from_bank(
+                &bank,
+                SocketAddrSpace::Unspecified,
+            ),
+            trust_forwarded_for: false,
+        };
+        let runtime = Runtime::new().unwrap();
+        let streamed_response = |request: &serde_json::Value| -> serde_json::Value {
+            runtime.block_on(async {
+                let response = responder
+                    .process(request.to_string().into_bytes(), None)
+                    .await;
+                let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+                serde_json::from_slice(&body).unwrap()
+            })
+        };
+        let response = |request: &serde_json::Value| -> serde_json::Value {
+            let response = runtime
+                .block_on(
+                    responder
+                        .io
+                        .handle_request(&request.to_string(), responder.request_processor.clone()),
+                )
+                .unwrap();
+            serde_json::from_str(&response).unwrap()
+        };
+
+        // Spans several chunks
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getProgramAccounts",
+            "params": [program_id.to_string(), {"encoding": "base64"}],
+        });
+        let streamed = streamed_response(&request);
+        assert_eq!(streamed["result"].as_array().unwrap().len(), 100);
+        assert_eq!(streamed, response(&request));
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": "invalid",
+            "method": "getProgramAccounts",
+            "params": ["invalid"],
+        });
+        let streamed = streamed_response(&request);
+        assert!(streamed["error"].is_object());
+        assert_eq!(streamed, response(&request));
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "getProgramAccounts",
+            "params": [
+                program_id.to_string(),
+                {"encoding": "base64", "withContext": true, "pageSize": 10},
+            ],
+        });
+        let streamed = streamed_response(&request);
+        assert_eq!(
+            streamed["result"]["value"]["accounts"]
+                .as_array()
+                .unwrap()
+                .len(),
+            10
+        );
+        assert!(streamed["result"]["value"]["cursor"].is_string());
+        assert_eq!(streamed, response(&request));
+
+        // Accounts too large for base58 fail the request before anything is streamed
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "getProgramAccounts",
+            "params": [program_id.to_string(), {"encoding": "base58"}],
+        });
+        let streamed = streamed_response(&request);
+        assert!(streamed["error"].is_object());
+        assert_eq!(streamed, response(&request));
+
+        // Not a streamed method
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "getBalance",
+            "params": [program_id.to_string()],
+        });
+        assert_eq!(streamed_response(&request), response(&request));
+    }
+
+    #[test]
+    fn test_streamed_block() {
+        let keypair = Keypair::new();
+        let block = ConfirmedBlock {
+            previous_blockhash: Hash::new_unique().to_string(),
+            blockhash: Hash::new_unique().to_string(),
+            parent_slot: 1,
+            transactions: (1..=10)
+                .map(|lamports| {
+                    TransactionWithStatusMeta::MissingMetadata(system_transaction::transfer(
+                        &keypair,
+                        &Pubkey::new_unique(),
+                        lamports,
+                        Hash::new_unique(),
+                    ))
+                })
+                .collect(),
+            rewards: vec![Reward {
+                pubkey: Pubkey::new_unique().to_string(),
+                lamports: 42,
+                post_balance: 42,
+                reward_type: None,
+                commission: None,
+            }],
+            block_time: Some(1),
+            block_height: Some(2),
+        };
+        let options = |transaction_details, show_rewards| BlockEncodingOptions {
+            transaction_details,
+            show_rewards,
+            max_supported_transaction_version: None,
+        };
+        for (transaction_details, show_rewards) in [
+            (TransactionDetails::Full, true),
+            (TransactionDetails::Signatures, false),
+            (TransactionDetails::None, true),
+        ] {
+            let streamed_block = StreamedBlock::new(BlockToEncode {
+                block: block.clone(),
+                encoding: UiTransactionEncoding::Base64,
+                options: options(transaction_details, show_rewards),
+            })
+            .unwrap();
+            let encoded_block = block
+                .clone()
+                .encode_with_options(
+                    UiTransactionEncoding::Base64,
+                    options(transaction_details, show_rewards),
+                )
+                .unwrap();
+            assert_eq!(
+                serde_json::to_value(&streamed_block).unwrap(),
+                serde_json::to_value(&encoded_block).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_rpc_method_names() {
+        let methods = rpc_method_names(true, true);
+        for method in WRITE_METHODS.iter().chain(
+            DEPRECATED_METHOD_ALIASES
+                .iter()
+                .flat_map(|(alias, method)| [alias, method]),
+        ) {
+            assert!(methods.contains(&method.to_string()), "{}", method);
+        }
+        let methods = rpc_method_names(false, false);
+        assert!(methods.contains(&"getSlot".to_string()));
+        assert!(!methods.contains(&"getBlock".to_string()));
+    }
 }
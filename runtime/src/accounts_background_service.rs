@@ -356,8 +356,10 @@ impl SnapshotRequestHandler {
             SnapshotError::FileNameToStrError(..) => true,
             SnapshotError::ParseSnapshotArchiveFileNameError(..) => true,
             SnapshotError::MismatchedBaseSlot(..) => true,
+            SnapshotError::MismatchedIncrementalSnapshotChain(..) => true,
             SnapshotError::NoSnapshotArchives => true,
             SnapshotError::MismatchedSlotHash(..) => true,
+            SnapshotError::VerificationFailed(..) => true,
         }
     }
 }
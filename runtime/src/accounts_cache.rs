@@ -1,4 +1,5 @@
 use {
+    crate::append_vec::StoredMetaWriteVersion,
     dashmap::DashMap,
     solana_sdk::{
         account::{AccountSharedData, ReadableAccount},
@@ -71,6 +72,7 @@ impl SlotCacheInner {
         account: AccountSharedData,
         hash: Option<impl Borrow<Hash>>,
         slot: Slot,
+        write_version: StoredMetaWriteVersion,
     ) -> CachedAccount {
         let data_len = account.data().len() as u64;
         let item = Arc::new(CachedAccountInner {
@@ -78,6 +80,7 @@ impl SlotCacheInner {
             hash: RwLock::new(hash.map(|h| *h.borrow())),
             slot,
             pubkey: *pubkey,
+            write_version,
         });
         if let Some(old) = self.cache.insert(*pubkey, item.clone()) {
             self.same_account_writes.fetch_add(1, Ordering::Relaxed);
@@ -143,6 +146,8 @@ pub struct CachedAccountInner {
     hash: RwLock<Option<Hash>>,
     slot: Slot,
     pubkey: Pubkey,
+    /// the write version assigned when the account was stored, a new one is assigned when it is flushed
+    write_version: StoredMetaWriteVersion,
 }
 
 impl CachedAccountInner {
@@ -165,6 +170,9 @@ impl CachedAccountInner {
     pub fn pubkey(&self) -> &Pubkey {
         &self.pubkey
     }
+    pub fn write_version(&self) -> StoredMetaWriteVersion {
+        self.write_version
+    }
 }
 
 #[derive(Debug, Default)]
@@ -227,6 +235,7 @@ impl AccountsCache {
         pubkey: &Pubkey,
         account: AccountSharedData,
         hash: Option<impl Borrow<Hash>>,
+        write_version: StoredMetaWriteVersion,
     ) -> CachedAccount {
         let slot_cache = self.slot_cache(slot).unwrap_or_else(||
             // DashMap entry.or_insert() returns a RefMut, essentially a write lock,
@@ -239,7 +248,7 @@ impl AccountsCache {
                 .or_insert(self.new_inner())
                 .clone());
 
-        slot_cache.insert(pubkey, account, hash, slot)
+        slot_cache.insert(pubkey, account, hash, slot, write_version)
     }
 
     pub fn load(&self, slot: Slot, pubkey: &Pubkey) -> Option<CachedAccount> {
@@ -336,6 +345,7 @@ pub mod tests {
             &Pubkey::new_unique(),
             AccountSharedData::new(1, 0, &Pubkey::default()),
             Some(&Hash::default()),
+            0,
         );
         // If the cache is told the size limit is 0, it should return the one slot
         let removed = cache.remove_slots_le(0);
@@ -354,6 +364,7 @@ pub mod tests {
             &Pubkey::new_unique(),
             AccountSharedData::new(1, 0, &Pubkey::default()),
             Some(&Hash::default()),
+            0,
         );
 
         // If the cache is told the size limit is 0, it should return nothing, because there's no
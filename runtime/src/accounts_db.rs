@@ -25,7 +25,10 @@ use {
         accounts_cache::{AccountsCache, CachedAccount, SlotCache},
         accounts_hash::{
             AccountsHash, CalcAccountsHashConfig, CalculateHashIntermediate, HashStats,
-            PreviousPass,
+        },
+        accounts_hash_checkpoint::{
+            storages_digest, AccountsHashCheckpoints, AccountsHashProgress,
+            AccountsHashProgressTracker,
         },
         accounts_index::{
             AccountIndexGetResult, AccountSecondaryIndexes, AccountsIndex, AccountsIndexConfig,
@@ -37,13 +40,16 @@ use {
         accounts_update_notifier_interface::AccountsUpdateNotifier,
         active_stats::{ActiveStatItem, ActiveStats},
         ancestors::Ancestors,
-        append_vec::{AppendVec, StoredAccountMeta, StoredMeta, StoredMetaWriteVersion},
+        append_vec::{
+            AppendVec, AppendVecBackend, StoredAccountMeta, StoredMeta, StoredMetaWriteVersion,
+        },
         bank::Rewrites,
         cache_hash_data::CacheHashData,
+        compaction_policy::{CompactionContext, CompactionPolicy, ThresholdCompactionPolicy},
         contains::Contains,
         expected_rent_collection::ExpectedRentCollection,
         pubkey_bins::PubkeyBinCalculator24,
-        read_only_accounts_cache::ReadOnlyAccountsCache,
+        read_only_accounts_cache::{ReadOnlyAccountsCache, ReadOnlyCacheEviction},
         rent_collector::RentCollector,
         sorted_storages::SortedStorages,
         storable_accounts::StorableAccounts,
@@ -84,19 +90,22 @@ use {
             atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
             Arc, Condvar, Mutex, MutexGuard, RwLock,
         },
-        thread::{sleep, Builder},
+        thread::{sleep, Builder, JoinHandle},
         time::{Duration, Instant},
     },
     tempfile::TempDir,
 };
 
-const PAGE_SIZE: u64 = 4 * 1024;
+pub(crate) const PAGE_SIZE: u64 = 4 * 1024;
 const MAX_RECYCLE_STORES: usize = 1000;
 const STORE_META_OVERHEAD: usize = 256;
 // when the accounts write cache exceeds this many bytes, we will flush it
 // this can be specified on the command line, too (--accounts-db-cache-limit-mb)
 const WRITE_CACHE_LIMIT_BYTES_DEFAULT: u64 = 15_000_000_000;
 const SCAN_SLOT_PAR_ITER_THRESHOLD: usize = 4000;
+const MAX_READ_ONLY_CACHE_DATA_SIZE: usize = 200_000_000;
+// Owners with the most read-only cache loads are reported individually
+const MAX_READ_ONLY_CACHE_OWNERS_REPORTED: usize = 10;
 
 pub const DEFAULT_FILE_SIZE: u64 = PAGE_SIZE * 1024;
 pub const DEFAULT_NUM_THREADS: u32 = 8;
@@ -135,6 +144,11 @@ pub const ACCOUNTS_DB_CONFIG_FOR_TESTING: AccountsDbConfig = AccountsDbConfig {
     hash_calc_num_passes: None,
     write_cache_limit_bytes: None,
     skip_rewrites: false,
+    compaction_policy: None,
+    read_only_cache_eviction: ReadOnlyCacheEviction::Lru,
+    read_only_cache_limit_bytes: None,
+    append_vec_backend: AppendVecBackend::Mmap,
+    accounts_hash_progress: None,
 };
 pub const ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS: AccountsDbConfig = AccountsDbConfig {
     index: Some(ACCOUNTS_INDEX_CONFIG_FOR_BENCHMARKS),
@@ -143,6 +157,11 @@ pub const ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS: AccountsDbConfig = AccountsDbConfig
     hash_calc_num_passes: None,
     write_cache_limit_bytes: None,
     skip_rewrites: false,
+    compaction_policy: None,
+    read_only_cache_eviction: ReadOnlyCacheEviction::Lru,
+    read_only_cache_limit_bytes: None,
+    append_vec_backend: AppendVecBackend::Mmap,
+    accounts_hash_progress: None,
 };
 
 pub type BinnedHashData = Vec<Vec<CalculateHashIntermediate>>;
@@ -181,6 +200,16 @@ pub struct AccountsDbConfig {
     pub hash_calc_num_passes: Option<usize>,
     pub write_cache_limit_bytes: Option<u64>,
     pub skip_rewrites: bool,
+    /// decides which storages are shrunk. If None, `ThresholdCompactionPolicy` with the shrink ratio is used
+    pub compaction_policy: Option<Arc<dyn CompactionPolicy>>,
+    /// decides which accounts the read-only accounts cache evicts when it is full
+    pub read_only_cache_eviction: ReadOnlyCacheEviction,
+    /// how large the read-only accounts cache can become. If None, `MAX_READ_ONLY_CACHE_DATA_SIZE` is used
+    pub read_only_cache_limit_bytes: Option<usize>,
+    /// how the storages access their files
+    pub append_vec_backend: AppendVecBackend,
+    /// where to report the progress of accounts hash calculations
+    pub accounts_hash_progress: Option<Arc<AccountsHashProgressTracker>>,
 }
 
 struct FoundStoredAccount<'a> {
@@ -394,7 +423,7 @@ type AccountSlots = HashMap<Pubkey, HashSet<Slot>>;
 type AppendVecOffsets = HashMap<AppendVecId, HashSet<usize>>;
 type ReclaimResult = (AccountSlots, AppendVecOffsets);
 type StorageFinder<'a> = Box<dyn Fn(Slot, usize) -> Arc<AccountStorageEntry> + 'a>;
-type ShrinkCandidates = HashMap<Slot, HashMap<AppendVecId, Arc<AccountStorageEntry>>>;
+pub type ShrinkCandidates = HashMap<Slot, HashMap<AppendVecId, Arc<AccountStorageEntry>>>;
 
 trait Versioned {
     fn version(&self) -> u64;
@@ -568,7 +597,7 @@ impl<'a> ReadableAccount for LoadedAccount<'a> {
 
     fn data(&self) -> &[u8] {
         match self {
-            LoadedAccount::Stored(stored_account_meta) => stored_account_meta.data,
+            LoadedAccount::Stored(stored_account_meta) => &stored_account_meta.data,
             LoadedAccount::Cached(cached_account) => cached_account.account.data(),
         }
     }
@@ -699,14 +728,24 @@ pub struct AccountStorageEntry {
     /// use case, this value ARE NOT strictly synchronized with count_and_status!
     approx_store_count: AtomicUsize,
 
-    alive_bytes: AtomicUsize,
+    pub(crate) alive_bytes: AtomicUsize,
 }
 
 impl AccountStorageEntry {
     pub fn new(path: &Path, slot: Slot, id: AppendVecId, file_size: u64) -> Self {
+        Self::new_with_backend(path, slot, id, file_size, AppendVecBackend::Mmap)
+    }
+
+    pub fn new_with_backend(
+        path: &Path,
+        slot: Slot,
+        id: AppendVecId,
+        file_size: u64,
+        backend: AppendVecBackend,
+    ) -> Self {
         let tail = AppendVec::file_name(slot, id);
         let path = Path::new(path).join(tail);
-        let accounts = AppendVec::new(&path, true, file_size as usize);
+        let accounts = AppendVec::new_with_backend(&path, true, file_size as usize, backend);
 
         Self {
             id: AtomicAppendVecId::new(id),
@@ -1011,6 +1050,13 @@ struct RemoveUnrootedSlotsSynchronization {
 
 type AccountInfoAccountsIndex = AccountsIndex<AccountInfo>;
 
+/// The thread started by `AccountsDb::rebuild_secondary_indexes_in_background()`
+#[derive(Debug)]
+struct SecondaryIndexRebuild {
+    exit: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
 // This structure handles the load/store of the accounts
 #[derive(Debug)]
 pub struct AccountsDb {
@@ -1037,6 +1083,15 @@ pub struct AccountsDb {
     /// Set of shrinkable stores organized by map of slot to append_vec_id
     pub shrink_candidate_slots: Mutex<ShrinkCandidates>,
 
+    /// Overrides the `ThresholdCompactionPolicy` built from `shrink_ratio`
+    compaction_policy: Option<Arc<dyn CompactionPolicy>>,
+
+    /// How new storages access their files
+    pub(crate) append_vec_backend: AppendVecBackend,
+
+    /// Progress of the latest accounts hash calculation
+    pub accounts_hash_progress: Arc<AccountsHashProgressTracker>,
+
     /// Legacy shrink slots to support non-cached code-path.
     pub shrink_candidate_slots_v1: Mutex<Vec<Slot>>,
 
@@ -1098,6 +1153,14 @@ pub struct AccountsDb {
     #[cfg(test)]
     load_limit: AtomicU64,
 
+    /// When not 0, an accounts hash calculation panics after this many passes,
+    /// as if the validator had stopped in the middle of it
+    #[cfg(test)]
+    interrupt_hash_calculation_after_passes: AtomicUsize,
+
+    /// The background rebuild of the secondary indexes, while it runs
+    secondary_index_rebuild: Mutex<Option<SecondaryIndexRebuild>>,
+
     /// true if drop_callback is attached to the bank.
     is_bank_drop_callback_enabled: AtomicBool,
 
@@ -1556,7 +1619,7 @@ impl<'a> ReadableAccount for StoredAccountMeta<'a> {
         self.account_meta.lamports
     }
     fn data(&self) -> &[u8] {
-        self.data
+        &self.data
     }
     fn owner(&self) -> &Pubkey {
         &self.account_meta.owner
@@ -1605,7 +1668,6 @@ impl AccountsDb {
         num_hash_scan_passes: Option<usize>,
     ) -> Self {
         let num_threads = get_thread_count();
-        const MAX_READ_ONLY_CACHE_DATA_SIZE: usize = 200_000_000;
 
         let mut temp_accounts_hash_cache_path = None;
         let accounts_hash_cache_path = accounts_hash_cache_path.unwrap_or_else(|| {
@@ -1636,6 +1698,9 @@ impl AccountsDb {
             next_id: AtomicAppendVecId::new(0),
             shrink_candidate_slots_v1: Mutex::new(Vec::new()),
             shrink_candidate_slots: Mutex::new(HashMap::new()),
+            compaction_policy: None,
+            append_vec_backend: AppendVecBackend::default(),
+            accounts_hash_progress: Arc::default(),
             write_cache_limit_bytes: None,
             write_version: AtomicU64::new(0),
             paths: vec![],
@@ -1663,6 +1728,9 @@ impl AccountsDb {
             load_delay: u64::default(),
             #[cfg(test)]
             load_limit: AtomicU64::default(),
+            #[cfg(test)]
+            interrupt_hash_calculation_after_passes: AtomicUsize::default(),
+            secondary_index_rebuild: Mutex::default(),
             is_bank_drop_callback_enabled: AtomicBool::default(),
             remove_unrooted_slots_synchronization: RemoveUnrootedSlotsSynchronization::default(),
             shrink_ratio: AccountShrinkThreshold::default(),
@@ -1722,6 +1790,21 @@ impl AccountsDb {
             .as_ref()
             .map(|config| config.skip_rewrites)
             .unwrap_or_default();
+        let compaction_policy = accounts_db_config
+            .as_ref()
+            .and_then(|config| config.compaction_policy.clone());
+        let read_only_cache_eviction = accounts_db_config
+            .as_ref()
+            .map(|config| config.read_only_cache_eviction)
+            .unwrap_or_default();
+        let append_vec_backend = accounts_db_config
+            .as_ref()
+            .map(|config| config.append_vec_backend)
+            .unwrap_or_default();
+        let accounts_hash_progress = accounts_db_config
+            .as_ref()
+            .and_then(|config| config.accounts_hash_progress.clone())
+            .unwrap_or_default();
 
         let filler_account_suffix = if filler_accounts_config.count > 0 {
             Some(solana_sdk::pubkey::new_rand())
@@ -1736,12 +1819,22 @@ impl AccountsDb {
             account_indexes,
             caching_enabled,
             shrink_ratio,
+            compaction_policy,
+            append_vec_backend,
+            accounts_hash_progress,
             accounts_update_notifier,
             filler_accounts_config,
             filler_account_suffix,
             write_cache_limit_bytes: accounts_db_config
                 .as_ref()
                 .and_then(|x| x.write_cache_limit_bytes),
+            read_only_accounts_cache: ReadOnlyAccountsCache::new_with_eviction(
+                accounts_db_config
+                    .as_ref()
+                    .and_then(|x| x.read_only_cache_limit_bytes)
+                    .unwrap_or(MAX_READ_ONLY_CACHE_DATA_SIZE),
+                read_only_cache_eviction,
+            ),
             ..Self::default_with_accounts_index(
                 accounts_index,
                 accounts_hash_cache_path,
@@ -1802,7 +1895,13 @@ impl AccountsDb {
     }
 
     fn new_storage_entry(&self, slot: Slot, path: &Path, size: u64) -> AccountStorageEntry {
-        AccountStorageEntry::new(path, slot, self.next_id(), size)
+        AccountStorageEntry::new_with_backend(
+            path,
+            slot,
+            self.next_id(),
+            size,
+            self.append_vec_backend,
+        )
     }
 
     pub fn expected_cluster_type(&self) -> ClusterType {
@@ -2827,7 +2926,7 @@ impl AccountsDb {
 
             for (pubkey, alive_account) in alive_accounts {
                 accounts.push((pubkey, &alive_account.account));
-                hashes.push(alive_account.account.hash);
+                hashes.push(&*alive_account.account.hash);
                 write_versions.push(alive_account.account.meta.write_version);
             }
             start.stop();
@@ -3004,7 +3103,7 @@ impl AccountsDb {
     /// shrinking by working on the most sparse entries first and if the overall alive ratio is
     /// achieved, it will stop and return the filtered-down candidates and the candidates which
     /// are skipped in this round and might be eligible for the future shrink.
-    fn select_candidates_by_total_usage(
+    pub(crate) fn select_candidates_by_total_usage(
         shrink_slots: &ShrinkCandidates,
         shrink_ratio: f64,
     ) -> (ShrinkCandidates, ShrinkCandidates) {
@@ -3095,27 +3194,38 @@ impl AccountsDb {
     pub fn shrink_candidate_slots(&self) -> usize {
         let shrink_candidates_slots =
             std::mem::take(&mut *self.shrink_candidate_slots.lock().unwrap());
-        let (shrink_slots, shrink_slots_next_batch) = {
-            if let AccountShrinkThreshold::TotalSpace { shrink_ratio } = self.shrink_ratio {
-                let (shrink_slots, shrink_slots_next_batch) =
-                    Self::select_candidates_by_total_usage(&shrink_candidates_slots, shrink_ratio);
-                (shrink_slots, Some(shrink_slots_next_batch))
-            } else {
-                (shrink_candidates_slots, None)
-            }
+        let num_candidate_slots = shrink_candidates_slots.len();
+        let num_candidate_stores = Self::count_shrink_candidate_stores(&shrink_candidates_slots);
+        let context = CompactionContext {
+            max_root: self.accounts_index.max_root_inclusive(),
+            unflushed_slots: self.accounts_cache.num_slots(),
+        };
+        let mut measure_select = Measure::start("select_shrink_candidates");
+        let (shrink_slots, shrink_slots_next_batch) = match &self.compaction_policy {
+            Some(policy) => policy.select_shrink_candidates(shrink_candidates_slots, &context),
+            None => ThresholdCompactionPolicy::new(self.shrink_ratio)
+                .select_shrink_candidates(shrink_candidates_slots, &context),
         };
+        measure_select.stop();
 
-        if shrink_slots.is_empty()
-            && shrink_slots_next_batch
-                .as_ref()
-                .map(|s| s.is_empty())
-                .unwrap_or(true)
-        {
+        if shrink_slots.is_empty() && shrink_slots_next_batch.is_empty() {
             return 0;
         }
 
         let _guard = self.active_stats.activate(ActiveStatItem::Shrink);
 
+        let num_selected_stores = Self::count_shrink_candidate_stores(&shrink_slots);
+        let num_deferred_stores = Self::count_shrink_candidate_stores(&shrink_slots_next_batch);
+        let (selected_total_bytes, selected_alive_bytes) = shrink_slots
+            .values()
+            .flat_map(|stores| stores.values())
+            .fold((0, 0), |(total_bytes, alive_bytes), store| {
+                (
+                    total_bytes + store.total_bytes(),
+                    alive_bytes + store.alive_bytes() as u64,
+                )
+            });
+
         let mut measure_shrink_all_candidates = Measure::start("shrink_all_candidate_slots-ms");
         let num_candidates = shrink_slots.len();
         let shrink_candidates_count: usize = self.thread_pool_clean.install(|| {
@@ -3137,7 +3247,7 @@ impl AccountsDb {
         );
         inc_new_counter_info!("shrink_all_candidate_slots-count", shrink_candidates_count);
         let mut pended_counts: usize = 0;
-        if let Some(shrink_slots_next_batch) = shrink_slots_next_batch {
+        {
             let mut shrink_slots = self.shrink_candidate_slots.lock().unwrap();
             for (slot, stores) in shrink_slots_next_batch {
                 pended_counts += stores.len();
@@ -3146,9 +3256,33 @@ impl AccountsDb {
         }
         inc_new_counter_info!("shrink_pended_stores-count", pended_counts);
 
+        datapoint_info!(
+            "shrink_candidate_pass",
+            ("max_root", context.max_root, i64),
+            ("unflushed_slots", context.unflushed_slots, i64),
+            ("candidate_slots", num_candidate_slots, i64),
+            ("candidate_stores", num_candidate_stores, i64),
+            ("selected_slots", num_candidates, i64),
+            ("selected_stores", num_selected_stores, i64),
+            ("deferred_stores", num_deferred_stores, i64),
+            (
+                "dropped_stores",
+                num_candidate_stores.saturating_sub(num_selected_stores + num_deferred_stores),
+                i64
+            ),
+            ("selected_total_bytes", selected_total_bytes, i64),
+            ("selected_alive_bytes", selected_alive_bytes, i64),
+            ("select_us", measure_select.as_us(), i64),
+            ("shrink_us", measure_shrink_all_candidates.as_us(), i64),
+        );
+
         num_candidates
     }
 
+    fn count_shrink_candidate_stores(candidates: &ShrinkCandidates) -> usize {
+        candidates.values().map(|stores| stores.len()).sum()
+    }
+
     pub fn shrink_all_slots(&self, is_startup: bool, last_full_snapshot_slot: Option<Slot>) {
         let _guard = self.active_stats.activate(ActiveStatItem::Shrink);
         const DIRTY_STORES_CLEANING_THRESHOLD: usize = 10_000;
@@ -3295,9 +3429,12 @@ impl AccountsDb {
             IndexKey::ProgramId(key) => key,
             IndexKey::SplTokenMint(key) => key,
             IndexKey::SplTokenOwner(key) => key,
+            IndexKey::Custom(_, key) => key,
         };
-        if !self.account_indexes.include_key(key) {
-            // the requested key was not indexed in the secondary index, so do a normal scan
+        if !self.account_indexes.include_key(key) || !self.accounts_index.secondary_indexes_ready()
+        {
+            // the requested key was not indexed in the secondary index, or the secondary index
+            // is still being rebuilt in the background, so do a normal scan
             let used_index = false;
             let scan_result = self.scan_accounts(ancestors, bank_id, scan_func, config)?;
             return Ok((scan_result, used_index));
@@ -3966,7 +4103,7 @@ impl AccountsDb {
         store
     }
 
-    fn page_align(size: u64) -> u64 {
+    pub(crate) fn page_align(size: u64) -> u64 {
         (size + (PAGE_SIZE - 1)) & !(PAGE_SIZE - 1)
     }
 
@@ -4982,7 +5119,13 @@ impl AccountsDb {
 
                 self.notify_account_at_accounts_update(slot, meta, &account);
 
-                let cached_account = self.accounts_cache.store(slot, &meta.pubkey, account, hash);
+                let cached_account = self.accounts_cache.store(
+                    slot,
+                    &meta.pubkey,
+                    account,
+                    hash,
+                    meta.write_version,
+                );
                 // hash this account in the bg
                 match &self.sender_bg_hasher {
                     Some(ref sender) => {
@@ -5868,12 +6011,39 @@ impl AccountsDb {
         let (num_hash_scan_passes, bins_per_pass) = Self::bins_per_pass(self.num_hash_scan_passes);
         let use_bg_thread_pool = config.use_bg_thread_pool;
         let mut scan_and_hash = move || {
-            let mut previous_pass = PreviousPass::default();
             let mut final_result = (Hash::default(), 0);
 
             let cache_hash_data = CacheHashData::new(&self.accounts_hash_cache_path);
 
-            for pass in 0..num_hash_scan_passes {
+            // Resume after the passes completed before a restart, if any
+            let slot_range = storages.range();
+            let storages_digest = storages_digest(storages);
+            let checkpoints = AccountsHashCheckpoints::new(&self.accounts_hash_cache_path);
+            let (first_pass, mut previous_pass) = checkpoints
+                .load(
+                    slot_range,
+                    &storages_digest,
+                    num_hash_scan_passes,
+                    bins_per_pass,
+                )
+                .map(|(completed_passes, previous_pass)| {
+                    info!(
+                        "resuming accounts hash calculation for slots {:?} after {} of {} passes",
+                        slot_range, completed_passes, num_hash_scan_passes
+                    );
+                    (completed_passes, previous_pass)
+                })
+                .unwrap_or_default();
+            let mut progress = AccountsHashProgress {
+                slot_range: slot_range.clone(),
+                completed_passes: first_pass,
+                num_passes: num_hash_scan_passes,
+                resumed_passes: first_pass,
+                done: false,
+            };
+            self.accounts_hash_progress.set(progress.clone());
+
+            for pass in first_pass..num_hash_scan_passes {
                 let bounds = Range {
                     start: pass * bins_per_pass,
                     end: (pass + 1) * bins_per_pass,
@@ -5906,7 +6076,34 @@ impl AccountsDb {
                 );
                 previous_pass = for_next_pass;
                 final_result = (hash, lamports);
+
+                if pass < num_hash_scan_passes - 1 {
+                    if let Err(err) = checkpoints.save(
+                        slot_range,
+                        &storages_digest,
+                        num_hash_scan_passes,
+                        bins_per_pass,
+                        pass + 1,
+                        &previous_pass,
+                    ) {
+                        warn!("failed to save accounts hash checkpoint: {}", err);
+                    }
+                }
+                progress.completed_passes = pass + 1;
+                self.accounts_hash_progress.set(progress.clone());
+
+                #[cfg(test)]
+                if self
+                    .interrupt_hash_calculation_after_passes
+                    .load(Ordering::Relaxed)
+                    == pass + 1
+                {
+                    panic!("accounts hash calculation interrupted");
+                }
             }
+            checkpoints.remove_through(slot_range);
+            progress.done = true;
+            self.accounts_hash_progress.set(progress);
 
             info!(
                 "calculate_accounts_hash_without_index: slot (exclusive): {} {:?}",
@@ -6239,14 +6436,9 @@ impl AccountsDb {
     }
 
     fn is_candidate_for_shrink(&self, store: &Arc<AccountStorageEntry>) -> bool {
-        match self.shrink_ratio {
-            AccountShrinkThreshold::TotalSpace { shrink_ratio: _ } => {
-                Self::page_align(store.alive_bytes() as u64) < store.total_bytes()
-            }
-            AccountShrinkThreshold::IndividualStore { shrink_ratio } => {
-                (Self::page_align(store.alive_bytes() as u64) as f64 / store.total_bytes() as f64)
-                    < shrink_ratio
-            }
+        match &self.compaction_policy {
+            Some(policy) => policy.is_shrink_candidate(store),
+            None => ThresholdCompactionPolicy::new(self.shrink_ratio).is_shrink_candidate(store),
         }
     }
 
@@ -6597,6 +6789,19 @@ impl AccountsDb {
                 ),
             );
 
+            for owner_stats in self
+                .read_only_accounts_cache
+                .get_and_reset_owner_stats(MAX_READ_ONLY_CACHE_OWNERS_REPORTED)
+            {
+                datapoint_info!(
+                    "read_only_accounts_cache_owner",
+                    ("owner", owner_stats.owner.to_string(), String),
+                    ("hits", owner_stats.hits, i64),
+                    ("misses", owner_stats.misses, i64),
+                    ("evictions", owner_stats.evictions, i64),
+                );
+            }
+
             let recycle_stores = self.recycle_stores.read().unwrap();
             datapoint_info!(
                 "accounts_db_store_timings2",
@@ -6941,7 +7146,9 @@ impl AccountsDb {
             return SlotIndexGenerationInfo::default();
         }
 
-        let secondary = !self.account_indexes.is_empty();
+        // secondary indexes that are not ready are rebuilt later by `rebuild_secondary_indexes_in_background()`
+        let secondary =
+            !self.account_indexes.is_empty() && self.accounts_index.secondary_indexes_ready();
 
         let mut accounts_data_len = 0;
         let mut num_accounts_rent_exempt = 0;
@@ -7161,6 +7368,12 @@ impl AccountsDb {
         );
         let accounts_data_len = AtomicU64::new(0);
 
+        if !self.account_indexes.is_empty() && self.accounts_index.is_secondary_index_disk_enabled()
+        {
+            // disk-backed secondary indexes are populated after startup instead of here
+            self.accounts_index.set_secondary_indexes_ready(false);
+        }
+
         // pass == 0 always runs and generates the index
         // pass == 1 only runs if verify == true.
         // verify checks that all the expected items are in the accounts index and measures how long it takes to look them all up
@@ -7339,13 +7552,114 @@ impl AccountsDb {
             timings.report();
         }
 
-        self.accounts_index.log_secondary_indexes();
+        if self.accounts_index.secondary_indexes_ready() {
+            self.accounts_index.log_secondary_indexes();
+        }
 
         IndexGenerationInfo {
             accounts_data_len: accounts_data_len.load(Ordering::Relaxed),
         }
     }
 
+    /// If `generate_index()` left the secondary indexes to be populated later, populate them from the
+    /// account storages on a background thread. Until this completes, scans do not use the secondary indexes.
+    /// `stop_secondary_index_rebuild()` stops the thread early.
+    pub fn rebuild_secondary_indexes_in_background(self: &Arc<Self>) {
+        if self.accounts_index.secondary_indexes_ready() {
+            return;
+        }
+        let exit = Arc::new(AtomicBool::default());
+        let accounts_db = Arc::clone(self);
+        let exit_ = Arc::clone(&exit);
+        let handle = Builder::new()
+            .name("solana-secondary-index-rebuild".to_string())
+            .spawn(move || accounts_db.rebuild_secondary_indexes(&exit_))
+            .unwrap();
+        *self.secondary_index_rebuild.lock().unwrap() =
+            Some(SecondaryIndexRebuild { exit, handle });
+    }
+
+    /// Stop the background rebuild of the secondary indexes, if it is running, and wait for it.
+    /// If it did not complete, the secondary indexes stay unused.
+    pub fn stop_secondary_index_rebuild(&self) {
+        if let Some(rebuild) = self.secondary_index_rebuild.lock().unwrap().take() {
+            rebuild.exit.store(true, Ordering::Relaxed);
+            rebuild.handle.join().unwrap();
+        }
+    }
+
+    /// Wait for the background rebuild of the secondary indexes to complete, if it is running
+    pub fn wait_for_secondary_index_rebuild(&self) {
+        if let Some(rebuild) = self.secondary_index_rebuild.lock().unwrap().take() {
+            rebuild.handle.join().unwrap();
+        }
+    }
+
+    /// Add the accounts in the storages to the secondary indexes.
+    /// Clean and shrink run meanwhile, so only the versions of accounts the accounts index refers to
+    /// are added. Older versions are on their way out, and the secondary indexes would keep their keys
+    /// after they are gone.
+    fn rebuild_secondary_indexes(&self, exit: &AtomicBool) {
+        let mut measure = Measure::start("rebuild_secondary_indexes");
+        let slots = self.storage.all_slots();
+        let num_accounts = AtomicU64::default();
+        self.thread_pool_clean.install(|| {
+            slots.par_iter().for_each(|slot| {
+                if exit.load(Ordering::Relaxed) {
+                    return;
+                }
+                let storage_maps = self
+                    .storage
+                    .get_slot_storage_entries(*slot)
+                    .unwrap_or_default();
+                storage_maps.iter().for_each(|storage| {
+                    storage
+                        .all_accounts()
+                        .into_iter()
+                        .filter(|stored_account| {
+                            !self.is_filler_account(&stored_account.meta.pubkey)
+                        })
+                        .for_each(|stored_account| {
+                            let pubkey = &stored_account.meta.pubkey;
+                            let entry = match self.accounts_index.get_account_read_entry(pubkey) {
+                                Some(entry) => entry,
+                                None => return,
+                            };
+                            let is_indexed_version =
+                                entry.slot_list().iter().any(|(_slot, account_info)| {
+                                    account_info.store_id() == storage.append_vec_id()
+                                        && account_info.offset() == stored_account.offset
+                                });
+                            if is_indexed_version {
+                                // Holding `entry` keeps its slot list from being emptied, so clean cannot
+                                // purge the key from the secondary indexes before it is added here
+                                num_accounts.fetch_add(1, Ordering::Relaxed);
+                                self.accounts_index.update_secondary_indexes(
+                                    pubkey,
+                                    &stored_account,
+                                    &self.account_indexes,
+                                );
+                            }
+                        })
+                })
+            })
+        });
+        measure.stop();
+        if exit.load(Ordering::Relaxed) {
+            info!("secondary index rebuild stopped after {}", measure);
+            return;
+        }
+        self.accounts_index.set_secondary_indexes_ready(true);
+        info!("{}", measure);
+        datapoint_info!(
+            "rebuild_secondary_indexes",
+            ("total_us", measure.as_us(), i64),
+            ("num_slots", slots.len(), i64),
+            ("num_accounts", num_accounts.load(Ordering::Relaxed), i64),
+        );
+        self.accounts_index.log_secondary_indexes();
+    }
+
     /// Startup processes can consume large amounts of memory while inserting accounts into the index as fast as possible.
     /// Calling this can slow down the insertion process to allow flushing to disk to keep pace.
     fn maybe_throttle_index_generation(&self) {
@@ -7741,6 +8055,7 @@ pub mod tests {
             accounts_hash::MERKLE_FANOUT,
             accounts_index::{tests::*, AccountSecondaryIndexesIncludeExclude, RefCount},
             append_vec::{test_utils::TempFile, AccountMeta},
+            compaction_policy::TargetCompactionPolicy,
             inline_spl_token,
         },
         assert_matches::assert_matches,
@@ -7754,6 +8069,7 @@ pub mod tests {
         },
         std::{
             iter::FromIterator,
+            panic::AssertUnwindSafe,
             str::FromStr,
             sync::atomic::AtomicBool,
             thread::{self, Builder, JoinHandle},
@@ -8220,6 +8536,112 @@ pub mod tests {
         assert_eq!(result, (expected_hash, sum));
     }
 
+    #[test]
+    fn test_accountsdb_calculate_accounts_hash_resumes_after_loading_snapshot() {
+        solana_logger::setup();
+
+        let db = AccountsDb::new(Vec::new(), &ClusterType::Development);
+        // Rebuilt from the snapshot, the append vecs are numbered from 0 again
+        db.next_id.store(100, Ordering::Release);
+        let max_slot = 3;
+        let mut sum = 0;
+        for slot in 1..=max_slot {
+            let accounts = (1..=10)
+                .map(|lamports| {
+                    (
+                        solana_sdk::pubkey::new_rand(),
+                        AccountSharedData::new(slot * 100 + lamports, 0, &Pubkey::default()),
+                    )
+                })
+                .collect::<Vec<_>>();
+            sum += accounts
+                .iter()
+                .map(|(_, account)| account.lamports())
+                .sum::<u64>();
+            db.store_uncached(
+                slot,
+                &accounts
+                    .iter()
+                    .map(|(pubkey, account)| (pubkey, account))
+                    .collect::<Vec<_>>(),
+            );
+            db.add_root(slot);
+        }
+        let config = CalcAccountsHashConfig {
+            use_bg_thread_pool: false,
+            check_hash: false,
+            ancestors: None,
+            use_write_cache: false,
+            rent_collector: &RentCollector::default(),
+        };
+        let (num_passes, bins_per_pass) = AccountsDb::bins_per_pass(db.num_hash_scan_passes);
+        assert!(num_passes > 1);
+
+        let storages = db.get_snapshot_storages(max_slot, None, None).0;
+        let expected = db
+            .calculate_accounts_hash_without_index(
+                &config,
+                &get_storage_refs(&storages),
+                HashStats::default(),
+            )
+            .unwrap();
+        assert_eq!(expected.1, sum);
+        assert_eq!(db.accounts_hash_progress.get().unwrap().resumed_passes, 0);
+
+        // the validator stops after the first pass of a calculation
+        db.interrupt_hash_calculation_after_passes
+            .store(1, Ordering::Relaxed);
+        let interrupted = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            db.calculate_accounts_hash_without_index(
+                &config,
+                &get_storage_refs(&storages),
+                HashStats::default(),
+            )
+        }));
+        assert!(interrupted.is_err());
+        assert!(!db.accounts_hash_progress.get().unwrap().done);
+
+        // and restarts from its snapshot, with the same accounts hash cache path
+        let mut restarted_db = reconstruct_accounts_db_via_serialization(&db, max_slot);
+        restarted_db.accounts_hash_cache_path = db.accounts_hash_cache_path.clone();
+        let restarted_storages = restarted_db.get_snapshot_storages(max_slot, None, None).0;
+        let append_vec_ids = |storages: &SnapshotStorages| {
+            storages
+                .iter()
+                .flatten()
+                .map(|storage| storage.append_vec_id())
+                .collect::<HashSet<_>>()
+        };
+        assert!(append_vec_ids(&storages).is_disjoint(&append_vec_ids(&restarted_storages)));
+
+        // the calculation resumes after the pass completed before the restart
+        let resumed = restarted_db
+            .calculate_accounts_hash_without_index(
+                &config,
+                &get_storage_refs(&restarted_storages),
+                HashStats::default(),
+            )
+            .unwrap();
+        assert_eq!(resumed, expected);
+        let progress = restarted_db.accounts_hash_progress.get().unwrap();
+        assert!(progress.done);
+        assert_eq!(progress.completed_passes, num_passes);
+        assert_eq!(progress.resumed_passes, 1);
+
+        // a finished calculation leaves no checkpoint behind
+        let restarted_storages = get_storage_refs(&restarted_storages);
+        let digest = storages_digest(&restarted_storages);
+        let checkpoints = AccountsHashCheckpoints::new(&restarted_db.accounts_hash_cache_path);
+        assert!(checkpoints
+            .load(
+                restarted_storages.range(),
+                &digest,
+                num_passes,
+                bins_per_pass
+            )
+            .is_none());
+    }
+
     fn sample_storage() -> (SnapshotStorages, usize, Slot) {
         let (_temp_dirs, paths) = get_temp_accounts_paths(1).unwrap();
         let slot_expected: Slot = 0;
@@ -9323,6 +9745,81 @@ pub mod tests {
         assert_eq!(accounts.alive_account_count_in_slot(1), 2);
     }
 
+    #[test]
+    fn test_rebuild_secondary_indexes() {
+        solana_logger::setup();
+
+        let drive = TempDir::new().unwrap();
+        // Store the accounts without secondary indexes, as a snapshot would have them
+        let mut accounts = AccountsDb::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            AccountShrinkThreshold::default(),
+            Some(AccountsDbConfig {
+                index: Some(AccountsIndexConfig {
+                    secondary_index_drives: Some(vec![drive.path().to_path_buf()]),
+                    ..ACCOUNTS_INDEX_CONFIG_FOR_TESTING
+                }),
+                ..ACCOUNTS_DB_CONFIG_FOR_TESTING
+            }),
+            None,
+        );
+        let token_account = |mint_key: &Pubkey| {
+            let mut data = vec![0; inline_spl_token::Account::get_packed_len()];
+            data[..PUBKEY_BYTES].clone_from_slice(&(mint_key.to_bytes()));
+            let mut account = AccountSharedData::new(1, 0, &inline_spl_token::id());
+            account.set_data(data);
+            account
+        };
+        let old_mint_key = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let pubkey1 = Pubkey::new_unique();
+        let pubkey2 = Pubkey::new_unique();
+
+        // pubkey1 moves to another mint, its version in slot 0 is stale
+        accounts.store_uncached(0, &[(&pubkey1, &token_account(&old_mint_key))]);
+        accounts.store_uncached(1, &[(&pubkey1, &token_account(&mint_key))]);
+        accounts.store_uncached(1, &[(&pubkey2, &token_account(&old_mint_key))]);
+        for slot in 0..2 {
+            accounts.get_accounts_delta_hash(slot);
+            accounts.add_root(slot);
+        }
+
+        accounts.account_indexes = spl_token_mint_index_enabled();
+        accounts.accounts_index.set_secondary_indexes_ready(false);
+        let accounts = Arc::new(accounts);
+        let scan_mint = |mint_key: &Pubkey| {
+            let mut found_accounts = HashSet::new();
+            accounts
+                .accounts_index
+                .index_scan_accounts(
+                    &Ancestors::default(),
+                    0,
+                    IndexKey::SplTokenMint(*mint_key),
+                    |key, _| {
+                        found_accounts.insert(*key);
+                    },
+                    &ScanConfig::default(),
+                )
+                .unwrap();
+            found_accounts
+        };
+
+        // A stopped rebuild leaves the secondary indexes unused
+        accounts.rebuild_secondary_indexes(&AtomicBool::new(true));
+        assert!(!accounts.accounts_index.secondary_indexes_ready());
+        assert!(scan_mint(&mint_key).is_empty());
+
+        accounts.rebuild_secondary_indexes_in_background();
+        accounts.wait_for_secondary_index_rebuild();
+        assert!(accounts.accounts_index.secondary_indexes_ready());
+        assert_eq!(scan_mint(&mint_key), HashSet::from([pubkey1]));
+        // Only the version of pubkey1 in the accounts index was indexed
+        assert_eq!(scan_mint(&old_mint_key), HashSet::from([pubkey2]));
+    }
+
     #[test]
     fn test_clean_old_with_both_normal_and_zero_lamport_accounts() {
         solana_logger::setup();
@@ -10123,12 +10620,12 @@ pub mod tests {
         let stored_size = 101;
         let hash = Hash::new_unique();
         let stored_account = StoredAccountMeta {
-            meta: &meta,
-            account_meta: &account_meta,
-            data: &data,
+            meta: Cow::Borrowed(&meta),
+            account_meta: Cow::Borrowed(&account_meta),
+            data: Cow::Borrowed(&data),
             offset,
             stored_size,
-            hash: &hash,
+            hash: Cow::Borrowed(&hash),
         };
         assert!(accounts_equal(&account, &stored_account));
     }
@@ -10163,12 +10660,12 @@ pub mod tests {
             unsafe { std::mem::transmute::<InputBlob, InputTuple>(blob) };
 
         let stored_account = StoredAccountMeta {
-            meta: &meta,
-            account_meta: &account_meta,
-            data: &data,
+            meta: Cow::Borrowed(&meta),
+            account_meta: Cow::Borrowed(&account_meta),
+            data: Cow::Borrowed(&data),
             offset,
             stored_size: CACHE_VIRTUAL_STORED_SIZE as usize,
-            hash: &hash,
+            hash: Cow::Borrowed(&hash),
         };
         let account = stored_account.clone_account();
 
@@ -11384,6 +11881,47 @@ pub mod tests {
         assert_eq!(0, next_candidates.len());
     }
 
+    #[test]
+    fn test_shrink_candidate_slots_with_compaction_policy() {
+        let db = AccountsDb::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            true,
+            AccountShrinkThreshold::default(),
+            Some(AccountsDbConfig {
+                compaction_policy: Some(Arc::new(TargetCompactionPolicy {
+                    min_slot_age: 10,
+                    ..TargetCompactionPolicy::default()
+                })),
+                ..ACCOUNTS_DB_CONFIG_FOR_TESTING
+            }),
+            None,
+        );
+        let slot = 1;
+        let store = Arc::new(AccountStorageEntry::new(
+            Path::new(""),
+            slot,
+            50,
+            4 * PAGE_SIZE,
+        ));
+        assert!(db.is_candidate_for_shrink(&store));
+        db.shrink_candidate_slots
+            .lock()
+            .unwrap()
+            .entry(slot)
+            .or_default()
+            .insert(store.append_vec_id(), store);
+
+        // the slot is too recent to shrink, so it stays queued for a later pass
+        assert_eq!(db.shrink_candidate_slots(), 0);
+        assert!(db
+            .shrink_candidate_slots
+            .lock()
+            .unwrap()
+            .contains_key(&slot));
+    }
+
     #[test]
     fn test_shrink_stale_slots_skipped() {
         solana_logger::setup();
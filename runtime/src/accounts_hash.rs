@@ -2,6 +2,7 @@ use {
     crate::{accounts_db::SnapshotStorages, ancestors::Ancestors, rent_collector::RentCollector},
     log::*,
     rayon::prelude::*,
+    serde::{Deserialize, Serialize},
     solana_measure::measure::Measure,
     solana_sdk::{
         hash::{Hash, Hasher},
@@ -19,7 +20,7 @@ use {
 pub const ZERO_RAW_LAMPORTS_SENTINEL: u64 = std::u64::MAX;
 pub const MERKLE_FANOUT: usize = 16;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct PreviousPass {
     pub reduced_hashes: Vec<Vec<Hash>>,
     pub remaining_unhashed: Vec<Hash>,
@@ -0,0 +1,314 @@
+//! Checkpoints of an in-flight accounts hash calculation, so it can resume after a restart.
+//!
+//! The calculation goes over the pubkey bins in passes. After each pass, the state carried
+//! to the next pass is saved along with the slot range and a digest of the storages being
+//! hashed. A calculation over the same storages with the same passes picks up after the last
+//! saved pass instead of starting over.
+use {
+    crate::{accounts_hash::PreviousPass, sorted_storages::SortedStorages},
+    log::*,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        clock::Slot,
+        hash::{Hash, Hasher},
+    },
+    std::{
+        fs,
+        io::{self, Write},
+        ops::Range,
+        path::{Path, PathBuf},
+        sync::RwLock,
+    },
+};
+
+const CHECKPOINT_FOLDER: &str = "accounts_hash_checkpoints";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AccountsHashCheckpoint {
+    /// the slots of the storages being hashed
+    slot_range: Range<Slot>,
+    /// see `storages_digest()`
+    storages_digest: Hash,
+    num_passes: usize,
+    bins_per_pass: usize,
+    /// `previous_pass` is the state after this many passes
+    completed_passes: usize,
+    previous_pass: PreviousPass,
+}
+
+/// Digest of the storages being hashed: the slot, length and stored account hashes of each
+/// append vec. The state saved after a pass is only valid for the very same storages.
+/// Append vec ids are left out, since the storages are given new ids when a snapshot is loaded.
+pub fn storages_digest(storages: &SortedStorages<'_>) -> Hash {
+    let mut hasher = Hasher::default();
+    for (slot, storage) in storages.iter_range(..) {
+        // The storages of a slot are ordered by id, so their digests are sorted instead
+        let mut entry_digests = storage
+            .into_iter()
+            .flatten()
+            .map(|entry| {
+                let mut hasher = Hasher::default();
+                hasher.hash(&entry.written_bytes().to_le_bytes());
+                let mut offset = 0;
+                while let Some((account, next_offset)) = entry.accounts.get_account(offset) {
+                    hasher.hash(account.hash.as_ref().as_ref());
+                    offset = next_offset;
+                }
+                hasher.result()
+            })
+            .collect::<Vec<_>>();
+        entry_digests.sort_unstable();
+        hasher.hash(&slot.to_le_bytes());
+        for entry_digest in entry_digests {
+            hasher.hash(entry_digest.as_ref());
+        }
+    }
+    hasher.result()
+}
+
+/// Saves and loads checkpoints of accounts hash calculations under a folder
+#[derive(Debug)]
+pub struct AccountsHashCheckpoints {
+    folder: PathBuf,
+}
+
+impl AccountsHashCheckpoints {
+    pub fn new(parent_folder: &Path) -> Self {
+        Self {
+            folder: parent_folder.join(CHECKPOINT_FOLDER),
+        }
+    }
+
+    fn checkpoint_path(&self, slot_range: &Range<Slot>) -> PathBuf {
+        self.folder
+            .join(format!("{}-{}", slot_range.start, slot_range.end))
+    }
+
+    /// Save the state after `completed_passes` passes of the calculation over `slot_range`
+    pub fn save(
+        &self,
+        slot_range: &Range<Slot>,
+        storages_digest: &Hash,
+        num_passes: usize,
+        bins_per_pass: usize,
+        completed_passes: usize,
+        previous_pass: &PreviousPass,
+    ) -> io::Result<()> {
+        fs::create_dir_all(&self.folder)?;
+        let checkpoint = AccountsHashCheckpoint {
+            slot_range: slot_range.clone(),
+            storages_digest: *storages_digest,
+            num_passes,
+            bins_per_pass,
+            completed_passes,
+            previous_pass: previous_pass.clone(),
+        };
+        let data = bincode::serialize(&checkpoint)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        // Write to a temporary file first, so a crash while saving leaves the previous checkpoint
+        let path = self.checkpoint_path(slot_range);
+        let temp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        fs::rename(temp_path, path)
+    }
+
+    /// Load the checkpoint of a calculation over the same storages with the same passes, if any.
+    /// Return the number of completed passes and the state after them.
+    /// A checkpoint of a calculation over other storages is removed.
+    pub fn load(
+        &self,
+        slot_range: &Range<Slot>,
+        storages_digest: &Hash,
+        num_passes: usize,
+        bins_per_pass: usize,
+    ) -> Option<(usize, PreviousPass)> {
+        let path = self.checkpoint_path(slot_range);
+        let data = fs::read(&path).ok()?;
+        let checkpoint: AccountsHashCheckpoint = match bincode::deserialize(&data) {
+            Ok(checkpoint) => checkpoint,
+            Err(err) => {
+                warn!("Ignoring accounts hash checkpoint {:?}: {}", path, err);
+                return None;
+            }
+        };
+        if checkpoint.slot_range != *slot_range || checkpoint.storages_digest != *storages_digest {
+            warn!(
+                "Removing accounts hash checkpoint {:?} of other storages",
+                path
+            );
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+        (checkpoint.num_passes == num_passes
+            && checkpoint.bins_per_pass == bins_per_pass
+            && checkpoint.completed_passes < num_passes)
+            .then(|| (checkpoint.completed_passes, checkpoint.previous_pass))
+    }
+
+    /// Remove the checkpoints of calculations that will not be resumed once the
+    /// calculation over `slot_range` is done: its own, and those of older slots.
+    pub fn remove_through(&self, slot_range: &Range<Slot>) {
+        let dir = match fs::read_dir(&self.folder) {
+            Ok(dir) => dir,
+            Err(_) => return,
+        };
+        for entry in dir.flatten() {
+            let path = entry.path();
+            let end = path
+                .file_stem()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.split('-').nth(1))
+                .and_then(|end| end.parse::<Slot>().ok());
+            if end.map(|end| end <= slot_range.end).unwrap_or(true) {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// Progress of the latest accounts hash calculation
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountsHashProgress {
+    /// the slots of the storages being hashed
+    pub slot_range: Range<Slot>,
+    pub completed_passes: usize,
+    pub num_passes: usize,
+    /// passes that were skipped because they were completed before a restart
+    pub resumed_passes: usize,
+    pub done: bool,
+}
+
+/// Shares the progress of accounts hash calculations with whoever reports it
+#[derive(Debug, Default)]
+pub struct AccountsHashProgressTracker {
+    progress: RwLock<Option<AccountsHashProgress>>,
+}
+
+impl AccountsHashProgressTracker {
+    /// The progress of the latest calculation, None before the first one starts
+    pub fn get(&self) -> Option<AccountsHashProgress> {
+        self.progress.read().unwrap().clone()
+    }
+
+    pub(crate) fn set(&self, progress: AccountsHashProgress) {
+        *self.progress.write().unwrap() = Some(progress);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            accounts_db::{get_temp_accounts_paths, AccountStorageEntry},
+            append_vec::StoredMeta,
+        },
+        solana_sdk::{account::AccountSharedData, pubkey::Pubkey},
+        std::sync::Arc,
+        tempfile::TempDir,
+    };
+
+    #[test]
+    fn test_accounts_hash_checkpoints() {
+        let temp_dir = TempDir::new().unwrap();
+        let checkpoints = AccountsHashCheckpoints::new(temp_dir.path());
+        let slot_range = 10..20;
+        let digest = Hash::new_unique();
+        assert!(checkpoints.load(&slot_range, &digest, 4, 16).is_none());
+
+        let previous_pass = PreviousPass {
+            reduced_hashes: vec![vec![Hash::new_unique()]],
+            remaining_unhashed: vec![Hash::new_unique(), Hash::new_unique()],
+            lamports: 42,
+        };
+        checkpoints
+            .save(&slot_range, &digest, 4, 16, 2, &previous_pass)
+            .unwrap();
+        checkpoints
+            .save(&(30..40), &digest, 4, 16, 1, &PreviousPass::default())
+            .unwrap();
+        let (completed_passes, loaded) = checkpoints.load(&slot_range, &digest, 4, 16).unwrap();
+        assert_eq!(completed_passes, 2);
+        assert_eq!(loaded.reduced_hashes, previous_pass.reduced_hashes);
+        assert_eq!(loaded.remaining_unhashed, previous_pass.remaining_unhashed);
+        assert_eq!(loaded.lamports, previous_pass.lamports);
+
+        // different slots or passes do not resume
+        assert!(checkpoints.load(&(10..21), &digest, 4, 16).is_none());
+        assert!(checkpoints.load(&slot_range, &digest, 2, 32).is_none());
+
+        // later calculations keep their checkpoints
+        checkpoints.remove_through(&slot_range);
+        assert!(checkpoints.load(&slot_range, &digest, 4, 16).is_none());
+        assert!(checkpoints.load(&(30..40), &digest, 4, 16).is_some());
+
+        // a checkpoint of other storages is discarded
+        assert!(checkpoints
+            .load(&(30..40), &Hash::new_unique(), 4, 16)
+            .is_none());
+        assert!(checkpoints.load(&(30..40), &digest, 4, 16).is_none());
+    }
+
+    #[test]
+    fn test_storages_digest() {
+        let (_temp_dirs, paths) = get_temp_accounts_paths(1).unwrap();
+        let new_storage = |slot, id| {
+            vec![Arc::new(AccountStorageEntry::new(
+                &paths[0], slot, id, 1024,
+            ))]
+        };
+        let storages = vec![new_storage(1, 0), new_storage(2, 1)];
+        let digest = storages_digest(&SortedStorages::new(&storages));
+        assert_eq!(digest, storages_digest(&SortedStorages::new(&storages)));
+
+        // the same append vecs with other ids, as after loading a snapshot
+        let renumbered_storages = vec![new_storage(1, 5), new_storage(2, 3)];
+        assert_eq!(
+            digest,
+            storages_digest(&SortedStorages::new(&renumbered_storages))
+        );
+
+        // an append vec for another slot
+        let other_storages = vec![new_storage(1, 0), new_storage(3, 1)];
+        assert_ne!(
+            digest,
+            storages_digest(&SortedStorages::new(&other_storages))
+        );
+
+        // an append vec with more accounts
+        let account = AccountSharedData::new(1, 0, &Pubkey::default());
+        storages[1][0].accounts.append_accounts(
+            &[(
+                StoredMeta {
+                    write_version: 0,
+                    pubkey: Pubkey::default(),
+                    data_len: 0,
+                },
+                Some(&account),
+            )],
+            &[&Hash::default()],
+        );
+        let digest_with_account = storages_digest(&SortedStorages::new(&storages));
+        assert_ne!(digest, digest_with_account);
+
+        // an append vec of the same length holding another account
+        let other_account = AccountSharedData::new(2, 0, &Pubkey::default());
+        renumbered_storages[1][0].accounts.append_accounts(
+            &[(
+                StoredMeta {
+                    write_version: 0,
+                    pubkey: Pubkey::default(),
+                    data_len: 0,
+                },
+                Some(&other_account),
+            )],
+            &[&Hash::new_unique()],
+        );
+        assert_ne!(
+            digest_with_account,
+            storages_digest(&SortedStorages::new(&renumbered_storages))
+        );
+    }
+}
@@ -11,6 +11,7 @@ use {
         rolling_bit_field::RollingBitField,
         secondary_index::*,
     },
+    dashmap::DashMap,
     log::*,
     ouroboros::self_referencing,
     rand::{thread_rng, Rng},
@@ -22,7 +23,7 @@ use {
     solana_sdk::{
         account::ReadableAccount,
         clock::{BankId, Slot},
-        pubkey::Pubkey,
+        pubkey::{Pubkey, PUBKEY_BYTES},
     },
     std::{
         collections::{btree_map::BTreeMap, HashSet},
@@ -33,6 +34,7 @@ use {
             Range, RangeBounds,
         },
         path::PathBuf,
+        str::FromStr,
         sync::{
             atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
             Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
@@ -54,6 +56,7 @@ pub const ACCOUNTS_INDEX_CONFIG_FOR_TESTING: AccountsIndexConfig = AccountsIndex
     ages_to_stay_in_cache: None,
     scan_results_limit_bytes: None,
     started_from_validator: false,
+    secondary_index_drives: None,
 };
 pub const ACCOUNTS_INDEX_CONFIG_FOR_BENCHMARKS: AccountsIndexConfig = AccountsIndexConfig {
     bins: Some(BINS_FOR_BENCHMARKS),
@@ -63,6 +66,7 @@ pub const ACCOUNTS_INDEX_CONFIG_FOR_BENCHMARKS: AccountsIndexConfig = AccountsIn
     ages_to_stay_in_cache: None,
     scan_results_limit_bytes: None,
     started_from_validator: false,
+    secondary_index_drives: None,
 };
 pub type ScanResult<T> = Result<T, ScanError>;
 pub type SlotList<T> = Vec<(Slot, T)>;
@@ -78,6 +82,10 @@ pub struct ScanConfig {
     /// true to allow return of all matching items and allow them to be unsorted.
     /// This is more efficient.
     pub collect_all_unsorted: bool,
+
+    /// if set, checked scans only visit the pubkeys in this range.
+    /// Unindexed scans skip the other pubkeys without loading them.
+    pub range: Option<(Bound<Pubkey>, Bound<Pubkey>)>,
 }
 
 impl ScanConfig {
@@ -100,6 +108,7 @@ impl ScanConfig {
         ScanConfig {
             abort: Some(self.abort.as_ref().map(Arc::clone).unwrap_or_default()),
             collect_all_unsorted: self.collect_all_unsorted,
+            range: self.range,
         }
     }
 
@@ -142,6 +151,8 @@ pub enum IndexKey {
     ProgramId(Pubkey),
     SplTokenMint(Pubkey),
     SplTokenOwner(Pubkey),
+    /// The key of a custom index, as returned by `CustomSecondaryIndex::index_key()`
+    Custom(CustomSecondaryIndex, Pubkey),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -149,6 +160,87 @@ pub enum AccountIndex {
     ProgramId,
     SplTokenMint,
     SplTokenOwner,
+    Custom(CustomSecondaryIndex),
+}
+
+/// A secondary index on a fixed-length field of the data of the accounts owned by a program,
+/// declared by the operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomSecondaryIndex {
+    pub program_id: Pubkey,
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl CustomSecondaryIndex {
+    /// Fields are indexed as pubkeys, so they cannot be longer than one
+    pub const MAX_LENGTH: usize = PUBKEY_BYTES;
+
+    pub fn new(program_id: Pubkey, offset: usize, length: usize) -> Result<Self, String> {
+        if length == 0 || length > Self::MAX_LENGTH {
+            return Err(format!(
+                "custom account index length must be between 1 and {}, got {}",
+                Self::MAX_LENGTH,
+                length
+            ));
+        }
+        offset
+            .checked_add(length)
+            .ok_or_else(|| format!("custom account index offset {} is too large", offset))?;
+        Ok(Self {
+            program_id,
+            offset,
+            length,
+        })
+    }
+
+    /// The key of an account's data in this index: the indexed field, zero-padded to a pubkey.
+    /// Returns None if the data is too short to hold the field.
+    pub fn index_key(&self, account_data: &[u8]) -> Option<Pubkey> {
+        account_data
+            .get(self.offset..self.offset + self.length)
+            .map(|field| self.key_from_bytes(field).unwrap())
+    }
+
+    /// The key in this index of the accounts whose field is `bytes`, if `bytes` is as long as the
+    /// field
+    pub fn key_from_bytes(&self, bytes: &[u8]) -> Option<Pubkey> {
+        (bytes.len() == self.length).then(|| {
+            let mut key = [0; PUBKEY_BYTES];
+            key[..self.length].copy_from_slice(bytes);
+            Pubkey::new_from_array(key)
+        })
+    }
+}
+
+impl FromStr for CustomSecondaryIndex {
+    type Err = String;
+
+    /// Parse a custom index declared as `PROGRAM_ID:OFFSET:LENGTH`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let (program_id, offset, length) =
+            match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                (Some(program_id), Some(offset), Some(length), None) => {
+                    (program_id, offset, length)
+                }
+                _ => {
+                    return Err(format!(
+                        "invalid custom account index {}, expected PROGRAM_ID:OFFSET:LENGTH",
+                        s
+                    ))
+                }
+            };
+        let program_id = Pubkey::from_str(program_id)
+            .map_err(|err| format!("invalid custom account index program id: {}", err))?;
+        let offset = offset
+            .parse::<usize>()
+            .map_err(|err| format!("invalid custom account index offset: {}", err))?;
+        let length = length
+            .parse::<usize>()
+            .map_err(|err| format!("invalid custom account index length: {}", err))?;
+        Self::new(program_id, offset, length)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -184,6 +276,8 @@ pub struct AccountsIndexConfig {
     pub scan_results_limit_bytes: Option<usize>,
     /// true if the accounts index is being created as a result of being started as a validator (as opposed to test, etc.)
     pub started_from_validator: bool,
+    /// if set, secondary indexes are stored on disk in these folders and rebuilt in the background at startup
+    pub secondary_index_drives: Option<Vec<PathBuf>>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -205,6 +299,18 @@ impl AccountSecondaryIndexes {
             None => true, // include all keys
         }
     }
+    /// The custom indexes declared on the accounts of `program_id`
+    pub fn custom_indexes_of<'a>(
+        &'a self,
+        program_id: &'a Pubkey,
+    ) -> impl Iterator<Item = &'a CustomSecondaryIndex> + 'a {
+        self.indexes.iter().filter_map(move |index| match index {
+            AccountIndex::Custom(custom_index) if custom_index.program_id == *program_id => {
+                Some(custom_index)
+            }
+            _ => None,
+        })
+    }
 }
 
 #[derive(Debug, Default)]
@@ -646,6 +752,12 @@ pub struct AccountsIndex<T: IndexValue> {
     program_id_index: SecondaryIndex<DashMapSecondaryIndexEntry>,
     spl_token_mint_index: SecondaryIndex<DashMapSecondaryIndexEntry>,
     spl_token_owner_index: SecondaryIndex<RwLockSecondaryIndexEntry>,
+    /// The custom indexes are created the first time an account is added to them
+    custom_indexes: DashMap<CustomSecondaryIndex, Arc<SecondaryIndex<DashMapSecondaryIndexEntry>>>,
+    /// folders holding the secondary indexes, if they are stored on disk
+    secondary_index_drives: Option<Vec<PathBuf>>,
+    /// false while the secondary indexes are being rebuilt in the background
+    secondary_indexes_ready: AtomicBool,
     pub(crate) roots_tracker: RwLock<RootsTracker>,
     ongoing_scan_roots: RwLock<BTreeMap<Slot, u64>>,
     // Each scan has some latest slot `S` that is the tip of the fork the scan
@@ -675,19 +787,31 @@ impl<T: IndexValue> AccountsIndex<T> {
         let scan_results_limit_bytes = config
             .as_ref()
             .and_then(|config| config.scan_results_limit_bytes);
+        let secondary_index_drives = config
+            .as_ref()
+            .and_then(|config| config.secondary_index_drives.clone());
         let (account_maps, bin_calculator, storage) = Self::allocate_accounts_index(config);
         Self {
             account_maps,
             bin_calculator,
-            program_id_index: SecondaryIndex::<DashMapSecondaryIndexEntry>::new(
+            program_id_index: Self::new_secondary_index(
                 "program_id_index_stats",
+                &secondary_index_drives,
+                "program_id_index",
             ),
-            spl_token_mint_index: SecondaryIndex::<DashMapSecondaryIndexEntry>::new(
+            spl_token_mint_index: Self::new_secondary_index(
                 "spl_token_mint_index_stats",
+                &secondary_index_drives,
+                "spl_token_mint_index",
             ),
-            spl_token_owner_index: SecondaryIndex::<RwLockSecondaryIndexEntry>::new(
+            spl_token_owner_index: Self::new_secondary_index(
                 "spl_token_owner_index_stats",
+                &secondary_index_drives,
+                "spl_token_owner_index",
             ),
+            custom_indexes: DashMap::default(),
+            secondary_index_drives,
+            secondary_indexes_ready: AtomicBool::new(true),
             roots_tracker: RwLock::<RootsTracker>::default(),
             ongoing_scan_roots: RwLock::<BTreeMap<Slot, u64>>::default(),
             removed_bank_ids: Mutex::<HashSet<BankId>>::default(),
@@ -696,6 +820,24 @@ impl<T: IndexValue> AccountsIndex<T> {
         }
     }
 
+    /// Create a secondary index, stored on disk in `folder_name` under each of `drives` if specified
+    fn new_secondary_index<SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send>(
+        metrics_name: &'static str,
+        drives: &Option<Vec<PathBuf>>,
+        folder_name: &str,
+    ) -> SecondaryIndex<SecondaryIndexEntryType> {
+        match drives {
+            Some(drives) => SecondaryIndex::new_with_disk(
+                metrics_name,
+                &drives
+                    .iter()
+                    .map(|drive| drive.join(folder_name))
+                    .collect::<Vec<_>>(),
+            ),
+            None => SecondaryIndex::new(metrics_name),
+        }
+    }
+
     fn allocate_accounts_index(
         config: Option<AccountsIndexConfig>,
     ) -> (
@@ -729,6 +871,21 @@ impl<T: IndexValue> AccountsIndex<T> {
         self.storage.storage.is_disk_index_enabled()
     }
 
+    /// are the secondary indexes using disk as a backing store
+    pub fn is_secondary_index_disk_enabled(&self) -> bool {
+        self.secondary_index_drives.is_some()
+    }
+
+    /// false while the secondary indexes are incomplete because they are still being rebuilt.
+    /// Scans must not use the secondary indexes until this is true.
+    pub fn secondary_indexes_ready(&self) -> bool {
+        self.secondary_indexes_ready.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_secondary_indexes_ready(&self, ready: bool) {
+        self.secondary_indexes_ready.store(ready, Ordering::Release);
+    }
+
     fn do_checked_scan_accounts<F, R>(
         &self,
         metric_name: &'static str,
@@ -921,6 +1078,19 @@ impl<T: IndexValue> AccountsIndex<T> {
                     config,
                 );
             }
+            ScanTypes::Indexed(IndexKey::Custom(custom_index, index_key)) => {
+                // No custom index means no account was ever added to it
+                if let Some(index) = self.get_custom_index(&custom_index) {
+                    self.do_scan_secondary_index(
+                        ancestors,
+                        func,
+                        &*index,
+                        &index_key,
+                        Some(max_root),
+                        config,
+                    );
+                }
+            }
         }
 
         {
@@ -1041,7 +1211,12 @@ impl<T: IndexValue> AccountsIndex<T> {
     ) where
         F: FnMut(&Pubkey, (&T, Slot)),
     {
-        for pubkey in index.get(index_key) {
+        for pubkey in index.get(index_key).into_iter().filter(|pubkey| {
+            config
+                .range
+                .as_ref()
+                .map_or(true, |range| range.contains(pubkey))
+        }) {
             // Maybe these reads from the AccountsIndex can be batched every time it
             // grabs the read lock as well...
             if let AccountIndexGetResult::Found(list_r, index) =
@@ -1116,7 +1291,7 @@ impl<T: IndexValue> AccountsIndex<T> {
             ancestors,
             scan_bank_id,
             func,
-            ScanTypes::Unindexed(None::<Range<Pubkey>>),
+            ScanTypes::Unindexed(config.range),
             config,
         )
     }
@@ -1409,20 +1584,70 @@ impl<T: IndexValue> AccountsIndex<T> {
         }
     }
 
+    fn get_custom_index(
+        &self,
+        custom_index: &CustomSecondaryIndex,
+    ) -> Option<Arc<SecondaryIndex<DashMapSecondaryIndexEntry>>> {
+        self.custom_indexes
+            .get(custom_index)
+            .map(|index| Arc::clone(&index))
+    }
+
+    fn update_custom_secondary_indexes(
+        &self,
+        pubkey: &Pubkey,
+        account_owner: &Pubkey,
+        account_data: &[u8],
+        account_indexes: &AccountSecondaryIndexes,
+    ) {
+        for custom_index in account_indexes.custom_indexes_of(account_owner) {
+            if let Some(index_key) = custom_index.index_key(account_data) {
+                if account_indexes.include_key(&index_key) {
+                    let index = self.get_custom_index(custom_index).unwrap_or_else(|| {
+                        Arc::clone(
+                            &self.custom_indexes.entry(*custom_index).or_insert_with(|| {
+                                Arc::new(Self::new_secondary_index(
+                                    "custom_index_stats",
+                                    &self.secondary_index_drives,
+                                    &format!(
+                                        "custom_index_{}_{}_{}",
+                                        custom_index.program_id,
+                                        custom_index.offset,
+                                        custom_index.length
+                                    ),
+                                ))
+                            }),
+                        )
+                    });
+                    index.insert(&index_key, pubkey);
+                }
+            }
+        }
+    }
+
     /// log any secondary index counts, if non-zero
     pub(crate) fn log_secondary_indexes(&self) {
-        if !self.program_id_index.index.is_empty() {
+        if !self.program_id_index.is_empty() {
             info!("secondary index: {:?}", AccountIndex::ProgramId);
             self.program_id_index.log_contents();
         }
-        if !self.spl_token_mint_index.index.is_empty() {
+        if !self.spl_token_mint_index.is_empty() {
             info!("secondary index: {:?}", AccountIndex::SplTokenMint);
             self.spl_token_mint_index.log_contents();
         }
-        if !self.spl_token_owner_index.index.is_empty() {
+        if !self.spl_token_owner_index.is_empty() {
             info!("secondary index: {:?}", AccountIndex::SplTokenOwner);
             self.spl_token_owner_index.log_contents();
         }
+        for custom_index in self.custom_indexes.iter() {
+            if !custom_index.value().is_empty() {
+                info!(
+                    "secondary index: {:?}",
+                    AccountIndex::Custom(*custom_index.key())
+                );
+                custom_index.value().log_contents();
+            }
+        }
     }
 
     pub(crate) fn update_secondary_indexes(
@@ -1471,6 +1696,7 @@ impl<T: IndexValue> AccountsIndex<T> {
             account_data,
             account_indexes,
         );
+        self.update_custom_secondary_indexes(pubkey, account_owner, account_data, account_indexes);
     }
 
     fn get_account_maps_write_lock(&self, pubkey: &Pubkey) -> AccountMapsWriteLock<T> {
@@ -1639,6 +1865,10 @@ impl<T: IndexValue> AccountsIndex<T> {
         if account_indexes.contains(&AccountIndex::SplTokenMint) {
             self.spl_token_mint_index.remove_by_inner_key(inner_key);
         }
+
+        for custom_index in self.custom_indexes.iter() {
+            custom_index.value().remove_by_inner_key(inner_key);
+        }
     }
 
     fn purge_older_root_entries(
@@ -2265,6 +2495,102 @@ pub mod tests {
         assert!(index.include_key(&pk2));
     }
 
+    #[test]
+    fn test_custom_secondary_index_from_str() {
+        let program_id = Pubkey::new_unique();
+        assert_eq!(
+            format!("{}:8:32", program_id)
+                .parse::<CustomSecondaryIndex>()
+                .unwrap(),
+            CustomSecondaryIndex {
+                program_id,
+                offset: 8,
+                length: 32,
+            }
+        );
+        assert!(format!("{}:8:0", program_id)
+            .parse::<CustomSecondaryIndex>()
+            .is_err());
+        assert!(format!("{}:8:33", program_id)
+            .parse::<CustomSecondaryIndex>()
+            .is_err());
+        assert!(format!("{}:8", program_id)
+            .parse::<CustomSecondaryIndex>()
+            .is_err());
+        assert!(format!("{}:8:32:1", program_id)
+            .parse::<CustomSecondaryIndex>()
+            .is_err());
+        assert!("invalid:8:32".parse::<CustomSecondaryIndex>().is_err());
+    }
+
+    #[test]
+    fn test_custom_secondary_index_key() {
+        let custom_index = CustomSecondaryIndex::new(Pubkey::new_unique(), 2, 4).unwrap();
+        let mut expected_key = [0; PUBKEY_BYTES];
+        expected_key[..4].copy_from_slice(&[3, 4, 5, 6]);
+        assert_eq!(
+            custom_index.index_key(&[1, 2, 3, 4, 5, 6, 7]),
+            Some(Pubkey::new_from_array(expected_key))
+        );
+        assert_eq!(
+            custom_index.key_from_bytes(&[3, 4, 5, 6]),
+            Some(Pubkey::new_from_array(expected_key))
+        );
+        assert_eq!(custom_index.index_key(&[1, 2, 3, 4, 5]), None);
+        assert_eq!(custom_index.key_from_bytes(&[3, 4, 5]), None);
+    }
+
+    #[test]
+    fn test_custom_secondary_index() {
+        let program_id = Pubkey::new_unique();
+        let other_program_id = Pubkey::new_unique();
+        let custom_index = CustomSecondaryIndex::new(program_id, 8, PUBKEY_BYTES).unwrap();
+        let mut secondary_indexes = AccountSecondaryIndexes::default();
+        secondary_indexes
+            .indexes
+            .insert(AccountIndex::Custom(custom_index));
+
+        let index = AccountsIndex::<bool>::default_for_tests();
+        let account_key = Pubkey::new_unique();
+        let index_key = Pubkey::new_unique();
+        let mut account_data = vec![0; 8 + PUBKEY_BYTES];
+        account_data[8..].copy_from_slice(index_key.as_ref());
+
+        // Accounts of other programs are not indexed
+        index.update_secondary_indexes(
+            &account_key,
+            &AccountSharedData::create(0, account_data.clone(), other_program_id, false, 0),
+            &secondary_indexes,
+        );
+        assert!(index.get_custom_index(&custom_index).is_none());
+
+        // Nor are accounts too short to hold the field
+        index.update_secondary_indexes(
+            &account_key,
+            &AccountSharedData::create(0, vec![0; 8], program_id, false, 0),
+            &secondary_indexes,
+        );
+        assert!(index.get_custom_index(&custom_index).is_none());
+
+        index.update_secondary_indexes(
+            &account_key,
+            &AccountSharedData::create(0, account_data, program_id, false, 0),
+            &secondary_indexes,
+        );
+        assert_eq!(
+            index
+                .get_custom_index(&custom_index)
+                .unwrap()
+                .get(&index_key),
+            vec![account_key]
+        );
+
+        index.purge_secondary_indexes_by_inner_key(&account_key, &secondary_indexes);
+        let custom_secondary_index = index.get_custom_index(&custom_index).unwrap();
+        assert!(custom_secondary_index.index.is_empty());
+        assert!(custom_secondary_index.reverse_index.is_empty());
+    }
+
     const UPSERT_PREVIOUS_SLOT_ENTRY_WAS_CACHED_FALSE: bool = false;
 
     #[test]
@@ -2853,6 +3179,28 @@ pub mod tests {
         assert_eq!(scanned_keys.len(), num_pubkeys);
     }
 
+    #[test]
+    fn test_scan_accounts_config_range() {
+        let (index, mut pubkeys) = setup_accounts_index_keys(3 * ITER_BATCH_SIZE);
+        pubkeys.sort();
+        let ancestors = Ancestors::default();
+
+        // The keys after the start of the range are scanned in order
+        let mut scanned_keys = vec![];
+        index
+            .scan_accounts(
+                &ancestors,
+                0,
+                |pubkey, _index| scanned_keys.push(*pubkey),
+                &ScanConfig {
+                    range: Some((Excluded(pubkeys[ITER_BATCH_SIZE]), Unbounded)),
+                    ..ScanConfig::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(scanned_keys, pubkeys[ITER_BATCH_SIZE + 1..]);
+    }
+
     #[test]
     fn test_scan_accounts() {
         run_test_scan_accounts(0);
@@ -3358,6 +3706,106 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_secondary_index_on_disk() {
+        let (key_start, key_end, secondary_indexes) = create_dashmap_secondary_index_state();
+        let drive = tempfile::TempDir::new().unwrap();
+        let index = AccountsIndex::<bool>::new(Some(AccountsIndexConfig {
+            secondary_index_drives: Some(vec![drive.path().to_path_buf()]),
+            ..ACCOUNTS_INDEX_CONFIG_FOR_TESTING
+        }));
+        assert!(index.is_secondary_index_disk_enabled());
+        assert!(index.spl_token_mint_index.is_disk_index_enabled());
+        assert!(index.spl_token_mint_index.is_empty());
+
+        let account_key = Pubkey::new_unique();
+        let index_key = Pubkey::new_unique();
+        let mut account_data = vec![0; inline_spl_token::Account::get_packed_len()];
+        account_data[key_start..key_end].clone_from_slice(&(index_key.to_bytes()));
+        let slots = vec![1, 2];
+        for slot in &slots {
+            index.upsert(
+                *slot,
+                *slot,
+                &account_key,
+                &AccountSharedData::create(
+                    0,
+                    account_data.to_vec(),
+                    inline_spl_token::id(),
+                    false,
+                    0,
+                ),
+                &secondary_indexes,
+                true,
+                &mut vec![],
+                UPSERT_PREVIOUS_SLOT_ENTRY_WAS_CACHED_FALSE,
+            );
+        }
+
+        // The in-memory maps are unused, the entry lives on disk
+        assert!(index.spl_token_mint_index.index.is_empty());
+        assert!(!index.spl_token_mint_index.is_empty());
+        assert_eq!(
+            index.spl_token_mint_index.get(&index_key),
+            vec![account_key]
+        );
+
+        index.purge_exact(
+            &account_key,
+            &slots.into_iter().collect::<HashSet<Slot>>(),
+            &mut vec![],
+        );
+        index.handle_dead_keys(&[&account_key], &secondary_indexes);
+        assert!(index.spl_token_mint_index.get(&index_key).is_empty());
+        assert!(index.spl_token_mint_index.is_empty());
+    }
+
+    #[test]
+    fn test_secondary_index_on_disk_pairs() {
+        let drive = tempfile::TempDir::new().unwrap();
+        let index = SecondaryIndex::<DashMapSecondaryIndexEntry>::new_with_disk(
+            "test_secondary_index_on_disk_pairs",
+            &[drive.path().to_path_buf()],
+        );
+        let key = Pubkey::new_unique();
+        // shares the leading bytes, and so the bucket, of `key`
+        let mut other_key = key.to_bytes();
+        other_key[31] ^= 1;
+        let other_key = Pubkey::new_from_array(other_key);
+        let inner_keys = (0..100).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let sorted = |mut keys: Vec<Pubkey>| {
+            keys.sort_unstable();
+            keys
+        };
+
+        inner_keys
+            .iter()
+            .for_each(|inner_key| index.insert(&key, inner_key));
+        index.insert(&key, &inner_keys[0]);
+        index.insert(&other_key, &inner_keys[0]);
+        assert_eq!(sorted(index.get(&key)), sorted(inner_keys.clone()));
+        assert_eq!(index.get(&other_key), vec![inner_keys[0]]);
+
+        index.remove_by_inner_key(&inner_keys[0]);
+        assert_eq!(sorted(index.get(&key)), sorted(inner_keys[1..].to_vec()));
+        assert!(index.get(&other_key).is_empty());
+
+        inner_keys[1..]
+            .iter()
+            .for_each(|inner_key| index.remove_by_inner_key(inner_key));
+        assert!(index.get(&key).is_empty());
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_secondary_indexes_ready() {
+        let index = AccountsIndex::<bool>::default_for_tests();
+        assert!(!index.is_secondary_index_disk_enabled());
+        assert!(index.secondary_indexes_ready());
+        index.set_secondary_indexes_ready(false);
+        assert!(!index.secondary_indexes_ready());
+    }
+
     #[test]
     fn test_purge_older_root_entries() {
         // No roots, should be no reclaims
@@ -5,6 +5,7 @@
 //! <https://docs.solana.com/implemented-proposals/persistent-account-storage>
 
 use {
+    buffer_pool::BUFFER_POOL,
     log::*,
     memmap2::MmapMut,
     serde::{Deserialize, Serialize},
@@ -15,12 +16,13 @@ use {
         pubkey::Pubkey,
     },
     std::{
-        borrow::Borrow,
+        borrow::{Borrow, Cow},
         convert::TryFrom,
-        fs::{remove_file, OpenOptions},
+        fs::{remove_file, File, OpenOptions},
         io::{self, Seek, SeekFrom, Write},
         mem,
         path::{Path, PathBuf},
+        str::FromStr,
         sync::{
             atomic::{AtomicUsize, Ordering},
             Mutex,
@@ -28,6 +30,8 @@ use {
     },
 };
 
+pub mod buffer_pool;
+
 // Data placement should be aligned at the next boundary. Without alignment accessing the memory may
 // crash on some architectures.
 pub const ALIGN_BOUNDARY_OFFSET: usize = mem::size_of::<u64>();
@@ -41,6 +45,45 @@ pub const MAXIMUM_APPEND_VEC_FILE_SIZE: u64 = 16 * 1024 * 1024 * 1024; // 16 GiB
 
 pub type StoredMetaWriteVersion = u64;
 
+// Accounts are read from file backed AppendVecs through a buffer of at least this size,
+// so that small accounts take a single read.
+const FILE_READ_BUFFER_SIZE: usize = 4 * 1024;
+// Buffer size for reading through all accounts of a file backed AppendVec
+const FILE_SCAN_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// How an `AppendVec` accesses its file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendVecBackend {
+    /// Memory-map the file and access accounts in place
+    Mmap,
+    /// Access the file with positioned reads and writes through pooled buffers.
+    /// Accounts are copied out of the file when read, so the kernel only caches what
+    /// is actually read instead of faulting in mapped pages.
+    File,
+}
+
+impl Default for AppendVecBackend {
+    fn default() -> Self {
+        Self::Mmap
+    }
+}
+
+impl FromStr for AppendVecBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mmap" => Ok(Self::Mmap),
+            "file" => Ok(Self::File),
+            _ => Err(format!("unknown append vec backend: {}", s)),
+        }
+    }
+}
+
+impl AppendVecBackend {
+    pub const VARIANTS: &'static [&'static str] = &["mmap", "file"];
+}
+
 /// Meta contains enough context to recover the index from storage itself
 /// This struct will be backed by mmaped and snapshotted data files.
 /// So the data layout must be stable and consistent across the entire cluster!
@@ -87,17 +130,17 @@ impl<'a, T: ReadableAccount> From<Option<&'a T>> for AccountMeta {
     }
 }
 
-/// References to account data stored elsewhere. Getting an `Account` requires cloning
-/// (see `StoredAccountMeta::clone_account()`).
+/// References to account data stored elsewhere, or a copy of it for file backed AppendVecs.
+/// Getting an `Account` requires cloning (see `StoredAccountMeta::clone_account()`).
 #[derive(PartialEq, Debug)]
 pub struct StoredAccountMeta<'a> {
-    pub meta: &'a StoredMeta,
+    pub meta: Cow<'a, StoredMeta>,
     /// account data
-    pub account_meta: &'a AccountMeta,
-    pub data: &'a [u8],
+    pub account_meta: Cow<'a, AccountMeta>,
+    pub data: Cow<'a, [u8]>,
     pub offset: usize,
     pub stored_size: usize,
-    pub hash: &'a Hash,
+    pub hash: Cow<'a, Hash>,
 }
 
 impl<'a> StoredAccountMeta<'a> {
@@ -145,8 +188,8 @@ pub struct AppendVec {
     /// The file path where the data is stored.
     path: PathBuf,
 
-    /// A file-backed block of memory that is used to store the data for each appended item.
-    map: MmapMut,
+    /// How the data for each appended item is stored in the file.
+    backing: AppendVecBacking,
 
     /// A lock used to serialize append operations.
     append_lock: Mutex<()>,
@@ -161,6 +204,14 @@ pub struct AppendVec {
     remove_on_drop: bool,
 }
 
+#[derive(Debug, AbiExample)]
+enum AppendVecBacking {
+    /// A file-backed block of memory
+    Mmap(MmapMut),
+    /// The file itself, accessed with positioned reads and writes
+    File(File),
+}
+
 impl Drop for AppendVec {
     fn drop(&mut self) {
         if self.remove_on_drop {
@@ -176,6 +227,15 @@ impl Drop for AppendVec {
 
 impl AppendVec {
     pub fn new(file: &Path, create: bool, size: usize) -> Self {
+        Self::new_with_backend(file, create, size, AppendVecBackend::Mmap)
+    }
+
+    pub fn new_with_backend(
+        file: &Path,
+        create: bool,
+        size: usize,
+        backend: AppendVecBackend,
+    ) -> Self {
         let initial_len = 0;
         AppendVec::sanitize_len_and_size(initial_len, size).unwrap();
 
@@ -207,20 +267,26 @@ impl AppendVec {
         data.seek(SeekFrom::Start(0)).unwrap();
         data.flush().unwrap();
 
-        //UNSAFE: Required to create a Mmap
-        let map = unsafe { MmapMut::map_mut(&data) };
-        let map = map.unwrap_or_else(|e| {
-            error!(
-                "Failed to map the data file (size: {}): {}.\n
+        let backing = match backend {
+            AppendVecBackend::Mmap => {
+                //UNSAFE: Required to create a Mmap
+                let map = unsafe { MmapMut::map_mut(&data) };
+                let map = map.unwrap_or_else(|e| {
+                    error!(
+                        "Failed to map the data file (size: {}): {}.\n
                     Please increase sysctl vm.max_map_count or equivalent for your platform.",
-                size, e
-            );
-            std::process::exit(1);
-        });
+                        size, e
+                    );
+                    std::process::exit(1);
+                });
+                AppendVecBacking::Mmap(map)
+            }
+            AppendVecBackend::File => AppendVecBacking::File(data),
+        };
 
         AppendVec {
             path: file.to_path_buf(),
-            map,
+            backing,
             // This mutex forces append to be single threaded, but concurrent with reads
             // See UNSAFE usage in `append_ptr`
             append_lock: Mutex::new(()),
@@ -246,7 +312,7 @@ impl AppendVec {
 
         AppendVec {
             path: PathBuf::from(String::default()),
-            map,
+            backing: AppendVecBacking::Mmap(map),
             append_lock: Mutex::new(()),
             current_len: AtomicUsize::new(current_len),
             file_size: 0, // will be filled by set_file()
@@ -279,7 +345,17 @@ impl AppendVec {
     }
 
     pub fn flush(&self) -> io::Result<()> {
-        self.map.flush()
+        match &self.backing {
+            AppendVecBacking::Mmap(map) => map.flush(),
+            AppendVecBacking::File(file) => file.sync_data(),
+        }
+    }
+
+    pub fn backend(&self) -> AppendVecBackend {
+        match &self.backing {
+            AppendVecBacking::Mmap(_) => AppendVecBackend::Mmap,
+            AppendVecBacking::File(_) => AppendVecBackend::File,
+        }
     }
 
     pub fn reset(&self) {
@@ -311,6 +387,14 @@ impl AppendVec {
     }
 
     pub fn new_from_file<P: AsRef<Path>>(path: P, current_len: usize) -> io::Result<(Self, usize)> {
+        Self::new_from_file_with_backend(path, current_len, AppendVecBackend::Mmap)
+    }
+
+    pub fn new_from_file_with_backend<P: AsRef<Path>>(
+        path: P,
+        current_len: usize,
+        backend: AppendVecBackend,
+    ) -> io::Result<(Self, usize)> {
         let data = OpenOptions::new()
             .read(true)
             .write(true)
@@ -320,18 +404,24 @@ impl AppendVec {
         let file_size = std::fs::metadata(&path)?.len();
         AppendVec::sanitize_len_and_size(current_len, file_size as usize)?;
 
-        let map = unsafe {
-            let result = MmapMut::map_mut(&data);
-            if result.is_err() {
-                // for vm.max_map_count, error is: {code: 12, kind: Other, message: "Cannot allocate memory"}
-                info!("memory map error: {:?}. This may be because vm.max_map_count is not set correctly.", result);
+        let backing = match backend {
+            AppendVecBackend::Mmap => {
+                let map = unsafe {
+                    let result = MmapMut::map_mut(&data);
+                    if result.is_err() {
+                        // for vm.max_map_count, error is: {code: 12, kind: Other, message: "Cannot allocate memory"}
+                        info!("memory map error: {:?}. This may be because vm.max_map_count is not set correctly.", result);
+                    }
+                    result?
+                };
+                AppendVecBacking::Mmap(map)
             }
-            result?
+            AppendVecBackend::File => AppendVecBacking::File(data),
         };
 
         let new = AppendVec {
             path: path.as_ref().to_path_buf(),
-            map,
+            backing,
             append_lock: Mutex::new(()),
             current_len: AtomicUsize::new(current_len),
             file_size,
@@ -349,6 +439,36 @@ impl AppendVec {
         Ok((new, num_accounts))
     }
 
+    /// Creates an AppendVec over a copy of the first `bytes.len()` bytes of an
+    /// AppendVec file, as read from a snapshot archive, with the same layout
+    /// checks as `new_from_file`. The AppendVec is only meant to be read.
+    pub fn new_from_bytes(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        let file_size = bytes.len().max(1);
+        AppendVec::sanitize_len_and_size(bytes.len(), file_size)?;
+
+        let mut map = MmapMut::map_anon(file_size)?;
+        map[..bytes.len()].copy_from_slice(bytes);
+
+        let new = AppendVec {
+            path: PathBuf::default(),
+            backing: AppendVecBacking::Mmap(map),
+            append_lock: Mutex::new(()),
+            current_len: AtomicUsize::new(bytes.len()),
+            file_size: file_size as u64,
+            remove_on_drop: false,
+        };
+
+        let (sanitized, num_accounts) = new.sanitize_layout_and_length();
+        if !sanitized {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "incorrect layout/length/data",
+            ));
+        }
+
+        Ok((new, num_accounts))
+    }
+
     fn sanitize_layout_and_length(&self) -> (bool, usize) {
         let mut offset = 0;
 
@@ -358,12 +478,18 @@ impl AppendVec {
         // extend it to be reused here because it would allow attackers to accumulate
         // some measurable amount of memory needlessly.
         let mut num_accounts = 0;
-        while let Some((account, next_offset)) = self.get_account(offset) {
+        let mut sanitized = true;
+        self.scan_accounts(0, |account, next_offset| {
             if !account.sanitize() {
-                return (false, num_accounts);
+                sanitized = false;
+                return false;
             }
             offset = next_offset;
             num_accounts += 1;
+            true
+        });
+        if !sanitized {
+            return (false, num_accounts);
         }
         let aligned_current_len = u64_align!(self.current_len.load(Ordering::Acquire));
 
@@ -374,17 +500,22 @@ impl AppendVec {
     /// doesn't overrun the internal buffer. Otherwise return None.
     /// Also return the offset of the first byte after the requested data that
     /// falls on a 64-byte boundary.
-    fn get_slice(&self, offset: usize, size: usize) -> Option<(&[u8], usize)> {
+    fn get_slice<'a>(
+        &self,
+        map: &'a MmapMut,
+        offset: usize,
+        size: usize,
+    ) -> Option<(&'a [u8], usize)> {
         let (next, overflow) = offset.overflowing_add(size);
         if overflow || next > self.len() {
             return None;
         }
-        let data = &self.map[offset..next];
+        let data = &map[offset..next];
         let next = u64_align!(next);
 
         Some((
-            //UNSAFE: This unsafe creates a slice that represents a chunk of self.map memory
-            //The lifetime of this slice is tied to &self, since it points to self.map memory
+            //UNSAFE: This unsafe creates a slice that represents a chunk of map memory
+            //The lifetime of this slice is tied to &self, since it points to the map memory
             unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, size) },
             next,
         ))
@@ -392,9 +523,9 @@ impl AppendVec {
 
     /// Copy `len` bytes from `src` to the first 64-byte boundary after position `offset` of
     /// the internal buffer. Then update `offset` to the first byte after the copied data.
-    fn append_ptr(&self, offset: &mut usize, src: *const u8, len: usize) {
+    fn append_ptr(map: &MmapMut, offset: &mut usize, src: *const u8, len: usize) {
         let pos = u64_align!(*offset);
-        let data = &self.map[pos..(pos + len)];
+        let data = &map[pos..(pos + len)];
         //UNSAFE: This mut append is safe because only 1 thread can append at a time
         //Mutex<()> guarantees exclusive write access to the memory occupied in
         //the range.
@@ -421,8 +552,27 @@ impl AppendVec {
         }
 
         let pos = u64_align!(*offset);
-        for val in vals {
-            self.append_ptr(offset, val.0, val.1)
+        match &self.backing {
+            AppendVecBacking::Mmap(map) => {
+                for val in vals {
+                    Self::append_ptr(map, offset, val.0, val.1)
+                }
+            }
+            AppendVecBacking::File(file) => {
+                // Lay out the values in a buffer and write them with a single write,
+                // so the account is complete in the file before `current_len` covers it.
+                let mut buffer = BUFFER_POOL.get(end - pos);
+                for val in vals {
+                    let start = u64_align!(*offset) - pos;
+                    buffer.resize(start, 0);
+                    //UNSAFE: `vals` point to values of the given lengths, as in `append_ptr`
+                    buffer.extend_from_slice(unsafe { std::slice::from_raw_parts(val.0, val.1) });
+                    *offset = pos + buffer.len();
+                }
+                write_all_at(file, &buffer, pos as u64).unwrap_or_else(|e| {
+                    panic!("Unable to write data file {}: {:?}", self.path.display(), e)
+                });
+            }
         }
         self.current_len.store(*offset, Ordering::Release);
         Some(pos)
@@ -431,8 +581,8 @@ impl AppendVec {
     /// Return a reference to the type at `offset` if its data doesn't overrun the internal buffer.
     /// Otherwise return None. Also return the offset of the first byte after the requested data
     /// that falls on a 64-byte boundary.
-    fn get_type<'a, T>(&self, offset: usize) -> Option<(&'a T, usize)> {
-        let (data, next) = self.get_slice(offset, mem::size_of::<T>())?;
+    fn get_type<'a, T>(&self, map: &MmapMut, offset: usize) -> Option<(&'a T, usize)> {
+        let (data, next) = self.get_slice(map, offset, mem::size_of::<T>())?;
         let ptr: *const T = data.as_ptr() as *const T;
         //UNSAFE: The cast is safe because the slice is aligned and fits into the memory
         //and the lifetime of the &T is tied to self, which holds the underlying memory map
@@ -443,29 +593,70 @@ impl AppendVec {
     /// the internal buffer. Otherwise return None. Also return the offset of the first byte
     /// after the requested data that falls on a 64-byte boundary.
     pub fn get_account<'a>(&'a self, offset: usize) -> Option<(StoredAccountMeta<'a>, usize)> {
-        let (meta, next): (&'a StoredMeta, _) = self.get_type(offset)?;
-        let (account_meta, next): (&'a AccountMeta, _) = self.get_type(next)?;
-        let (hash, next): (&'a Hash, _) = self.get_type(next)?;
-        let (data, next) = self.get_slice(next, meta.data_len as usize)?;
+        match &self.backing {
+            AppendVecBacking::Mmap(map) => self.get_account_from_map(map, offset),
+            AppendVecBacking::File(file) => {
+                FileReader::new(file, self.len(), FILE_READ_BUFFER_SIZE).get_account(offset)
+            }
+        }
+    }
+
+    fn get_account_from_map<'a>(
+        &self,
+        map: &'a MmapMut,
+        offset: usize,
+    ) -> Option<(StoredAccountMeta<'a>, usize)> {
+        let (meta, next): (&'a StoredMeta, _) = self.get_type(map, offset)?;
+        let (account_meta, next): (&'a AccountMeta, _) = self.get_type(map, next)?;
+        let (hash, next): (&'a Hash, _) = self.get_type(map, next)?;
+        let (data, next) = self.get_slice(map, next, meta.data_len as usize)?;
         let stored_size = next - offset;
         Some((
             StoredAccountMeta {
-                meta,
-                account_meta,
-                data,
+                meta: Cow::Borrowed(meta),
+                account_meta: Cow::Borrowed(account_meta),
+                data: Cow::Borrowed(data),
                 offset,
                 stored_size,
-                hash,
+                hash: Cow::Borrowed(hash),
             },
             next,
         ))
     }
 
+    /// Call `callback` with each account starting from `offset` and the offset of the
+    /// account after it, until there are no more accounts or `callback` returns false.
+    fn scan_accounts<'a>(
+        &'a self,
+        mut offset: usize,
+        mut callback: impl FnMut(StoredAccountMeta<'a>, usize) -> bool,
+    ) {
+        match &self.backing {
+            AppendVecBacking::Mmap(map) => {
+                while let Some((account, next)) = self.get_account_from_map(map, offset) {
+                    if !callback(account, next) {
+                        break;
+                    }
+                    offset = next;
+                }
+            }
+            AppendVecBacking::File(file) => {
+                let mut reader = FileReader::new(file, self.len(), FILE_SCAN_BUFFER_SIZE);
+                while let Some((account, next)) = reader.get_account(offset) {
+                    if !callback(account, next) {
+                        break;
+                    }
+                    offset = next;
+                }
+            }
+        }
+    }
+
     #[cfg(test)]
     pub fn get_account_test(&self, offset: usize) -> Option<(StoredMeta, AccountSharedData)> {
         let (stored_account, _) = self.get_account(offset)?;
-        let meta = stored_account.meta.clone();
-        Some((meta, stored_account.clone_account()))
+        let account = stored_account.clone_account();
+        Some((stored_account.meta.into_owned(), account))
     }
 
     pub fn get_path(&self) -> PathBuf {
@@ -473,12 +664,12 @@ impl AppendVec {
     }
 
     /// Return account metadata for each account, starting from `offset`.
-    pub fn accounts(&self, mut offset: usize) -> Vec<StoredAccountMeta> {
+    pub fn accounts(&self, offset: usize) -> Vec<StoredAccountMeta> {
         let mut accounts = vec![];
-        while let Some((account, next)) = self.get_account(offset) {
+        self.scan_accounts(offset, |account, _next| {
             accounts.push(account);
-            offset = next;
-        }
+            true
+        });
         accounts
     }
 
@@ -542,6 +733,126 @@ impl AppendVec {
     }
 }
 
+/// Reads accounts from a file backed AppendVec through a buffer holding a window of the file
+struct FileReader<'a> {
+    file: &'a File,
+    /// `current_len` of the AppendVec; nothing past it is read
+    len: usize,
+    buffer: buffer_pool::PooledBuffer<'static>,
+    /// file offset of the start of `buffer`
+    buffer_offset: usize,
+    /// how many bytes to read at a time, at least
+    read_size: usize,
+}
+
+impl<'a> FileReader<'a> {
+    fn new(file: &'a File, len: usize, buffer_size: usize) -> Self {
+        Self {
+            file,
+            len,
+            buffer: BUFFER_POOL.get(buffer_size),
+            buffer_offset: 0,
+            read_size: buffer_size,
+        }
+    }
+
+    /// Return the `size` bytes at `offset` if they don't overrun `len`, reading them
+    /// into the buffer if needed. Also return the offset of the first byte after the
+    /// requested data that falls on a 64-byte boundary.
+    fn read(&mut self, offset: usize, size: usize) -> Option<(&[u8], usize)> {
+        let (next, overflow) = offset.overflowing_add(size);
+        if overflow || next > self.len {
+            return None;
+        }
+        if offset < self.buffer_offset || next > self.buffer_offset + self.buffer.len() {
+            // Fill the buffer from `offset`, reading ahead of the requested data
+            let read_len = size.max(self.read_size).min(self.len - offset);
+            self.buffer.resize(read_len, 0);
+            if let Err(e) = read_exact_at(self.file, &mut self.buffer, offset as u64) {
+                error!("Unable to read data file at offset {}: {:?}", offset, e);
+                self.buffer.clear();
+                return None;
+            }
+            self.buffer_offset = offset;
+        }
+        let start = offset - self.buffer_offset;
+        Some((&self.buffer[start..start + size], u64_align!(next)))
+    }
+
+    /// Copy the value of type `T` at `offset` out of the file
+    fn read_type<T>(&mut self, offset: usize) -> Option<(T, usize)> {
+        let (data, next) = self.read(offset, mem::size_of::<T>())?;
+        //UNSAFE: The read is unaligned since the buffer may not be aligned for `T`.
+        //The bytes are copied as they are, like the mmap backend references them as they are.
+        Some((
+            unsafe { std::ptr::read_unaligned(data.as_ptr() as *const T) },
+            next,
+        ))
+    }
+
+    fn get_account<'b>(&mut self, offset: usize) -> Option<(StoredAccountMeta<'b>, usize)> {
+        let (meta, next): (StoredMeta, _) = self.read_type(offset)?;
+        let (account_meta, next): (AccountMeta, _) = self.read_type(next)?;
+        let (hash, next): (Hash, _) = self.read_type(next)?;
+        let (data, next) = self.read(next, meta.data_len as usize)?;
+        let data = data.to_vec();
+        let stored_size = next - offset;
+        Some((
+            StoredAccountMeta {
+                meta: Cow::Owned(meta),
+                account_meta: Cow::Owned(account_meta),
+                data: Cow::Owned(data),
+                offset,
+                stored_size,
+                hash: Cow::Owned(hash),
+            },
+            next,
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset)? {
+            0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+            n => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn write_all_at(file: &File, buf: &[u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::write_all_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn write_all_at(file: &File, mut buf: &[u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buf.is_empty() {
+        match file.seek_write(buf, offset)? {
+            0 => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+            n => {
+                buf = &buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
 pub mod test_utils {
     use {
         super::StoredMeta,
@@ -806,6 +1117,71 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_append_vec_file_backend() {
+        let file = get_append_vec_path("test_append_vec_file_backend");
+        let path = &file.path;
+        let mut av =
+            AppendVec::new_with_backend(path, true, 4 * 1024 * 1024, AppendVecBackend::File);
+        av.set_no_remove_on_drop();
+        assert_eq!(av.backend(), AppendVecBackend::File);
+        let size = 1000;
+        let mut indexes = vec![];
+        for sample in 0..size {
+            let account = create_test_account(sample);
+            let pos = av.append_account_test(&account).unwrap();
+            assert_eq!(av.get_account_test(pos).unwrap(), account);
+            indexes.push(pos)
+        }
+        for sample in [0, 255, 256, size - 1] {
+            let account = create_test_account(sample);
+            assert_eq!(av.get_account_test(indexes[sample]).unwrap(), account);
+        }
+        let accounts = av.accounts(0);
+        assert_eq!(accounts.len(), size);
+        for (sample, v) in accounts.iter().enumerate() {
+            assert_eq!(v.offset, indexes[sample]);
+            assert_eq!(v.clone_account(), create_test_account(sample).1);
+        }
+        // an account too large to fit in the read buffer
+        let mut large_account = create_test_account(0);
+        large_account.1.set_data(vec![7; FILE_SCAN_BUFFER_SIZE + 1]);
+        large_account.0.data_len = large_account.1.data().len() as u64;
+        let large_index = av.append_account_test(&large_account).unwrap();
+        assert_eq!(av.get_account_test(large_index).unwrap(), large_account);
+        assert_eq!(av.accounts(0).len(), size + 1);
+
+        av.flush().unwrap();
+        let accounts_len = av.len();
+        drop(av);
+
+        // the file is laid out the same way with either backend
+        for backend in [AppendVecBackend::Mmap, AppendVecBackend::File] {
+            let (mut av, num_accounts) =
+                AppendVec::new_from_file_with_backend(path, accounts_len, backend).unwrap();
+            av.set_no_remove_on_drop();
+            assert_eq!(num_accounts, size + 1);
+            assert_eq!(
+                av.get_account_test(indexes[10]).unwrap(),
+                create_test_account(10)
+            );
+            assert_eq!(av.get_account_test(large_index).unwrap(), large_account);
+            assert!(av.get_account(accounts_len).is_none());
+        }
+    }
+
+    #[test]
+    fn test_append_vec_backend_from_str() {
+        for name in AppendVecBackend::VARIANTS {
+            assert!(name.parse::<AppendVecBackend>().is_ok());
+        }
+        assert_eq!(
+            "file".parse::<AppendVecBackend>(),
+            Ok(AppendVecBackend::File)
+        );
+        assert!("direct".parse::<AppendVecBackend>().is_err());
+    }
+
     #[test]
     fn test_new_from_file_crafted_zero_lamport_account() {
         let file = get_append_vec_path("test_append");
@@ -834,6 +1210,27 @@ pub mod tests {
         assert_matches!(result, Err(ref message) if message.to_string() == *"incorrect layout/length/data");
     }
 
+    #[test]
+    fn test_new_from_bytes() {
+        let file = get_append_vec_path("test_new_from_bytes");
+        let path = &file.path;
+        let av = AppendVec::new(path, true, 1024 * 1024);
+        let account1 = create_test_account(10);
+        let account2 = create_test_account(20);
+        av.append_account_test(&account1).unwrap();
+        let offset2 = av.append_account_test(&account2).unwrap();
+        av.flush().unwrap();
+
+        let bytes = std::fs::read(path).unwrap();
+        let (copy, num_accounts) = AppendVec::new_from_bytes(&bytes[..av.len()]).unwrap();
+        assert_eq!(num_accounts, 2);
+        assert_eq!(copy.get_account_test(offset2).unwrap(), account2);
+
+        // A truncated account is rejected
+        let result = AppendVec::new_from_bytes(&bytes[..av.len() - 1]);
+        assert_matches!(result, Err(ref message) if message.to_string() == *"incorrect layout/length/data");
+    }
+
     #[test]
     fn test_new_from_file_crafted_data_len() {
         let file = get_append_vec_path("test_new_from_file_crafted_data_len");
@@ -0,0 +1,216 @@
+//! Reusable buffers for reading and writing file backed `AppendVec`s.
+//!
+//! Each read or write of a file backed `AppendVec` goes through a buffer. Taking the
+//! buffers from a pool avoids allocating one per access, and bounds the memory held
+//! by idle buffers.
+use {
+    lazy_static::lazy_static,
+    std::{
+        ops::{Deref, DerefMut},
+        sync::{
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            Mutex,
+        },
+    },
+};
+
+/// Idle buffers beyond this many bytes are freed instead of returned to the pool
+pub const DEFAULT_MAX_POOLED_BYTES: usize = 256 * 1024 * 1024;
+
+// Buffers are pooled by size class: class `i` holds buffers with a capacity of at least
+// `MIN_POOLED_BUFFER_SIZE << i`, so taking one is a pop from its class' list rather than a
+// search of every idle buffer, and readers of different sizes don't contend on one lock.
+const MIN_POOLED_BUFFER_SIZE: usize = 4 * 1024;
+// Buffers larger than this are not pooled; they are only needed for very large accounts.
+const MAX_POOLED_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+const NUM_SIZE_CLASSES: usize =
+    (MAX_POOLED_BUFFER_SIZE / MIN_POOLED_BUFFER_SIZE).trailing_zeros() as usize + 1;
+
+lazy_static! {
+    pub(crate) static ref BUFFER_POOL: BufferPool = BufferPool::new(DEFAULT_MAX_POOLED_BYTES);
+}
+
+#[derive(Debug, Default)]
+pub struct BufferPoolStats {
+    /// buffers handed out from the pool
+    pub reused: AtomicU64,
+    /// buffers allocated because the pool had none large enough
+    pub allocated: AtomicU64,
+}
+
+#[derive(Debug)]
+pub struct BufferPool {
+    /// idle buffers, by size class
+    size_classes: Vec<Mutex<Vec<Vec<u8>>>>,
+    pooled_bytes: AtomicUsize,
+    max_pooled_bytes: usize,
+    pub stats: BufferPoolStats,
+}
+
+impl BufferPool {
+    pub fn new(max_pooled_bytes: usize) -> Self {
+        Self {
+            size_classes: (0..NUM_SIZE_CLASSES).map(|_| Mutex::default()).collect(),
+            pooled_bytes: AtomicUsize::default(),
+            max_pooled_bytes,
+            stats: BufferPoolStats::default(),
+        }
+    }
+
+    /// The smallest size class whose buffers all have room for `capacity` bytes,
+    /// None if `capacity` is too large to be pooled
+    fn size_class_for(capacity: usize) -> Option<usize> {
+        (capacity <= MAX_POOLED_BUFFER_SIZE).then(|| {
+            let class_size = capacity.max(MIN_POOLED_BUFFER_SIZE).next_power_of_two();
+            (class_size / MIN_POOLED_BUFFER_SIZE).trailing_zeros() as usize
+        })
+    }
+
+    /// The largest size class whose buffers may have a capacity of `capacity`,
+    /// None if the buffer is too small or too large to be pooled
+    fn size_class_of(capacity: usize) -> Option<usize> {
+        (MIN_POOLED_BUFFER_SIZE..=MAX_POOLED_BUFFER_SIZE)
+            .contains(&capacity)
+            .then(|| {
+                let class_size = 1 << (usize::BITS - 1 - capacity.leading_zeros());
+                (class_size / MIN_POOLED_BUFFER_SIZE).trailing_zeros() as usize
+            })
+    }
+
+    /// Take an empty buffer with room for at least `capacity` bytes.
+    /// The buffer goes back to the pool when dropped.
+    pub fn get(&self, capacity: usize) -> PooledBuffer<'_> {
+        let size_class = Self::size_class_for(capacity);
+        let buffer =
+            size_class.and_then(|size_class| self.size_classes[size_class].lock().unwrap().pop());
+        let buffer = match buffer {
+            Some(mut buffer) => {
+                self.pooled_bytes
+                    .fetch_sub(buffer.capacity(), Ordering::Relaxed);
+                self.stats.reused.fetch_add(1, Ordering::Relaxed);
+                buffer.clear();
+                buffer
+            }
+            None => {
+                self.stats.allocated.fetch_add(1, Ordering::Relaxed);
+                // Allocate the whole class size, so the buffer can be reused for any
+                // request of its class
+                let capacity = size_class
+                    .map(|size_class| MIN_POOLED_BUFFER_SIZE << size_class)
+                    .unwrap_or(capacity);
+                Vec::with_capacity(capacity)
+            }
+        };
+        PooledBuffer { buffer, pool: self }
+    }
+
+    fn put(&self, buffer: Vec<u8>) {
+        let capacity = buffer.capacity();
+        let size_class = match Self::size_class_of(capacity) {
+            Some(size_class) => size_class,
+            None => return,
+        };
+        if self.pooled_bytes.fetch_add(capacity, Ordering::Relaxed) + capacity
+            > self.max_pooled_bytes
+        {
+            self.pooled_bytes.fetch_sub(capacity, Ordering::Relaxed);
+            return;
+        }
+        self.size_classes[size_class].lock().unwrap().push(buffer);
+    }
+
+    /// Number of bytes held by idle buffers
+    pub fn pooled_bytes(&self) -> usize {
+        self.pooled_bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// A buffer taken from a `BufferPool`
+#[derive(Debug)]
+pub struct PooledBuffer<'a> {
+    buffer: Vec<u8>,
+    pool: &'a BufferPool,
+}
+
+impl<'a> Deref for PooledBuffer<'a> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl<'a> DerefMut for PooledBuffer<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl<'a> Drop for PooledBuffer<'a> {
+    fn drop(&mut self) {
+        self.pool.put(std::mem::take(&mut self.buffer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_pool_size_classes() {
+        assert_eq!(BufferPool::size_class_for(0), Some(0));
+        assert_eq!(BufferPool::size_class_for(MIN_POOLED_BUFFER_SIZE), Some(0));
+        assert_eq!(
+            BufferPool::size_class_for(MIN_POOLED_BUFFER_SIZE + 1),
+            Some(1)
+        );
+        assert_eq!(
+            BufferPool::size_class_for(MAX_POOLED_BUFFER_SIZE),
+            Some(NUM_SIZE_CLASSES - 1)
+        );
+        assert_eq!(BufferPool::size_class_for(MAX_POOLED_BUFFER_SIZE + 1), None);
+
+        assert_eq!(BufferPool::size_class_of(MIN_POOLED_BUFFER_SIZE - 1), None);
+        assert_eq!(BufferPool::size_class_of(MIN_POOLED_BUFFER_SIZE), Some(0));
+        // a buffer that grew past its class size still only serves its class
+        assert_eq!(
+            BufferPool::size_class_of(3 * MIN_POOLED_BUFFER_SIZE),
+            Some(1)
+        );
+        assert_eq!(
+            BufferPool::size_class_of(MAX_POOLED_BUFFER_SIZE),
+            Some(NUM_SIZE_CLASSES - 1)
+        );
+        assert_eq!(BufferPool::size_class_of(MAX_POOLED_BUFFER_SIZE + 1), None);
+    }
+
+    #[test]
+    fn test_buffer_pool_reuse() {
+        let pool = BufferPool::new(64 * 1024);
+        {
+            let mut buffer = pool.get(100);
+            buffer.extend_from_slice(&[1; 100]);
+        }
+        assert_eq!(pool.pooled_bytes(), MIN_POOLED_BUFFER_SIZE);
+        {
+            // the pooled buffer is too small
+            let _buffer = pool.get(2 * MIN_POOLED_BUFFER_SIZE);
+        }
+        assert_eq!(pool.stats.allocated.load(Ordering::Relaxed), 2);
+        let buffer = pool.get(50);
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 50);
+        assert_eq!(pool.stats.reused.load(Ordering::Relaxed), 1);
+        assert_eq!(pool.pooled_bytes(), 2 * MIN_POOLED_BUFFER_SIZE);
+        drop(buffer);
+
+        // buffers beyond the pool's budget are freed
+        let buffers: Vec<_> = (0..20).map(|_| pool.get(100)).collect();
+        drop(buffers);
+        assert!(pool.pooled_bytes() <= 64 * 1024);
+
+        // buffers too large to pool are freed too
+        drop(pool.get(MAX_POOLED_BUFFER_SIZE + 1));
+        assert!(pool.pooled_bytes() <= 64 * 1024);
+    }
+}
@@ -0,0 +1,165 @@
+//! The framing of the archive formats ending with an index of their content,
+//! so that a reader finds any part of the archive without reading it all.
+//!
+//! Layout of a framed archive:
+//! - header: the magic bytes of the format, then its version as a
+//!   little-endian u32
+//! - body: the content of the archive, laid out by the format
+//! - index: the index of the body, encoded by the format
+//! - footer: the offset and length of the index as little-endian u64s, then
+//!   the magic bytes again, so that a truncated archive is detected
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+pub type Magic = [u8; 8];
+pub const HEADER_LEN: u64 = 8 + 4;
+pub const FOOTER_LEN: u64 = 16 + 8;
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub fn write_header(writer: &mut impl Write, magic: &Magic, version: u32) -> io::Result<()> {
+    writer.write_all(magic)?;
+    writer.write_all(&version.to_le_bytes())
+}
+
+/// Writes the footer of an index written at `index_offset`
+pub fn write_footer(
+    writer: &mut impl Write,
+    magic: &Magic,
+    index_offset: u64,
+    index_len: u64,
+) -> io::Result<()> {
+    writer.write_all(&index_offset.to_le_bytes())?;
+    writer.write_all(&index_len.to_le_bytes())?;
+    writer.write_all(magic)
+}
+
+/// The header and footer of an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveFrame {
+    pub version: u32,
+    pub index_offset: u64,
+    pub index_len: u64,
+}
+
+impl ArchiveFrame {
+    /// Reads and checks the header and footer of an archive, leaving `reader`
+    /// at an unspecified position. `name` names the format in the errors.
+    pub fn read<R: Read + Seek>(
+        reader: &mut R,
+        magic: &Magic,
+        latest_version: u32,
+        name: &str,
+    ) -> io::Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut header_magic = Magic::default();
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut header_magic)?;
+        reader.read_exact(&mut version)?;
+        if &header_magic != magic {
+            return Err(invalid_data(format!("Not a valid {}", name)));
+        }
+        let version = u32::from_le_bytes(version);
+        if version > latest_version {
+            return Err(invalid_data(format!(
+                "Unsupported {} version {}, the latest supported version is {}",
+                name, version, latest_version
+            )));
+        }
+
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        if file_len < HEADER_LEN + FOOTER_LEN {
+            return Err(invalid_data(format!("Truncated {}", name)));
+        }
+        reader.seek(SeekFrom::End(-(FOOTER_LEN as i64)))?;
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        reader.read_exact(&mut footer)?;
+        if &footer[16..] != magic {
+            return Err(invalid_data(format!("Truncated {}", name)));
+        }
+        let index_offset = u64::from_le_bytes(footer[..8].try_into().unwrap());
+        let index_len = u64::from_le_bytes(footer[8..16].try_into().unwrap());
+        if index_offset < HEADER_LEN
+            || index_offset.checked_add(index_len) != Some(file_len - FOOTER_LEN)
+        {
+            return Err(invalid_data(format!("Corrupted {} footer", name)));
+        }
+
+        Ok(Self {
+            version,
+            index_offset,
+            index_len,
+        })
+    }
+
+    /// Check that `len` bytes at `offset` are within the body of the archive
+    pub fn body_contains(&self, offset: u64, len: u64) -> bool {
+        offset >= HEADER_LEN
+            && offset
+                .checked_add(len)
+                .map(|end| end <= self.index_offset)
+                .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::io::Cursor};
+
+    const MAGIC: &Magic = b"SOLTESTA";
+
+    fn framed_archive(version: u32, body: &[u8], index: &[u8]) -> Vec<u8> {
+        let mut archive = vec![];
+        write_header(&mut archive, MAGIC, version).unwrap();
+        archive.extend_from_slice(body);
+        archive.extend_from_slice(index);
+        write_footer(
+            &mut archive,
+            MAGIC,
+            HEADER_LEN + body.len() as u64,
+            index.len() as u64,
+        )
+        .unwrap();
+        archive
+    }
+
+    fn read_frame(archive: Vec<u8>) -> io::Result<ArchiveFrame> {
+        ArchiveFrame::read(&mut Cursor::new(archive), MAGIC, 2, "test archive")
+    }
+
+    #[test]
+    fn test_archive_frame() {
+        let frame = read_frame(framed_archive(2, &[1; 10], &[2; 5])).unwrap();
+        assert_eq!(
+            frame,
+            ArchiveFrame {
+                version: 2,
+                index_offset: HEADER_LEN + 10,
+                index_len: 5,
+            }
+        );
+        assert!(frame.body_contains(HEADER_LEN, 10));
+        assert!(frame.body_contains(HEADER_LEN + 10, 0));
+        assert!(!frame.body_contains(HEADER_LEN, 11));
+        assert!(!frame.body_contains(HEADER_LEN - 1, 1));
+        assert!(!frame.body_contains(HEADER_LEN, u64::MAX));
+
+        // An empty body and index are valid
+        assert!(read_frame(framed_archive(1, &[], &[])).is_ok());
+
+        assert!(read_frame(framed_archive(3, &[], &[])).is_err());
+        let mut archive = framed_archive(1, &[1; 10], &[2; 5]);
+        archive[0] = 0;
+        assert!(read_frame(archive).is_err());
+        let archive = framed_archive(1, &[1; 10], &[2; 5]);
+        assert!(read_frame(archive[..archive.len() - 1].to_vec()).is_err());
+        assert!(read_frame(archive[1..].to_vec()).is_err());
+
+        // The offset and length of the index overflowing is a corrupted footer
+        let mut archive = framed_archive(1, &[1; 10], &[2; 5]);
+        let index_len_offset = archive.len() - FOOTER_LEN as usize + 8;
+        archive[index_len_offset..index_len_offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(read_frame(archive).is_err());
+    }
+}
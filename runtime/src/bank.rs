@@ -6174,33 +6174,51 @@ impl Bank {
         self.get_signature_status_slot(signature).is_some()
     }
 
-    /// Hash the `accounts` HashMap. This represents a validator's interpretation
-    ///  of the delta of the ledger since the last vote and up to now
-    fn hash_internal_state(&self) -> Hash {
-        // If there are no accounts, return the hash of the previous state and the latest blockhash
-        let accounts_delta_hash = self
-            .rc
-            .accounts
-            .bank_hash_info_at(self.slot(), &self.rewrites_skipped_this_slot);
+    /// Hash of a bank's state, from the hash of its parent, its accounts delta hash, its
+    /// signature count and its last blockhash, mixing in the hard fork at its slot if any
+    pub(crate) fn compute_hash(
+        parent_hash: &Hash,
+        accounts_delta_hash: &Hash,
+        signature_count: u64,
+        last_blockhash: &Hash,
+        hard_forks: &HardForks,
+        slot: Slot,
+        parent_slot: Slot,
+    ) -> Hash {
         let mut signature_count_buf = [0u8; 8];
-        LittleEndian::write_u64(&mut signature_count_buf[..], self.signature_count() as u64);
+        LittleEndian::write_u64(&mut signature_count_buf[..], signature_count);
 
         let mut hash = hashv(&[
-            self.parent_hash.as_ref(),
-            accounts_delta_hash.hash.as_ref(),
+            parent_hash.as_ref(),
+            accounts_delta_hash.as_ref(),
             &signature_count_buf,
-            self.last_blockhash().as_ref(),
+            last_blockhash.as_ref(),
         ]);
 
-        if let Some(buf) = self
-            .hard_forks
-            .read()
-            .unwrap()
-            .get_hash_data(self.slot(), self.parent_slot())
-        {
-            info!("hard fork at bank {}", self.slot());
+        if let Some(buf) = hard_forks.get_hash_data(slot, parent_slot) {
+            info!("hard fork at bank {}", slot);
             hash = extend_and_hash(&hash, &buf)
         }
+        hash
+    }
+
+    /// Hash the `accounts` HashMap. This represents a validator's interpretation
+    ///  of the delta of the ledger since the last vote and up to now
+    fn hash_internal_state(&self) -> Hash {
+        // If there are no accounts, return the hash of the previous state and the latest blockhash
+        let accounts_delta_hash = self
+            .rc
+            .accounts
+            .bank_hash_info_at(self.slot(), &self.rewrites_skipped_this_slot);
+        let hash = Self::compute_hash(
+            &self.parent_hash,
+            &accounts_delta_hash.hash,
+            self.signature_count(),
+            &self.last_blockhash(),
+            &self.hard_forks.read().unwrap(),
+            self.slot(),
+            self.parent_slot(),
+        );
 
         info!(
             "bank frozen: {} hash: {} accounts_delta: {} signature_count: {} last_blockhash: {} capitalization: {}",
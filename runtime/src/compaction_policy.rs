@@ -0,0 +1,336 @@
+//! Policies deciding which account storages are shrunk, and when.
+//!
+//! Shrinking rewrites the alive accounts of a sparse storage into a new, smaller one.
+//! Shrinking more often keeps disk usage down at the cost of more writes, so the policy
+//! is pluggable through `AccountsDbConfig::compaction_policy`.
+use {
+    crate::accounts_db::{
+        AccountShrinkThreshold, AccountStorageEntry, AccountsDb, ShrinkCandidates,
+    },
+    solana_sdk::clock::Slot,
+    std::fmt::Debug,
+};
+
+/// Above this many slots waiting in the write cache to be flushed, the validator is considered busy
+pub const DEFAULT_BUSY_UNFLUSHED_SLOTS: usize = 200;
+
+/// The state of the accounts db a `CompactionPolicy` may take into account
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CompactionContext {
+    /// The highest root, to tell how old a candidate slot is
+    pub max_root: Slot,
+    /// Number of slots in the write cache waiting to be flushed
+    pub unflushed_slots: usize,
+}
+
+pub trait CompactionPolicy: Debug + Send + Sync {
+    /// Whether `store`, which just had some of its accounts die, should be queued for shrinking
+    fn is_shrink_candidate(&self, store: &AccountStorageEntry) -> bool;
+
+    /// Split the queued `candidates` into the storages to shrink in this pass and the storages
+    /// to keep queued for a later pass. Candidates in neither are dropped from the queue until
+    /// more of their accounts die.
+    fn select_shrink_candidates(
+        &self,
+        candidates: ShrinkCandidates,
+        context: &CompactionContext,
+    ) -> (ShrinkCandidates, ShrinkCandidates);
+}
+
+/// The default policy: shrink according to a single `AccountShrinkThreshold`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThresholdCompactionPolicy {
+    pub threshold: AccountShrinkThreshold,
+}
+
+impl ThresholdCompactionPolicy {
+    pub fn new(threshold: AccountShrinkThreshold) -> Self {
+        Self { threshold }
+    }
+}
+
+impl CompactionPolicy for ThresholdCompactionPolicy {
+    fn is_shrink_candidate(&self, store: &AccountStorageEntry) -> bool {
+        is_below_threshold(&self.threshold, store)
+    }
+
+    fn select_shrink_candidates(
+        &self,
+        candidates: ShrinkCandidates,
+        _context: &CompactionContext,
+    ) -> (ShrinkCandidates, ShrinkCandidates) {
+        select_by_threshold(&self.threshold, candidates)
+    }
+}
+
+/// A policy with separate thresholds for when the validator is idle and when it is busy,
+/// which leaves recent slots alone and bounds how much is rewritten in one pass.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetCompactionPolicy {
+    /// Threshold used while fewer than `busy_unflushed_slots` slots are waiting to be flushed
+    pub idle_threshold: AccountShrinkThreshold,
+    /// Threshold used while the validator is busy
+    pub busy_threshold: AccountShrinkThreshold,
+    pub busy_unflushed_slots: usize,
+    /// Candidates less than this many slots behind the max root are kept for a later pass.
+    /// Recent storages tend to keep losing accounts, so shrinking them early rewrites them again.
+    pub min_slot_age: Slot,
+    /// Most slots shrunk in one pass. The oldest slots go first and the rest are kept for a later pass.
+    pub max_slots_per_pass: Option<usize>,
+}
+
+impl Default for TargetCompactionPolicy {
+    fn default() -> Self {
+        Self {
+            idle_threshold: AccountShrinkThreshold::default(),
+            busy_threshold: AccountShrinkThreshold::default(),
+            busy_unflushed_slots: DEFAULT_BUSY_UNFLUSHED_SLOTS,
+            min_slot_age: 0,
+            max_slots_per_pass: None,
+        }
+    }
+}
+
+impl TargetCompactionPolicy {
+    fn threshold(&self, context: &CompactionContext) -> &AccountShrinkThreshold {
+        if context.unflushed_slots >= self.busy_unflushed_slots {
+            &self.busy_threshold
+        } else {
+            &self.idle_threshold
+        }
+    }
+}
+
+impl CompactionPolicy for TargetCompactionPolicy {
+    fn is_shrink_candidate(&self, store: &AccountStorageEntry) -> bool {
+        // queue anything either threshold could select, the threshold is picked per pass
+        is_below_threshold(&self.idle_threshold, store)
+            || is_below_threshold(&self.busy_threshold, store)
+    }
+
+    fn select_shrink_candidates(
+        &self,
+        candidates: ShrinkCandidates,
+        context: &CompactionContext,
+    ) -> (ShrinkCandidates, ShrinkCandidates) {
+        let (old_enough, mut next_batch): (ShrinkCandidates, ShrinkCandidates) = candidates
+            .into_iter()
+            .partition(|(slot, _)| context.max_root.saturating_sub(*slot) >= self.min_slot_age);
+
+        let (mut shrink_slots, threshold_next_batch) =
+            select_by_threshold(self.threshold(context), old_enough);
+        next_batch.extend(threshold_next_batch);
+
+        if let Some(max_slots_per_pass) = self.max_slots_per_pass {
+            if shrink_slots.len() > max_slots_per_pass {
+                let mut slots = shrink_slots.keys().copied().collect::<Vec<_>>();
+                slots.sort_unstable();
+                for slot in &slots[max_slots_per_pass..] {
+                    let stores = shrink_slots.remove(slot).unwrap();
+                    next_batch.entry(*slot).or_default().extend(stores);
+                }
+            }
+        }
+        (shrink_slots, next_batch)
+    }
+}
+
+fn is_below_threshold(threshold: &AccountShrinkThreshold, store: &AccountStorageEntry) -> bool {
+    let aligned_alive_bytes = AccountsDb::page_align(store.alive_bytes() as u64);
+    match threshold {
+        AccountShrinkThreshold::TotalSpace { shrink_ratio: _ } => {
+            aligned_alive_bytes < store.total_bytes()
+        }
+        AccountShrinkThreshold::IndividualStore { shrink_ratio } => {
+            (aligned_alive_bytes as f64 / store.total_bytes() as f64) < *shrink_ratio
+        }
+    }
+}
+
+fn select_by_threshold(
+    threshold: &AccountShrinkThreshold,
+    candidates: ShrinkCandidates,
+) -> (ShrinkCandidates, ShrinkCandidates) {
+    match threshold {
+        AccountShrinkThreshold::TotalSpace { shrink_ratio } => {
+            AccountsDb::select_candidates_by_total_usage(&candidates, *shrink_ratio)
+        }
+        AccountShrinkThreshold::IndividualStore { shrink_ratio: _ } => {
+            // the candidates may have been queued by another threshold, so check them against
+            // this one, keeping the others for a later pass
+            let mut shrink_slots = ShrinkCandidates::default();
+            let mut next_batch = ShrinkCandidates::default();
+            for (slot, stores) in candidates {
+                for (append_vec_id, store) in stores {
+                    let batch = if is_below_threshold(threshold, &store) {
+                        &mut shrink_slots
+                    } else {
+                        &mut next_batch
+                    };
+                    batch.entry(slot).or_default().insert(append_vec_id, store);
+                }
+            }
+            (shrink_slots, next_batch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::accounts_db::{AppendVecId, PAGE_SIZE},
+        std::{
+            collections::HashMap,
+            path::Path,
+            sync::{atomic::Ordering, Arc},
+        },
+    };
+
+    fn new_candidates(slots: impl Iterator<Item = Slot>) -> ShrinkCandidates {
+        slots
+            .enumerate()
+            .map(|(id, slot)| {
+                let store = Arc::new(AccountStorageEntry::new(Path::new(""), slot, id as _, 4096));
+                (slot, HashMap::from([(store.append_vec_id(), store)]))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_target_compaction_policy_min_slot_age() {
+        let policy = TargetCompactionPolicy {
+            idle_threshold: AccountShrinkThreshold::IndividualStore { shrink_ratio: 1.0 },
+            min_slot_age: 10,
+            ..TargetCompactionPolicy::default()
+        };
+        let context = CompactionContext {
+            max_root: 20,
+            unflushed_slots: 0,
+        };
+        let (shrink_slots, next_batch) =
+            policy.select_shrink_candidates(new_candidates(5..15), &context);
+        let mut shrink_slots = shrink_slots.into_keys().collect::<Vec<_>>();
+        shrink_slots.sort_unstable();
+        let mut next_batch = next_batch.into_keys().collect::<Vec<_>>();
+        next_batch.sort_unstable();
+        assert_eq!(shrink_slots, (5..=10).collect::<Vec<_>>());
+        assert_eq!(next_batch, (11..15).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_target_compaction_policy_max_slots_per_pass() {
+        let policy = TargetCompactionPolicy {
+            idle_threshold: AccountShrinkThreshold::IndividualStore { shrink_ratio: 1.0 },
+            max_slots_per_pass: Some(3),
+            ..TargetCompactionPolicy::default()
+        };
+        let (shrink_slots, next_batch) =
+            policy.select_shrink_candidates(new_candidates(0..5), &CompactionContext::default());
+        let mut shrink_slots = shrink_slots.into_keys().collect::<Vec<_>>();
+        shrink_slots.sort_unstable();
+        let mut next_batch = next_batch.into_keys().collect::<Vec<_>>();
+        next_batch.sort_unstable();
+        assert_eq!(shrink_slots, vec![0, 1, 2]);
+        assert_eq!(next_batch, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_target_compaction_policy_busy_threshold() {
+        let policy = TargetCompactionPolicy {
+            idle_threshold: AccountShrinkThreshold::TotalSpace { shrink_ratio: 0.8 },
+            busy_threshold: AccountShrinkThreshold::TotalSpace { shrink_ratio: 0.4 },
+            busy_unflushed_slots: 10,
+            ..TargetCompactionPolicy::default()
+        };
+        let candidates = || {
+            let mut candidates: ShrinkCandidates = HashMap::new();
+            for slot in 1..3 {
+                // each store has an alive ratio of 0.5
+                let store = Arc::new(AccountStorageEntry::new(
+                    Path::new(""),
+                    slot,
+                    slot as AppendVecId + 40,
+                    4 * PAGE_SIZE,
+                ));
+                store
+                    .alive_bytes
+                    .store(2 * PAGE_SIZE as usize, Ordering::Release);
+                candidates
+                    .entry(slot)
+                    .or_default()
+                    .insert(store.append_vec_id(), store);
+            }
+            candidates
+        };
+
+        let idle = CompactionContext {
+            max_root: 2,
+            unflushed_slots: 9,
+        };
+        let (selected_candidates, next_candidates) =
+            policy.select_shrink_candidates(candidates(), &idle);
+        assert_eq!(2, selected_candidates.len());
+        assert!(next_candidates.is_empty());
+
+        // the overall alive ratio already meets the busy target, so nothing is shrunk
+        let busy = CompactionContext {
+            max_root: 2,
+            unflushed_slots: 10,
+        };
+        let (selected_candidates, next_candidates) =
+            policy.select_shrink_candidates(candidates(), &busy);
+        assert!(selected_candidates.is_empty());
+        assert!(next_candidates.is_empty());
+    }
+
+    #[test]
+    fn test_target_compaction_policy_busy_individual_store_threshold() {
+        let policy = TargetCompactionPolicy {
+            idle_threshold: AccountShrinkThreshold::IndividualStore { shrink_ratio: 0.8 },
+            busy_threshold: AccountShrinkThreshold::IndividualStore { shrink_ratio: 0.4 },
+            busy_unflushed_slots: 10,
+            ..TargetCompactionPolicy::default()
+        };
+        let candidates = || {
+            let mut candidates: ShrinkCandidates = HashMap::new();
+            for slot in 1..3 {
+                // each store has an alive ratio of 0.5
+                let store = Arc::new(AccountStorageEntry::new(
+                    Path::new(""),
+                    slot,
+                    slot as AppendVecId + 40,
+                    4 * PAGE_SIZE,
+                ));
+                store
+                    .alive_bytes
+                    .store(2 * PAGE_SIZE as usize, Ordering::Release);
+                assert!(policy.is_shrink_candidate(&store));
+                candidates
+                    .entry(slot)
+                    .or_default()
+                    .insert(store.append_vec_id(), store);
+            }
+            candidates
+        };
+
+        let idle = CompactionContext {
+            max_root: 2,
+            unflushed_slots: 9,
+        };
+        let (selected_candidates, next_candidates) =
+            policy.select_shrink_candidates(candidates(), &idle);
+        assert_eq!(2, selected_candidates.len());
+        assert!(next_candidates.is_empty());
+
+        // the stores are above the busy ratio, so they wait for the validator to be idle
+        let busy = CompactionContext {
+            max_root: 2,
+            unflushed_slots: 10,
+        };
+        let (selected_candidates, next_candidates) =
+            policy.select_shrink_candidates(candidates(), &busy);
+        assert!(selected_candidates.is_empty());
+        assert_eq!(2, next_candidates.len());
+    }
+}
@@ -6,7 +6,7 @@ use {
     std::{
         collections::HashMap,
         fs::{self, File},
-        io::{BufReader, Read},
+        io::{self, BufReader, Read},
         path::{
             Component::{self, CurDir, Normal},
             Path, PathBuf,
@@ -114,11 +114,7 @@ where
         // first by ourselves when there are odd paths like including `..` or /
         // for our clearer pattern matching reasoning:
         //   https://docs.rs/tar/0.4.26/src/tar/entry.rs.html#371
-        let parts = path.components().map(|p| match p {
-            CurDir => Some("."),
-            Normal(c) => c.to_str(),
-            _ => None, // Prefix (for Windows) and RootDir are forbidden
-        });
+        let parts = path_parts(&path);
 
         // Reject old-style BSD directory entries that aren't explicitly tagged as directories
         let legacy_dir_entry =
@@ -185,22 +181,30 @@ where
     }
     info!("unpacked {} entries total", total_entries);
 
-    return Ok(());
+    Ok(())
+}
 
-    #[cfg(unix)]
-    fn set_perms(dst: &Path, mode: u32) -> std::io::Result<()> {
-        use std::os::unix::fs::PermissionsExt;
+fn path_parts(path: &Path) -> impl Iterator<Item = Option<&str>> + Clone {
+    path.components().map(|p| match p {
+        CurDir => Some("."),
+        Normal(c) => c.to_str(),
+        _ => None, // Prefix (for Windows) and RootDir are forbidden
+    })
+}
 
-        let perm = fs::Permissions::from_mode(mode as _);
-        fs::set_permissions(dst, perm)
-    }
+#[cfg(unix)]
+fn set_perms(dst: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
 
-    #[cfg(windows)]
-    fn set_perms(dst: &Path, _mode: u32) -> std::io::Result<()> {
-        let mut perm = fs::metadata(dst)?.permissions();
-        perm.set_readonly(false);
-        fs::set_permissions(dst, perm)
-    }
+    let perm = fs::Permissions::from_mode(mode as _);
+    fs::set_permissions(dst, perm)
+}
+
+#[cfg(windows)]
+fn set_perms(dst: &Path, _mode: u32) -> std::io::Result<()> {
+    let mut perm = fs::metadata(dst)?.permissions();
+    perm.set_readonly(false);
+    fs::set_permissions(dst, perm)
 }
 
 // return Err on file system error
@@ -340,6 +344,87 @@ pub fn unpack_snapshot<A: Read>(
     .map(|_| unpacked_append_vec_map)
 }
 
+/// Unpacks the files of a snapshot archive that is not a tar archive, one
+/// entry at a time, applying the same checks as `unpack_snapshot` to the
+/// entry paths, sizes and counts.
+pub struct SnapshotEntryUnpacker<'a> {
+    ledger_dir: &'a Path,
+    account_paths: &'a [PathBuf],
+    unpacked_append_vec_map: UnpackedAppendVecMap,
+    total_size: u64,
+    total_count: u64,
+}
+
+impl<'a> SnapshotEntryUnpacker<'a> {
+    pub fn new(ledger_dir: &'a Path, account_paths: &'a [PathBuf]) -> Self {
+        assert!(!account_paths.is_empty());
+        Self {
+            ledger_dir,
+            account_paths,
+            unpacked_append_vec_map: UnpackedAppendVecMap::new(),
+            total_size: 0,
+            total_count: 0,
+        }
+    }
+
+    /// Unpacks a regular file of `size` bytes read from `reader`
+    pub fn unpack_entry<R: Read>(&mut self, path: &Path, size: u64, reader: R) -> Result<()> {
+        let path_str = path.display().to_string();
+        let parts = path_parts(path);
+        if parts.clone().any(|p| p.is_none()) {
+            return Err(UnpackError::Archive(format!(
+                "invalid path found: {:?}",
+                path_str
+            )));
+        }
+        let parts: Vec<_> = parts.map(|p| p.unwrap()).collect();
+        if !is_valid_snapshot_archive_entry(&parts, Regular) {
+            return Err(UnpackError::Archive(format!(
+                "extra entry found: {:?}",
+                path_str
+            )));
+        }
+
+        // Entries are written out in full, their apparent and actual sizes are the same
+        self.total_size = checked_total_size_sum(
+            self.total_size,
+            size,
+            MAX_SNAPSHOT_ARCHIVE_UNPACKED_ACTUAL_SIZE,
+        )?;
+        self.total_count =
+            checked_total_count_increment(self.total_count, MAX_SNAPSHOT_ARCHIVE_UNPACKED_COUNT)?;
+
+        let unpack_dir = match parts.as_slice() {
+            ["accounts", file] => {
+                // Randomly distribute the accounts files about the available `account_paths`,
+                let path_index = thread_rng().gen_range(0, self.account_paths.len());
+                let account_path = &self.account_paths[path_index];
+                self.unpacked_append_vec_map
+                    .insert(file.to_string(), account_path.join("accounts").join(file));
+                account_path.as_path()
+            }
+            _ => self.ledger_dir,
+        };
+        let target = match sanitize_path(path, unpack_dir)? {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+
+        // Read at most one byte more than announced, to detect longer entries
+        // without unpacking them in full
+        let mut file = File::create(&target)?;
+        let unpacked_size = io::copy(&mut reader.take(size.saturating_add(1)), &mut file)?;
+        check_unpack_result(unpacked_size == size, path_str)?;
+        set_perms(&target, 0o644)?;
+        Ok(())
+    }
+
+    pub fn finish(self) -> UnpackedAppendVecMap {
+        info!("unpacked {} entries total", self.total_count);
+        self.unpacked_append_vec_map
+    }
+}
+
 fn all_digits(v: &str) -> bool {
     if v.is_empty() {
         return false;
@@ -934,4 +1019,52 @@ mod tests {
                 if message == "too many files in snapshot: 1000000000000"
         );
     }
+
+    #[test]
+    fn test_snapshot_entry_unpacker() {
+        let ledger_dir = tempfile::TempDir::new().unwrap();
+        let account_dir = tempfile::TempDir::new().unwrap();
+        let account_paths = vec![account_dir.path().to_path_buf()];
+        let mut unpacker = SnapshotEntryUnpacker::new(ledger_dir.path(), &account_paths);
+
+        unpacker
+            .unpack_entry(Path::new("version"), 5, &b"1.2.0"[..])
+            .unwrap();
+        unpacker
+            .unpack_entry(Path::new("accounts/3.4"), 3, &[1u8, 2, 3][..])
+            .unwrap();
+
+        // Paths are checked like tar entries
+        assert_matches!(
+            unpacker.unpack_entry(Path::new("../version"), 1, &[0u8][..]),
+            Err(UnpackError::Archive(ref message)) if message == "invalid path found: \"../version\""
+        );
+        assert_matches!(
+            unpacker.unpack_entry(Path::new("accounts/foo"), 1, &[0u8][..]),
+            Err(UnpackError::Archive(ref message)) if message == "extra entry found: \"accounts/foo\""
+        );
+        // Entries shorter or longer than announced are rejected
+        assert_matches!(
+            unpacker.unpack_entry(Path::new("snapshots/3/3"), 4, &[0u8; 3][..]),
+            Err(UnpackError::Archive(_))
+        );
+        assert_matches!(
+            unpacker.unpack_entry(Path::new("snapshots/3/3"), 2, &[0u8; 3][..]),
+            Err(UnpackError::Archive(_))
+        );
+        // Sizes are checked before unpacking
+        assert_matches!(
+            unpacker.unpack_entry(Path::new("snapshots/status_cache"), u64::MAX, &[0u8][..]),
+            Err(UnpackError::Archive(ref message)) if message.starts_with("too large archive")
+        );
+
+        let unpacked_append_vec_map = unpacker.finish();
+        assert_eq!(
+            fs::read_to_string(ledger_dir.path().join("version")).unwrap(),
+            "1.2.0"
+        );
+        let account_file = account_dir.path().join("accounts").join("3.4");
+        assert_eq!(unpacked_append_vec_map.get("3.4"), Some(&account_file));
+        assert_eq!(fs::read(account_file).unwrap(), vec![1, 2, 3]);
+    }
 }
@@ -12,12 +12,14 @@ pub mod accounts_background_service;
 pub mod accounts_cache;
 pub mod accounts_db;
 pub mod accounts_hash;
+pub mod accounts_hash_checkpoint;
 pub mod accounts_index;
 pub mod accounts_index_storage;
 pub mod accounts_update_notifier_interface;
 mod active_stats;
 pub mod ancestors;
 pub mod append_vec;
+pub mod archive_framing;
 pub mod bank;
 pub mod bank_client;
 pub mod bank_forks;
@@ -30,6 +32,7 @@ pub mod builtins;
 pub mod cache_hash_data;
 pub mod cache_hash_data_stats;
 pub mod commitment;
+pub mod compaction_policy;
 pub mod contains;
 pub mod cost_model;
 pub mod cost_tracker;
@@ -47,7 +50,7 @@ pub mod message_processor;
 pub mod non_circulating_supply;
 mod nonce_keyed_account;
 mod pubkey_bins;
-mod read_only_accounts_cache;
+pub mod read_only_accounts_cache;
 pub mod rent_collector;
 mod rolling_bit_field;
 pub mod runtime_config;
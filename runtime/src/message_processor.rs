@@ -79,6 +79,9 @@ impl MessageProcessor {
             lamports_per_signature,
             current_accounts_data_len,
         );
+        if timings.details.is_program_profiling_enabled() {
+            invoke_context.timings.enable_program_profiling();
+        }
 
         debug_assert_eq!(program_indices.len(), message.instructions().len());
         for (instruction_index, ((program_id, instruction), program_indices)) in message
@@ -144,6 +147,12 @@ impl MessageProcessor {
                 result.is_err(),
             );
             timings.details.accumulate(&invoke_context.timings);
+            // The invoke context timings are accumulated after every
+            // instruction, so its program profiles must be reset to only be
+            // counted once
+            if let Some(profiles) = invoke_context.timings.per_program_profiles.as_mut() {
+                profiles.clear();
+            }
             saturating_add_assign!(
                 timings.execute_accessories.process_instructions.total_us,
                 time.as_us()
@@ -8,9 +8,14 @@ use {
         clock::Slot,
         pubkey::Pubkey,
     },
-    std::sync::{
-        atomic::{AtomicU64, AtomicUsize, Ordering},
-        Mutex,
+    std::{
+        collections::{BTreeSet, HashMap},
+        fmt::Debug,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            Mutex,
+        },
     },
 };
 
@@ -19,42 +24,349 @@ const CACHE_ENTRY_SIZE: usize =
 
 type ReadOnlyCacheKey = (Pubkey, Slot);
 
+// Identifies one insertion of an entry, so that an eviction policy cannot mistake it
+// for a later insertion of the same key.
+type EntryToken = u64;
+
 #[derive(Debug)]
 struct ReadOnlyAccountCacheEntry {
     account: AccountSharedData,
-    index: Index, // Index of the entry in the eviction queue.
+    token: EntryToken, // Identifies the entry in the eviction policy.
+}
+
+/// Which entries the read-only accounts cache evicts once it is over its size budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadOnlyCacheEviction {
+    /// Evict the least recently used entry
+    Lru,
+    /// Entries start out on probation and are protected once they are loaded again.
+    /// Entries on probation are evicted first, so accounts that are only loaded once
+    /// cannot flush out the accounts that are loaded over and over.
+    SegmentedLru,
+    /// Evict the entry with the fewest loads per byte, so a large account has to be
+    /// loaded proportionally more often than a small one to stay cached
+    SizeAwareLfu,
+}
+
+impl Default for ReadOnlyCacheEviction {
+    fn default() -> Self {
+        Self::Lru
+    }
+}
+
+impl FromStr for ReadOnlyCacheEviction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lru" => Ok(Self::Lru),
+            "segmented-lru" => Ok(Self::SegmentedLru),
+            "size-aware-lfu" => Ok(Self::SizeAwareLfu),
+            _ => Err(format!("unknown read-only cache eviction policy: {}", s)),
+        }
+    }
+}
+
+impl ReadOnlyCacheEviction {
+    pub const VARIANTS: &'static [&'static str] = &["lru", "segmented-lru", "size-aware-lfu"];
+
+    fn new_policy(&self, max_data_size: usize) -> Box<dyn EvictionPolicy> {
+        match self {
+            Self::Lru => Box::new(LruEvictionPolicy::default()),
+            Self::SegmentedLru => Box::new(SegmentedLruEvictionPolicy::new(max_data_size)),
+            Self::SizeAwareLfu => Box::new(SizeAwareLfuEvictionPolicy::default()),
+        }
+    }
+}
+
+/// Tracks the entries of the cache to decide which one to evict next.
+/// Entries are identified by the token they were inserted with, as another
+/// thread may already have inserted a new entry for the same key.
+trait EvictionPolicy: Debug + Send {
+    /// The entry `token` for `key` was added to the cache, taking up `size` bytes
+    fn insert(&mut self, key: ReadOnlyCacheKey, token: EntryToken, size: usize);
+    /// The entry `token` was loaded from the cache
+    fn touch(&mut self, token: EntryToken);
+    /// The entry `token` was removed from the cache
+    fn remove(&mut self, token: EntryToken);
+    /// The entry to evict next
+    fn victim(&self) -> Option<(ReadOnlyCacheKey, EntryToken)>;
+}
+
+#[derive(Debug, Default)]
+struct LruEvictionPolicy {
+    // Entries are added to the end of the queue and moved back to the end each time
+    // they are loaded, so the front of the queue is the least recently used entry.
+    queue: IndexList<(ReadOnlyCacheKey, EntryToken)>,
+    indexes: HashMap<EntryToken, Index>,
+}
+
+impl EvictionPolicy for LruEvictionPolicy {
+    fn insert(&mut self, key: ReadOnlyCacheKey, token: EntryToken, _size: usize) {
+        let index = self.queue.insert_last((key, token));
+        self.indexes.insert(token, index);
+    }
+
+    fn touch(&mut self, token: EntryToken) {
+        if let Some(index) = self.indexes.get_mut(&token) {
+            if let Some(entry) = self.queue.remove(*index) {
+                *index = self.queue.insert_last(entry);
+            }
+        }
+    }
+
+    fn remove(&mut self, token: EntryToken) {
+        if let Some(index) = self.indexes.remove(&token) {
+            self.queue.remove(index);
+        }
+    }
+
+    fn victim(&self) -> Option<(ReadOnlyCacheKey, EntryToken)> {
+        self.queue.get_first().copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Segment {
+    Probation,
+    Protected,
+}
+
+#[derive(Debug)]
+struct SegmentedLruEvictionPolicy {
+    probation: IndexList<(ReadOnlyCacheKey, EntryToken)>,
+    protected: IndexList<(ReadOnlyCacheKey, EntryToken)>,
+    entries: HashMap<EntryToken, (Segment, Index, usize)>,
+    protected_data_size: usize,
+    // Beyond this, the least recently used protected entries go back on probation
+    max_protected_data_size: usize,
+}
+
+impl SegmentedLruEvictionPolicy {
+    // Share of the cache's byte budget that protected entries may take up
+    const PROTECTED_RATIO: f64 = 0.8;
+
+    fn new(max_data_size: usize) -> Self {
+        Self {
+            probation: IndexList::default(),
+            protected: IndexList::default(),
+            entries: HashMap::default(),
+            protected_data_size: 0,
+            max_protected_data_size: (max_data_size as f64 * Self::PROTECTED_RATIO) as usize,
+        }
+    }
+
+    fn queue(&mut self, segment: Segment) -> &mut IndexList<(ReadOnlyCacheKey, EntryToken)> {
+        match segment {
+            Segment::Probation => &mut self.probation,
+            Segment::Protected => &mut self.protected,
+        }
+    }
+}
+
+impl EvictionPolicy for SegmentedLruEvictionPolicy {
+    fn insert(&mut self, key: ReadOnlyCacheKey, token: EntryToken, size: usize) {
+        let index = self.probation.insert_last((key, token));
+        self.entries
+            .insert(token, (Segment::Probation, index, size));
+    }
+
+    fn touch(&mut self, token: EntryToken) {
+        let (segment, index, size) = match self.entries.get(&token) {
+            None => return,
+            Some(entry) => *entry,
+        };
+        let entry = match self.queue(segment).remove(index) {
+            None => return,
+            Some(entry) => entry,
+        };
+        let index = self.protected.insert_last(entry);
+        self.entries
+            .insert(token, (Segment::Protected, index, size));
+        if segment == Segment::Probation {
+            self.protected_data_size += size;
+        }
+        // Demote the least recently used protected entries back to probation
+        while self.protected_data_size > self.max_protected_data_size {
+            let demoted = match self.protected.remove_first() {
+                None => break,
+                Some(demoted) => demoted,
+            };
+            let index = self.probation.insert_last(demoted);
+            let entry = self.entries.get_mut(&demoted.1).unwrap();
+            self.protected_data_size -= entry.2;
+            *entry = (Segment::Probation, index, entry.2);
+        }
+    }
+
+    fn remove(&mut self, token: EntryToken) {
+        if let Some((segment, index, size)) = self.entries.remove(&token) {
+            self.queue(segment).remove(index);
+            if segment == Segment::Protected {
+                self.protected_data_size -= size;
+            }
+        }
+    }
+
+    fn victim(&self) -> Option<(ReadOnlyCacheKey, EntryToken)> {
+        self.probation
+            .get_first()
+            .or_else(|| self.protected.get_first())
+            .copied()
+    }
+}
+
+#[derive(Debug, Default)]
+struct SizeAwareLfuEvictionPolicy {
+    // Ordered by (loads per byte, last use), so the first entry is the one to evict
+    order: BTreeSet<(u64, u64, ReadOnlyCacheKey, EntryToken)>,
+    entries: HashMap<EntryToken, SizeAwareLfuEntry>,
+    // Increments with each insert or load, to break ties in favor of recently used entries
+    clock: u64,
+    // When `clock` gets here, the load counts are halved
+    next_decay: u64,
+}
+
+#[derive(Debug)]
+struct SizeAwareLfuEntry {
+    key: ReadOnlyCacheKey,
+    loads: u64,
+    size: usize,
+    priority: u64,
+    last_use: u64,
+}
+
+impl SizeAwareLfuEntry {
+    fn order_key(&self, token: EntryToken) -> (u64, u64, ReadOnlyCacheKey, EntryToken) {
+        (self.priority, self.last_use, self.key, token)
+    }
+}
+
+impl SizeAwareLfuEvictionPolicy {
+    // Load counts saturate here, so that `priority()` cannot overflow
+    const MAX_LOADS: u64 = 1024;
+    // The load counts are halved after this many inserts and loads per entry, or after
+    // `MIN_DECAY_INTERVAL` if that is longer. Entries that were hot long ago lose their
+    // lead this way, and can be evicted once they are no longer loaded.
+    const DECAY_INTERVAL_PER_ENTRY: u64 = 8;
+    const MIN_DECAY_INTERVAL: u64 = 1024;
+
+    fn priority(loads: u64, size: usize) -> u64 {
+        (loads << 32) / (size.max(1) as u64)
+    }
+
+    fn next_clock(&mut self) -> u64 {
+        self.clock += 1;
+        if self.clock >= self.next_decay {
+            self.decay();
+        }
+        self.clock
+    }
+
+    fn decay(&mut self) {
+        self.order.clear();
+        for (token, entry) in self.entries.iter_mut() {
+            entry.loads = (entry.loads / 2).max(1);
+            entry.priority = Self::priority(entry.loads, entry.size);
+            self.order.insert(entry.order_key(*token));
+        }
+        let interval = (self.entries.len() as u64 * Self::DECAY_INTERVAL_PER_ENTRY)
+            .max(Self::MIN_DECAY_INTERVAL);
+        self.next_decay = self.clock + interval;
+    }
+}
+
+impl EvictionPolicy for SizeAwareLfuEvictionPolicy {
+    fn insert(&mut self, key: ReadOnlyCacheKey, token: EntryToken, size: usize) {
+        let last_use = self.next_clock();
+        let entry = SizeAwareLfuEntry {
+            key,
+            loads: 1,
+            size,
+            priority: Self::priority(1, size),
+            last_use,
+        };
+        self.order.insert(entry.order_key(token));
+        self.entries.insert(token, entry);
+    }
+
+    fn touch(&mut self, token: EntryToken) {
+        let last_use = self.next_clock();
+        if let Some(entry) = self.entries.get_mut(&token) {
+            self.order.remove(&entry.order_key(token));
+            entry.loads = (entry.loads + 1).min(Self::MAX_LOADS);
+            entry.priority = Self::priority(entry.loads, entry.size);
+            entry.last_use = last_use;
+            self.order.insert(entry.order_key(token));
+        }
+    }
+
+    fn remove(&mut self, token: EntryToken) {
+        if let Some(entry) = self.entries.remove(&token) {
+            self.order.remove(&entry.order_key(token));
+        }
+    }
+
+    fn victim(&self) -> Option<(ReadOnlyCacheKey, EntryToken)> {
+        self.order
+            .iter()
+            .next()
+            .map(|(_, _, key, token)| (*key, *token))
+    }
+}
+
+#[derive(Debug, Default)]
+struct OwnerStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+/// Hit, miss and eviction counts of the accounts owned by one program
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ReadOnlyCacheOwnerStats {
+    pub(crate) owner: Pubkey,
+    pub(crate) hits: u64,
+    pub(crate) misses: u64,
+    pub(crate) evictions: u64,
 }
 
 #[derive(Debug)]
 pub(crate) struct ReadOnlyAccountsCache {
     cache: DashMap<ReadOnlyCacheKey, ReadOnlyAccountCacheEntry>,
-    // When an item is first entered into the cache, it is added to the end of
-    // the queue. Also each time an entry is looked up from the cache it is
-    // moved to the end of the queue. As a result, items in the queue are
-    // always sorted in the order that they have last been accessed. When doing
-    // LRU eviction, cache entries are evicted from the front of the queue.
-    queue: Mutex<IndexList<ReadOnlyCacheKey>>,
+    // Only modified while holding a reference to the affected cache entry,
+    // so that another thread cannot write to the same key.
+    eviction_policy: Mutex<Box<dyn EvictionPolicy>>,
+    // The token of the next entry inserted
+    next_token: AtomicU64,
     max_data_size: usize,
     data_size: AtomicUsize,
     hits: AtomicU64,
     misses: AtomicU64,
+    owner_stats: DashMap<Pubkey, OwnerStats>,
 }
 
 impl ReadOnlyAccountsCache {
     pub(crate) fn new(max_data_size: usize) -> Self {
+        Self::new_with_eviction(max_data_size, ReadOnlyCacheEviction::default())
+    }
+
+    pub(crate) fn new_with_eviction(max_data_size: usize, eviction: ReadOnlyCacheEviction) -> Self {
         Self {
             max_data_size,
             cache: DashMap::default(),
-            queue: Mutex::<IndexList<ReadOnlyCacheKey>>::default(),
+            eviction_policy: Mutex::new(eviction.new_policy(max_data_size)),
+            next_token: AtomicU64::default(),
             data_size: AtomicUsize::default(),
             hits: AtomicU64::default(),
             misses: AtomicU64::default(),
+            owner_stats: DashMap::default(),
         }
     }
 
     pub(crate) fn load(&self, pubkey: Pubkey, slot: Slot) -> Option<AccountSharedData> {
         let key = (pubkey, slot);
-        let mut entry = match self.cache.get_mut(&key) {
+        let entry = match self.cache.get(&key) {
             None => {
                 self.misses.fetch_add(1, Ordering::Relaxed);
                 return None;
@@ -62,14 +374,10 @@ impl ReadOnlyAccountsCache {
             Some(entry) => entry,
         };
         self.hits.fetch_add(1, Ordering::Relaxed);
-        // Move the entry to the end of the queue.
-        // self.queue is modified while holding a reference to the cache entry;
-        // so that another thread cannot write to the same key.
-        {
-            let mut queue = self.queue.lock().unwrap();
-            queue.remove(entry.index);
-            entry.index = queue.insert_last(key);
-        }
+        self.owner_stats(entry.account.owner())
+            .hits
+            .fetch_add(1, Ordering::Relaxed);
+        self.eviction_policy.lock().unwrap().touch(entry.token);
         Some(entry.account.clone())
     }
 
@@ -77,49 +385,76 @@ impl ReadOnlyAccountsCache {
         CACHE_ENTRY_SIZE + account.data().len()
     }
 
+    fn owner_stats(&self, owner: &Pubkey) -> dashmap::mapref::one::Ref<'_, Pubkey, OwnerStats> {
+        self.owner_stats.get(owner).unwrap_or_else(|| {
+            self.owner_stats
+                .entry(*owner)
+                .or_insert_with(OwnerStats::default)
+                .downgrade()
+        })
+    }
+
     pub(crate) fn store(&self, pubkey: Pubkey, slot: Slot, account: AccountSharedData) {
         let key = (pubkey, slot);
         let account_size = self.account_size(&account);
+        let token = self.next_token.fetch_add(1, Ordering::Relaxed);
         self.data_size.fetch_add(account_size, Ordering::Relaxed);
-        // self.queue is modified while holding a reference to the cache entry;
-        // so that another thread cannot write to the same key.
         match self.cache.entry(key) {
             Entry::Vacant(entry) => {
-                // Insert the entry at the end of the queue.
-                let mut queue = self.queue.lock().unwrap();
-                let index = queue.insert_last(key);
-                entry.insert(ReadOnlyAccountCacheEntry { account, index });
+                // Accounts are stored after they were missed in the cache
+                self.owner_stats(account.owner())
+                    .misses
+                    .fetch_add(1, Ordering::Relaxed);
+                self.eviction_policy
+                    .lock()
+                    .unwrap()
+                    .insert(key, token, account_size);
+                entry.insert(ReadOnlyAccountCacheEntry { account, token });
             }
             Entry::Occupied(mut entry) => {
                 let entry = entry.get_mut();
-                let account_size = self.account_size(&entry.account);
-                self.data_size.fetch_sub(account_size, Ordering::Relaxed);
-                entry.account = account;
-                // Move the entry to the end of the queue.
-                let mut queue = self.queue.lock().unwrap();
-                queue.remove(entry.index);
-                entry.index = queue.insert_last(key);
+                let previous_account_size = self.account_size(&entry.account);
+                self.data_size
+                    .fetch_sub(previous_account_size, Ordering::Relaxed);
+                let mut eviction_policy = self.eviction_policy.lock().unwrap();
+                eviction_policy.remove(entry.token);
+                eviction_policy.insert(key, token, account_size);
+                *entry = ReadOnlyAccountCacheEntry { account, token };
             }
         };
-        // Evict entries from the front of the queue.
+        // Evict entries as chosen by the eviction policy.
         while self.data_size.load(Ordering::Relaxed) > self.max_data_size {
-            let (pubkey, slot) = match self.queue.lock().unwrap().get_first() {
+            let (key, token) = match self.eviction_policy.lock().unwrap().victim() {
                 None => break,
-                Some(key) => *key,
+                Some(victim) => victim,
             };
-            self.remove(pubkey, slot);
+            if let Some(account) = self.remove_entry(&key, token) {
+                self.owner_stats(account.owner())
+                    .evictions
+                    .fetch_add(1, Ordering::Relaxed);
+            }
         }
     }
 
     pub(crate) fn remove(&self, pubkey: Pubkey, slot: Slot) -> Option<AccountSharedData> {
         let (_, entry) = self.cache.remove(&(pubkey, slot))?;
-        // self.queue should be modified only after removing the entry from the
-        // cache, so that this is still safe if another thread writes to the
-        // same key.
-        self.queue.lock().unwrap().remove(entry.index);
+        Some(self.removed(entry))
+    }
+
+    /// Remove the entry `token` for `key`, unless it was already replaced
+    fn remove_entry(&self, key: &ReadOnlyCacheKey, token: EntryToken) -> Option<AccountSharedData> {
+        let (_, entry) = self.cache.remove_if(key, |_, entry| entry.token == token)?;
+        Some(self.removed(entry))
+    }
+
+    fn removed(&self, entry: ReadOnlyAccountCacheEntry) -> AccountSharedData {
+        // The eviction policy should be modified only after removing the entry
+        // from the cache. Another thread may have inserted a new entry for the same
+        // key in the meantime, so only the removed entry's token is dropped.
+        self.eviction_policy.lock().unwrap().remove(entry.token);
         let account_size = self.account_size(&entry.account);
         self.data_size.fetch_sub(account_size, Ordering::Relaxed);
-        Some(entry.account)
+        entry.account
     }
 
     pub(crate) fn cache_len(&self) -> usize {
@@ -135,6 +470,27 @@ impl ReadOnlyAccountsCache {
         let misses = self.misses.swap(0, Ordering::Relaxed);
         (hits, misses)
     }
+
+    /// Returns the per owner stats of the `max_owners` owners with the most loads since the last
+    /// call, busiest first
+    pub(crate) fn get_and_reset_owner_stats(
+        &self,
+        max_owners: usize,
+    ) -> Vec<ReadOnlyCacheOwnerStats> {
+        let mut owner_stats = Vec::with_capacity(self.owner_stats.len());
+        self.owner_stats.retain(|owner, stats| {
+            owner_stats.push(ReadOnlyCacheOwnerStats {
+                owner: *owner,
+                hits: stats.hits.load(Ordering::Relaxed),
+                misses: stats.misses.load(Ordering::Relaxed),
+                evictions: stats.evictions.load(Ordering::Relaxed),
+            });
+            false
+        });
+        owner_stats.sort_unstable_by_key(|stats| std::cmp::Reverse(stats.hits + stats.misses));
+        owner_stats.truncate(max_owners);
+        owner_stats
+    }
 }
 
 #[cfg(test)]
@@ -277,4 +633,196 @@ mod tests {
             );
         }
     }
+
+    fn new_account(data_size: usize, owner: Pubkey) -> AccountSharedData {
+        AccountSharedData::from(Account {
+            data: vec![0; data_size],
+            owner,
+            ..Account::default()
+        })
+    }
+
+    #[test]
+    fn test_read_only_cache_eviction_from_str() {
+        for name in ReadOnlyCacheEviction::VARIANTS {
+            assert!(name.parse::<ReadOnlyCacheEviction>().is_ok());
+        }
+        assert_eq!(
+            "segmented-lru".parse::<ReadOnlyCacheEviction>(),
+            Ok(ReadOnlyCacheEviction::SegmentedLru)
+        );
+        assert!("mru".parse::<ReadOnlyCacheEviction>().is_err());
+    }
+
+    #[test]
+    fn test_read_only_accounts_cache_segmented_lru() {
+        let data_size = 100;
+        let per_account_size = CACHE_ENTRY_SIZE + data_size;
+        let cache = ReadOnlyAccountsCache::new_with_eviction(
+            per_account_size * 4,
+            ReadOnlyCacheEviction::SegmentedLru,
+        );
+        let slot = 0;
+        let hot = Pubkey::new_unique();
+        cache.store(hot, slot, new_account(data_size, Pubkey::default()));
+        assert!(cache.load(hot, slot).is_some());
+        // a scan over more accounts than fit in the cache only churns the probation segment
+        let scanned: Vec<_> = repeat_with(Pubkey::new_unique).take(10).collect();
+        for pubkey in &scanned {
+            cache.store(*pubkey, slot, new_account(data_size, Pubkey::default()));
+        }
+        assert_eq!(cache.cache_len(), 4);
+        assert!(cache.load(hot, slot).is_some());
+        assert!(cache.load(scanned[0], slot).is_none());
+        assert!(cache.load(scanned[9], slot).is_some());
+
+        // with plain lru the scan evicts the hot account
+        let cache = ReadOnlyAccountsCache::new(per_account_size * 4);
+        cache.store(hot, slot, new_account(data_size, Pubkey::default()));
+        assert!(cache.load(hot, slot).is_some());
+        for pubkey in &scanned {
+            cache.store(*pubkey, slot, new_account(data_size, Pubkey::default()));
+        }
+        assert!(cache.load(hot, slot).is_none());
+    }
+
+    #[test]
+    fn test_read_only_accounts_cache_segmented_lru_demotes() {
+        let data_size = 100;
+        let per_account_size = CACHE_ENTRY_SIZE + data_size;
+        let mut policy = SegmentedLruEvictionPolicy::new(per_account_size * 5);
+        let entries: Vec<_> = (0..5)
+            .map(|token| ((Pubkey::new_unique(), 0), token))
+            .collect();
+        for (key, token) in &entries {
+            policy.insert(*key, *token, per_account_size);
+            policy.touch(*token);
+        }
+        // only 4 entries fit in the protected segment, so the oldest was demoted
+        assert_eq!(policy.protected_data_size, per_account_size * 4);
+        assert_eq!(policy.victim(), Some(entries[0]));
+        policy.remove(entries[0].1);
+        assert_eq!(policy.victim(), Some(entries[1]));
+        policy.remove(entries[1].1);
+        assert_eq!(policy.protected_data_size, per_account_size * 3);
+    }
+
+    #[test]
+    fn test_read_only_accounts_cache_size_aware_lfu_decay() {
+        let mut policy = SizeAwareLfuEvictionPolicy::default();
+        let old = ((Pubkey::new_unique(), 0), 0);
+        let new = ((Pubkey::new_unique(), 0), 1);
+        policy.insert(old.0, old.1, 100);
+        for _ in 0..SizeAwareLfuEvictionPolicy::MAX_LOADS {
+            policy.touch(old.1);
+        }
+        policy.insert(new.0, new.1, 100);
+        for _ in 0..10 {
+            policy.touch(new.1);
+        }
+        assert_eq!(policy.victim(), Some(new));
+
+        // once `old` is no longer loaded, the decay lets `new` overtake it
+        for _ in 0..SizeAwareLfuEvictionPolicy::MIN_DECAY_INTERVAL * 4 {
+            policy.touch(new.1);
+        }
+        assert_eq!(policy.victim(), Some(old));
+    }
+
+    #[test]
+    fn test_read_only_accounts_cache_remove_races_store() {
+        let data_size = 100;
+        let cache = ReadOnlyAccountsCache::new((CACHE_ENTRY_SIZE + data_size) * 2);
+        let key = (Pubkey::new_unique(), 0);
+        cache.store(key.0, key.1, new_account(data_size, Pubkey::default()));
+
+        // `remove()` takes the entry out of the cache, then another thread stores the same
+        // key before `remove()` updates the eviction policy
+        let (_, removed) = cache.cache.remove(&key).unwrap();
+        cache.store(key.0, key.1, new_account(data_size, Pubkey::default()));
+        let token = cache.cache.get(&key).unwrap().token;
+        assert_ne!(token, removed.token);
+        cache.removed(removed);
+
+        // the new entry is still tracked, so it can be evicted
+        assert_eq!(
+            cache.eviction_policy.lock().unwrap().victim(),
+            Some((key, token))
+        );
+        assert_eq!(cache.data_size(), CACHE_ENTRY_SIZE + data_size);
+        assert!(cache.load(key.0, key.1).is_some());
+    }
+
+    #[test]
+    fn test_read_only_accounts_cache_size_aware_lfu() {
+        let small_data_size = 100;
+        let large_data_size = 10 * 1024;
+        let cache = ReadOnlyAccountsCache::new_with_eviction(
+            CACHE_ENTRY_SIZE * 4 + large_data_size + small_data_size * 3,
+            ReadOnlyCacheEviction::SizeAwareLfu,
+        );
+        let slot = 0;
+        let large = Pubkey::new_unique();
+        cache.store(large, slot, new_account(large_data_size, Pubkey::default()));
+        // loaded more often than any small account, but not per byte
+        for _ in 0..5 {
+            assert!(cache.load(large, slot).is_some());
+        }
+        let small: Vec<_> = repeat_with(Pubkey::new_unique).take(4).collect();
+        for pubkey in &small {
+            cache.store(
+                *pubkey,
+                slot,
+                new_account(small_data_size, Pubkey::default()),
+            );
+            assert!(cache.load(*pubkey, slot).is_some());
+        }
+        assert!(cache.load(large, slot).is_none());
+        for pubkey in &small {
+            assert!(cache.load(*pubkey, slot).is_some());
+        }
+    }
+
+    #[test]
+    fn test_read_only_accounts_cache_owner_stats() {
+        let data_size = 100;
+        let cache = ReadOnlyAccountsCache::new((CACHE_ENTRY_SIZE + data_size) * 2);
+        let slot = 0;
+        let owner1 = Pubkey::new_unique();
+        let owner2 = Pubkey::new_unique();
+        let keys: Vec<_> = repeat_with(Pubkey::new_unique).take(3).collect();
+        cache.store(keys[0], slot, new_account(data_size, owner1));
+        cache.store(keys[1], slot, new_account(data_size, owner1));
+        assert!(cache.load(keys[0], slot).is_some());
+        assert!(cache.load(keys[0], slot).is_some());
+        // evicts keys[1]
+        cache.store(keys[2], slot, new_account(data_size, owner2));
+        // invalidation is not an eviction
+        cache.remove(keys[2], slot);
+        assert_eq!(
+            cache.get_and_reset_owner_stats(10),
+            vec![
+                ReadOnlyCacheOwnerStats {
+                    owner: owner1,
+                    hits: 2,
+                    misses: 2,
+                    evictions: 1,
+                },
+                ReadOnlyCacheOwnerStats {
+                    owner: owner2,
+                    hits: 0,
+                    misses: 1,
+                    evictions: 0,
+                },
+            ]
+        );
+        assert!(cache.get_and_reset_owner_stats(10).is_empty());
+
+        cache.store(keys[1], slot, new_account(data_size, owner2));
+        assert!(cache.load(keys[0], slot).is_some());
+        assert!(cache.load(keys[0], slot).is_some());
+        let owner_stats = cache.get_and_reset_owner_stats(1);
+        assert_eq!(owner_stats.len(), 1);
+        assert_eq!(owner_stats[0].owner, owner1);
+    }
 }
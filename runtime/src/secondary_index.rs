@@ -1,10 +1,13 @@
 use {
     dashmap::{mapref::entry::Entry::Occupied, DashMap},
     log::*,
-    solana_sdk::{pubkey::Pubkey, timing::AtomicInterval},
+    solana_bucket_map::bucket_map::{BucketMap, BucketMapConfig},
+    solana_sdk::{hash::hashv, pubkey::Pubkey, timing::AtomicInterval},
     std::{
-        collections::HashSet,
+        collections::{HashMap, HashSet},
         fmt::Debug,
+        ops::{RangeFull, RangeInclusive},
+        path::PathBuf,
         sync::{
             atomic::{AtomicU64, Ordering},
             RwLock,
@@ -18,6 +21,10 @@ use {
 // though we are running some key existence checks.
 pub type SecondaryReverseIndexEntry = RwLock<Vec<Pubkey>>;
 
+// Number of buckets in each of the disk-backed maps of a secondary index.
+// Looking up a key reads a whole bucket, so more buckets make lookups cheaper but use more files.
+const DISK_INDEX_BINS: usize = 1024;
+
 pub trait SecondaryIndexEntry: Debug {
     fn insert_if_not_exists(&self, key: &Pubkey, inner_keys_count: &AtomicU64);
     // Removes a value from the set. Returns whether the value was present in the set.
@@ -99,12 +106,188 @@ impl SecondaryIndexEntry for RwLockSecondaryIndexEntry {
     }
 }
 
+/// The disk-backed equivalent of `SecondaryIndex::index` and `SecondaryIndex::reverse_index`.
+///
+/// `index` holds one entry per (key, inner key) pair, so adding or removing a pair costs the same
+/// however many inner keys the key has. A pair is stored at `pair_location()`, which starts with the
+/// leading bytes of its key, so all the pairs of a key are in one bucket and `get()` only reads that
+/// bucket. Each entry is a list of flattened pairs, in case two pairs share a location.
+///
+/// `reverse_index` maps an inner key to the list of its keys. As these lists are almost always one key
+/// long (see `SecondaryReverseIndexEntry`), they are rewritten on every change.
+///
+/// `BucketMap` erases its drives when it is created and when it is dropped, so nothing persists across
+/// restarts: the index is rebuilt at every startup, and it saves memory, not startup time.
+#[derive(Debug)]
+struct SecondaryIndexDisk {
+    index: BucketMap<Pubkey>,
+    reverse_index: BucketMap<Pubkey>,
+}
+
+impl SecondaryIndexDisk {
+    /// Number of leading bytes of a key that start the location of each of its pairs
+    const KEY_PREFIX_LEN: usize = 16;
+
+    fn new(drives: &[PathBuf]) -> Self {
+        let new_map = |name: &str| {
+            let mut config = BucketMapConfig::new(DISK_INDEX_BINS);
+            config.drives = Some(drives.iter().map(|drive| drive.join(name)).collect());
+            BucketMap::new(config)
+        };
+        Self {
+            index: new_map("index"),
+            reverse_index: new_map("reverse_index"),
+        }
+    }
+
+    /// Where the pair (`key`, `inner_key`) is stored in `index`
+    fn pair_location(key: &Pubkey, inner_key: &Pubkey) -> Pubkey {
+        let mut location = [0; 32];
+        location[..Self::KEY_PREFIX_LEN].copy_from_slice(&key.as_ref()[..Self::KEY_PREFIX_LEN]);
+        location[Self::KEY_PREFIX_LEN..].copy_from_slice(
+            &hashv(&[key.as_ref(), inner_key.as_ref()]).as_ref()[Self::KEY_PREFIX_LEN..],
+        );
+        Pubkey::new_from_array(location)
+    }
+
+    /// The range of locations holding the pairs of `key`
+    fn key_locations(key: &Pubkey) -> RangeInclusive<Pubkey> {
+        let mut first = [0; 32];
+        first[..Self::KEY_PREFIX_LEN].copy_from_slice(&key.as_ref()[..Self::KEY_PREFIX_LEN]);
+        let mut last = [u8::MAX; 32];
+        last[..Self::KEY_PREFIX_LEN].copy_from_slice(&key.as_ref()[..Self::KEY_PREFIX_LEN]);
+        Pubkey::new_from_array(first)..=Pubkey::new_from_array(last)
+    }
+
+    fn get(&self, key: &Pubkey) -> Vec<Pubkey> {
+        // the locations of `key` all start with the same bytes, so they are in the bucket of `key`
+        self.index
+            .get_bucket(key)
+            .items_in_range(&Some(&Self::key_locations(key)))
+            .into_iter()
+            .flat_map(|item| {
+                item.slot_list
+                    .chunks_exact(2)
+                    .filter(|pair| pair[0] == *key)
+                    .map(|pair| pair[1])
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Add the pair (`key`, `inner_key`). Returns whether it was newly added.
+    fn insert(&self, key: &Pubkey, inner_key: &Pubkey) -> bool {
+        let location = Self::pair_location(key, inner_key);
+        let pair = [*key, *inner_key];
+        let contains_pair = |pairs: &[Pubkey]| pairs.chunks_exact(2).any(|other| other == pair);
+        if self
+            .index
+            .read_value(&location)
+            .map(|(pairs, _ref_count)| contains_pair(&pairs))
+            .unwrap_or_default()
+        {
+            return false;
+        }
+        let mut inserted = false;
+        self.index.update(&location, |current| {
+            let mut pairs = current
+                .map(|(pairs, _ref_count)| pairs.to_vec())
+                .unwrap_or_default();
+            // Other threads may have interleaved writes to this location, so check again
+            if !contains_pair(&pairs) {
+                pairs.extend_from_slice(&pair);
+                inserted = true;
+            }
+            Some((pairs, 0))
+        });
+        inserted
+    }
+
+    /// Remove the pair (`key`, `inner_key`). Returns whether it was present.
+    fn remove(&self, key: &Pubkey, inner_key: &Pubkey) -> bool {
+        let pair = [*key, *inner_key];
+        let mut removed = false;
+        self.index
+            .update(&Self::pair_location(key, inner_key), |current| {
+                let pairs = current?.0;
+                let remaining = pairs
+                    .chunks_exact(2)
+                    .filter(|other| *other != pair)
+                    .flatten()
+                    .copied()
+                    .collect::<Vec<_>>();
+                removed = remaining.len() != pairs.len();
+                (!remaining.is_empty()).then(|| (remaining, 0))
+            });
+        removed
+    }
+
+    /// Add `key` to the keys of `inner_key` in the reverse index
+    fn insert_reverse(&self, inner_key: &Pubkey, key: &Pubkey) {
+        let has_key = self
+            .reverse_index
+            .read_value(inner_key)
+            .map(|(keys, _ref_count)| keys.contains(key))
+            .unwrap_or_default();
+        if has_key {
+            return;
+        }
+        self.reverse_index.update(inner_key, |current| {
+            let mut keys = current
+                .map(|(keys, _ref_count)| keys.to_vec())
+                .unwrap_or_default();
+            // Other threads may have interleaved writes to this `inner_key`, so check again
+            if !keys.contains(key) {
+                keys.push(*key);
+            }
+            Some((keys, 0))
+        });
+    }
+
+    /// Remove `inner_key` from the reverse index and return its keys
+    fn take_reverse(&self, inner_key: &Pubkey) -> Vec<Pubkey> {
+        let mut taken = vec![];
+        self.reverse_index.update(inner_key, |current| {
+            if let Some((keys, _ref_count)) = current {
+                taken = keys.to_vec();
+            }
+            None
+        });
+        taken
+    }
+
+    fn len(map: &BucketMap<Pubkey>) -> usize {
+        (0..map.num_buckets())
+            .map(|ix| map.get_bucket_from_index(ix).bucket_len() as usize)
+            .sum()
+    }
+
+    /// The number of inner keys of each key
+    fn key_lens(&self) -> HashMap<Pubkey, usize> {
+        let mut key_lens = HashMap::new();
+        (0..self.index.num_buckets())
+            .flat_map(|ix| {
+                self.index
+                    .get_bucket_from_index(ix)
+                    .items_in_range(&None::<&RangeFull>)
+            })
+            .for_each(|item| {
+                item.slot_list.chunks_exact(2).for_each(|pair| {
+                    *key_lens.entry(pair[0]).or_default() += 1;
+                })
+            });
+        key_lens
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct SecondaryIndex<SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send> {
     metrics_name: &'static str,
     // Map from index keys to index values
     pub index: DashMap<Pubkey, SecondaryIndexEntryType>,
     pub reverse_index: DashMap<Pubkey, SecondaryReverseIndexEntry>,
+    // If present, the index lives here on disk and `index` and `reverse_index` stay empty
+    disk: Option<SecondaryIndexDisk>,
     stats: SecondaryIndexStats,
 }
 
@@ -118,7 +301,72 @@ impl<SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send>
         }
     }
 
+    /// Create an index that is stored on disk in `drives` rather than in memory.
+    /// Anything already in `drives` is erased: the disk index does not survive a restart.
+    pub fn new_with_disk(metrics_name: &'static str, drives: &[PathBuf]) -> Self {
+        Self {
+            metrics_name,
+            disk: Some(SecondaryIndexDisk::new(drives)),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_disk_index_enabled(&self) -> bool {
+        self.disk.is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match &self.disk {
+            Some(disk) => SecondaryIndexDisk::len(&disk.index) == 0,
+            None => self.index.is_empty(),
+        }
+    }
+
+    /// The number of keys in the index.
+    /// The disk index does not track its keys, so this counts its (key, inner key) pairs instead.
+    fn num_keys(&self) -> usize {
+        match &self.disk {
+            Some(disk) => SecondaryIndexDisk::len(&disk.index),
+            None => self.index.len(),
+        }
+    }
+
+    fn num_reverse_index_keys(&self) -> usize {
+        match &self.disk {
+            Some(disk) => SecondaryIndexDisk::len(&disk.reverse_index),
+            None => self.reverse_index.len(),
+        }
+    }
+
     pub fn insert(&self, key: &Pubkey, inner_key: &Pubkey) {
+        if let Some(disk) = &self.disk {
+            if disk.insert(key, inner_key) {
+                self.stats.num_inner_keys.fetch_add(1, Ordering::Relaxed);
+            }
+            disk.insert_reverse(inner_key, key);
+        } else {
+            self.insert_in_mem(key, inner_key);
+        }
+
+        if self.stats.last_report.should_update(1000) {
+            datapoint_info!(
+                self.metrics_name,
+                ("num_secondary_keys", self.num_keys() as i64, i64),
+                (
+                    "num_inner_keys",
+                    self.stats.num_inner_keys.load(Ordering::Relaxed) as i64,
+                    i64
+                ),
+                (
+                    "num_reverse_index_keys",
+                    self.num_reverse_index_keys() as i64,
+                    i64
+                ),
+            );
+        }
+    }
+
+    fn insert_in_mem(&self, key: &Pubkey, inner_key: &Pubkey) {
         {
             let pubkeys_map = self.index.get(key).unwrap_or_else(|| {
                 self.index
@@ -146,27 +394,15 @@ impl<SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send>
                 }
             }
         }
-
-        if self.stats.last_report.should_update(1000) {
-            datapoint_info!(
-                self.metrics_name,
-                ("num_secondary_keys", self.index.len() as i64, i64),
-                (
-                    "num_inner_keys",
-                    self.stats.num_inner_keys.load(Ordering::Relaxed) as i64,
-                    i64
-                ),
-                (
-                    "num_reverse_index_keys",
-                    self.reverse_index.len() as i64,
-                    i64
-                ),
-            );
-        }
     }
 
     // Only safe to call from `remove_by_inner_key()` due to asserts
     fn remove_index_entries(&self, outer_key: &Pubkey, removed_inner_key: &Pubkey) {
+        if let Some(disk) = &self.disk {
+            assert!(disk.remove(outer_key, removed_inner_key));
+            return;
+        }
+
         let is_outer_key_empty = {
             let inner_key_map = self
                 .index
@@ -197,7 +433,9 @@ impl<SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send>
 
         // Check if the entry for `inner_key` in the reverse index is empty
         // and can be removed
-        if let Some((_, outer_keys_set)) = self.reverse_index.remove(inner_key) {
+        if let Some(disk) = &self.disk {
+            removed_outer_keys.extend(disk.take_reverse(inner_key));
+        } else if let Some((_, outer_keys_set)) = self.reverse_index.remove(inner_key) {
             for removed_outer_key in outer_keys_set.into_inner().unwrap().into_iter() {
                 removed_outer_keys.insert(removed_outer_key);
             }
@@ -218,7 +456,9 @@ impl<SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send>
     }
 
     pub fn get(&self, key: &Pubkey) -> Vec<Pubkey> {
-        if let Some(inner_keys_map) = self.index.get(key) {
+        if let Some(disk) = &self.disk {
+            disk.get(key)
+        } else if let Some(inner_keys_map) = self.index.get(key) {
             inner_keys_map.keys()
         } else {
             vec![]
@@ -227,11 +467,18 @@ impl<SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send>
 
     /// log top 20 (owner, # accounts) in descending order of # accounts
     pub fn log_contents(&self) {
-        let mut entries = self
-            .index
-            .iter()
-            .map(|entry| (entry.value().len(), *entry.key()))
-            .collect::<Vec<_>>();
+        let mut entries = match &self.disk {
+            Some(disk) => disk
+                .key_lens()
+                .into_iter()
+                .map(|(key, len)| (len, key))
+                .collect::<Vec<_>>(),
+            None => self
+                .index
+                .iter()
+                .map(|entry| (entry.value().len(), *entry.key()))
+                .collect::<Vec<_>>(),
+        };
         entries.sort_unstable();
         entries
             .iter()
@@ -5,9 +5,11 @@ use {
             AccountShrinkThreshold, AccountStorageEntry, AccountsDb, AccountsDbConfig, AppendVecId,
             AtomicAppendVecId, BankHashInfo, IndexGenerationInfo, SnapshotStorage,
         },
+        accounts_hash::{CalcAccountsHashConfig, HashStats},
         accounts_index::AccountSecondaryIndexes,
         accounts_update_notifier_interface::AccountsUpdateNotifier,
-        append_vec::{AppendVec, StoredMetaWriteVersion},
+        ancestors::Ancestors,
+        append_vec::{AppendVec, AppendVecBackend, StoredMetaWriteVersion},
         bank::{Bank, BankFieldsToDeserialize, BankRc},
         blockhash_queue::BlockhashQueue,
         builtins::Builtins,
@@ -15,6 +17,7 @@ use {
         hardened_unpack::UnpackedAppendVecMap,
         rent_collector::RentCollector,
         snapshot_utils::{self, BANK_SNAPSHOT_PRE_FILENAME_EXTENSION},
+        sorted_storages::SortedStorages,
         stakes::Stakes,
     },
     bincode::{self, config::Options, Error},
@@ -27,7 +30,7 @@ use {
         deserialize_utils::default_on_eof,
         epoch_schedule::EpochSchedule,
         fee_calculator::{FeeCalculator, FeeRateGovernor},
-        genesis_config::GenesisConfig,
+        genesis_config::{ClusterType, GenesisConfig},
         hard_forks::HardForks,
         hash::Hash,
         inflation::Inflation,
@@ -78,62 +81,64 @@ struct AccountsDbFields<T>(
 );
 
 /// Helper type to wrap BufReader streams when deserializing and reconstructing from either just a
-/// full snapshot, or both a full and incremental snapshot
+/// full snapshot, or a full snapshot and a chain of incremental snapshots
 pub struct SnapshotStreams<'a, R> {
     pub full_snapshot_stream: &'a mut BufReader<R>,
-    pub incremental_snapshot_stream: Option<&'a mut BufReader<R>>,
+    /// The streams of the incremental snapshots, in chain order
+    pub incremental_snapshot_streams: Vec<&'a mut BufReader<R>>,
 }
 
 /// Helper type to wrap AccountsDbFields when reconstructing AccountsDb from either just a full
-/// snapshot, or both a full and incremental snapshot
+/// snapshot, or a full snapshot and a chain of incremental snapshots
 #[derive(Debug)]
 struct SnapshotAccountsDbFields<T> {
     full_snapshot_accounts_db_fields: AccountsDbFields<T>,
-    incremental_snapshot_accounts_db_fields: Option<AccountsDbFields<T>>,
+    /// The fields of the incremental snapshots, in chain order
+    incremental_snapshot_accounts_db_fields: Vec<AccountsDbFields<T>>,
 }
 
 impl<T> SnapshotAccountsDbFields<T> {
     /// Collapse the SnapshotAccountsDbFields into a single AccountsDbFields.  If there is no
     /// incremental snapshot, this returns the AccountsDbFields from the full snapshot.
-    /// Otherwise, use the AccountsDbFields from the incremental snapshot, and a combination
-    /// of the storages from both the full and incremental snapshots.
+    /// Otherwise, use the AccountsDbFields from the last incremental snapshot, and a combination
+    /// of the storages from the full snapshot and each incremental snapshot of the chain.
     fn collapse_into(self) -> Result<AccountsDbFields<T>, Error> {
-        match self.incremental_snapshot_accounts_db_fields {
-            None => Ok(self.full_snapshot_accounts_db_fields),
-            Some(AccountsDbFields(
-                mut incremental_snapshot_storages,
+        let mut collapsed = self.full_snapshot_accounts_db_fields;
+        for AccountsDbFields(
+            mut incremental_snapshot_storages,
+            incremental_snapshot_version,
+            incremental_snapshot_slot,
+            incremental_snapshot_bank_hash_info,
+            incremental_snapshot_historical_roots,
+            incremental_snapshot_historical_roots_with_hash,
+        ) in self.incremental_snapshot_accounts_db_fields
+        {
+            let base_snapshot_storages = collapsed.0;
+            let base_snapshot_slot = collapsed.2;
+
+            // filter out incremental snapshot storages with slot <= base snapshot slot
+            incremental_snapshot_storages.retain(|slot, _| *slot > base_snapshot_slot);
+
+            // There must not be any overlap in the slots of storages between the base snapshot and the incremental snapshot
+            incremental_snapshot_storages
+                .iter()
+                .all(|storage_entry| !base_snapshot_storages.contains_key(storage_entry.0)).then(|| ()).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "Snapshots are incompatible: There are storages for the same slot in both the base snapshot and the incremental snapshot!")
+                })?;
+
+            let mut combined_storages = base_snapshot_storages;
+            combined_storages.extend(incremental_snapshot_storages.into_iter());
+
+            collapsed = AccountsDbFields(
+                combined_storages,
                 incremental_snapshot_version,
                 incremental_snapshot_slot,
                 incremental_snapshot_bank_hash_info,
                 incremental_snapshot_historical_roots,
                 incremental_snapshot_historical_roots_with_hash,
-            )) => {
-                let full_snapshot_storages = self.full_snapshot_accounts_db_fields.0;
-                let full_snapshot_slot = self.full_snapshot_accounts_db_fields.2;
-
-                // filter out incremental snapshot storages with slot <= full snapshot slot
-                incremental_snapshot_storages.retain(|slot, _| *slot > full_snapshot_slot);
-
-                // There must not be any overlap in the slots of storages between the full snapshot and the incremental snapshot
-                incremental_snapshot_storages
-                    .iter()
-                    .all(|storage_entry| !full_snapshot_storages.contains_key(storage_entry.0)).then(|| ()).ok_or_else(|| {
-                        io::Error::new(io::ErrorKind::InvalidData, "Snapshots are incompatible: There are storages for the same slot in both the full snapshot and the incremental snapshot!")
-                    })?;
-
-                let mut combined_storages = full_snapshot_storages;
-                combined_storages.extend(incremental_snapshot_storages.into_iter());
-
-                Ok(AccountsDbFields(
-                    combined_storages,
-                    incremental_snapshot_version,
-                    incremental_snapshot_slot,
-                    incremental_snapshot_bank_hash_info,
-                    incremental_snapshot_historical_roots,
-                    incremental_snapshot_historical_roots_with_hash,
-                ))
-            }
+            );
         }
+        Ok(collapsed)
     }
 }
 
@@ -242,16 +247,16 @@ where
         ($style:ident) => {{
             let (full_snapshot_bank_fields, full_snapshot_accounts_db_fields) =
                 $style::Context::deserialize_bank_fields(snapshot_streams.full_snapshot_stream)?;
-            let (incremental_snapshot_bank_fields, incremental_snapshot_accounts_db_fields) =
-                if let Some(ref mut incremental_snapshot_stream) =
-                    snapshot_streams.incremental_snapshot_stream
-                {
-                    let (bank_fields, accounts_db_fields) =
-                        $style::Context::deserialize_bank_fields(incremental_snapshot_stream)?;
-                    (Some(bank_fields), Some(accounts_db_fields))
-                } else {
-                    (None, None)
-                };
+            let mut incremental_snapshot_bank_fields = None;
+            let mut incremental_snapshot_accounts_db_fields = vec![];
+            for incremental_snapshot_stream in
+                snapshot_streams.incremental_snapshot_streams.iter_mut()
+            {
+                let (bank_fields, accounts_db_fields) =
+                    $style::Context::deserialize_bank_fields(incremental_snapshot_stream)?;
+                incremental_snapshot_bank_fields = Some(bank_fields);
+                incremental_snapshot_accounts_db_fields.push(accounts_db_fields);
+            }
 
             let snapshot_accounts_db_fields = SnapshotAccountsDbFields {
                 full_snapshot_accounts_db_fields,
@@ -285,6 +290,180 @@ where
     })
 }
 
+/// The fields of a snapshot needed to verify it without reconstructing its bank
+pub(crate) struct SnapshotFieldsToVerify {
+    pub(crate) bank_fields: BankFieldsToDeserialize,
+    pub(crate) bank_hash_info: BankHashInfo,
+    /// The id and current length of the storages of each slot
+    storages: HashMap<Slot, Vec<(SerializedAppendVecId, usize)>>,
+    historical_roots: Vec<Slot>,
+    historical_roots_with_hash: Vec<(Slot, Hash)>,
+}
+
+/// The hashes and capitalization computed from the storages of a snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SnapshotAccountsHashes {
+    /// The accounts delta hash of the snapshot's slot
+    pub(crate) accounts_delta_hash: Hash,
+    pub(crate) accounts_hash: Hash,
+    pub(crate) capitalization: u64,
+}
+
+pub(crate) fn fields_to_verify_from_streams<R>(
+    serde_style: SerdeStyle,
+    snapshot_streams: &mut SnapshotStreams<R>,
+) -> std::result::Result<SnapshotFieldsToVerify, Error>
+where
+    R: Read,
+{
+    macro_rules! INTO {
+        ($style:ident) => {{
+            let (full_snapshot_bank_fields, full_snapshot_accounts_db_fields) =
+                $style::Context::deserialize_bank_fields(snapshot_streams.full_snapshot_stream)?;
+            let mut incremental_snapshot_bank_fields = None;
+            let mut incremental_snapshot_accounts_db_fields = vec![];
+            for incremental_snapshot_stream in
+                snapshot_streams.incremental_snapshot_streams.iter_mut()
+            {
+                let (bank_fields, accounts_db_fields) =
+                    $style::Context::deserialize_bank_fields(incremental_snapshot_stream)?;
+                incremental_snapshot_bank_fields = Some(bank_fields);
+                incremental_snapshot_accounts_db_fields.push(accounts_db_fields);
+            }
+
+            let AccountsDbFields(
+                snapshot_storages,
+                _snapshot_version,
+                _snapshot_slot,
+                snapshot_bank_hash_info,
+                snapshot_historical_roots,
+                snapshot_historical_roots_with_hash,
+            ) = SnapshotAccountsDbFields {
+                full_snapshot_accounts_db_fields,
+                incremental_snapshot_accounts_db_fields,
+            }
+            .collapse_into()?;
+
+            Ok(SnapshotFieldsToVerify {
+                bank_fields: incremental_snapshot_bank_fields.unwrap_or(full_snapshot_bank_fields),
+                bank_hash_info: snapshot_bank_hash_info,
+                storages: snapshot_storages
+                    .into_iter()
+                    .map(|(slot, slot_storage)| {
+                        let slot_storage = slot_storage
+                            .iter()
+                            .map(|storage_entry| (storage_entry.id(), storage_entry.current_len()))
+                            .collect();
+                        (slot, slot_storage)
+                    })
+                    .collect(),
+                historical_roots: snapshot_historical_roots,
+                historical_roots_with_hash: snapshot_historical_roots_with_hash,
+            })
+        }};
+    }
+    match serde_style {
+        SerdeStyle::Newer => INTO!(newer),
+    }
+}
+
+impl SnapshotFieldsToVerify {
+    /// Loads the storages of the snapshot, without generating the accounts index, to calculate
+    /// the accounts delta hash of the snapshot's slot, and the accounts hash and capitalization
+    /// the same way they are verified when booting from the snapshot
+    pub(crate) fn calculate_accounts_hashes(
+        &self,
+        account_paths: &[PathBuf],
+        unpacked_append_vec_map: &UnpackedAppendVecMap,
+    ) -> Result<SnapshotAccountsHashes, Error> {
+        // The cluster type is not part of the snapshot, and is not used to calculate hashes
+        let accounts_db = AccountsDb::new_with_config(
+            account_paths.to_vec(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            true,
+            AccountShrinkThreshold::default(),
+            None,
+            None,
+        );
+        reconstruct_historical_roots(
+            &accounts_db,
+            self.historical_roots.clone(),
+            self.historical_roots_with_hash.clone(),
+        );
+
+        let mut slots: Vec<_> = self.storages.keys().copied().collect();
+        slots.sort_unstable();
+        let mut next_append_vec_id = 0;
+        let mut snapshot_storages = Vec::with_capacity(slots.len());
+        for slot in slots {
+            let mut slot_storage = HashMap::new();
+            for (id, current_len) in &self.storages[&slot] {
+                let file_name = AppendVec::file_name(slot, *id);
+                let append_vec_path = unpacked_append_vec_map.get(&file_name).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("{} not found in unpacked append vecs", file_name),
+                    )
+                })?;
+                let (accounts, num_accounts) =
+                    AppendVec::new_from_file(append_vec_path, *current_len)?;
+                slot_storage.insert(
+                    next_append_vec_id,
+                    Arc::new(AccountStorageEntry::new_existing(
+                        slot,
+                        next_append_vec_id,
+                        accounts,
+                        num_accounts,
+                    )),
+                );
+                next_append_vec_id += 1;
+            }
+            if slot_storage.is_empty() {
+                continue;
+            }
+            snapshot_storages.push((slot_storage.values().cloned().collect::<Vec<_>>(), slot));
+            accounts_db
+                .storage
+                .map
+                .insert(slot, Arc::new(RwLock::new(slot_storage)));
+            accounts_db.accounts_index.add_root(slot, false);
+        }
+
+        let ancestors = Ancestors::from(&self.bank_fields.ancestors);
+        let rent_collector = self
+            .bank_fields
+            .rent_collector
+            .clone_with_epoch(self.bank_fields.epoch);
+        let storages = SortedStorages::new_with_slots(
+            snapshot_storages
+                .iter()
+                .map(|(slot_storage, slot)| (slot_storage, *slot)),
+            None,
+            Some(self.bank_fields.slot),
+        );
+        let (accounts_hash, capitalization) = accounts_db
+            .calculate_accounts_hash_without_index(
+                &CalcAccountsHashConfig {
+                    use_bg_thread_pool: false,
+                    check_hash: false,
+                    ancestors: Some(&ancestors),
+                    use_write_cache: false,
+                    rent_collector: &rent_collector,
+                },
+                &storages,
+                HashStats::default(),
+            )
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", err)))?;
+
+        Ok(SnapshotAccountsHashes {
+            accounts_delta_hash: accounts_db.get_accounts_delta_hash(self.bank_fields.slot),
+            accounts_hash,
+            capitalization,
+        })
+    }
+}
+
 pub(crate) fn bank_to_stream<W>(
     serde_style: SerdeStyle,
     stream: &mut BufWriter<W>,
@@ -447,6 +626,10 @@ where
         debug_do_not_add_builtins,
         reconstructed_accounts_db_info.accounts_data_len,
     );
+    bank.rc
+        .accounts
+        .accounts_db
+        .rebuild_secondary_indexes_in_background();
 
     info!("rent_collector: {:?}", bank.rent_collector());
 
@@ -458,13 +641,17 @@ fn reconstruct_single_storage<E>(
     append_vec_path: &Path,
     storage_entry: &E,
     append_vec_id: AppendVecId,
+    append_vec_backend: AppendVecBackend,
     new_slot_storage: &mut HashMap<AppendVecId, Arc<AccountStorageEntry>>,
 ) -> Result<(), Error>
 where
     E: SerializableStorage,
 {
-    let (accounts, num_accounts) =
-        AppendVec::new_from_file(append_vec_path, storage_entry.current_len())?;
+    let (accounts, num_accounts) = AppendVec::new_from_file_with_backend(
+        append_vec_path,
+        storage_entry.current_len(),
+        append_vec_backend,
+    )?;
     let u_storage_entry =
         AccountStorageEntry::new_existing(*slot, append_vec_id, accounts, num_accounts);
 
@@ -531,6 +718,7 @@ where
     // Remap the deserialized AppendVec paths to point to correct local paths
     let num_collisions = AtomicUsize::new(0);
     let next_append_vec_id = AtomicAppendVecId::new(0);
+    let append_vec_backend = accounts_db.append_vec_backend;
     let mut measure_remap = Measure::start("remap");
     let mut storage = (0..snapshot_storages.len())
         .into_par_iter()
@@ -582,6 +770,7 @@ where
                     &remapped_append_vec_path,
                     storage_entry,
                     remapped_append_vec_id,
+                    append_vec_backend,
                     &mut new_slot_storage,
                 )?;
             }
@@ -68,7 +68,7 @@ where
     let accounts_db_fields = C::deserialize_accounts_db_fields(stream)?;
     let snapshot_accounts_db_fields = SnapshotAccountsDbFields {
         full_snapshot_accounts_db_fields: accounts_db_fields,
-        incremental_snapshot_accounts_db_fields: None,
+        incremental_snapshot_accounts_db_fields: vec![],
     };
     reconstruct_accountsdb_from_fields(
         snapshot_accounts_db_fields,
@@ -280,7 +280,7 @@ fn test_bank_serialize_style(
         copy_append_vecs(&bank2.rc.accounts.accounts_db, copied_accounts.path()).unwrap();
     let mut snapshot_streams = SnapshotStreams {
         full_snapshot_stream: &mut reader,
-        incremental_snapshot_stream: None,
+        incremental_snapshot_streams: vec![],
     };
     let mut dbank = crate::serde_snapshot::bank_from_streams(
         serde_style,
@@ -95,11 +95,15 @@ impl Ord for FullSnapshotArchiveInfo {
     }
 }
 
-/// Information about an incremental snapshot archive: its path, slot, base slot, hash, and archive format
+/// Information about an incremental snapshot archive: its path, slot, base slot, full snapshot
+/// slot, hash, and archive format
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct IncrementalSnapshotArchiveInfo {
-    /// The slot that the incremental snapshot was based from.  This is the same as the full
-    /// snapshot slot used when making the incremental snapshot.
+    /// The slot of the full snapshot at the root of this incremental snapshot's chain
+    full_snapshot_slot: Slot,
+
+    /// The slot that the incremental snapshot was based from.  This is either the full snapshot
+    /// slot, or the slot of the previous incremental snapshot in the chain.
     base_slot: Slot,
 
     /// Use the `SnapshotArchiveInfo` struct for the common fields: path, slot, hash, and
@@ -111,10 +115,11 @@ impl IncrementalSnapshotArchiveInfo {
     /// Parse the path to an incremental snapshot archive and return a new `IncrementalSnapshotArchiveInfo`
     pub fn new_from_path(path: PathBuf) -> Result<Self> {
         let filename = snapshot_utils::path_to_file_name_str(path.as_path())?;
-        let (base_slot, slot, hash, archive_format) =
+        let (full_snapshot_slot, base_slot, slot, hash, archive_format) =
             snapshot_utils::parse_incremental_snapshot_archive_filename(filename)?;
 
         Ok(Self::new(
+            full_snapshot_slot,
             base_slot,
             SnapshotArchiveInfo {
                 path,
@@ -125,16 +130,31 @@ impl IncrementalSnapshotArchiveInfo {
         ))
     }
 
-    pub(crate) fn new(base_slot: Slot, snapshot_archive_info: SnapshotArchiveInfo) -> Self {
+    pub(crate) fn new(
+        full_snapshot_slot: Slot,
+        base_slot: Slot,
+        snapshot_archive_info: SnapshotArchiveInfo,
+    ) -> Self {
         Self {
+            full_snapshot_slot,
             base_slot,
             inner: snapshot_archive_info,
         }
     }
 
+    pub fn full_snapshot_slot(&self) -> Slot {
+        self.full_snapshot_slot
+    }
+
     pub fn base_slot(&self) -> Slot {
         self.base_slot
     }
+
+    /// Is this incremental snapshot based directly on its full snapshot, or on a previous
+    /// incremental snapshot of the chain?
+    pub fn is_based_on_full_snapshot(&self) -> bool {
+        self.base_slot == self.full_snapshot_slot
+    }
 }
 
 impl SnapshotArchiveInfoGetter for IncrementalSnapshotArchiveInfo {
@@ -149,12 +169,13 @@ impl PartialOrd for IncrementalSnapshotArchiveInfo {
     }
 }
 
-// Order `IncrementalSnapshotArchiveInfo` by base slot (ascending), then slot (ascending), which
-// practically is sorting chronologically
+// Order `IncrementalSnapshotArchiveInfo` by full snapshot slot (ascending), then slot (ascending),
+// then base slot (ascending), which practically is sorting chronologically
 impl Ord for IncrementalSnapshotArchiveInfo {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.base_slot()
-            .cmp(&other.base_slot())
+        self.full_snapshot_slot()
+            .cmp(&other.full_snapshot_slot())
             .then(self.slot().cmp(&other.slot()))
+            .then(self.base_slot().cmp(&other.base_slot()))
     }
 }
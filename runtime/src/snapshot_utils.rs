@@ -8,7 +8,10 @@ use {
         bank::{Bank, BankSlotDelta},
         builtins::Builtins,
         hardened_unpack::{unpack_snapshot, ParallelSelector, UnpackError, UnpackedAppendVecMap},
-        serde_snapshot::{bank_from_streams, bank_to_stream, SerdeStyle, SnapshotStreams},
+        serde_snapshot::{
+            bank_from_streams, bank_to_stream, fields_to_verify_from_streams, SerdeStyle,
+            SnapshotStreams,
+        },
         shared_buffer_reader::{SharedBuffer, SharedBufferReader},
         snapshot_archive_info::{
             FullSnapshotArchiveInfo, IncrementalSnapshotArchiveInfo, SnapshotArchiveInfoGetter,
@@ -16,6 +19,7 @@ use {
         snapshot_package::{
             AccountsPackage, PendingAccountsPackage, SnapshotPackage, SnapshotType,
         },
+        status_cache,
     },
     bincode::{config::Options, serialize_into},
     bzip2::bufread::BzDecoder,
@@ -27,8 +31,8 @@ use {
     solana_measure::measure::Measure,
     solana_sdk::{clock::Slot, genesis_config::GenesisConfig, hash::Hash, pubkey::Pubkey},
     std::{
-        cmp::{max, Ordering},
-        collections::HashSet,
+        cmp::{max, Ordering, Reverse},
+        collections::{hash_map::Entry, HashMap, HashSet},
         fmt,
         fs::{self, File},
         io::{BufReader, BufWriter, Error as IoError, ErrorKind, Read, Seek, Write},
@@ -44,6 +48,7 @@ use {
 
 mod archive_format;
 pub use archive_format::*;
+pub mod indexed_archive;
 
 pub const SNAPSHOT_STATUS_CACHE_FILENAME: &str = "status_cache";
 pub const SNAPSHOT_ARCHIVE_DOWNLOAD_DIR: &str = "remote";
@@ -58,8 +63,8 @@ pub const BANK_SNAPSHOT_PRE_FILENAME_EXTENSION: &str = "pre";
 pub const MAX_BANK_SNAPSHOTS_TO_RETAIN: usize = 8; // Save some bank snapshots but not too many
 pub const DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN: usize = 2;
 pub const DEFAULT_MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN: usize = 4;
-pub const FULL_SNAPSHOT_ARCHIVE_FILENAME_REGEX: &str = r"^snapshot-(?P<slot>[[:digit:]]+)-(?P<hash>[[:alnum:]]+)\.(?P<ext>tar|tar\.bz2|tar\.zst|tar\.gz)$";
-pub const INCREMENTAL_SNAPSHOT_ARCHIVE_FILENAME_REGEX: &str = r"^incremental-snapshot-(?P<base>[[:digit:]]+)-(?P<slot>[[:digit:]]+)-(?P<hash>[[:alnum:]]+)\.(?P<ext>tar|tar\.bz2|tar\.zst|tar\.gz)$";
+pub const FULL_SNAPSHOT_ARCHIVE_FILENAME_REGEX: &str = r"^snapshot-(?P<slot>[[:digit:]]+)-(?P<hash>[[:alnum:]]+)\.(?P<ext>tar|tar\.bz2|tar\.zst|tar\.gz|idx\.zst)$";
+pub const INCREMENTAL_SNAPSHOT_ARCHIVE_FILENAME_REGEX: &str = r"^incremental-snapshot-(?:(?P<full>[[:digit:]]+)-)?(?P<base>[[:digit:]]+)-(?P<slot>[[:digit:]]+)-(?P<hash>[[:alnum:]]+)\.(?P<ext>tar|tar\.bz2|tar\.zst|tar\.gz|idx\.zst)$";
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum SnapshotVersion {
@@ -158,11 +163,12 @@ pub enum BankSnapshotType {
 }
 
 /// Helper type when rebuilding from snapshots.  Designed to handle when rebuilding from just a
-/// full snapshot, or from both a full snapshot and an incremental snapshot.
+/// full snapshot, or from both a full snapshot and a chain of incremental snapshots.
 #[derive(Debug)]
 struct SnapshotRootPaths {
     full_snapshot_root_file_path: PathBuf,
-    incremental_snapshot_root_file_path: Option<PathBuf>,
+    /// The root file paths of the incremental snapshots, in chain order
+    incremental_snapshot_root_file_paths: Vec<PathBuf>,
 }
 
 /// Helper type to bundle up the results from `unarchive_snapshot()`
@@ -215,11 +221,17 @@ pub enum SnapshotError {
     #[error("snapshots are incompatible: full snapshot slot ({0}) and incremental snapshot base slot ({1}) do not match")]
     MismatchedBaseSlot(Slot, Slot),
 
+    #[error("snapshots are incompatible: previous incremental snapshot slot ({0}) and incremental snapshot base slot ({1}) do not match")]
+    MismatchedIncrementalSnapshotChain(Slot, Slot),
+
     #[error("no snapshot archives to load from")]
     NoSnapshotArchives,
 
     #[error("snapshot has mismatch: deserialized bank: {:?}, snapshot archive info: {:?}", .0, .1)]
     MismatchedSlotHash((Slot, Hash), (Slot, Hash)),
+
+    #[error("snapshot verification failed: {0}")]
+    VerificationFailed(String),
 }
 pub type Result<T> = std::result::Result<T, SnapshotError>;
 
@@ -369,6 +381,17 @@ pub fn archive_snapshot_package(
             ArchiveFormat::Tar => {
                 do_archive_files(&mut archive_file)?;
             }
+            ArchiveFormat::IndexedZstd => {
+                indexed_archive::archive_staging_dir(
+                    archive_file,
+                    staging_dir.as_ref(),
+                    snapshot_package
+                        .snapshot_storages
+                        .iter()
+                        .flatten()
+                        .map(|storage| storage.as_ref()),
+                )?;
+            }
         };
     }
 
@@ -529,7 +552,7 @@ pub fn deserialize_snapshot_data_file<T: Sized>(
 
     let wrapped_data_file_path = SnapshotRootPaths {
         full_snapshot_root_file_path: data_file_path.to_path_buf(),
-        incremental_snapshot_root_file_path: None,
+        incremental_snapshot_root_file_paths: vec![],
     };
 
     deserialize_snapshot_data_files_capped(
@@ -585,26 +608,22 @@ fn deserialize_snapshot_data_files_capped<T: Sized>(
             maximum_file_size,
         )?;
 
-    let (incremental_snapshot_file_size, mut incremental_snapshot_data_file_stream) =
-        if let Some(ref incremental_snapshot_root_file_path) =
-            snapshot_root_paths.incremental_snapshot_root_file_path
-        {
-            let (incremental_snapshot_file_size, incremental_snapshot_data_file_stream) =
-                create_snapshot_data_file_stream(
-                    incremental_snapshot_root_file_path,
-                    maximum_file_size,
-                )?;
-            (
-                Some(incremental_snapshot_file_size),
-                Some(incremental_snapshot_data_file_stream),
-            )
-        } else {
-            (None, None)
-        };
+    let (incremental_snapshot_file_sizes, mut incremental_snapshot_data_file_streams): (
+        Vec<_>,
+        Vec<_>,
+    ) = snapshot_root_paths
+        .incremental_snapshot_root_file_paths
+        .iter()
+        .map(|incremental_snapshot_root_file_path| {
+            create_snapshot_data_file_stream(incremental_snapshot_root_file_path, maximum_file_size)
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .unzip();
 
     let mut snapshot_streams = SnapshotStreams {
         full_snapshot_stream: &mut full_snapshot_data_file_stream,
-        incremental_snapshot_stream: incremental_snapshot_data_file_stream.as_mut(),
+        incremental_snapshot_streams: incremental_snapshot_data_file_streams.iter_mut().collect(),
     };
     let ret = deserializer(&mut snapshot_streams)?;
 
@@ -614,13 +633,19 @@ fn deserialize_snapshot_data_files_capped<T: Sized>(
         &mut full_snapshot_data_file_stream,
     )?;
 
-    if let Some(ref incremental_snapshot_root_file_path) =
-        snapshot_root_paths.incremental_snapshot_root_file_path
+    for (
+        (incremental_snapshot_root_file_path, incremental_snapshot_file_size),
+        incremental_snapshot_data_file_stream,
+    ) in snapshot_root_paths
+        .incremental_snapshot_root_file_paths
+        .iter()
+        .zip(incremental_snapshot_file_sizes)
+        .zip(incremental_snapshot_data_file_streams.iter_mut())
     {
         check_deserialize_file_consumed(
-            incremental_snapshot_file_size.unwrap(),
+            incremental_snapshot_file_size,
             incremental_snapshot_root_file_path,
-            incremental_snapshot_data_file_stream.as_mut().unwrap(),
+            incremental_snapshot_data_file_stream,
         )?;
     }
 
@@ -781,14 +806,14 @@ pub struct BankFromArchiveTimings {
 // From testing, 4 seems to be a sweet spot for ranges of 60M-360M accounts and 16-64 cores. This may need to be tuned later.
 const PARALLEL_UNTAR_READERS_DEFAULT: usize = 4;
 
-/// Rebuild bank from snapshot archives.  Handles either just a full snapshot, or both a full
-/// snapshot and an incremental snapshot.
+/// Rebuild bank from snapshot archives.  Handles either just a full snapshot, or a full snapshot
+/// and a chain of incremental snapshots, which are replayed in order.
 #[allow(clippy::too_many_arguments)]
 pub fn bank_from_snapshot_archives(
     account_paths: &[PathBuf],
     bank_snapshots_dir: impl AsRef<Path>,
     full_snapshot_archive_info: &FullSnapshotArchiveInfo,
-    incremental_snapshot_archive_info: Option<&IncrementalSnapshotArchiveInfo>,
+    incremental_snapshot_archive_infos: &[IncrementalSnapshotArchiveInfo],
     genesis_config: &GenesisConfig,
     debug_keys: Option<Arc<HashSet<Pubkey>>>,
     additional_builtins: Option<&Builtins>,
@@ -804,7 +829,7 @@ pub fn bank_from_snapshot_archives(
 ) -> Result<(Bank, BankFromArchiveTimings)> {
     check_are_snapshots_compatible(
         full_snapshot_archive_info,
-        incremental_snapshot_archive_info,
+        incremental_snapshot_archive_infos,
     )?;
 
     let parallel_divisions = std::cmp::min(
@@ -822,9 +847,10 @@ pub fn bank_from_snapshot_archives(
         parallel_divisions,
     )?;
 
-    let mut unarchived_incremental_snapshot =
-        if let Some(incremental_snapshot_archive_info) = incremental_snapshot_archive_info {
-            let unarchived_incremental_snapshot = unarchive_snapshot(
+    let mut unarchived_incremental_snapshots = incremental_snapshot_archive_infos
+        .iter()
+        .map(|incremental_snapshot_archive_info| {
+            unarchive_snapshot(
                 &bank_snapshots_dir,
                 TMP_SNAPSHOT_ARCHIVE_PREFIX,
                 incremental_snapshot_archive_info.path(),
@@ -832,14 +858,12 @@ pub fn bank_from_snapshot_archives(
                 account_paths,
                 incremental_snapshot_archive_info.archive_format(),
                 parallel_divisions,
-            )?;
-            Some(unarchived_incremental_snapshot)
-        } else {
-            None
-        };
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
 
     let mut unpacked_append_vec_map = unarchived_full_snapshot.unpacked_append_vec_map;
-    if let Some(ref mut unarchive_preparation_result) = unarchived_incremental_snapshot {
+    for unarchive_preparation_result in unarchived_incremental_snapshots.iter_mut() {
         let incremental_snapshot_unpacked_append_vec_map =
             std::mem::take(&mut unarchive_preparation_result.unpacked_append_vec_map);
         unpacked_append_vec_map.extend(incremental_snapshot_unpacked_append_vec_map.into_iter());
@@ -848,11 +872,12 @@ pub fn bank_from_snapshot_archives(
     let mut measure_rebuild = Measure::start("rebuild bank from snapshots");
     let bank = rebuild_bank_from_snapshots(
         &unarchived_full_snapshot.unpacked_snapshots_dir_and_version,
-        unarchived_incremental_snapshot
-            .as_ref()
+        &unarchived_incremental_snapshots
+            .iter()
             .map(|unarchive_preparation_result| {
                 &unarchive_preparation_result.unpacked_snapshots_dir_and_version
-            }),
+            })
+            .collect::<Vec<_>>(),
         account_paths,
         unpacked_append_vec_map,
         genesis_config,
@@ -883,17 +908,18 @@ pub fn bank_from_snapshot_archives(
     let timings = BankFromArchiveTimings {
         rebuild_bank_from_snapshots_us: measure_rebuild.as_us(),
         full_snapshot_untar_us: unarchived_full_snapshot.measure_untar.as_us(),
-        incremental_snapshot_untar_us: unarchived_incremental_snapshot
-            .map_or(0, |unarchive_preparation_result| {
-                unarchive_preparation_result.measure_untar.as_us()
-            }),
+        incremental_snapshot_untar_us: unarchived_incremental_snapshots
+            .iter()
+            .map(|unarchive_preparation_result| unarchive_preparation_result.measure_untar.as_us())
+            .sum(),
         verify_snapshot_bank_us: measure_verify.as_us(),
     };
     Ok((bank, timings))
 }
 
 /// Rebuild bank from snapshot archives.  This function searches `snapshot_archives_dir` for the
-/// highest full snapshot and highest corresponding incremental snapshot, then rebuilds the bank.
+/// highest full snapshot and the chain of incremental snapshots reaching the highest slot on top
+/// of it, then rebuilds the bank.
 #[allow(clippy::too_many_arguments)]
 pub fn bank_from_latest_snapshot_archives(
     bank_snapshots_dir: impl AsRef<Path>,
@@ -914,33 +940,34 @@ pub fn bank_from_latest_snapshot_archives(
 ) -> Result<(
     Bank,
     FullSnapshotArchiveInfo,
-    Option<IncrementalSnapshotArchiveInfo>,
+    Vec<IncrementalSnapshotArchiveInfo>,
 )> {
     let full_snapshot_archive_info = get_highest_full_snapshot_archive_info(&snapshot_archives_dir)
         .ok_or(SnapshotError::NoSnapshotArchives)?;
 
-    let incremental_snapshot_archive_info = get_highest_incremental_snapshot_archive_info(
+    let incremental_snapshot_archive_infos = get_incremental_snapshot_archive_chain(
         &snapshot_archives_dir,
         full_snapshot_archive_info.slot(),
     );
 
     info!(
-        "Loading bank from full snapshot: {}, and incremental snapshot: {:?}",
+        "Loading bank from full snapshot: {}, and incremental snapshots: {:?}",
         full_snapshot_archive_info.path().display(),
-        incremental_snapshot_archive_info
-            .as_ref()
+        incremental_snapshot_archive_infos
+            .iter()
             .map(
                 |incremental_snapshot_archive_info| incremental_snapshot_archive_info
                     .path()
                     .display()
             )
+            .collect::<Vec<_>>()
     );
 
     let (bank, timings) = bank_from_snapshot_archives(
         account_paths,
         bank_snapshots_dir.as_ref(),
         &full_snapshot_archive_info,
-        incremental_snapshot_archive_info.as_ref(),
+        &incremental_snapshot_archive_infos,
         genesis_config,
         debug_keys,
         additional_builtins,
@@ -981,11 +1008,11 @@ pub fn bank_from_latest_snapshot_archives(
 
     verify_bank_against_expected_slot_hash(
         &bank,
-        incremental_snapshot_archive_info.as_ref().map_or(
+        incremental_snapshot_archive_infos.last().map_or(
             full_snapshot_archive_info.slot(),
             |incremental_snapshot_archive_info| incremental_snapshot_archive_info.slot(),
         ),
-        incremental_snapshot_archive_info.as_ref().map_or(
+        incremental_snapshot_archive_infos.last().map_or(
             *full_snapshot_archive_info.hash(),
             |incremental_snapshot_archive_info| *incremental_snapshot_archive_info.hash(),
         ),
@@ -994,10 +1021,224 @@ pub fn bank_from_latest_snapshot_archives(
     Ok((
         bank,
         full_snapshot_archive_info,
-        incremental_snapshot_archive_info,
+        incremental_snapshot_archive_infos,
     ))
 }
 
+/// What `verify_snapshot_archives()` found out about a valid snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedSnapshot {
+    pub slot: Slot,
+    pub bank_hash: Hash,
+    pub accounts_hash: Hash,
+    pub capitalization: u64,
+    pub num_storages: usize,
+    pub num_status_cache_slots: usize,
+}
+
+/// Verify snapshot archives without rebuilding their bank nor its accounts index.  The archives
+/// are unpacked in temporary directories of `bank_snapshots_dir`, with the same checks as when
+/// booting from them, and the incremental snapshots of the chain are replayed in order.  Then the
+/// bank fields, the status cache, the bank hash, the accounts hash and the capitalization are
+/// verified, in that order, against each other and against the slot and hash of the last
+/// archive's name.  Fails on the first mismatch.
+pub fn verify_snapshot_archives(
+    bank_snapshots_dir: impl AsRef<Path>,
+    full_snapshot_archive_info: &FullSnapshotArchiveInfo,
+    incremental_snapshot_archive_infos: &[IncrementalSnapshotArchiveInfo],
+) -> Result<VerifiedSnapshot> {
+    check_are_snapshots_compatible(
+        full_snapshot_archive_info,
+        incremental_snapshot_archive_infos,
+    )?;
+    let (expected_slot, expected_hash) = incremental_snapshot_archive_infos.last().map_or(
+        (
+            full_snapshot_archive_info.slot(),
+            *full_snapshot_archive_info.hash(),
+        ),
+        |incremental_snapshot_archive_info| {
+            (
+                incremental_snapshot_archive_info.slot(),
+                *incremental_snapshot_archive_info.hash(),
+            )
+        },
+    );
+
+    let accounts_dir = tempfile::Builder::new()
+        .prefix(TMP_SNAPSHOT_ARCHIVE_PREFIX)
+        .tempdir_in(&bank_snapshots_dir)?;
+    let account_paths = &[accounts_dir.path().to_path_buf()];
+    let parallel_divisions = std::cmp::min(
+        PARALLEL_UNTAR_READERS_DEFAULT,
+        std::cmp::max(1, num_cpus::get() / 4),
+    );
+    info!(
+        "Unpacking full snapshot {}",
+        full_snapshot_archive_info.path().display()
+    );
+    let unarchived_full_snapshot = unarchive_snapshot(
+        &bank_snapshots_dir,
+        TMP_SNAPSHOT_ARCHIVE_PREFIX,
+        full_snapshot_archive_info.path(),
+        "snapshot untar",
+        account_paths,
+        full_snapshot_archive_info.archive_format(),
+        parallel_divisions,
+    )?;
+    let mut unarchived_incremental_snapshots = incremental_snapshot_archive_infos
+        .iter()
+        .map(|incremental_snapshot_archive_info| {
+            info!(
+                "Unpacking incremental snapshot {}",
+                incremental_snapshot_archive_info.path().display()
+            );
+            unarchive_snapshot(
+                &bank_snapshots_dir,
+                TMP_SNAPSHOT_ARCHIVE_PREFIX,
+                incremental_snapshot_archive_info.path(),
+                "incremental snapshot untar",
+                account_paths,
+                incremental_snapshot_archive_info.archive_format(),
+                parallel_divisions,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut unpacked_append_vec_map = unarchived_full_snapshot.unpacked_append_vec_map;
+    let (full_snapshot_version, full_snapshot_root_paths) =
+        verify_unpacked_snapshots_dir_and_version(
+            &unarchived_full_snapshot.unpacked_snapshots_dir_and_version,
+        )?;
+    let mut latest_snapshot_version = full_snapshot_version;
+    let mut latest_unpacked_snapshots_dir = unarchived_full_snapshot
+        .unpacked_snapshots_dir_and_version
+        .unpacked_snapshots_dir;
+    let mut snapshot_root_paths = SnapshotRootPaths {
+        full_snapshot_root_file_path: full_snapshot_root_paths.snapshot_path,
+        incremental_snapshot_root_file_paths: vec![],
+    };
+    for unarchived_incremental_snapshot in unarchived_incremental_snapshots.iter_mut() {
+        unpacked_append_vec_map.extend(std::mem::take(
+            &mut unarchived_incremental_snapshot.unpacked_append_vec_map,
+        ));
+        let (incremental_snapshot_version, incremental_snapshot_root_paths) =
+            verify_unpacked_snapshots_dir_and_version(
+                &unarchived_incremental_snapshot.unpacked_snapshots_dir_and_version,
+            )?;
+        latest_snapshot_version = incremental_snapshot_version;
+        latest_unpacked_snapshots_dir = unarchived_incremental_snapshot
+            .unpacked_snapshots_dir_and_version
+            .unpacked_snapshots_dir
+            .clone();
+        snapshot_root_paths
+            .incremental_snapshot_root_file_paths
+            .push(incremental_snapshot_root_paths.snapshot_path);
+    }
+
+    info!("Verifying bank fields of snapshot slot {}", expected_slot);
+    let fields = deserialize_snapshot_data_files(&snapshot_root_paths, |snapshot_streams| {
+        Ok(match latest_snapshot_version {
+            SnapshotVersion::V1_2_0 => {
+                fields_to_verify_from_streams(SerdeStyle::Newer, snapshot_streams)
+            }
+        }?)
+    })?;
+    let bank_fields = &fields.bank_fields;
+    if bank_fields.slot != expected_slot {
+        return Err(SnapshotError::VerificationFailed(format!(
+            "bank slot {} does not match the archive's slot {}",
+            bank_fields.slot, expected_slot
+        )));
+    }
+    let epoch = bank_fields.epoch_schedule.get_epoch(bank_fields.slot);
+    if bank_fields.epoch != epoch || !bank_fields.epoch_stakes.contains_key(&epoch) {
+        return Err(SnapshotError::VerificationFailed(format!(
+            "bank epoch {} is inconsistent with slot {}",
+            bank_fields.epoch, bank_fields.slot
+        )));
+    }
+    info!("Verifying status cache of snapshot slot {}", expected_slot);
+    let status_cache_path = latest_unpacked_snapshots_dir.join(SNAPSHOT_STATUS_CACHE_FILENAME);
+    let slot_deltas = deserialize_snapshot_data_file(&status_cache_path, |stream| {
+        let slot_deltas: Vec<BankSlotDelta> = bincode::options()
+            .with_limit(MAX_SNAPSHOT_DATA_FILE_SIZE)
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .deserialize_from(stream)?;
+        Ok(slot_deltas)
+    })?;
+    if slot_deltas.len() > status_cache::MAX_CACHE_ENTRIES {
+        return Err(SnapshotError::VerificationFailed(format!(
+            "status cache has {} slots, more than the maximum of {}",
+            slot_deltas.len(),
+            status_cache::MAX_CACHE_ENTRIES
+        )));
+    }
+    if let Some((slot, _, _)) = slot_deltas
+        .iter()
+        .find(|(slot, is_root, _)| *slot > bank_fields.slot || !is_root)
+    {
+        return Err(SnapshotError::VerificationFailed(format!(
+            "status cache slot {} is not a root of bank slot {}",
+            slot, bank_fields.slot
+        )));
+    }
+
+    info!(
+        "Calculating accounts hash of snapshot slot {}",
+        expected_slot
+    );
+    let mut measure_hash = Measure::start("snapshot accounts hash");
+    let accounts_hashes =
+        fields.calculate_accounts_hashes(account_paths, &unpacked_append_vec_map)?;
+    measure_hash.stop();
+    info!("{}", measure_hash);
+    // The accounts delta hash is not part of the snapshot, it is calculated from the storage of
+    // the snapshot's slot, as when booting from the snapshot
+    let bank_hash = Bank::compute_hash(
+        &bank_fields.parent_hash,
+        &accounts_hashes.accounts_delta_hash,
+        bank_fields.signature_count,
+        &bank_fields.blockhash_queue.last_hash(),
+        &bank_fields.hard_forks,
+        bank_fields.slot,
+        bank_fields.parent_slot,
+    );
+    if bank_hash != bank_fields.hash {
+        return Err(SnapshotError::VerificationFailed(format!(
+            "bank hash {} (calculated) does not match bank hash {} (expected)",
+            bank_hash, bank_fields.hash
+        )));
+    }
+    if accounts_hashes.accounts_hash != expected_hash {
+        return Err(SnapshotError::MismatchedSlotHash(
+            (bank_fields.slot, accounts_hashes.accounts_hash),
+            (expected_slot, expected_hash),
+        ));
+    }
+    if accounts_hashes.accounts_hash != fields.bank_hash_info.snapshot_hash {
+        return Err(SnapshotError::VerificationFailed(format!(
+            "accounts hash {} (calculated) does not match accounts hash {} (expected)",
+            accounts_hashes.accounts_hash, fields.bank_hash_info.snapshot_hash
+        )));
+    }
+    if accounts_hashes.capitalization != bank_fields.capitalization {
+        return Err(SnapshotError::VerificationFailed(format!(
+            "capitalization {} (calculated) does not match capitalization {} (expected)",
+            accounts_hashes.capitalization, bank_fields.capitalization
+        )));
+    }
+
+    Ok(VerifiedSnapshot {
+        slot: bank_fields.slot,
+        bank_hash,
+        accounts_hash: accounts_hashes.accounts_hash,
+        capitalization: accounts_hashes.capitalization,
+        num_storages: unpacked_append_vec_map.len(),
+        num_status_cache_slots: slot_deltas.len(),
+    })
+}
+
 /// Check to make sure the deserialized bank's slot and hash matches the snapshot archive's slot
 /// and hash
 fn verify_bank_against_expected_slot_hash(
@@ -1086,26 +1327,41 @@ fn snapshot_version_from_file(path: impl AsRef<Path>) -> Result<String> {
     Ok(snapshot_version.trim().to_string())
 }
 
-/// Check if an incremental snapshot is compatible with a full snapshot.  This is done by checking
-/// if the incremental snapshot's base slot is the same as the full snapshot's slot.
+/// Check if a chain of incremental snapshots is compatible with a full snapshot.  This is done by
+/// checking that every incremental snapshot of the chain belongs to the full snapshot, and that
+/// the base slot of each incremental snapshot is the slot of the snapshot before it.
 fn check_are_snapshots_compatible(
     full_snapshot_archive_info: &FullSnapshotArchiveInfo,
-    incremental_snapshot_archive_info: Option<&IncrementalSnapshotArchiveInfo>,
+    incremental_snapshot_archive_infos: &[IncrementalSnapshotArchiveInfo],
 ) -> Result<()> {
-    if incremental_snapshot_archive_info.is_none() {
-        return Ok(());
-    }
-
-    let incremental_snapshot_archive_info = incremental_snapshot_archive_info.unwrap();
-
-    (full_snapshot_archive_info.slot() == incremental_snapshot_archive_info.base_slot())
-        .then(|| ())
-        .ok_or_else(|| {
-            SnapshotError::MismatchedBaseSlot(
+    let mut previous_snapshot_slot = full_snapshot_archive_info.slot();
+    for incremental_snapshot_archive_info in incremental_snapshot_archive_infos {
+        if incremental_snapshot_archive_info.full_snapshot_slot()
+            != full_snapshot_archive_info.slot()
+        {
+            return Err(SnapshotError::MismatchedBaseSlot(
                 full_snapshot_archive_info.slot(),
-                incremental_snapshot_archive_info.base_slot(),
-            )
-        })
+                incremental_snapshot_archive_info.full_snapshot_slot(),
+            ));
+        }
+        if incremental_snapshot_archive_info.base_slot() != previous_snapshot_slot {
+            return Err(
+                if previous_snapshot_slot == full_snapshot_archive_info.slot() {
+                    SnapshotError::MismatchedBaseSlot(
+                        previous_snapshot_slot,
+                        incremental_snapshot_archive_info.base_slot(),
+                    )
+                } else {
+                    SnapshotError::MismatchedIncrementalSnapshotChain(
+                        previous_snapshot_slot,
+                        incremental_snapshot_archive_info.base_slot(),
+                    )
+                },
+            );
+        }
+        previous_snapshot_slot = incremental_snapshot_archive_info.slot();
+    }
+    Ok(())
 }
 
 /// Get the `&str` from a `&Path`
@@ -1148,11 +1404,36 @@ pub fn build_incremental_snapshot_archive_path(
     hash: &Hash,
     archive_format: ArchiveFormat,
 ) -> PathBuf {
-    snapshot_archives_dir.as_ref().join(format!(
-        "incremental-snapshot-{}-{}-{}.{}",
+    build_chained_incremental_snapshot_archive_path(
+        snapshot_archives_dir,
+        base_slot,
         base_slot,
         slot,
         hash,
+        archive_format,
+    )
+}
+
+/// Build the path of an incremental snapshot archive of a chain: the full snapshot slot is only
+/// part of the name when the base slot is the slot of a previous incremental snapshot, so that
+/// incremental snapshots based on a full snapshot keep their usual name.
+pub fn build_chained_incremental_snapshot_archive_path(
+    snapshot_archives_dir: impl AsRef<Path>,
+    full_snapshot_slot: Slot,
+    base_slot: Slot,
+    slot: Slot,
+    hash: &Hash,
+    archive_format: ArchiveFormat,
+) -> PathBuf {
+    let slots = if base_slot == full_snapshot_slot {
+        format!("{}-{}", base_slot, slot)
+    } else {
+        format!("{}-{}-{}", full_snapshot_slot, base_slot, slot)
+    };
+    snapshot_archives_dir.as_ref().join(format!(
+        "incremental-snapshot-{}-{}.{}",
+        slots,
+        hash,
         archive_format.extension(),
     ))
 }
@@ -1189,10 +1470,12 @@ pub(crate) fn parse_full_snapshot_archive_filename(
     })
 }
 
-/// Parse an incremental snapshot archive filename into its base Slot, actual Slot, Hash, and Archive Format
+/// Parse an incremental snapshot archive filename into its full snapshot Slot, base Slot, actual
+/// Slot, Hash, and Archive Format.  The full snapshot slot is the base slot when it is not part of
+/// the name.
 pub(crate) fn parse_incremental_snapshot_archive_filename(
     archive_filename: &str,
-) -> Result<(Slot, Slot, Hash, ArchiveFormat)> {
+) -> Result<(Slot, Slot, Slot, Hash, ArchiveFormat)> {
     lazy_static! {
         static ref RE: Regex = Regex::new(INCREMENTAL_SNAPSHOT_ARCHIVE_FILENAME_REGEX).unwrap();
     }
@@ -1203,6 +1486,10 @@ pub(crate) fn parse_incremental_snapshot_archive_filename(
                 .name("base")
                 .map(|x| x.as_str().parse::<Slot>())?
                 .ok()?;
+            let full_snapshot_slot = match captures.name("full") {
+                Some(x) => x.as_str().parse::<Slot>().ok()?,
+                None => base_slot,
+            };
             let slot = captures
                 .name("slot")
                 .map(|x| x.as_str().parse::<Slot>())?
@@ -1216,7 +1503,7 @@ pub(crate) fn parse_incremental_snapshot_archive_filename(
                 .map(|x| x.as_str().parse::<ArchiveFormat>())?
                 .ok()?;
 
-            Some((base_slot, slot, hash, archive_format))
+            Some((full_snapshot_slot, base_slot, slot, hash, archive_format))
         })
     };
 
@@ -1311,7 +1598,9 @@ where
 }
 
 /// Get the path for the incremental snapshot archive with the highest slot, for a given full
-/// snapshot slot, in a directory
+/// snapshot slot, in a directory.  Only the incremental snapshot archives based directly on the
+/// full snapshot are considered, since the ones based on a previous incremental snapshot of a
+/// chain cannot be loaded on their own.
 pub fn get_highest_incremental_snapshot_archive_info<P>(
     snapshot_archives_dir: P,
     full_snapshot_slot: Slot,
@@ -1326,13 +1615,82 @@ where
         get_incremental_snapshot_archives(snapshot_archives_dir)
             .into_iter()
             .filter(|incremental_snapshot_archive_info| {
-                incremental_snapshot_archive_info.base_slot() == full_snapshot_slot
+                incremental_snapshot_archive_info.full_snapshot_slot() == full_snapshot_slot
+                    && incremental_snapshot_archive_info.is_based_on_full_snapshot()
             })
             .collect::<Vec<_>>();
     incremental_snapshot_archives.sort_unstable();
     incremental_snapshot_archives.into_iter().rev().next()
 }
 
+/// Get the chain of incremental snapshot archives, in order, that reaches the highest slot on top
+/// of a given full snapshot slot, in a directory.  When several chains reach that slot, the one
+/// with the fewest incremental snapshots is picked.  Returns an empty chain if there are no
+/// incremental snapshot archives for the full snapshot.
+pub fn get_incremental_snapshot_archive_chain<P>(
+    snapshot_archives_dir: P,
+    full_snapshot_slot: Slot,
+) -> Vec<IncrementalSnapshotArchiveInfo>
+where
+    P: AsRef<Path>,
+{
+    get_incremental_snapshot_archive_chains(
+        get_incremental_snapshot_archives(snapshot_archives_dir),
+        full_snapshot_slot,
+    )
+    .into_iter()
+    .max_by_key(|chain| (chain.last().unwrap().slot(), Reverse(chain.len())))
+    .unwrap_or_default()
+}
+
+/// Get the shortest complete chain of each incremental snapshot archive of a given full snapshot
+/// slot, in chronological order of the incremental snapshot ending the chain.  The incremental
+/// snapshot archives missing a link of their chain are left out.
+fn get_incremental_snapshot_archive_chains(
+    incremental_snapshot_archives: Vec<IncrementalSnapshotArchiveInfo>,
+    full_snapshot_slot: Slot,
+) -> Vec<Vec<IncrementalSnapshotArchiveInfo>> {
+    let mut incremental_snapshot_archives = incremental_snapshot_archives
+        .into_iter()
+        .filter(|incremental_snapshot_archive_info| {
+            incremental_snapshot_archive_info.full_snapshot_slot() == full_snapshot_slot
+                && incremental_snapshot_archive_info.base_slot()
+                    < incremental_snapshot_archive_info.slot()
+        })
+        .collect::<Vec<_>>();
+    // Sorting by slot ensures the chains reaching the base slot of an incremental snapshot are
+    // known by the time it is visited
+    incremental_snapshot_archives.sort_unstable();
+
+    let mut shortest_chain_by_slot = HashMap::<Slot, Vec<IncrementalSnapshotArchiveInfo>>::new();
+    let mut chains = vec![];
+    for incremental_snapshot_archive_info in incremental_snapshot_archives {
+        let base_chain = if incremental_snapshot_archive_info.is_based_on_full_snapshot() {
+            Some(vec![])
+        } else {
+            shortest_chain_by_slot
+                .get(&incremental_snapshot_archive_info.base_slot())
+                .cloned()
+        };
+        if let Some(mut chain) = base_chain {
+            chain.push(incremental_snapshot_archive_info);
+            let slot = chain.last().unwrap().slot();
+            match shortest_chain_by_slot.entry(slot) {
+                Entry::Occupied(mut entry) => {
+                    if chain.len() < entry.get().len() {
+                        entry.insert(chain.clone());
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(chain.clone());
+                }
+            }
+            chains.push(chain);
+        }
+    }
+    chains
+}
+
 pub fn purge_old_snapshot_archives<P>(
     snapshot_archives_dir: P,
     maximum_full_snapshot_archives_to_retain: usize,
@@ -1365,58 +1723,39 @@ pub fn purge_old_snapshot_archives<P>(
             .unwrap_or_else(|err| info!("Failed to remove old full snapshot archive: {}", err));
     }
 
-    // Purge incremental snapshots with a different base slot than the highest full snapshot slot.
-    // Of the incremental snapshots with the same base slot, purge the oldest ones and retain the
-    // latest.
-    //
-    // First split the incremental snapshot archives into two vectors:
-    // - One vector will be all the incremental snapshot archives with a _different_ base slot than
-    // the highest full snapshot slot.
-    // - The other vector will be all the incremental snapshot archives with the _same_ base slot
-    // as the highest full snapshot slot.
-    //
-    // To find the incremental snapshot archives to retain, first sort the second vector (the
-    // _same_ base slot), then reverse (so highest slots are first) and skip the first
-    // `maximum_incremental_snapshot_archives_to_retain`.
-    //
-    // Purge all the rest.
+    // Purge incremental snapshots with a different full snapshot slot than the highest full
+    // snapshot slot.  Of the incremental snapshots of the highest full snapshot slot, retain the
+    // latest `maximum_incremental_snapshot_archives_to_retain` ones that can be loaded, together
+    // with the incremental snapshots of their chains that they depend on, and purge all the rest.
+    // Incremental snapshots missing a link of their chain cannot be loaded, so they are purged
+    // too.
     let highest_full_snapshot_slot = get_highest_full_snapshot_archive_slot(&snapshot_archives_dir);
-    let mut incremental_snapshot_archives_with_same_base_slot = vec![];
-    let mut incremental_snapshot_archives_with_different_base_slot = vec![];
-    get_incremental_snapshot_archives(&snapshot_archives_dir)
-        .drain(..)
-        .for_each(|incremental_snapshot_archive| {
-            if Some(incremental_snapshot_archive.base_slot()) == highest_full_snapshot_slot {
-                incremental_snapshot_archives_with_same_base_slot
-                    .push(incremental_snapshot_archive);
-            } else {
-                incremental_snapshot_archives_with_different_base_slot
-                    .push(incremental_snapshot_archive);
-            }
-        });
-
-    if !incremental_snapshot_archives_with_different_base_slot.is_empty() {
-        trace!(
-            "Purging {} incremental snapshot archives with a different base slot than the highest full snapshot slot",
-            incremental_snapshot_archives_with_different_base_slot.len()
-        );
-    }
+    let incremental_snapshot_archives = get_incremental_snapshot_archives(&snapshot_archives_dir);
+    let incremental_snapshot_archive_paths_to_retain = highest_full_snapshot_slot
+        .map_or_else(Vec::new, |highest_full_snapshot_slot| {
+            get_incremental_snapshot_archive_chains(
+                incremental_snapshot_archives.clone(),
+                highest_full_snapshot_slot,
+            )
+        })
+        .into_iter()
+        .rev()
+        .take(maximum_incremental_snapshot_archives_to_retain)
+        .flatten()
+        .map(|incremental_snapshot_archive| incremental_snapshot_archive.path().clone())
+        .collect::<HashSet<_>>();
     trace!(
-        "There are {} incremental snapshots with same base slot as the highest full snapshot slot, purging {} of them",
-        incremental_snapshot_archives_with_same_base_slot.len(),
-        incremental_snapshot_archives_with_same_base_slot.len()
-            .saturating_sub(maximum_incremental_snapshot_archives_to_retain)
+        "There are {} incremental snapshots, purging {} of them",
+        incremental_snapshot_archives.len(),
+        incremental_snapshot_archives.len() - incremental_snapshot_archive_paths_to_retain.len(),
     );
 
-    incremental_snapshot_archives_with_same_base_slot.sort_unstable();
-    incremental_snapshot_archives_with_different_base_slot
+    incremental_snapshot_archives
         .iter()
-        .chain(
-            incremental_snapshot_archives_with_same_base_slot
-                .iter()
-                .rev()
-                .skip(maximum_incremental_snapshot_archives_to_retain),
-        )
+        .filter(|incremental_snapshot_archive| {
+            !incremental_snapshot_archive_paths_to_retain
+                .contains(incremental_snapshot_archive.path())
+        })
         .for_each(|incremental_snapshot_archive| {
             trace!(
                 "Purging old incremental snapshot archive: {}",
@@ -1498,6 +1837,12 @@ fn untar_snapshot_in<P: AsRef<Path>>(
             account_paths,
             parallel_divisions,
         )?,
+        ArchiveFormat::IndexedZstd => indexed_archive::unpack_indexed_archive(
+            snapshot_tar.as_ref(),
+            unpack_dir,
+            account_paths,
+            parallel_divisions,
+        )?,
     };
     Ok(account_paths_map)
 }
@@ -1533,9 +1878,7 @@ fn verify_unpacked_snapshots_dir_and_version(
 #[allow(clippy::too_many_arguments)]
 fn rebuild_bank_from_snapshots(
     full_snapshot_unpacked_snapshots_dir_and_version: &UnpackedSnapshotsDirAndVersion,
-    incremental_snapshot_unpacked_snapshots_dir_and_version: Option<
-        &UnpackedSnapshotsDirAndVersion,
-    >,
+    incremental_snapshot_unpacked_snapshots_dirs_and_versions: &[&UnpackedSnapshotsDirAndVersion],
     account_paths: &[PathBuf],
     unpacked_append_vec_map: UnpackedAppendVecMap,
     genesis_config: &GenesisConfig,
@@ -1553,69 +1896,60 @@ fn rebuild_bank_from_snapshots(
         verify_unpacked_snapshots_dir_and_version(
             full_snapshot_unpacked_snapshots_dir_and_version,
         )?;
-    let (incremental_snapshot_version, incremental_snapshot_root_paths) =
-        if let Some(snapshot_unpacked_snapshots_dir_and_version) =
-            incremental_snapshot_unpacked_snapshots_dir_and_version
-        {
-            let (snapshot_version, bank_snapshot_info) = verify_unpacked_snapshots_dir_and_version(
-                snapshot_unpacked_snapshots_dir_and_version,
-            )?;
-            (Some(snapshot_version), Some(bank_snapshot_info))
-        } else {
-            (None, None)
-        };
+    let mut latest_snapshot_version = full_snapshot_version;
+    let mut incremental_snapshot_root_file_paths = vec![];
+    for snapshot_unpacked_snapshots_dir_and_version in
+        incremental_snapshot_unpacked_snapshots_dirs_and_versions
+    {
+        let (snapshot_version, bank_snapshot_info) =
+            verify_unpacked_snapshots_dir_and_version(snapshot_unpacked_snapshots_dir_and_version)?;
+        latest_snapshot_version = snapshot_version;
+        incremental_snapshot_root_file_paths.push(bank_snapshot_info.snapshot_path);
+    }
     info!(
-        "Loading bank from full snapshot {} and incremental snapshot {:?}",
+        "Loading bank from full snapshot {} and incremental snapshots {:?}",
         full_snapshot_root_paths.snapshot_path.display(),
-        incremental_snapshot_root_paths
-            .as_ref()
-            .map(|paths| paths.snapshot_path.display()),
+        incremental_snapshot_root_file_paths
+            .iter()
+            .map(|path| path.display())
+            .collect::<Vec<_>>(),
     );
 
     let snapshot_root_paths = SnapshotRootPaths {
         full_snapshot_root_file_path: full_snapshot_root_paths.snapshot_path,
-        incremental_snapshot_root_file_path: incremental_snapshot_root_paths
-            .map(|root_paths| root_paths.snapshot_path),
+        incremental_snapshot_root_file_paths,
     };
 
     let bank = deserialize_snapshot_data_files(&snapshot_root_paths, |snapshot_streams| {
-        Ok(
-            match incremental_snapshot_version.unwrap_or(full_snapshot_version) {
-                SnapshotVersion::V1_2_0 => bank_from_streams(
-                    SerdeStyle::Newer,
-                    snapshot_streams,
-                    account_paths,
-                    unpacked_append_vec_map,
-                    genesis_config,
-                    debug_keys,
-                    additional_builtins,
-                    account_secondary_indexes,
-                    accounts_db_caching_enabled,
-                    limit_load_slot_count_from_snapshot,
-                    shrink_ratio,
-                    verify_index,
-                    accounts_db_config,
-                    accounts_update_notifier,
-                ),
-            }?,
-        )
+        Ok(match latest_snapshot_version {
+            SnapshotVersion::V1_2_0 => bank_from_streams(
+                SerdeStyle::Newer,
+                snapshot_streams,
+                account_paths,
+                unpacked_append_vec_map,
+                genesis_config,
+                debug_keys,
+                additional_builtins,
+                account_secondary_indexes,
+                accounts_db_caching_enabled,
+                limit_load_slot_count_from_snapshot,
+                shrink_ratio,
+                verify_index,
+                accounts_db_config,
+                accounts_update_notifier,
+            ),
+        }?)
     })?;
 
-    // The status cache is rebuilt from the latest snapshot.  So, if there's an incremental
-    // snapshot, use that.  Otherwise use the full snapshot.
-    let status_cache_path = incremental_snapshot_unpacked_snapshots_dir_and_version
-        .map_or_else(
-            || {
-                full_snapshot_unpacked_snapshots_dir_and_version
-                    .unpacked_snapshots_dir
-                    .as_path()
-            },
-            |unpacked_snapshots_dir_and_version| {
-                unpacked_snapshots_dir_and_version
-                    .unpacked_snapshots_dir
-                    .as_path()
-            },
+    // The status cache is rebuilt from the latest snapshot.  So, if there are incremental
+    // snapshots, use the last one.  Otherwise use the full snapshot.
+    let status_cache_path = incremental_snapshot_unpacked_snapshots_dirs_and_versions
+        .last()
+        .map_or(
+            full_snapshot_unpacked_snapshots_dir_and_version,
+            |unpacked_snapshots_dir_and_version| unpacked_snapshots_dir_and_version,
         )
+        .unpacked_snapshots_dir
         .join(SNAPSHOT_STATUS_CACHE_FILENAME);
     let slot_deltas = deserialize_snapshot_data_file(&status_cache_path, |stream| {
         info!(
@@ -1865,25 +2199,63 @@ pub fn bank_to_incremental_snapshot_archive(
     archive_format: ArchiveFormat,
     maximum_full_snapshot_archives_to_retain: usize,
     maximum_incremental_snapshot_archives_to_retain: usize,
+) -> Result<IncrementalSnapshotArchiveInfo> {
+    bank_to_chained_incremental_snapshot_archive(
+        bank_snapshots_dir,
+        bank,
+        full_snapshot_slot,
+        full_snapshot_slot,
+        snapshot_version,
+        snapshot_archives_dir,
+        archive_format,
+        maximum_full_snapshot_archives_to_retain,
+        maximum_incremental_snapshot_archives_to_retain,
+    )
+}
+
+/// Convenience function to create an incremental snapshot archive of a chain out of any Bank,
+/// regardless of state.  The incremental snapshot is based on `base_slot`, which is either
+/// `full_snapshot_slot` or the slot of the last incremental snapshot of the chain.  The Bank will
+/// be frozen during the process.
+///
+/// Requires:
+///     - `bank` is complete
+///     - `bank`'s slot is greater than `base_slot`
+///     - `base_slot` is not less than `full_snapshot_slot`
+#[allow(clippy::too_many_arguments)]
+pub fn bank_to_chained_incremental_snapshot_archive(
+    bank_snapshots_dir: impl AsRef<Path>,
+    bank: &Bank,
+    full_snapshot_slot: Slot,
+    base_slot: Slot,
+    snapshot_version: Option<SnapshotVersion>,
+    snapshot_archives_dir: impl AsRef<Path>,
+    archive_format: ArchiveFormat,
+    maximum_full_snapshot_archives_to_retain: usize,
+    maximum_incremental_snapshot_archives_to_retain: usize,
 ) -> Result<IncrementalSnapshotArchiveInfo> {
     let snapshot_version = snapshot_version.unwrap_or_default();
 
     assert!(bank.is_complete());
-    assert!(bank.slot() > full_snapshot_slot);
+    assert!(bank.slot() > base_slot);
+    assert!(base_slot >= full_snapshot_slot);
     bank.squash(); // Bank may not be a root
     bank.force_flush_accounts_cache();
+    // Zero lamport accounts must be kept since the full snapshot, not only since the base
+    // snapshot, for the chain to shadow the older versions of the full snapshot
     bank.clean_accounts(true, false, Some(full_snapshot_slot));
     bank.update_accounts_hash();
     bank.rehash(); // Bank accounts may have been manually modified by the caller
 
     let temp_dir = tempfile::tempdir_in(bank_snapshots_dir)?;
-    let snapshot_storages = bank.get_snapshot_storages(Some(full_snapshot_slot));
+    let snapshot_storages = bank.get_snapshot_storages(Some(base_slot));
     let bank_snapshot_info =
         add_bank_snapshot(&temp_dir, bank, &snapshot_storages, snapshot_version)?;
 
-    package_and_archive_incremental_snapshot(
+    package_and_archive_chained_incremental_snapshot(
         bank,
         full_snapshot_slot,
+        base_slot,
         &bank_snapshot_info,
         &temp_dir,
         snapshot_archives_dir,
@@ -1952,38 +2324,80 @@ pub fn package_and_archive_incremental_snapshot(
     maximum_full_snapshot_archives_to_retain: usize,
     maximum_incremental_snapshot_archives_to_retain: usize,
 ) -> Result<IncrementalSnapshotArchiveInfo> {
-    let accounts_package = AccountsPackage::new(
+    package_and_archive_chained_incremental_snapshot(
         bank,
+        incremental_snapshot_base_slot,
+        incremental_snapshot_base_slot,
         bank_snapshot_info,
         bank_snapshots_dir,
-        bank.src.slot_deltas(&bank.src.roots()),
         snapshot_archives_dir,
         snapshot_storages,
         archive_format,
         snapshot_version,
-        None,
-        Some(SnapshotType::IncrementalSnapshot(
-            incremental_snapshot_base_slot,
-        )),
-    )?;
-
-    crate::serde_snapshot::reserialize_bank_with_new_accounts_hash(
-        accounts_package.snapshot_links.path(),
-        accounts_package.slot,
-        &bank.get_accounts_hash(),
-    );
-
-    let snapshot_package = SnapshotPackage::new(accounts_package, bank.get_accounts_hash());
-    archive_snapshot_package(
-        &snapshot_package,
         maximum_full_snapshot_archives_to_retain,
         maximum_incremental_snapshot_archives_to_retain,
-    )?;
-
-    Ok(IncrementalSnapshotArchiveInfo::new(
-        incremental_snapshot_base_slot,
-        snapshot_package.snapshot_archive_info,
-    ))
+    )
+}
+
+/// Helper function to hold shared code to package, process, and archive incremental snapshots of
+/// a chain
+#[allow(clippy::too_many_arguments)]
+fn package_and_archive_chained_incremental_snapshot(
+    bank: &Bank,
+    full_snapshot_slot: Slot,
+    incremental_snapshot_base_slot: Slot,
+    bank_snapshot_info: &BankSnapshotInfo,
+    bank_snapshots_dir: impl AsRef<Path>,
+    snapshot_archives_dir: impl AsRef<Path>,
+    snapshot_storages: SnapshotStorages,
+    archive_format: ArchiveFormat,
+    snapshot_version: SnapshotVersion,
+    maximum_full_snapshot_archives_to_retain: usize,
+    maximum_incremental_snapshot_archives_to_retain: usize,
+) -> Result<IncrementalSnapshotArchiveInfo> {
+    let accounts_package = AccountsPackage::new(
+        bank,
+        bank_snapshot_info,
+        bank_snapshots_dir,
+        bank.src.slot_deltas(&bank.src.roots()),
+        &snapshot_archives_dir,
+        snapshot_storages,
+        archive_format,
+        snapshot_version,
+        None,
+        Some(SnapshotType::IncrementalSnapshot(
+            incremental_snapshot_base_slot,
+        )),
+    )?;
+
+    crate::serde_snapshot::reserialize_bank_with_new_accounts_hash(
+        accounts_package.snapshot_links.path(),
+        accounts_package.slot,
+        &bank.get_accounts_hash(),
+    );
+
+    let mut snapshot_package = SnapshotPackage::new(accounts_package, bank.get_accounts_hash());
+    // The package is named after its base slot only, which is not enough for the incremental
+    // snapshots of a chain based on a previous incremental snapshot
+    snapshot_package.snapshot_archive_info.path = build_chained_incremental_snapshot_archive_path(
+        snapshot_archives_dir,
+        full_snapshot_slot,
+        incremental_snapshot_base_slot,
+        snapshot_package.slot(),
+        snapshot_package.hash(),
+        snapshot_package.archive_format(),
+    );
+    archive_snapshot_package(
+        &snapshot_package,
+        maximum_full_snapshot_archives_to_retain,
+        maximum_incremental_snapshot_archives_to_retain,
+    )?;
+
+    Ok(IncrementalSnapshotArchiveInfo::new(
+        full_snapshot_slot,
+        incremental_snapshot_base_slot,
+        snapshot_package.snapshot_archive_info,
+    ))
 }
 
 pub fn should_take_full_snapshot(
@@ -2046,7 +2460,7 @@ mod tests {
             system_transaction,
             transaction::SanitizedTransaction,
         },
-        std::{convert::TryFrom, mem::size_of},
+        std::{convert::TryFrom, mem::size_of, slice},
         tempfile::NamedTempFile,
     };
 
@@ -2099,7 +2513,7 @@ mod tests {
 
         let snapshot_root_paths = SnapshotRootPaths {
             full_snapshot_root_file_path: temp_dir.path().join("data-file"),
-            incremental_snapshot_root_file_path: None,
+            incremental_snapshot_root_file_paths: vec![],
         };
 
         let actual_data = deserialize_snapshot_data_files_capped(
@@ -2133,7 +2547,7 @@ mod tests {
 
         let snapshot_root_paths = SnapshotRootPaths {
             full_snapshot_root_file_path: temp_dir.path().join("data-file"),
-            incremental_snapshot_root_file_path: None,
+            incremental_snapshot_root_file_paths: vec![],
         };
 
         let result = deserialize_snapshot_data_files_capped(
@@ -2167,7 +2581,7 @@ mod tests {
 
         let snapshot_root_paths = SnapshotRootPaths {
             full_snapshot_root_file_path: temp_dir.path().join("data-file"),
-            incremental_snapshot_root_file_path: None,
+            incremental_snapshot_root_file_paths: vec![],
         };
 
         let result = deserialize_snapshot_data_files_capped(
@@ -2276,7 +2690,7 @@ mod tests {
                 Hash::default()
             ))
             .unwrap(),
-            (42, 123, Hash::default(), ArchiveFormat::TarBzip2)
+            (42, 42, 123, Hash::default(), ArchiveFormat::TarBzip2)
         );
         assert_eq!(
             parse_incremental_snapshot_archive_filename(&format!(
@@ -2284,7 +2698,7 @@ mod tests {
                 Hash::default()
             ))
             .unwrap(),
-            (43, 234, Hash::default(), ArchiveFormat::TarZstd)
+            (43, 43, 234, Hash::default(), ArchiveFormat::TarZstd)
         );
         assert_eq!(
             parse_incremental_snapshot_archive_filename(&format!(
@@ -2292,7 +2706,15 @@ mod tests {
                 Hash::default()
             ))
             .unwrap(),
-            (44, 345, Hash::default(), ArchiveFormat::Tar)
+            (44, 44, 345, Hash::default(), ArchiveFormat::Tar)
+        );
+        assert_eq!(
+            parse_incremental_snapshot_archive_filename(&format!(
+                "incremental-snapshot-45-456-567-{}.tar.zst",
+                Hash::default()
+            ))
+            .unwrap(),
+            (45, 456, 567, Hash::default(), ArchiveFormat::TarZstd)
         );
 
         assert!(parse_incremental_snapshot_archive_filename("invalid").is_err());
@@ -2328,6 +2750,57 @@ mod tests {
             Hash::new_unique()
         ))
         .is_err());
+
+        assert!(parse_incremental_snapshot_archive_filename(&format!(
+            "incremental-snapshot-12341234-bad!slot-56785678-{}.tar",
+            Hash::new_unique()
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_build_chained_incremental_snapshot_archive_path() {
+        let hash = Hash::new_unique();
+        let path = build_chained_incremental_snapshot_archive_path(
+            "/dir",
+            100,
+            100,
+            200,
+            &hash,
+            ArchiveFormat::TarZstd,
+        );
+        assert_eq!(
+            path,
+            build_incremental_snapshot_archive_path(
+                "/dir",
+                100,
+                200,
+                &hash,
+                ArchiveFormat::TarZstd
+            )
+        );
+
+        let path = build_chained_incremental_snapshot_archive_path(
+            "/dir",
+            100,
+            200,
+            300,
+            &hash,
+            ArchiveFormat::TarZstd,
+        );
+        assert_eq!(
+            path,
+            PathBuf::from(format!(
+                "/dir/incremental-snapshot-100-200-300-{}.tar.zst",
+                hash
+            ))
+        );
+        let incremental_snapshot_archive_info =
+            IncrementalSnapshotArchiveInfo::new_from_path(path).unwrap();
+        assert_eq!(incremental_snapshot_archive_info.full_snapshot_slot(), 100);
+        assert_eq!(incremental_snapshot_archive_info.base_slot(), 200);
+        assert_eq!(incremental_snapshot_archive_info.slot(), 300);
+        assert!(!incremental_snapshot_archive_info.is_based_on_full_snapshot());
     }
 
     #[test]
@@ -2342,7 +2815,7 @@ mod tests {
         ))
         .unwrap();
 
-        assert!(check_are_snapshots_compatible(&full_snapshot_archive_info, None,).is_ok());
+        assert!(check_are_snapshots_compatible(&full_snapshot_archive_info, &[]).is_ok());
 
         let incremental_snapshot_archive_info =
             IncrementalSnapshotArchiveInfo::new_from_path(PathBuf::from(format!(
@@ -2355,10 +2828,46 @@ mod tests {
 
         assert!(check_are_snapshots_compatible(
             &full_snapshot_archive_info,
-            Some(&incremental_snapshot_archive_info)
+            &[incremental_snapshot_archive_info.clone()]
         )
         .is_ok());
 
+        let chained_incremental_snapshot_archive_info =
+            IncrementalSnapshotArchiveInfo::new_from_path(PathBuf::from(format!(
+                "/dir/incremental-snapshot-{}-{}-{}-{}.tar",
+                slot1,
+                slot2,
+                slot3,
+                Hash::new_unique()
+            )))
+            .unwrap();
+
+        assert!(check_are_snapshots_compatible(
+            &full_snapshot_archive_info,
+            &[
+                incremental_snapshot_archive_info.clone(),
+                chained_incremental_snapshot_archive_info.clone()
+            ]
+        )
+        .is_ok());
+        assert!(matches!(
+            check_are_snapshots_compatible(
+                &full_snapshot_archive_info,
+                &[chained_incremental_snapshot_archive_info]
+            ),
+            Err(SnapshotError::MismatchedBaseSlot(..))
+        ));
+        assert!(matches!(
+            check_are_snapshots_compatible(
+                &full_snapshot_archive_info,
+                &[
+                    incremental_snapshot_archive_info.clone(),
+                    incremental_snapshot_archive_info
+                ]
+            ),
+            Err(SnapshotError::MismatchedIncrementalSnapshotChain(..))
+        ));
+
         let incremental_snapshot_archive_info =
             IncrementalSnapshotArchiveInfo::new_from_path(PathBuf::from(format!(
                 "/dir/incremental-snapshot-{}-{}-{}.tar",
@@ -2370,7 +2879,7 @@ mod tests {
 
         assert!(check_are_snapshots_compatible(
             &full_snapshot_archive_info,
-            Some(&incremental_snapshot_archive_info)
+            &[incremental_snapshot_archive_info]
         )
         .is_err());
     }
@@ -2836,6 +3345,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_incremental_snapshot_archive_chains() {
+        let snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+        for snapshot_filename in [
+            format!("snapshot-50-{}.tar", Hash::default()),
+            format!("snapshot-100-{}.tar", Hash::default()),
+            format!("incremental-snapshot-50-60-{}.tar", Hash::default()),
+            format!("incremental-snapshot-100-200-{}.tar", Hash::default()),
+            format!("incremental-snapshot-100-250-{}.tar", Hash::default()),
+            format!("incremental-snapshot-100-300-{}.tar", Hash::default()),
+            format!("incremental-snapshot-100-200-300-{}.tar", Hash::default()),
+            format!("incremental-snapshot-100-300-400-{}.tar", Hash::default()),
+            // missing its base, so it cannot be loaded
+            format!("incremental-snapshot-100-450-500-{}.tar", Hash::default()),
+        ] {
+            File::create(snapshot_archives_dir.path().join(snapshot_filename)).unwrap();
+        }
+        let slots = |incremental_snapshot_archives: Vec<IncrementalSnapshotArchiveInfo>| {
+            incremental_snapshot_archives
+                .iter()
+                .map(|incremental_snapshot_archive| {
+                    (
+                        incremental_snapshot_archive.base_slot(),
+                        incremental_snapshot_archive.slot(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // The highest slot that can be loaded is reached by the shortest chain
+        assert_eq!(
+            slots(get_incremental_snapshot_archive_chain(
+                snapshot_archives_dir.path(),
+                100
+            )),
+            vec![(100, 300), (300, 400)]
+        );
+        assert_eq!(
+            slots(get_incremental_snapshot_archive_chain(
+                snapshot_archives_dir.path(),
+                50
+            )),
+            vec![(50, 60)]
+        );
+        assert!(
+            get_incremental_snapshot_archive_chain(snapshot_archives_dir.path(), 10).is_empty()
+        );
+        assert_eq!(
+            get_highest_incremental_snapshot_archive_slot(snapshot_archives_dir.path(), 100),
+            Some(300)
+        );
+
+        // Retaining the latest two incremental snapshots retains the ones their chains depend on
+        purge_old_snapshot_archives(snapshot_archives_dir.path(), 2, 2);
+        let mut remaining_incremental_snapshot_archives =
+            get_incremental_snapshot_archives(snapshot_archives_dir.path());
+        remaining_incremental_snapshot_archives.sort_unstable();
+        assert_eq!(
+            slots(remaining_incremental_snapshot_archives),
+            vec![(100, 200), (100, 300), (200, 300), (300, 400)]
+        );
+
+        purge_old_snapshot_archives(snapshot_archives_dir.path(), 2, 1);
+        let mut remaining_incremental_snapshot_archives =
+            get_incremental_snapshot_archives(snapshot_archives_dir.path());
+        remaining_incremental_snapshot_archives.sort_unstable();
+        assert_eq!(
+            slots(remaining_incremental_snapshot_archives),
+            vec![(100, 300), (300, 400)]
+        );
+    }
+
     #[test]
     fn test_purge_all_incremental_snapshot_archives_when_no_full_snapshot_archives() {
         let snapshot_archives_dir = tempfile::TempDir::new().unwrap();
@@ -2893,7 +3474,78 @@ mod tests {
             &[PathBuf::from(accounts_dir.path())],
             bank_snapshots_dir.path(),
             &snapshot_archive_info,
+            &[],
+            &genesis_config,
+            None,
+            None,
+            AccountSecondaryIndexes::default(),
+            false,
             None,
+            AccountShrinkThreshold::default(),
+            false,
+            false,
+            false,
+            Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(original_bank, roundtrip_bank);
+    }
+
+    /// Test roundtrip of bank to an indexed full snapshot, then back again, and look up the
+    /// accounts of the snapshot through its index
+    #[test]
+    fn test_roundtrip_bank_to_and_from_indexed_snapshot() {
+        solana_logger::setup();
+        let key1 = Keypair::new();
+        let key2 = Keypair::new();
+
+        let (genesis_config, mint_keypair) = create_genesis_config(1_000_000);
+        let original_bank = Bank::new_for_tests(&genesis_config);
+        original_bank
+            .transfer(1, &mint_keypair, &key1.pubkey())
+            .unwrap();
+        original_bank
+            .transfer(2, &mint_keypair, &key2.pubkey())
+            .unwrap();
+        while !original_bank.is_complete() {
+            original_bank.register_tick(&Hash::new_unique());
+        }
+
+        let accounts_dir = tempfile::TempDir::new().unwrap();
+        let bank_snapshots_dir = tempfile::TempDir::new().unwrap();
+        let snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+
+        let snapshot_archive_info = bank_to_full_snapshot_archive(
+            &bank_snapshots_dir,
+            &original_bank,
+            None,
+            snapshot_archives_dir.path(),
+            ArchiveFormat::IndexedZstd,
+            DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+            DEFAULT_MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+        )
+        .unwrap();
+        assert_eq!(
+            snapshot_archive_info.archive_format(),
+            ArchiveFormat::IndexedZstd
+        );
+
+        let mut reader =
+            indexed_archive::IndexedArchiveReader::open(snapshot_archive_info.path()).unwrap();
+        for pubkey in [key1.pubkey(), key2.pubkey(), mint_keypair.pubkey()] {
+            assert_eq!(
+                reader.get_account(&pubkey).unwrap(),
+                original_bank.get_account(&pubkey)
+            );
+        }
+
+        let (roundtrip_bank, _) = bank_from_snapshot_archives(
+            &[PathBuf::from(accounts_dir.path())],
+            bank_snapshots_dir.path(),
+            &snapshot_archive_info,
+            &[],
             &genesis_config,
             None,
             None,
@@ -2984,7 +3636,7 @@ mod tests {
             &[PathBuf::from(accounts_dir.path())],
             bank_snapshots_dir.path(),
             &full_snapshot_archive_info,
-            None,
+            &[],
             &genesis_config,
             None,
             None,
@@ -3094,7 +3746,7 @@ mod tests {
             &[PathBuf::from(accounts_dir.path())],
             bank_snapshots_dir.path(),
             &full_snapshot_archive_info,
-            Some(&incremental_snapshot_archive_info),
+            slice::from_ref(&incremental_snapshot_archive_info),
             &genesis_config,
             None,
             None,
@@ -3113,6 +3765,261 @@ mod tests {
         assert_eq!(*bank4, roundtrip_bank);
     }
 
+    /// Test roundtrip of bank to snapshots, then back again, with a chain of incremental snapshots.
+    /// Take a full snapshot, then an incremental snapshot based on it, then an incremental snapshot
+    /// based on the first incremental snapshot, in which an account is drained to zero lamports.
+    /// Rebuild the bank from the whole chain, both explicitly and from the latest snapshot
+    /// archives, and ensure it matches the original bank.
+    #[test]
+    fn test_roundtrip_bank_to_and_from_incremental_snapshot_chain() {
+        solana_logger::setup();
+        let collector = Pubkey::new_unique();
+        let key1 = Keypair::new();
+        let key2 = Keypair::new();
+        let key3 = Keypair::new();
+
+        let (genesis_config, mint_keypair) = create_genesis_config(1_000_000);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        bank0.transfer(1, &mint_keypair, &key1.pubkey()).unwrap();
+        bank0
+            .transfer(100_000, &mint_keypair, &key2.pubkey())
+            .unwrap();
+        while !bank0.is_complete() {
+            bank0.register_tick(&Hash::new_unique());
+        }
+
+        let accounts_dir = tempfile::TempDir::new().unwrap();
+        let bank_snapshots_dir = tempfile::TempDir::new().unwrap();
+        let snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+        let snapshot_archive_format = ArchiveFormat::TarZstd;
+
+        let full_snapshot_archive_info = bank_to_full_snapshot_archive(
+            bank_snapshots_dir.path(),
+            &bank0,
+            None,
+            snapshot_archives_dir.path(),
+            snapshot_archive_format,
+            DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+            DEFAULT_MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+        )
+        .unwrap();
+
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &collector, 1));
+        bank1.transfer(2, &mint_keypair, &key1.pubkey()).unwrap();
+        bank1.transfer(3, &mint_keypair, &key3.pubkey()).unwrap();
+        while !bank1.is_complete() {
+            bank1.register_tick(&Hash::new_unique());
+        }
+
+        let first_incremental_snapshot_archive_info = bank_to_incremental_snapshot_archive(
+            bank_snapshots_dir.path(),
+            &bank1,
+            full_snapshot_archive_info.slot(),
+            None,
+            snapshot_archives_dir.path(),
+            snapshot_archive_format,
+            DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+            DEFAULT_MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+        )
+        .unwrap();
+
+        let bank2 = Arc::new(Bank::new_from_parent(&bank1, &collector, 2));
+        bank2.transfer(4, &mint_keypair, &key1.pubkey()).unwrap();
+        while !bank2.is_complete() {
+            bank2.register_tick(&Hash::new_unique());
+        }
+
+        let bank3 = Arc::new(Bank::new_from_parent(&bank2, &collector, 3));
+        let lamports_to_drain =
+            bank3.get_balance(&key2.pubkey()) - bank3.get_lamports_per_signature();
+        bank3
+            .transfer(lamports_to_drain, &key2, &mint_keypair.pubkey())
+            .unwrap();
+        while !bank3.is_complete() {
+            bank3.register_tick(&Hash::new_unique());
+        }
+
+        let second_incremental_snapshot_archive_info =
+            bank_to_chained_incremental_snapshot_archive(
+                bank_snapshots_dir.path(),
+                &bank3,
+                full_snapshot_archive_info.slot(),
+                first_incremental_snapshot_archive_info.slot(),
+                None,
+                snapshot_archives_dir.path(),
+                snapshot_archive_format,
+                DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+                DEFAULT_MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+            )
+            .unwrap();
+        assert_eq!(
+            IncrementalSnapshotArchiveInfo::new_from_path(
+                second_incremental_snapshot_archive_info.path().clone()
+            )
+            .unwrap(),
+            second_incremental_snapshot_archive_info
+        );
+        let incremental_snapshot_archive_infos = vec![
+            first_incremental_snapshot_archive_info,
+            second_incremental_snapshot_archive_info,
+        ];
+
+        let (roundtrip_bank, _) = bank_from_snapshot_archives(
+            &[PathBuf::from(accounts_dir.path())],
+            bank_snapshots_dir.path(),
+            &full_snapshot_archive_info,
+            &incremental_snapshot_archive_infos,
+            &genesis_config,
+            None,
+            None,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+            AccountShrinkThreshold::default(),
+            false,
+            false,
+            false,
+            Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
+            None,
+        )
+        .unwrap();
+        assert_eq!(*bank3, roundtrip_bank);
+        assert_eq!(roundtrip_bank.get_balance(&key2.pubkey()), 0);
+
+        let verified_snapshot = verify_snapshot_archives(
+            bank_snapshots_dir.path(),
+            &full_snapshot_archive_info,
+            &incremental_snapshot_archive_infos,
+        )
+        .unwrap();
+        assert_eq!(verified_snapshot.slot, bank3.slot());
+        assert_eq!(verified_snapshot.bank_hash, bank3.hash());
+
+        let accounts_dir = tempfile::TempDir::new().unwrap();
+        let (deserialized_bank, _, deserialized_incremental_snapshot_archive_infos) =
+            bank_from_latest_snapshot_archives(
+                bank_snapshots_dir.path(),
+                snapshot_archives_dir.path(),
+                &[accounts_dir.as_ref().to_path_buf()],
+                &genesis_config,
+                None,
+                None,
+                AccountSecondaryIndexes::default(),
+                false,
+                None,
+                AccountShrinkThreshold::default(),
+                false,
+                false,
+                false,
+                Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
+                None,
+            )
+            .unwrap();
+        assert_eq!(*bank3, deserialized_bank);
+        assert_eq!(
+            deserialized_incremental_snapshot_archive_infos,
+            incremental_snapshot_archive_infos
+        );
+    }
+
+    /// Test verifying full and incremental snapshot archives, and that an archive whose name
+    /// does not match its contents fails to verify
+    #[test]
+    fn test_verify_snapshot_archives() {
+        solana_logger::setup();
+        let collector = Pubkey::new_unique();
+        let key1 = Keypair::new();
+        let key2 = Keypair::new();
+
+        let (genesis_config, mint_keypair) = create_genesis_config(1_000_000);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        bank0.transfer(1, &mint_keypair, &key1.pubkey()).unwrap();
+        while !bank0.is_complete() {
+            bank0.register_tick(&Hash::new_unique());
+        }
+
+        let slot = 1;
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &collector, slot));
+        bank1.transfer(2, &mint_keypair, &key2.pubkey()).unwrap();
+        while !bank1.is_complete() {
+            bank1.register_tick(&Hash::new_unique());
+        }
+
+        let bank_snapshots_dir = tempfile::TempDir::new().unwrap();
+        let snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+        let snapshot_archive_format = ArchiveFormat::TarZstd;
+
+        let full_snapshot_slot = slot;
+        let full_snapshot_archive_info = bank_to_full_snapshot_archive(
+            bank_snapshots_dir.path(),
+            &bank1,
+            None,
+            snapshot_archives_dir.path(),
+            snapshot_archive_format,
+            DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+            DEFAULT_MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+        )
+        .unwrap();
+
+        let slot = slot + 1;
+        let bank2 = Arc::new(Bank::new_from_parent(&bank1, &collector, slot));
+        bank2.transfer(3, &mint_keypair, &key1.pubkey()).unwrap();
+        while !bank2.is_complete() {
+            bank2.register_tick(&Hash::new_unique());
+        }
+
+        let incremental_snapshot_archive_info = bank_to_incremental_snapshot_archive(
+            bank_snapshots_dir.path(),
+            &bank2,
+            full_snapshot_slot,
+            None,
+            snapshot_archives_dir.path(),
+            snapshot_archive_format,
+            DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+            DEFAULT_MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+        )
+        .unwrap();
+
+        let verified_snapshot =
+            verify_snapshot_archives(bank_snapshots_dir.path(), &full_snapshot_archive_info, &[])
+                .unwrap();
+        assert_eq!(verified_snapshot.slot, bank1.slot());
+        assert_eq!(verified_snapshot.bank_hash, bank1.hash());
+        assert_eq!(
+            verified_snapshot.accounts_hash,
+            *full_snapshot_archive_info.hash()
+        );
+        assert_eq!(verified_snapshot.capitalization, bank1.capitalization());
+
+        let verified_snapshot = verify_snapshot_archives(
+            bank_snapshots_dir.path(),
+            &full_snapshot_archive_info,
+            slice::from_ref(&incremental_snapshot_archive_info),
+        )
+        .unwrap();
+        assert_eq!(verified_snapshot.slot, bank2.slot());
+        assert_eq!(verified_snapshot.bank_hash, bank2.hash());
+        assert_eq!(
+            verified_snapshot.accounts_hash,
+            *incremental_snapshot_archive_info.hash()
+        );
+        assert_eq!(verified_snapshot.capitalization, bank2.capitalization());
+
+        let mislabeled_archive_path = build_full_snapshot_archive_path(
+            snapshot_archives_dir.path(),
+            full_snapshot_slot,
+            &Hash::new_unique(),
+            snapshot_archive_format,
+        );
+        fs::copy(full_snapshot_archive_info.path(), &mislabeled_archive_path).unwrap();
+        let mislabeled_archive_info =
+            FullSnapshotArchiveInfo::new_from_path(mislabeled_archive_path).unwrap();
+        assert_matches!(
+            verify_snapshot_archives(bank_snapshots_dir.path(), &mislabeled_archive_info, &[]),
+            Err(SnapshotError::MismatchedSlotHash(..))
+        );
+    }
+
     /// Test rebuilding bank from the latest snapshot archives
     #[test]
     fn test_bank_from_latest_snapshot_archives() {
@@ -3331,7 +4238,7 @@ mod tests {
             &[accounts_dir.path().to_path_buf()],
             bank_snapshots_dir.path(),
             &full_snapshot_archive_info,
-            Some(&incremental_snapshot_archive_info),
+            slice::from_ref(&incremental_snapshot_archive_info),
             &genesis_config,
             None,
             None,
@@ -3393,7 +4300,7 @@ mod tests {
             &[accounts_dir.path().to_path_buf()],
             bank_snapshots_dir.path(),
             &full_snapshot_archive_info,
-            Some(&incremental_snapshot_archive_info),
+            slice::from_ref(&incremental_snapshot_archive_info),
             &genesis_config,
             None,
             None,
@@ -4,6 +4,7 @@ pub const TAR_BZIP2_EXTENSION: &str = "tar.bz2";
 pub const TAR_GZIP_EXTENSION: &str = "tar.gz";
 pub const TAR_ZSTD_EXTENSION: &str = "tar.zst";
 pub const TAR_EXTENSION: &str = "tar";
+pub const INDEXED_ZSTD_EXTENSION: &str = "idx.zst";
 
 /// The different archive formats used for snapshots
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -12,6 +13,9 @@ pub enum ArchiveFormat {
     TarGzip,
     TarZstd,
     Tar,
+    /// Seekable archive of zstd frames, with an index of the files and
+    /// accounts, see `indexed_archive`
+    IndexedZstd,
 }
 
 impl ArchiveFormat {
@@ -22,6 +26,7 @@ impl ArchiveFormat {
             ArchiveFormat::TarGzip => TAR_GZIP_EXTENSION,
             ArchiveFormat::TarZstd => TAR_ZSTD_EXTENSION,
             ArchiveFormat::Tar => TAR_EXTENSION,
+            ArchiveFormat::IndexedZstd => INDEXED_ZSTD_EXTENSION,
         }
     }
 }
@@ -37,6 +42,7 @@ impl TryFrom<&str> for ArchiveFormat {
             TAR_GZIP_EXTENSION => Ok(ArchiveFormat::TarGzip),
             TAR_ZSTD_EXTENSION => Ok(ArchiveFormat::TarZstd),
             TAR_EXTENSION => Ok(ArchiveFormat::Tar),
+            INDEXED_ZSTD_EXTENSION => Ok(ArchiveFormat::IndexedZstd),
             _ => Err(ParseError::InvalidExtension),
         }
     }
@@ -66,6 +72,10 @@ mod tests {
         assert_eq!(ArchiveFormat::TarGzip.extension(), TAR_GZIP_EXTENSION);
         assert_eq!(ArchiveFormat::TarZstd.extension(), TAR_ZSTD_EXTENSION);
         assert_eq!(ArchiveFormat::Tar.extension(), TAR_EXTENSION);
+        assert_eq!(
+            ArchiveFormat::IndexedZstd.extension(),
+            INDEXED_ZSTD_EXTENSION
+        );
     }
 
     #[test]
@@ -86,6 +96,10 @@ mod tests {
             ArchiveFormat::try_from(TAR_EXTENSION),
             Ok(ArchiveFormat::Tar)
         );
+        assert_eq!(
+            ArchiveFormat::try_from(INDEXED_ZSTD_EXTENSION),
+            Ok(ArchiveFormat::IndexedZstd)
+        );
         assert_eq!(
             ArchiveFormat::try_from(INVALID_EXTENSION),
             Err(ParseError::InvalidExtension)
@@ -110,6 +124,10 @@ mod tests {
             ArchiveFormat::from_str(TAR_EXTENSION),
             Ok(ArchiveFormat::Tar)
         );
+        assert_eq!(
+            ArchiveFormat::from_str(INDEXED_ZSTD_EXTENSION),
+            Ok(ArchiveFormat::IndexedZstd)
+        );
         assert_eq!(
             ArchiveFormat::from_str(INVALID_EXTENSION),
             Err(ParseError::InvalidExtension)
@@ -0,0 +1,615 @@
+//! A snapshot archive format that can be read without unpacking it.
+//!
+//! Every file of the snapshot is compressed as its own zstd frame, and the
+//! archive ends with an index of the files and of the accounts they hold, so
+//! that a single file or account can be read by seeking to its frame.
+//!
+//! The archive is framed as described in `archive_framing`. In version 1, its
+//! body holds the zstd frame of each file, in the order of the index, and its
+//! index is the zstd compressed, bincode encoded `IndexedArchiveIndex`.
+use {
+    super::{Result, SnapshotError},
+    crate::{
+        accounts_db::AccountStorageEntry,
+        append_vec::AppendVec,
+        archive_framing::{self, ArchiveFrame, Magic, HEADER_LEN},
+        hardened_unpack::{SnapshotEntryUnpacker, UnpackedAppendVecMap},
+    },
+    rayon::prelude::*,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount},
+        clock::Slot,
+        pubkey::Pubkey,
+    },
+    std::{
+        fs::{self, File},
+        io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
+};
+
+const MAGIC: &Magic = b"SOLSNPIX";
+pub const INDEXED_ARCHIVE_VERSION: u32 = 1;
+/// Limit of the decompressed size of the index relative to the length of the archive, so that
+/// the index of a corrupted or crafted archive is not decompressed without end. An account takes
+/// 52 bytes in the index, and its pubkey and hash take 64 bytes that don't compress in the body.
+const MAX_INDEX_SIZE_RATIO: u64 = 4;
+/// Limit of the decompressed size of the index of the smallest archives
+const MIN_MAX_INDEX_SIZE: u64 = 1024 * 1024;
+const ZSTD_COMPRESSION_LEVEL: i32 = 0;
+
+fn invalid_archive(message: impl Into<String>) -> SnapshotError {
+    SnapshotError::Io(io::Error::new(io::ErrorKind::InvalidData, message.into()))
+}
+
+/// A file of the snapshot
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedArchiveEntry {
+    /// Path of the file in the snapshot, as in the tar archive formats
+    pub path: String,
+    /// Offset of the zstd frame of the file in the archive
+    pub offset: u64,
+    pub compressed_len: u64,
+    /// Length of the file once decompressed
+    pub len: u64,
+    /// For AppendVec files, the length of the accounts they hold
+    pub accounts_len: Option<u64>,
+}
+
+/// The location of the latest version of an account in the snapshot
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexedAccount {
+    pub pubkey: Pubkey,
+    pub slot: Slot,
+    /// Index of the AppendVec entry holding the account
+    pub entry: u32,
+    /// Offset of the account in the AppendVec
+    pub offset: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct IndexedArchiveIndex {
+    pub entries: Vec<IndexedArchiveEntry>,
+    /// Sorted by pubkey
+    pub accounts: Vec<IndexedAccount>,
+}
+
+pub struct IndexedArchiveWriter<W: Write> {
+    writer: W,
+    offset: u64,
+    index: IndexedArchiveIndex,
+    /// The (pubkey, slot, write version, entry, offset) of every stored account
+    stored_accounts: Vec<(Pubkey, Slot, u64, u32, u64)>,
+}
+
+impl<W: Write> IndexedArchiveWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self> {
+        archive_framing::write_header(&mut writer, MAGIC, INDEXED_ARCHIVE_VERSION)?;
+        Ok(Self {
+            writer,
+            offset: HEADER_LEN,
+            index: IndexedArchiveIndex::default(),
+            stored_accounts: vec![],
+        })
+    }
+
+    fn append(
+        &mut self,
+        path: &str,
+        reader: &mut impl Read,
+        accounts_len: Option<u64>,
+    ) -> Result<u32> {
+        let mut encoder = zstd::stream::Encoder::new(
+            CountingWriter::new(&mut self.writer),
+            ZSTD_COMPRESSION_LEVEL,
+        )?;
+        let len = io::copy(reader, &mut encoder)?;
+        let compressed_len = encoder.finish()?.count;
+        let entry = self.index.entries.len() as u32;
+        self.index.entries.push(IndexedArchiveEntry {
+            path: path.to_string(),
+            offset: self.offset,
+            compressed_len,
+            len,
+            accounts_len,
+        });
+        self.offset += compressed_len;
+        Ok(entry)
+    }
+
+    /// Appends a file of the snapshot other than an AppendVec
+    pub fn append_file(&mut self, path: &str, file_path: &Path) -> Result<()> {
+        self.append(path, &mut BufReader::new(File::open(file_path)?), None)?;
+        Ok(())
+    }
+
+    /// Appends the AppendVec of `storage`, indexing its accounts
+    pub fn append_storage(&mut self, path: &str, storage: &AccountStorageEntry) -> Result<()> {
+        let accounts_len = storage.accounts.len() as u64;
+        let entry = self.append(
+            path,
+            &mut BufReader::new(File::open(storage.get_path())?),
+            Some(accounts_len),
+        )?;
+        let slot = storage.slot();
+        self.stored_accounts
+            .extend(storage.all_accounts().into_iter().map(|account| {
+                (
+                    account.meta.pubkey,
+                    slot,
+                    account.meta.write_version,
+                    entry,
+                    account.offset as u64,
+                )
+            }));
+        Ok(())
+    }
+
+    /// Writes the index and the footer
+    pub fn finish(mut self) -> Result<W> {
+        // Only index the latest version of each account
+        self.stored_accounts
+            .sort_unstable_by(|a, b| a.0.cmp(&b.0).then((b.1, b.2).cmp(&(a.1, a.2))));
+        self.stored_accounts.dedup_by_key(|account| account.0);
+        self.index.accounts = self
+            .stored_accounts
+            .iter()
+            .map(|(pubkey, slot, _, entry, offset)| IndexedAccount {
+                pubkey: *pubkey,
+                slot: *slot,
+                entry: *entry,
+                offset: *offset,
+            })
+            .collect();
+
+        let index = zstd::encode_all(
+            bincode::serialize(&self.index)?.as_slice(),
+            ZSTD_COMPRESSION_LEVEL,
+        )?;
+        self.writer.write_all(&index)?;
+        archive_framing::write_footer(&mut self.writer, MAGIC, self.offset, index.len() as u64)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+struct CountingWriter<W: Write> {
+    writer: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    fn new(writer: W) -> Self {
+        Self { writer, count: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes the snapshot staged in `staging_dir` into an indexed archive.
+/// The AppendVecs are read from `storages` rather than from the staging
+/// directory, to index their accounts.
+pub(crate) fn archive_staging_dir<'a>(
+    archive_file: File,
+    staging_dir: &Path,
+    storages: impl Iterator<Item = &'a AccountStorageEntry>,
+) -> Result<()> {
+    let mut writer = IndexedArchiveWriter::new(BufWriter::new(archive_file))?;
+    writer.append_file("version", &staging_dir.join("version"))?;
+    let mut snapshot_files = vec![];
+    collect_files(
+        &staging_dir.join("snapshots"),
+        "snapshots",
+        &mut snapshot_files,
+    )?;
+    snapshot_files.sort();
+    for (path, file_path) in snapshot_files {
+        writer.append_file(&path, &file_path)?;
+    }
+    for storage in storages {
+        let path = format!(
+            "accounts/{}",
+            AppendVec::file_name(storage.slot(), storage.append_vec_id())
+        );
+        writer.append_storage(&path, storage)?;
+    }
+    writer
+        .finish()?
+        .into_inner()
+        .map_err(|err| err.into_error())?;
+    Ok(())
+}
+
+/// Collects the (archive path, file path) of the files in `dir`, recursively
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let file_name = file_name
+            .to_str()
+            .ok_or_else(|| SnapshotError::FileNameToStrError(entry.path()))?;
+        let path = format!("{}/{}", prefix, file_name);
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), &path, files)?;
+        } else {
+            files.push((path, entry.path()));
+        }
+    }
+    Ok(())
+}
+
+/// Reads the files and accounts of an indexed archive
+pub struct IndexedArchiveReader {
+    path: PathBuf,
+    reader: BufReader<File>,
+    version: u32,
+    /// Shared by the readers opened with `try_clone`
+    index: Arc<IndexedArchiveIndex>,
+}
+
+impl IndexedArchiveReader {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = BufReader::new(File::open(&path)?);
+        let frame = ArchiveFrame::read(
+            &mut reader,
+            MAGIC,
+            INDEXED_ARCHIVE_VERSION,
+            "indexed snapshot archive",
+        )?;
+
+        reader.seek(SeekFrom::Start(frame.index_offset))?;
+        let max_index_size = (frame.index_offset + frame.index_len)
+            .saturating_mul(MAX_INDEX_SIZE_RATIO)
+            .max(MIN_MAX_INDEX_SIZE);
+        let mut index = vec![];
+        zstd::stream::read::Decoder::new((&mut reader).take(frame.index_len))?
+            .take(max_index_size + 1)
+            .read_to_end(&mut index)?;
+        if index.len() as u64 > max_index_size {
+            return Err(invalid_archive("too large indexed snapshot archive index"));
+        }
+        let index: IndexedArchiveIndex = bincode::deserialize(&index)?;
+        Self::sanitize_index(&index, &frame)?;
+
+        Ok(Self {
+            path,
+            reader,
+            version: frame.version,
+            index: Arc::new(index),
+        })
+    }
+
+    /// Opens another reader of the archive, sharing the index rather than reading it again
+    pub fn try_clone(&self) -> Result<Self> {
+        Ok(Self {
+            path: self.path.clone(),
+            reader: BufReader::new(File::open(&self.path)?),
+            version: self.version,
+            index: self.index.clone(),
+        })
+    }
+
+    /// Checks that the entries are within the archive and that the accounts
+    /// refer to AppendVec entries
+    fn sanitize_index(index: &IndexedArchiveIndex, frame: &ArchiveFrame) -> Result<()> {
+        let entries_valid = index.entries.iter().all(|entry| {
+            frame.body_contains(entry.offset, entry.compressed_len)
+                && entry
+                    .accounts_len
+                    .map(|accounts_len| accounts_len <= entry.len)
+                    .unwrap_or(true)
+        });
+        let accounts_valid = index.accounts.iter().all(|account| {
+            index
+                .entries
+                .get(account.entry as usize)
+                .and_then(|entry| entry.accounts_len)
+                .map(|accounts_len| account.offset < accounts_len)
+                .unwrap_or(false)
+        }) && index
+            .accounts
+            .windows(2)
+            .all(|accounts| accounts[0].pubkey < accounts[1].pubkey);
+        if entries_valid && accounts_valid {
+            Ok(())
+        } else {
+            Err(invalid_archive("corrupted indexed snapshot archive index"))
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn entries(&self) -> &[IndexedArchiveEntry] {
+        &self.index.entries
+    }
+
+    /// The latest version of every account of the snapshot, sorted by pubkey
+    pub fn accounts(&self) -> &[IndexedAccount] {
+        &self.index.accounts
+    }
+
+    /// Returns a reader of the decompressed content of the entry, which
+    /// yields at most `entry.len` bytes
+    pub fn read_entry(&mut self, entry_index: usize) -> Result<impl Read + '_> {
+        let entry = self
+            .index
+            .entries
+            .get(entry_index)
+            .ok_or_else(|| invalid_archive(format!("no entry {}", entry_index)))?
+            .clone();
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        let reader = (&mut self.reader).take(entry.compressed_len);
+        Ok(zstd::stream::read::Decoder::with_buffer(reader)?.take(entry.len))
+    }
+
+    /// Reads the accounts of an AppendVec entry, decompressing the whole entry
+    pub fn read_append_vec(&mut self, entry_index: usize) -> Result<AppendVec> {
+        let accounts_len = self
+            .index
+            .entries
+            .get(entry_index)
+            .and_then(|entry| entry.accounts_len)
+            .ok_or_else(|| invalid_archive(format!("entry {} is not an AppendVec", entry_index)))?;
+        let mut bytes = Vec::with_capacity(accounts_len as usize);
+        self.read_entry(entry_index)?
+            .take(accounts_len)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != accounts_len {
+            return Err(invalid_archive(format!(
+                "entry {} is shorter than its accounts",
+                entry_index
+            )));
+        }
+        Ok(AppendVec::new_from_bytes(&bytes)?.0)
+    }
+
+    /// Returns the latest version of an account, `None` if the snapshot does
+    /// not hold it, or holds it with zero lamports.
+    ///
+    /// A zstd frame cannot be read from the middle, so the whole AppendVec
+    /// holding the account is decompressed: reading an account costs as much
+    /// as reading its AppendVec.
+    pub fn get_account(&mut self, pubkey: &Pubkey) -> Result<Option<AccountSharedData>> {
+        let account = match self
+            .index
+            .accounts
+            .binary_search_by_key(pubkey, |account| account.pubkey)
+        {
+            Ok(position) => self.index.accounts[position],
+            Err(_) => return Ok(None),
+        };
+        let append_vec = self.read_append_vec(account.entry as usize)?;
+        let (stored_account, _) = append_vec
+            .get_account(account.offset as usize)
+            .ok_or_else(|| invalid_archive(format!("no account {} in its entry", pubkey)))?;
+        if stored_account.meta.pubkey != *pubkey {
+            return Err(invalid_archive(format!(
+                "the index location of account {} holds another account",
+                pubkey
+            )));
+        }
+        let account = stored_account.clone_account();
+        if account.lamports() == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(account))
+        }
+    }
+}
+
+/// Unpacks an indexed archive like a tar archive, the files being unpacked in
+/// `parallel_divisions` threads
+pub(crate) fn unpack_indexed_archive(
+    archive_path: &Path,
+    ledger_dir: &Path,
+    account_paths: &[PathBuf],
+    parallel_divisions: usize,
+) -> Result<UnpackedAppendVecMap> {
+    assert!(parallel_divisions > 0);
+    let archive_reader = IndexedArchiveReader::open(archive_path)?;
+    let unpacked_append_vec_maps = (0..parallel_divisions)
+        .into_par_iter()
+        .map(|division| {
+            let mut reader = archive_reader.try_clone()?;
+            let mut unpacker = SnapshotEntryUnpacker::new(ledger_dir, account_paths);
+            let archive_index = reader.index.clone();
+            for (entry_index, entry) in archive_index.entries.iter().enumerate() {
+                if entry_index % parallel_divisions == division {
+                    unpacker.unpack_entry(
+                        Path::new(&entry.path),
+                        entry.len,
+                        reader.read_entry(entry_index)?,
+                    )?;
+                }
+            }
+            Ok(unpacker.finish())
+        })
+        .collect::<Vec<Result<_>>>();
+    let mut unpacked_append_vec_map = UnpackedAppendVecMap::new();
+    for map in unpacked_append_vec_maps {
+        unpacked_append_vec_map.extend(map?);
+    }
+    Ok(unpacked_append_vec_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{accounts_db::AppendVecId, append_vec::StoredMeta},
+        solana_sdk::hash::Hash,
+        tempfile::TempDir,
+    };
+
+    fn storage_with_accounts(
+        dir: &Path,
+        slot: Slot,
+        id: AppendVecId,
+        accounts: &[(Pubkey, u64, AccountSharedData)],
+    ) -> AccountStorageEntry {
+        let storage = AccountStorageEntry::new(dir, slot, id, 1024 * 1024);
+        for (pubkey, write_version, account) in accounts {
+            storage
+                .accounts
+                .append_account(
+                    StoredMeta {
+                        write_version: *write_version,
+                        pubkey: *pubkey,
+                        data_len: account.data().len() as u64,
+                    },
+                    account,
+                    Hash::default(),
+                )
+                .unwrap();
+        }
+        storage.flush().unwrap();
+        storage
+    }
+
+    #[test]
+    fn test_indexed_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let staging_dir = temp_dir.path().join("staging");
+        let storages_dir = temp_dir.path().join("storages");
+        fs::create_dir_all(staging_dir.join("snapshots").join("2")).unwrap();
+        fs::create_dir_all(&storages_dir).unwrap();
+        fs::write(staging_dir.join("version"), "1.2.0").unwrap();
+        fs::write(
+            staging_dir.join("snapshots").join("2").join("2"),
+            [7u8; 100],
+        )
+        .unwrap();
+        fs::write(
+            staging_dir.join("snapshots").join("status_cache"),
+            [8u8; 10],
+        )
+        .unwrap();
+
+        let owner = Pubkey::new_unique();
+        let updated = Pubkey::new_unique();
+        let unchanged = Pubkey::new_unique();
+        let removed = Pubkey::new_unique();
+        let account = |lamports| AccountSharedData::new(lamports, 8, &owner);
+        let storages = vec![
+            storage_with_accounts(
+                &storages_dir,
+                1,
+                0,
+                &[
+                    (updated, 1, account(1)),
+                    (unchanged, 2, account(2)),
+                    (removed, 3, account(3)),
+                ],
+            ),
+            storage_with_accounts(
+                &storages_dir,
+                2,
+                1,
+                &[
+                    (updated, 4, account(4)),
+                    (removed, 5, AccountSharedData::default()),
+                ],
+            ),
+        ];
+
+        let archive_path = temp_dir.path().join("snapshot.idx.zst");
+        archive_staging_dir(
+            File::create(&archive_path).unwrap(),
+            &staging_dir,
+            storages.iter(),
+        )
+        .unwrap();
+
+        let mut reader = IndexedArchiveReader::open(&archive_path).unwrap();
+        assert_eq!(reader.version(), INDEXED_ARCHIVE_VERSION);
+        assert_eq!(
+            reader
+                .entries()
+                .iter()
+                .map(|entry| entry.path.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "version",
+                "snapshots/2/2",
+                "snapshots/status_cache",
+                "accounts/1.0",
+                "accounts/2.1"
+            ]
+        );
+        assert_eq!(reader.accounts().len(), 3);
+        assert_eq!(reader.get_account(&updated).unwrap(), Some(account(4)));
+        assert_eq!(reader.get_account(&unchanged).unwrap(), Some(account(2)));
+        assert_eq!(reader.get_account(&removed).unwrap(), None);
+        assert_eq!(reader.get_account(&Pubkey::new_unique()).unwrap(), None);
+        let mut version = String::new();
+        reader
+            .read_entry(0)
+            .unwrap()
+            .read_to_string(&mut version)
+            .unwrap();
+        assert_eq!(version, "1.2.0");
+
+        let ledger_dir = temp_dir.path().join("ledger");
+        let account_paths = vec![temp_dir.path().join("accounts")];
+        fs::create_dir_all(&ledger_dir).unwrap();
+        let unpacked_append_vec_map =
+            unpack_indexed_archive(&archive_path, &ledger_dir, &account_paths, 2).unwrap();
+        assert_eq!(unpacked_append_vec_map.len(), 2);
+        assert_eq!(
+            fs::read(ledger_dir.join("snapshots").join("2").join("2")).unwrap(),
+            vec![7u8; 100]
+        );
+        let unpacked_storage = unpacked_append_vec_map.get("2.1").unwrap();
+        assert_eq!(
+            fs::read(unpacked_storage).unwrap(),
+            fs::read(storages[1].get_path()).unwrap()
+        );
+
+        // A truncated archive is rejected
+        let contents = fs::read(&archive_path).unwrap();
+        fs::write(&archive_path, &contents[..contents.len() - 1]).unwrap();
+        assert!(IndexedArchiveReader::open(&archive_path).is_err());
+    }
+
+    #[test]
+    fn test_indexed_archive_index_size_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("snapshot.idx.zst");
+        let write_archive = |index_size: u64| {
+            let index =
+                zstd::stream::encode_all(io::repeat(0).take(index_size), ZSTD_COMPRESSION_LEVEL)
+                    .unwrap();
+            let mut archive = vec![];
+            archive_framing::write_header(&mut archive, MAGIC, INDEXED_ARCHIVE_VERSION).unwrap();
+            archive.extend_from_slice(&index);
+            archive_framing::write_footer(&mut archive, MAGIC, HEADER_LEN, index.len() as u64)
+                .unwrap();
+            fs::write(&archive_path, archive).unwrap();
+        };
+
+        // An index decompressing to zeroes reads as an empty index, as long as it is not larger
+        // than the limit relative to the tiny archive
+        write_archive(MIN_MAX_INDEX_SIZE);
+        let reader = IndexedArchiveReader::open(&archive_path).unwrap();
+        assert!(reader.entries().is_empty());
+        assert!(reader.accounts().is_empty());
+
+        write_archive(MIN_MAX_INDEX_SIZE + 1);
+        assert!(IndexedArchiveReader::open(&archive_path).is_err());
+    }
+}
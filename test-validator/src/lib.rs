@@ -7,6 +7,7 @@ use {
         tower_storage::TowerStorage,
         validator::{Validator, ValidatorConfig, ValidatorStartProgress},
     },
+    solana_geyser_plugin_manager::geyser_plugin_manager::GeyserPluginManager,
     solana_gossip::{
         cluster_info::{ClusterInfo, Node},
         gossip_service::discover_cluster,
@@ -863,6 +864,10 @@ impl TestValidator {
     pub fn bank_forks(&self) -> Arc<RwLock<BankForks>> {
         self.validator.as_ref().unwrap().bank_forks.clone()
     }
+
+    pub fn geyser_plugin_manager(&self) -> Option<Arc<RwLock<GeyserPluginManager>>> {
+        self.validator.as_ref().unwrap().geyser_plugin_manager()
+    }
 }
 
 impl Drop for TestValidator {
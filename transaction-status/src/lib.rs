@@ -666,6 +666,20 @@ impl TransactionWithStatusMeta {
         }
     }
 
+    /// Check that the transaction can be encoded for a client supporting up to
+    /// `max_supported_transaction_version`, without encoding it
+    pub fn validate_version(
+        &self,
+        max_supported_transaction_version: Option<u8>,
+    ) -> Result<(), EncodeError> {
+        match self {
+            Self::MissingMetadata(_) => Ok(()),
+            Self::Complete(tx_with_meta) => tx_with_meta
+                .validate_version(max_supported_transaction_version)
+                .map(|_| ()),
+        }
+    }
+
     pub fn account_keys(&self) -> AccountKeys {
         match self {
             Self::MissingMetadata(tx) => AccountKeys::new(&tx.message.account_keys, None),
@@ -675,12 +689,11 @@ impl TransactionWithStatusMeta {
 }
 
 impl VersionedTransactionWithStatusMeta {
-    pub fn encode(
-        self,
-        encoding: UiTransactionEncoding,
+    pub fn validate_version(
+        &self,
         max_supported_transaction_version: Option<u8>,
-    ) -> Result<EncodedTransactionWithStatusMeta, EncodeError> {
-        let version = match (
+    ) -> Result<Option<TransactionVersion>, EncodeError> {
+        match (
             max_supported_transaction_version,
             self.transaction.version(),
         ) {
@@ -697,7 +710,15 @@ impl VersionedTransactionWithStatusMeta {
                     Err(EncodeError::UnsupportedTransactionVersion(version))
                 }
             }
-        }?;
+        }
+    }
+
+    pub fn encode(
+        self,
+        encoding: UiTransactionEncoding,
+        max_supported_transaction_version: Option<u8>,
+    ) -> Result<EncodedTransactionWithStatusMeta, EncodeError> {
+        let version = self.validate_version(max_supported_transaction_version)?;
 
         Ok(EncodedTransactionWithStatusMeta {
             transaction: self.transaction.encode_with_meta(encoding, &self.meta),
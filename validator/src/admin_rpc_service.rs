@@ -9,8 +9,13 @@ use {
     solana_core::{
         consensus::Tower, tower_storage::TowerStorage, validator::ValidatorStartProgress,
     },
+    solana_geyser_plugin_manager::geyser_plugin_manager::GeyserPluginManager,
     solana_gossip::{cluster_info::ClusterInfo, contact_info::ContactInfo},
-    solana_runtime::bank_forks::BankForks,
+    solana_rpc::rpc_rate_limiter::{RpcClientUsage, RpcRateLimiter},
+    solana_runtime::{
+        accounts_hash_checkpoint::{AccountsHashProgress, AccountsHashProgressTracker},
+        bank_forks::BankForks,
+    },
     solana_sdk::{
         exit::Exit,
         pubkey::Pubkey,
@@ -31,6 +36,7 @@ pub struct AdminRpcRequestMetadataPostInit {
     pub cluster_info: Arc<ClusterInfo>,
     pub bank_forks: Arc<RwLock<BankForks>>,
     pub vote_account: Pubkey,
+    pub geyser_plugin_manager: Option<Arc<RwLock<GeyserPluginManager>>>,
 }
 
 #[derive(Clone)]
@@ -41,6 +47,8 @@ pub struct AdminRpcRequestMetadata {
     pub validator_exit: Arc<RwLock<Exit>>,
     pub authorized_voter_keypairs: Arc<RwLock<Vec<Arc<Keypair>>>>,
     pub tower_storage: Arc<dyn TowerStorage>,
+    pub accounts_hash_progress: Arc<AccountsHashProgressTracker>,
+    pub rpc_rate_limiter: Option<Arc<RpcRateLimiter>>,
     pub post_init: Arc<RwLock<Option<AdminRpcRequestMetadataPostInit>>>,
 }
 impl Metadata for AdminRpcRequestMetadata {}
@@ -58,6 +66,21 @@ impl AdminRpcRequestMetadata {
             ))
         }
     }
+
+    fn with_geyser_plugin_manager<F, R>(&self, func: F) -> Result<R>
+    where
+        F: FnOnce(&RwLock<GeyserPluginManager>) -> Result<R>,
+    {
+        self.with_post_init(|post_init| {
+            if let Some(geyser_plugin_manager) = post_init.geyser_plugin_manager.as_ref() {
+                func(geyser_plugin_manager)
+            } else {
+                Err(jsonrpc_core::error::Error::invalid_params(
+                    "The validator was not started with --geyser-plugin-config",
+                ))
+            }
+        })
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -165,6 +188,24 @@ pub trait AdminRpc {
 
     #[rpc(meta, name = "contactInfo")]
     fn contact_info(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo>;
+
+    #[rpc(meta, name = "accountsHashProgress")]
+    fn accounts_hash_progress(&self, meta: Self::Metadata) -> Result<Option<AccountsHashProgress>>;
+
+    #[rpc(meta, name = "rpcTopConsumers")]
+    fn rpc_top_consumers(&self, meta: Self::Metadata) -> Result<Vec<RpcClientUsage>>;
+
+    #[rpc(meta, name = "listPlugins")]
+    fn list_plugins(&self, meta: Self::Metadata) -> Result<Vec<String>>;
+
+    #[rpc(meta, name = "loadPlugin")]
+    fn load_plugin(&self, meta: Self::Metadata, config_file: String) -> Result<String>;
+
+    #[rpc(meta, name = "unloadPlugin")]
+    fn unload_plugin(&self, meta: Self::Metadata, name: String) -> Result<()>;
+
+    #[rpc(meta, name = "reloadPlugin")]
+    fn reload_plugin(&self, meta: Self::Metadata, name: String, config_file: String) -> Result<()>;
 }
 
 pub struct AdminRpcImpl;
@@ -280,6 +321,77 @@ impl AdminRpc for AdminRpcImpl {
     fn contact_info(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo> {
         meta.with_post_init(|post_init| Ok(post_init.cluster_info.my_contact_info().into()))
     }
+
+    fn accounts_hash_progress(&self, meta: Self::Metadata) -> Result<Option<AccountsHashProgress>> {
+        // available during startup too, when the snapshot's accounts hash is being verified
+        Ok(meta.accounts_hash_progress.get())
+    }
+
+    fn rpc_top_consumers(&self, meta: Self::Metadata) -> Result<Vec<RpcClientUsage>> {
+        Ok(meta
+            .rpc_rate_limiter
+            .as_ref()
+            .map(|rate_limiter| rate_limiter.top_consumers())
+            .unwrap_or_default())
+    }
+
+    fn list_plugins(&self, meta: Self::Metadata) -> Result<Vec<String>> {
+        debug!("list_plugins admin rpc request received");
+        meta.with_geyser_plugin_manager(|geyser_plugin_manager| {
+            Ok(geyser_plugin_manager.read().unwrap().list_plugins())
+        })
+    }
+
+    fn load_plugin(&self, meta: Self::Metadata, config_file: String) -> Result<String> {
+        debug!("load_plugin admin rpc request received");
+        meta.with_geyser_plugin_manager(|geyser_plugin_manager| {
+            let name = GeyserPluginManager::load_plugin_from_config(
+                geyser_plugin_manager,
+                Path::new(&config_file),
+            )
+            .map_err(|err| {
+                jsonrpc_core::error::Error::invalid_params(format!(
+                    "Failed to load plugin from {}: {:?}",
+                    config_file, err
+                ))
+            })?;
+            warn!("Geyser plugin {} loaded from {}", name, config_file);
+            Ok(name)
+        })
+    }
+
+    fn unload_plugin(&self, meta: Self::Metadata, name: String) -> Result<()> {
+        debug!("unload_plugin admin rpc request received");
+        meta.with_geyser_plugin_manager(|geyser_plugin_manager| {
+            GeyserPluginManager::unload_plugin(geyser_plugin_manager, &name).map_err(|err| {
+                jsonrpc_core::error::Error::invalid_params(format!(
+                    "Failed to unload plugin {}: {:?}",
+                    name, err
+                ))
+            })?;
+            warn!("Geyser plugin {} unloaded", name);
+            Ok(())
+        })
+    }
+
+    fn reload_plugin(&self, meta: Self::Metadata, name: String, config_file: String) -> Result<()> {
+        debug!("reload_plugin admin rpc request received");
+        meta.with_geyser_plugin_manager(|geyser_plugin_manager| {
+            GeyserPluginManager::reload_plugin(
+                geyser_plugin_manager,
+                &name,
+                Path::new(&config_file),
+            )
+            .map_err(|err| {
+                jsonrpc_core::error::Error::invalid_params(format!(
+                    "Failed to reload plugin {} from {}: {:?}",
+                    name, config_file, err
+                ))
+            })?;
+            warn!("Geyser plugin {} reloaded from {}", name, config_file);
+            Ok(())
+        })
+    }
 }
 
 // Start the Admin RPC interface
@@ -632,6 +632,8 @@ fn main() {
             authorized_voter_keypairs: genesis.authorized_voter_keypairs.clone(),
             post_init: admin_service_post_init.clone(),
             tower_storage: tower_storage.clone(),
+            accounts_hash_progress: Arc::default(),
+            rpc_rate_limiter: None,
         },
     );
     let dashboard = if output == Output::Dashboard {
@@ -744,6 +746,7 @@ fn main() {
                     bank_forks: test_validator.bank_forks(),
                     cluster_info: test_validator.cluster_info(),
                     vote_account: test_validator.vote_account_address(),
+                    geyser_plugin_manager: test_validator.geyser_plugin_manager(),
                 });
             if let Some(dashboard) = dashboard {
                 dashboard.run(Duration::from_millis(250));
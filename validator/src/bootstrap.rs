@@ -45,6 +45,7 @@ use {
 pub struct RpcBootstrapConfig {
     pub no_genesis_fetch: bool,
     pub no_snapshot_fetch: bool,
+    pub verify_downloaded_snapshot: bool,
     pub only_known_rpc: bool,
     pub max_genesis_archive_unpacked_size: u64,
     pub check_vote_account: Option<String>,
@@ -410,6 +411,18 @@ fn get_highest_local_snapshot_hash(
     }
 }
 
+/// Where a downloaded snapshot archive is unpacked to verify it, if it is to be verified
+fn verify_snapshot_dir<'a>(
+    validator_config: &'a ValidatorConfig,
+    bootstrap_config: &RpcBootstrapConfig,
+) -> Option<&'a Path> {
+    validator_config
+        .snapshot_config
+        .as_ref()
+        .filter(|_| bootstrap_config.verify_downloaded_snapshot)
+        .map(|snapshot_config| snapshot_config.bank_snapshots_dir.as_path())
+}
+
 mod without_incremental_snapshots {
     use super::*;
 
@@ -570,6 +583,7 @@ mod without_incremental_snapshots {
                                 maximum_full_snapshot_archives_to_retain,
                                 maximum_incremental_snapshot_archives_to_retain,
                                 use_progress_bar,
+                                verify_snapshot_dir(validator_config, &bootstrap_config),
                                 &mut Some(Box::new(|download_progress: &DownloadProgressRecord| {
                                     debug!("Download progress: {:?}", download_progress);
 
@@ -1553,6 +1567,7 @@ mod with_incremental_snapshots {
             maximum_full_snapshot_archives_to_retain,
             maximum_incremental_snapshot_archives_to_retain,
             use_progress_bar,
+            verify_snapshot_dir(validator_config, bootstrap_config),
             &mut Some(Box::new(|download_progress: &DownloadProgressRecord| {
                 debug!("Download progress: {:?}", download_progress);
                 if download_progress.last_throughput < minimal_snapshot_download_speed
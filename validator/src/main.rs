@@ -39,18 +39,25 @@ use {
     solana_poh::poh_service,
     solana_rpc::{
         rpc::{JsonRpcConfig, RpcBigtableConfig},
+        rpc_method_access::RpcMethodAccess,
         rpc_pubsub_service::PubSubConfig,
+        rpc_rate_limiter::{RpcRateLimitConfig, RpcRateLimiter, TokenBucketConfig},
+        rpc_service::rpc_method_names,
     },
     solana_runtime::{
         accounts_db::{
             AccountShrinkThreshold, AccountsDbConfig, FillerAccountsConfig,
             DEFAULT_ACCOUNTS_SHRINK_OPTIMIZE_TOTAL_SPACE, DEFAULT_ACCOUNTS_SHRINK_RATIO,
         },
+        accounts_hash_checkpoint::AccountsHashProgressTracker,
         accounts_index::{
             AccountIndex, AccountSecondaryIndexes, AccountSecondaryIndexesIncludeExclude,
-            AccountsIndexConfig, IndexLimitMb,
+            AccountsIndexConfig, CustomSecondaryIndex, IndexLimitMb,
         },
+        append_vec::AppendVecBackend,
+        compaction_policy::{CompactionPolicy, TargetCompactionPolicy},
         hardened_unpack::MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
+        read_only_accounts_cache::ReadOnlyCacheEviction,
         runtime_config::RuntimeConfig,
         snapshot_config::SnapshotConfig,
         snapshot_utils::{
@@ -377,6 +384,109 @@ fn hardforks_of(matches: &ArgMatches<'_>, name: &str) -> Option<Vec<Slot>> {
     }
 }
 
+fn is_rpc_method_value(value: String) -> Result<(), String> {
+    parse_rpc_method_value(&value).map(|_| ())
+}
+
+fn is_rpc_method_rate_limit(value: String) -> Result<(), String> {
+    let (_method, tokens_per_second) = parse_rpc_method_value(&value)?;
+    is_rpc_rate_limit(tokens_per_second.to_string())
+}
+
+fn is_rpc_rate_limit(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(0) => Err(
+            "a rate limit must be greater than 0; use --rpc-deny-method to disable \
+                      an RPC method"
+                .to_string(),
+        ),
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("invalid rate limit {}: {}", value, err)),
+    }
+}
+
+fn parse_rpc_method_value(value: &str) -> Result<(String, u64), String> {
+    let (method, tokens) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected METHOD:VALUE, got {}", value))?;
+    if method.is_empty() {
+        return Err(format!("missing the RPC method in {}", value));
+    }
+    let tokens = tokens
+        .parse::<u64>()
+        .map_err(|err| format!("invalid value in {}: {}", value, err))?;
+    Ok((method.to_string(), tokens))
+}
+
+fn rpc_method_access_of(matches: &ArgMatches<'_>) -> RpcMethodAccess {
+    let mut method_access = matches
+        .value_of("rpc_method_access_config")
+        .map(|path| {
+            RpcMethodAccess::load(Path::new(path)).unwrap_or_else(|err| {
+                eprintln!("Unable to load RPC method access config {}: {}", path, err);
+                exit(1);
+            })
+        })
+        .unwrap_or_default();
+    if let Some(methods) = matches.values_of("rpc_allow_method") {
+        method_access
+            .allowed_methods
+            .get_or_insert_with(HashSet::default)
+            .extend(methods.map(|method| method.to_string()));
+    }
+    method_access.denied_methods.extend(
+        matches
+            .values_of("rpc_deny_method")
+            .unwrap_or_default()
+            .map(|method| method.to_string()),
+    );
+    method_access.read_only |= matches.is_present("rpc_read_only");
+    let served_methods = rpc_method_names(
+        matches.is_present("full_rpc_api"),
+        matches.is_present("obsolete_v1_7_rpc_api"),
+    );
+    if let Err(err) = method_access.verify(served_methods.iter().map(String::as_str)) {
+        eprintln!("Invalid RPC method access rules: {}", err);
+        exit(1);
+    }
+    method_access
+}
+
+fn rpc_rate_limiter_of(matches: &ArgMatches<'_>) -> Option<Arc<RpcRateLimiter>> {
+    let mut config = RpcRateLimitConfig {
+        per_ip: value_t!(matches, "rpc_rate_limit_per_ip", u64)
+            .ok()
+            .map(|tokens_per_second| TokenBucketConfig {
+                tokens_per_second,
+                burst: value_t!(matches, "rpc_rate_limit_per_ip_burst", u64)
+                    .unwrap_or(tokens_per_second),
+            }),
+        trust_forwarded_for: matches.is_present("rpc_rate_limit_trust_forwarded_for"),
+        ..RpcRateLimitConfig::default()
+    };
+    for value in matches
+        .values_of("rpc_rate_limit_method")
+        .unwrap_or_default()
+    {
+        let (method, tokens_per_second) = parse_rpc_method_value(value).unwrap();
+        config.per_method.insert(
+            method,
+            TokenBucketConfig {
+                tokens_per_second,
+                burst: tokens_per_second,
+            },
+        );
+    }
+    if config.per_ip.is_none() && config.per_method.is_empty() {
+        return None;
+    }
+    for value in matches.values_of("rpc_method_weight").unwrap_or_default() {
+        let (method, weight) = parse_rpc_method_value(value).unwrap();
+        config.method_weights.insert(method, weight);
+    }
+    Some(Arc::new(RpcRateLimiter::new(config)))
+}
+
 fn validators_set(
     identity_pubkey: &Pubkey,
     matches: &ArgMatches<'_>,
@@ -545,6 +655,15 @@ pub fn main() {
                 .help("Do not attempt to fetch a snapshot from the cluster, \
                       start from a local snapshot if present"),
         )
+        .arg(
+            Arg::with_name("verify_downloaded_snapshot")
+                .long("verify-downloaded-snapshot")
+                .takes_value(false)
+                .conflicts_with("no_snapshot_fetch")
+                .help("Verify a snapshot archive downloaded from the cluster against its hash \
+                      before starting from it. This unpacks and hashes the whole archive, \
+                      which takes about as long as loading it"),
+        )
         .arg(
             Arg::with_name("no_genesis_fetch")
                 .long("no-genesis-fetch")
@@ -1407,6 +1526,109 @@ pub fn main() {
                 .requires("enable_rpc_transaction_history")
                 .help("Verifies blockstore roots on boot and fixes any gaps"),
         )
+        .arg(
+            Arg::with_name("rpc_method_access_config")
+                .long("rpc-method-access-config")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("JSON file with the RPC methods to serve: \
+                       {\"allowedMethods\": [..], \"deniedMethods\": [..], \"readOnly\": bool}. \
+                       The --rpc-allow-method, --rpc-deny-method and --rpc-read-only \
+                       flags add to it. The rules for a method apply to its deprecated \
+                       aliases, and do not apply to the PubSub websocket"),
+        )
+        .arg(
+            Arg::with_name("rpc_allow_method")
+                .long("rpc-allow-method")
+                .value_name("METHOD")
+                .takes_value(true)
+                .multiple(true)
+                .help("Only serve these RPC methods. May be specified multiple times \
+                       [default: all the enabled methods]"),
+        )
+        .arg(
+            Arg::with_name("rpc_deny_method")
+                .long("rpc-deny-method")
+                .value_name("METHOD")
+                .takes_value(true)
+                .multiple(true)
+                .help("Do not serve this RPC method. May be specified multiple times"),
+        )
+        .arg(
+            Arg::with_name("rpc_read_only")
+                .long("rpc-read-only")
+                .takes_value(false)
+                .help("Do not serve the RPC methods that submit transactions, \
+                       such as sendTransaction and requestAirdrop"),
+        )
+        .arg(
+            Arg::with_name("unrestricted_rpc_port")
+                .long("unrestricted-rpc-port")
+                .value_name("PORT")
+                .takes_value(true)
+                .validator(solana_validator::port_validator)
+                .requires("rpc_port")
+                .help("Also serve JSON RPC on this port, without the RPC method access \
+                       rules and rate limits, for trusted clients"),
+        )
+        .arg(
+            Arg::with_name("unrestricted_rpc_bind_address")
+                .long("unrestricted-rpc-bind-address")
+                .value_name("HOST")
+                .takes_value(true)
+                .validator(solana_net_utils::is_host)
+                .requires("unrestricted_rpc_port")
+                .help("IP address to bind the unrestricted RPC port [default: 127.0.0.1]"),
+        )
+        .arg(
+            Arg::with_name("rpc_rate_limit_per_ip")
+                .long("rpc-rate-limit-per-ip")
+                .value_name("TOKENS_PER_SECOND")
+                .takes_value(true)
+                .validator(is_rpc_rate_limit)
+                .help("Limit the RPC requests of each client IP to this many tokens per second. \
+                       Each request takes its method's weight in tokens [default: no limit]"),
+        )
+        .arg(
+            Arg::with_name("rpc_rate_limit_per_ip_burst")
+                .long("rpc-rate-limit-per-ip-burst")
+                .value_name("TOKENS")
+                .takes_value(true)
+                .validator(is_rpc_rate_limit)
+                .requires("rpc_rate_limit_per_ip")
+                .help("Largest burst of tokens a client IP may use at once \
+                       [default: --rpc-rate-limit-per-ip]"),
+        )
+        .arg(
+            Arg::with_name("rpc_rate_limit_trust_forwarded_for")
+                .long("rpc-rate-limit-trust-forwarded-for")
+                .takes_value(false)
+                .requires("rpc_rate_limit_per_ip")
+                .help("Take the client IP of RPC requests from the last address of the \
+                       X-Forwarded-For header rather than from the connection. Only use behind \
+                       a reverse proxy that sets the header, as clients can forge it"),
+        )
+        .arg(
+            Arg::with_name("rpc_rate_limit_method")
+                .long("rpc-rate-limit-method")
+                .value_name("METHOD:TOKENS_PER_SECOND")
+                .takes_value(true)
+                .multiple(true)
+                .validator(is_rpc_method_rate_limit)
+                .help("Limit the requests of all the clients for an RPC method to this many \
+                       tokens per second. May be specified multiple times"),
+        )
+        .arg(
+            Arg::with_name("rpc_method_weight")
+                .long("rpc-method-weight")
+                .value_name("METHOD:WEIGHT")
+                .takes_value(true)
+                .multiple(true)
+                .validator(is_rpc_method_value)
+                .help("Number of tokens a request for an RPC method takes from the rate limits. \
+                       May be specified multiple times \
+                       [default: higher for scans such as getProgramAccounts, 1 otherwise]"),
+        )
         .arg(
             Arg::with_name("enable_accountsdb_repl")
                 .long("enable-accountsdb-repl")
@@ -1451,6 +1673,15 @@ pub fn main() {
                 .multiple(true)
                 .help("Specify the configuration file for the Geyser plugin."),
         )
+        .arg(
+            Arg::with_name("bank_diff_path")
+                .long("bank-diff-path")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Write the accounts modified in each rooted slot to one file per epoch \
+                       in this directory. Each account is recorded with its owner, lamports, \
+                       data hash and write version"),
+        )
         .arg(
             Arg::with_name("halt_on_known_validators_accounts_hash_mismatch")
                 .alias("halt-on-trusted-validators-accounts-hash-mismatch")
@@ -1463,7 +1694,7 @@ pub fn main() {
             Arg::with_name("snapshot_archive_format")
                 .long("snapshot-archive-format")
                 .alias("snapshot-compression") // Legacy name used by Solana v1.5.x and older
-                .possible_values(&["bz2", "gzip", "zstd", "tar", "none"])
+                .possible_values(&["bz2", "gzip", "zstd", "tar", "none", "indexed-zstd"])
                 .default_value("zstd")
                 .value_name("ARCHIVE_TYPE")
                 .takes_value(true)
@@ -1540,6 +1771,18 @@ pub fn main() {
                 .value_name("INDEX")
                 .help("Enable an accounts index, indexed by the selected account field"),
         )
+        .arg(
+            Arg::with_name("account_index_custom")
+                .long("account-index-custom")
+                .takes_value(true)
+                .multiple(true)
+                .validator(|value| value.parse::<CustomSecondaryIndex>().map(|_| ()))
+                .value_name("PROGRAM_ID:OFFSET:LENGTH")
+                .help("Enable an accounts index of the accounts owned by PROGRAM_ID, indexed by \
+                       the LENGTH bytes at OFFSET in their data. LENGTH must be at most 32. \
+                       getProgramAccounts uses this index when a memcmp filter compares the \
+                       whole field"),
+        )
         .arg(
             Arg::with_name("account_index_exclude_key")
                 .long(EXCLUDE_KEY)
@@ -1633,6 +1876,18 @@ pub fn main() {
                        May be specified multiple times. \
                        [default: [ledger]/accounts_index]"),
          )
+        .arg(
+            Arg::with_name("accounts_secondary_index_path")
+                .long("accounts-secondary-index-path")
+                .value_name("PATH")
+                .takes_value(true)
+                .multiple(true)
+                .help("Store the secondary indexes enabled with --account-index on disk at this \
+                       location and rebuild them in the background at startup. \
+                       The location is erased at startup, the indexes are not kept across restarts. \
+                       Until the rebuild completes, queries do not use the secondary indexes. \
+                       May be specified multiple times."),
+         )
          .arg(Arg::with_name("accounts_filler_count")
             .long("accounts-filler-count")
             .value_name("COUNT")
@@ -1699,6 +1954,64 @@ pub fn main() {
                        it becomes a candidate for shrinking. The value must between 0. and 1.0 \
                        inclusive."),
         )
+        .arg(
+            Arg::with_name("accounts_shrink_busy_ratio")
+                .long("accounts-shrink-busy-ratio")
+                .takes_value(true)
+                .value_name("RATIO")
+                .validator(is_parsable::<f64>)
+                .help("Shrink ratio to use instead of accounts-shrink-ratio while the accounts \
+                       write cache is backed up. A lower ratio shrinks less while the \
+                       validator is busy. [default: accounts-shrink-ratio]"),
+        )
+        .arg(
+            Arg::with_name("accounts_shrink_min_slot_age")
+                .long("accounts-shrink-min-slot-age")
+                .takes_value(true)
+                .value_name("SLOTS")
+                .validator(is_parsable::<Slot>)
+                .help("Do not shrink storages less than this many slots older than the latest \
+                       root, since they are likely to shrink further soon"),
+        )
+        .arg(
+            Arg::with_name("accounts_shrink_max_slots_per_pass")
+                .long("accounts-shrink-max-slots-per-pass")
+                .takes_value(true)
+                .value_name("SLOTS")
+                .validator(is_parsable::<usize>)
+                .help("Maximum number of slots to shrink at a time, oldest first"),
+        )
+        .arg(
+            Arg::with_name("accounts_db_read_cache_limit_mb")
+                .long("accounts-db-read-cache-limit-mb")
+                .value_name("MEGABYTES")
+                .validator(is_parsable::<usize>)
+                .takes_value(true)
+                .help("How large the read-only cache for account data can become. \
+                       Once this is exceeded, accounts are evicted from the cache \
+                       as chosen by --accounts-db-read-cache-eviction"),
+        )
+        .arg(
+            Arg::with_name("accounts_db_read_cache_eviction")
+                .long("accounts-db-read-cache-eviction")
+                .takes_value(true)
+                .value_name("POLICY")
+                .possible_values(ReadOnlyCacheEviction::VARIANTS)
+                .default_value("lru")
+                .help("Which accounts the read-only accounts cache evicts when it is full. \
+                       segmented-lru keeps accounts loaded more than once over accounts loaded \
+                       only once, size-aware-lfu keeps accounts loaded most often per byte"),
+        )
+        .arg(
+            Arg::with_name("accounts_db_append_vec_backend")
+                .long("accounts-db-append-vec-backend")
+                .takes_value(true)
+                .value_name("BACKEND")
+                .possible_values(AppendVecBackend::VARIANTS)
+                .default_value("mmap")
+                .help("How account storage files are accessed. mmap maps the files into memory, \
+                       file uses positioned reads and writes through a pool of buffers"),
+        )
         .arg(
             Arg::with_name("no_duplicate_instance_check")
                 .long("no-duplicate-instance-check")
@@ -1793,6 +2106,14 @@ pub fn main() {
                     .help("Output display mode")
             )
         )
+        .subcommand(
+            SubCommand::with_name("accounts-hash-progress")
+            .about("Display the progress of the latest accounts hash calculation")
+        )
+        .subcommand(
+            SubCommand::with_name("rpc-top-consumers")
+            .about("Display the RPC clients using the most rate limit tokens")
+        )
         .subcommand(
             SubCommand::with_name("init")
             .about("Initialize the ledger directory then exit")
@@ -1801,6 +2122,63 @@ pub fn main() {
             SubCommand::with_name("monitor")
             .about("Monitor the validator")
         )
+        .subcommand(
+            SubCommand::with_name("plugin")
+            .about("Manage and view Geyser plugins")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .setting(AppSettings::InferSubcommands)
+            .subcommand(
+                SubCommand::with_name("list")
+                .about("List all current running Geyser plugins")
+            )
+            .subcommand(
+                SubCommand::with_name("load")
+                .about("Load a new Geyser plugin")
+                .arg(
+                    Arg::with_name("config")
+                        .required(true)
+                        .index(1)
+                        .value_name("CONFIG")
+                        .takes_value(true)
+                        .help("Path to the plugin's config file")
+                )
+            )
+            .subcommand(
+                SubCommand::with_name("unload")
+                .about("Unload a Geyser plugin")
+                .arg(
+                    Arg::with_name("name")
+                        .required(true)
+                        .index(1)
+                        .value_name("NAME")
+                        .takes_value(true)
+                        .help("Name of the plugin")
+                )
+            )
+            .subcommand(
+                SubCommand::with_name("reload")
+                .about("Reload a Geyser plugin")
+                .arg(
+                    Arg::with_name("name")
+                        .required(true)
+                        .index(1)
+                        .value_name("NAME")
+                        .takes_value(true)
+                        .help("Name of the plugin")
+                )
+                .arg(
+                    Arg::with_name("config")
+                        .required(true)
+                        .index(2)
+                        .value_name("CONFIG")
+                        .takes_value(true)
+                        .help("Path to the plugin's config file")
+                )
+            )
+            .after_help("Note: plugins are only notified of the account, transaction and \
+                         entry updates the validator was started with a plugin for, and \
+                         changes only apply to the currently running validator instance")
+        )
         .subcommand(
             SubCommand::with_name("run")
             .about("Run the validator")
@@ -1951,6 +2329,137 @@ pub fn main() {
             }
             return;
         }
+        ("accounts-hash-progress", _) => {
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            let progress = admin_rpc_service::runtime()
+                .block_on(async move { admin_client.await?.accounts_hash_progress().await })
+                .unwrap_or_else(|err| {
+                    eprintln!("Accounts hash progress query failed: {}", err);
+                    exit(1);
+                });
+            match progress {
+                Some(progress) => {
+                    println!(
+                        "Slots {}..{}: {}/{} passes{}{}",
+                        progress.slot_range.start,
+                        progress.slot_range.end,
+                        progress.completed_passes,
+                        progress.num_passes,
+                        if progress.resumed_passes > 0 {
+                            format!(", {} resumed from a checkpoint", progress.resumed_passes)
+                        } else {
+                            String::default()
+                        },
+                        if progress.done { ", done" } else { "" },
+                    );
+                }
+                None => println!("No accounts hash calculation has started"),
+            }
+            return;
+        }
+        ("rpc-top-consumers", _) => {
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            let consumers = admin_rpc_service::runtime()
+                .block_on(async move { admin_client.await?.rpc_top_consumers().await })
+                .unwrap_or_else(|err| {
+                    eprintln!("RPC top consumers query failed: {}", err);
+                    exit(1);
+                });
+            if consumers.is_empty() {
+                println!("No RPC usage recorded; is RPC rate limiting enabled?");
+            }
+            for consumer in consumers {
+                println!(
+                    "{}: {} requests, {} tokens, {} rate limited",
+                    consumer
+                        .ip
+                        .map(|ip| ip.to_string())
+                        .unwrap_or_else(|| "unknown".to_string()),
+                    consumer.requests,
+                    consumer.weight,
+                    consumer.rate_limited_requests,
+                );
+            }
+            return;
+        }
+        ("plugin", Some(plugin_subcommand_matches)) => {
+            match plugin_subcommand_matches.subcommand() {
+                ("list", _) => {
+                    let admin_client = admin_rpc_service::connect(&ledger_path);
+                    let plugins = admin_rpc_service::runtime()
+                        .block_on(async move { admin_client.await?.list_plugins().await })
+                        .unwrap_or_else(|err| {
+                            println!("Failed to list plugins: {}", err);
+                            exit(1);
+                        });
+                    if !plugins.is_empty() {
+                        println!("Currently the following plugins are loaded:");
+                        for (plugin, i) in plugins.into_iter().zip(1..) {
+                            println!("  {}) {}", i, plugin);
+                        }
+                    } else {
+                        println!("There are currently no plugins loaded");
+                    }
+                    return;
+                }
+                ("load", Some(subcommand_matches)) => {
+                    let config = value_t_or_exit!(subcommand_matches, "config", String);
+                    let config = fs::canonicalize(&config).unwrap_or_else(|err| {
+                        println!("Unable to access path: {}: {:?}", config, err);
+                        exit(1);
+                    });
+                    let admin_client = admin_rpc_service::connect(&ledger_path);
+                    let name = admin_rpc_service::runtime()
+                        .block_on(async move {
+                            admin_client
+                                .await?
+                                .load_plugin(config.display().to_string())
+                                .await
+                        })
+                        .unwrap_or_else(|err| {
+                            println!("Failed to load plugin: {}", err);
+                            exit(1);
+                        });
+                    println!("Successfully loaded plugin: {}", name);
+                    return;
+                }
+                ("unload", Some(subcommand_matches)) => {
+                    let name = value_t_or_exit!(subcommand_matches, "name", String);
+                    let admin_client = admin_rpc_service::connect(&ledger_path);
+                    admin_rpc_service::runtime()
+                        .block_on(async { admin_client.await?.unload_plugin(name.clone()).await })
+                        .unwrap_or_else(|err| {
+                            println!("Failed to unload plugin {}: {:?}", name, err);
+                            exit(1);
+                        });
+                    println!("Successfully unloaded plugin: {}", name);
+                    return;
+                }
+                ("reload", Some(subcommand_matches)) => {
+                    let name = value_t_or_exit!(subcommand_matches, "name", String);
+                    let config = value_t_or_exit!(subcommand_matches, "config", String);
+                    let config = fs::canonicalize(&config).unwrap_or_else(|err| {
+                        println!("Unable to access path: {}: {:?}", config, err);
+                        exit(1);
+                    });
+                    let admin_client = admin_rpc_service::connect(&ledger_path);
+                    admin_rpc_service::runtime()
+                        .block_on(async {
+                            admin_client
+                                .await?
+                                .reload_plugin(name.clone(), config.display().to_string())
+                                .await
+                        })
+                        .unwrap_or_else(|err| {
+                            println!("Failed to reload plugin {}: {:?}", name, err);
+                            exit(1);
+                        });
+                    println!("Successfully reloaded plugin: {}", name);
+                    return;
+                }
+                _ => unreachable!(),
+            }
+        }
         ("init", _) => Operation::Initialize,
         ("exit", Some(subcommand_matches)) => {
             let min_idle_time = value_t_or_exit!(subcommand_matches, "min_idle_time", usize);
@@ -2102,6 +2611,7 @@ pub fn main() {
     let rpc_bootstrap_config = bootstrap::RpcBootstrapConfig {
         no_genesis_fetch: matches.is_present("no_genesis_fetch"),
         no_snapshot_fetch: matches.is_present("no_snapshot_fetch"),
+        verify_downloaded_snapshot: matches.is_present("verify_downloaded_snapshot"),
         check_vote_account: matches
             .value_of("check_vote_account")
             .map(|url| url.to_string()),
@@ -2191,10 +2701,38 @@ pub fn main() {
         exit(1);
     }
 
-    let accounts_shrink_ratio = if accounts_shrink_optimize_total_space {
-        AccountShrinkThreshold::TotalSpace { shrink_ratio }
+    let new_shrink_threshold = |shrink_ratio| {
+        if accounts_shrink_optimize_total_space {
+            AccountShrinkThreshold::TotalSpace { shrink_ratio }
+        } else {
+            AccountShrinkThreshold::IndividualStore { shrink_ratio }
+        }
+    };
+    let accounts_shrink_ratio = new_shrink_threshold(shrink_ratio);
+
+    let compaction_policy: Option<Arc<dyn CompactionPolicy>> = if matches
+        .is_present("accounts_shrink_busy_ratio")
+        || matches.is_present("accounts_shrink_min_slot_age")
+        || matches.is_present("accounts_shrink_max_slots_per_pass")
+    {
+        let busy_shrink_ratio =
+            value_t!(matches, "accounts_shrink_busy_ratio", f64).unwrap_or(shrink_ratio);
+        if !(0.0..=1.0).contains(&busy_shrink_ratio) {
+            eprintln!(
+                "The specified accounts-shrink-busy-ratio is invalid, it must be between 0. and 1.0 inclusive: {}",
+                busy_shrink_ratio
+            );
+            exit(1);
+        }
+        Some(Arc::new(TargetCompactionPolicy {
+            idle_threshold: accounts_shrink_ratio,
+            busy_threshold: new_shrink_threshold(busy_shrink_ratio),
+            min_slot_age: value_t!(matches, "accounts_shrink_min_slot_age", Slot).unwrap_or(0),
+            max_slots_per_pass: value_t!(matches, "accounts_shrink_max_slots_per_pass", usize).ok(),
+            ..TargetCompactionPolicy::default()
+        }))
     } else {
-        AccountShrinkThreshold::IndividualStore { shrink_ratio }
+        None
     };
     let entrypoint_addrs = values_t!(matches, "entrypoint", String)
         .unwrap_or_default()
@@ -2294,6 +2832,14 @@ pub fn main() {
         }
         accounts_index_config.drives = Some(accounts_index_paths);
     }
+    if matches.is_present("accounts_secondary_index_path") {
+        accounts_index_config.secondary_index_drives = Some(
+            values_t_or_exit!(matches, "accounts_secondary_index_path", String)
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+        );
+    }
 
     const MB: usize = 1_024 * 1_024;
     accounts_index_config.scan_results_limit_bytes =
@@ -2306,6 +2852,8 @@ pub fn main() {
         size: value_t_or_exit!(matches, "accounts_filler_size", usize),
     };
 
+    let accounts_hash_progress = Arc::new(AccountsHashProgressTracker::default());
+    let rpc_rate_limiter = rpc_rate_limiter_of(&matches);
     let mut accounts_db_config = AccountsDbConfig {
         index: Some(accounts_index_config),
         accounts_hash_cache_path: Some(ledger_path.clone()),
@@ -2314,6 +2862,21 @@ pub fn main() {
             .ok()
             .map(|mb| mb * MB as u64),
         skip_rewrites: matches.is_present("accounts_db_skip_rewrites"),
+        compaction_policy,
+        read_only_cache_eviction: value_t_or_exit!(
+            matches,
+            "accounts_db_read_cache_eviction",
+            ReadOnlyCacheEviction
+        ),
+        read_only_cache_limit_bytes: value_t!(matches, "accounts_db_read_cache_limit_mb", usize)
+            .ok()
+            .map(|mb| mb * MB),
+        append_vec_backend: value_t_or_exit!(
+            matches,
+            "accounts_db_append_vec_backend",
+            AppendVecBackend
+        ),
+        accounts_hash_progress: Some(accounts_hash_progress.clone()),
         ..AccountsDbConfig::default()
     };
 
@@ -2426,6 +2989,20 @@ pub fn main() {
             rpc_niceness_adj: value_t_or_exit!(matches, "rpc_niceness_adj", i8),
             account_indexes: account_indexes.clone(),
             rpc_scan_and_fix_roots: matches.is_present("rpc_scan_and_fix_roots"),
+            rate_limiter: rpc_rate_limiter.clone(),
+            method_access: rpc_method_access_of(&matches),
+            unrestricted_rpc_addr: value_t!(matches, "unrestricted_rpc_port", u16).ok().map(
+                |unrestricted_rpc_port| {
+                    let unrestricted_rpc_bind_address = matches
+                        .value_of("unrestricted_rpc_bind_address")
+                        .map(|address| {
+                            solana_net_utils::parse_host(address)
+                                .expect("invalid unrestricted_rpc_bind_address")
+                        })
+                        .unwrap_or_else(|| solana_net_utils::parse_host("127.0.0.1").unwrap());
+                    SocketAddr::new(unrestricted_rpc_bind_address, unrestricted_rpc_port)
+                },
+            ),
         },
         geyser_plugin_config_files,
         rpc_addrs: value_t!(matches, "rpc_port", u16).ok().map(|rpc_port| {
@@ -2511,6 +3088,7 @@ pub fn main() {
             bpf_jit: !matches.is_present("no_bpf_jit"),
             ..RuntimeConfig::default()
         },
+        bank_diff_path: matches.value_of("bank_diff_path").map(PathBuf::from),
         ..ValidatorConfig::default()
     };
 
@@ -2611,6 +3189,7 @@ pub fn main() {
             "gzip" => ArchiveFormat::TarGzip,
             "zstd" => ArchiveFormat::TarZstd,
             "tar" | "none" => ArchiveFormat::Tar,
+            "indexed-zstd" => ArchiveFormat::IndexedZstd,
             _ => panic!("Archive format not recognized: {}", archive_format_str),
         }
     };
@@ -2763,6 +3342,8 @@ pub fn main() {
             authorized_voter_keypairs: authorized_voter_keypairs.clone(),
             post_init: admin_service_post_init.clone(),
             tower_storage: validator_config.tower_storage.clone(),
+            accounts_hash_progress,
+            rpc_rate_limiter,
         },
     );
 
@@ -2919,6 +3500,7 @@ pub fn main() {
             bank_forks: validator.bank_forks.clone(),
             cluster_info: validator.cluster_info.clone(),
             vote_account,
+            geyser_plugin_manager: validator.geyser_plugin_manager(),
         });
 
     if let Some(filename) = init_complete_file {
@@ -2942,6 +3524,12 @@ fn process_account_indexes(matches: &ArgMatches) -> AccountSecondaryIndexes {
             "spl-token-owner" => AccountIndex::SplTokenOwner,
             _ => unreachable!(),
         })
+        .chain(
+            values_t!(matches, "account_index_custom", CustomSecondaryIndex)
+                .unwrap_or_default()
+                .into_iter()
+                .map(AccountIndex::Custom),
+        )
         .collect();
 
     let account_indexes_include_keys: HashSet<Pubkey> =